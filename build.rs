@@ -64,6 +64,14 @@ fn main() {
     combine_with_exported_module!(&mut lib, "rhai_sci_moving", moving_functions);
     combine_with_exported_module!(&mut lib, "rhai_sci_validate", validation_functions);
     combine_with_exported_module!(&mut lib, "rhai_sci_trig", trig_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_signal", signal_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_ode", ode_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_roots", roots_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_optimization", optimization_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_dual", dual_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_distributions", distribution_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_random", random_functions);
+    combine_with_exported_module!(&mut lib, "rhai_sci_experiments", experiment_functions);
     engine.register_global_module(rhai::Shared::new(lib));
 
     // Extract metadata
@@ -221,6 +229,14 @@ mod functions {
     include!("src/validate.rs");
     include!("src/patterns.rs");
     include!("src/trig.rs");
+    include!("src/signal.rs");
+    include!("src/ode.rs");
+    include!("src/roots.rs");
+    include!("src/optimization.rs");
+    include!("src/dual.rs");
+    include!("src/distributions.rs");
+    include!("src/random.rs");
+    include!("src/experiments.rs");
 }
 
 #[cfg(feature = "metadata")]
@@ -2,8 +2,8 @@ use rhai::plugin::*;
 
 #[export_module]
 pub mod cum_functions {
-    use crate::{if_list_convert_to_vec_float_and_do, if_list_do};
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT, INT};
+    use crate::{arithmetic_error, if_list_convert_to_vec_float_and_do, if_list_do};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
 
     fn accumulate<G>(arr: &mut Array, mut f: G) -> Result<Array, Box<EvalAltResult>>
     where
@@ -73,11 +73,7 @@ pub mod cum_functions {
     #[rhai_fn(name = "cumtrapz", return_raw)]
     pub fn cumtrapz(x: Array, y: Array) -> Result<Array, Box<EvalAltResult>> {
         if x.len() != y.len() {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have the same length".to_string(),
-                Position::NONE,
-            )
-            .into())
+            Err(arithmetic_error("The arrays must have the same length"))
         } else {
             if_list_convert_to_vec_float_and_do(&mut y.clone(), |yf| {
                 if_list_convert_to_vec_float_and_do(&mut x.clone(), |xf| {
@@ -112,4 +108,68 @@ pub mod cum_functions {
             Ok(cumtrapsum)
         })
     }
+
+    /// Returns the cumulative approximate integral of the curve defined by `y` and `x` using
+    /// Simpson's rule. Each interval is integrated with the quadratic through its end points
+    /// and the next sample, or the previous sample for the last interval, so the result is
+    /// exact for quadratics. Two samples fall back to the trapezoidal method.
+    /// ```typescript
+    /// let x = [0.0, 1.0, 2.0, 3.0];
+    /// let c = cumsimpson(x, x.map(|x| x ** 2));
+    /// assert_approx_eq(c, [0.0, 1.0 / 3.0, 8.0 / 3.0, 9.0], 1e-12);
+    /// ```
+    /// ```typescript
+    /// let x = [0.0, 0.2, 0.7, 1.5];
+    /// let c = cumsimpson(x, x.map(|x| 3.0 * x ** 2 + 1.0));
+    /// assert_approx_eq(c, x.map(|x| x ** 3 + x), 1e-12);
+    /// ```
+    #[rhai_fn(name = "cumsimpson", return_raw)]
+    pub fn cumsimpson(x: Array, y: Array) -> Result<Array, Box<EvalAltResult>> {
+        /// Integral over `[x0, x0 + h0]` of the quadratic through the samples at `x0`,
+        /// `x0 + h0` and `x0 + h0 + h1`.
+        fn first_interval(h0: FLOAT, h1: FLOAT, y: [FLOAT; 3]) -> FLOAT {
+            let total = h0 + h1;
+            (h0 / 2.0 - h0 * h0 / (6.0 * total)) * y[0]
+                + (total * h0 / 2.0 - h0 * h0 / 3.0) / h1 * y[1]
+                - h0.powi(3) / (6.0 * total * h1) * y[2]
+        }
+
+        if x.len() != y.len() {
+            return Err(arithmetic_error("The arrays must have the same length"));
+        }
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |yf| {
+            if_list_convert_to_vec_float_and_do(&mut x.clone(), |xf| {
+                let h: Vec<FLOAT> = xf.windows(2).map(|w| w[1] - w[0]).collect();
+                if h.contains(&0.0) {
+                    return Err(arithmetic_error("The x values must be distinct"));
+                }
+                let n = yf.len();
+                let mut total = 0.0;
+                let mut cumulative = vec![Dynamic::FLOAT_ZERO];
+                for i in 0..h.len() {
+                    total += if n == 2 {
+                        h[0] * (yf[0] + yf[1]) / 2.0
+                    } else if i + 2 < n {
+                        first_interval(h[i], h[i + 1], [yf[i], yf[i + 1], yf[i + 2]])
+                    } else {
+                        -first_interval(-h[i], -h[i - 1], [yf[i + 1], yf[i], yf[i - 1]])
+                    };
+                    cumulative.push(Dynamic::from_float(total));
+                }
+                Ok(cumulative)
+            })
+        })
+    }
+
+    /// Returns the cumulative approximate integral of the curve defined by `y` using Simpson's
+    /// rule. Assumes unit spacing in the x direction.
+    /// ```typescript
+    /// let c = cumsimpson([0, 1, 4, 9]);
+    /// assert_approx_eq(c, [0.0, 1.0 / 3.0, 8.0 / 3.0, 9.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "cumsimpson", return_raw, pure)]
+    pub fn cumsimpson_unit(y: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        let x = (0..y.len()).map(|i| Dynamic::from_int(i as INT)).collect();
+        cumsimpson(x, y.clone())
+    }
 }
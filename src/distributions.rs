@@ -0,0 +1,1160 @@
+use rhai::plugin::*;
+
+/// Special functions for the probability distributions.
+mod special {
+    use rhai::FLOAT;
+
+    /// Relative accuracy of the series and continued fractions.
+    const EPS: FLOAT = FLOAT::EPSILON;
+
+    /// Smallest magnitude used to avoid division by zero in Lentz's method.
+    const TINY: FLOAT = 1e-300;
+
+    /// Maximum number of terms of the series and continued fractions.
+    const MAX_TERMS: usize = 10_000;
+
+    /// Coefficients of the Lanczos approximation with `g = 7`.
+    const LANCZOS: [FLOAT; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    /// Returns the natural logarithm of the absolute value of the gamma function.
+    pub(super) fn ln_gamma(x: FLOAT) -> FLOAT {
+        if x < 0.5 {
+            let pi = std::f64::consts::PI;
+            return (pi / (pi * x).sin().abs()).ln() - ln_gamma(1.0 - x);
+        }
+        let x = x - 1.0;
+        let series = LANCZOS[1..]
+            .iter()
+            .enumerate()
+            .fold(LANCZOS[0], |sum, (i, c)| sum + c / (x + i as FLOAT + 1.0));
+        let t = x + 7.5;
+        0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+    }
+
+    /// Returns the natural logarithm of the beta function.
+    pub(super) fn ln_beta(a: FLOAT, b: FLOAT) -> FLOAT {
+        ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+    }
+
+    /// Returns the prefactor `x^a e^-x / Gamma(a)` of the incomplete gamma function.
+    fn gamma_prefactor(a: FLOAT, x: FLOAT) -> FLOAT {
+        (a * x.ln() - x - ln_gamma(a)).exp()
+    }
+
+    /// Returns the lower regularized incomplete gamma function `P(a, x)` from its series.
+    fn gamma_series(a: FLOAT, x: FLOAT) -> FLOAT {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..MAX_TERMS {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        sum * gamma_prefactor(a, x)
+    }
+
+    /// Returns the upper regularized incomplete gamma function `Q(a, x)` from its continued
+    /// fraction, evaluated by Lentz's method.
+    fn gamma_continued_fraction(a: FLOAT, x: FLOAT) -> FLOAT {
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..MAX_TERMS {
+            let an = -(i as FLOAT) * (i as FLOAT - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        gamma_prefactor(a, x) * h
+    }
+
+    /// Returns the correction `R_a` of Temme's uniform asymptotic expansion, with which
+    /// `Q(a, x) = Phi(-eta sqrt(a)) + R_a` and `P(a, x) = Phi(eta sqrt(a)) - R_a`, together
+    /// with `eta sqrt(a)`. Two terms of the expansion are accurate to about `a^-2.5`.
+    fn gamma_temme(a: FLOAT, x: FLOAT) -> (FLOAT, FLOAT) {
+        let d = (x - a) / a;
+        // d - ln(1 + d), from its series where the two terms cancel
+        let half_eta_squared = if d.abs() < 0.1 {
+            let (mut power, mut sum) = (-d, 0.0);
+            for k in 2..20 {
+                power *= -d;
+                sum += power / k as FLOAT;
+            }
+            sum
+        } else {
+            d - d.ln_1p()
+        };
+        let eta = (2.0 * half_eta_squared).sqrt().copysign(d);
+        let (c0, c1) = if eta.abs() < 0.01 {
+            // The closed forms cancel near the peak, where their series are used instead
+            (
+                -1.0 / 3.0 + eta / 12.0 - 2.0 * eta * eta / 135.0 + eta.powi(3) / 864.0,
+                -1.0 / 540.0 - eta / 288.0,
+            )
+        } else {
+            (
+                1.0 / d - 1.0 / eta,
+                1.0 / eta.powi(3) - 1.0 / d.powi(3) - 1.0 / (d * d) - 1.0 / (12.0 * d),
+            )
+        };
+        let r =
+            (-a * eta * eta / 2.0).exp() / (2.0 * std::f64::consts::PI * a).sqrt() * (c0 + c1 / a);
+        (r, eta * a.sqrt())
+    }
+
+    /// Returns whether the incomplete gamma function is evaluated by Temme's expansion, which
+    /// is used for shapes at which the series and continued fraction would need more than
+    /// `MAX_TERMS` terms.
+    fn use_temme(a: FLOAT, x: FLOAT) -> bool {
+        a > 1e5 && x.is_finite()
+    }
+
+    /// Returns the lower regularized incomplete gamma function `P(a, x)`.
+    pub(super) fn gamma_p(a: FLOAT, x: FLOAT) -> FLOAT {
+        if x <= 0.0 {
+            0.0
+        } else if use_temme(a, x) {
+            let (r, z) = gamma_temme(a, x);
+            (normal_cdf(z) - r).clamp(0.0, 1.0)
+        } else if x < a + 1.0 {
+            gamma_series(a, x)
+        } else {
+            1.0 - gamma_continued_fraction(a, x)
+        }
+    }
+
+    /// Returns the upper regularized incomplete gamma function `Q(a, x) = 1 - P(a, x)`.
+    pub(super) fn gamma_q(a: FLOAT, x: FLOAT) -> FLOAT {
+        if x <= 0.0 {
+            1.0
+        } else if use_temme(a, x) {
+            let (r, z) = gamma_temme(a, x);
+            (normal_cdf(-z) + r).clamp(0.0, 1.0)
+        } else if x < a + 1.0 {
+            1.0 - gamma_series(a, x)
+        } else {
+            gamma_continued_fraction(a, x)
+        }
+    }
+
+    /// Returns the continued fraction for the incomplete beta function, evaluated by Lentz's
+    /// method.
+    fn beta_continued_fraction(a: FLOAT, b: FLOAT, x: FLOAT) -> FLOAT {
+        let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+        let mut c = 1.0;
+        let mut d = 1.0 - qab * x / qap;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        d = 1.0 / d;
+        let mut h = d;
+        for m in 1..MAX_TERMS {
+            let m = m as FLOAT;
+            let m2 = 2.0 * m;
+            for aa in [
+                m * (b - m) * x / ((qam + m2) * (a + m2)),
+                -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2)),
+            ] {
+                d = 1.0 + aa * d;
+                if d.abs() < TINY {
+                    d = TINY;
+                }
+                c = 1.0 + aa / c;
+                if c.abs() < TINY {
+                    c = TINY;
+                }
+                d = 1.0 / d;
+                h *= d * c;
+            }
+            if (d * c - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        h
+    }
+
+    /// Returns the regularized incomplete beta function `I_x(a, b)`.
+    pub(super) fn beta_inc(a: FLOAT, b: FLOAT, x: FLOAT) -> FLOAT {
+        if x <= 0.0 {
+            return 0.0;
+        }
+        if x >= 1.0 {
+            return 1.0;
+        }
+        let front = (a * x.ln() + b * (1.0 - x).ln() - ln_beta(a, b)).exp();
+        if x < (a + 1.0) / (a + b + 2.0) {
+            front * beta_continued_fraction(a, b, x) / a
+        } else {
+            1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+        }
+    }
+
+    /// Returns the standard normal cumulative distribution function.
+    pub(super) fn normal_cdf(z: FLOAT) -> FLOAT {
+        // Phi(z) = erfc(-z / sqrt(2)) / 2, with erfc from the incomplete gamma function
+        let half_square = z * z / 2.0;
+        if z < 0.0 {
+            0.5 * gamma_q(0.5, half_square)
+        } else {
+            0.5 + 0.5 * gamma_p(0.5, half_square)
+        }
+    }
+
+    /// Returns the standard normal quantile function, from Acklam's rational approximation
+    /// refined by one step of Halley's method.
+    pub(super) fn normal_quantile(p: FLOAT) -> FLOAT {
+        const A: [FLOAT; 6] = [
+            -3.969_683_028_665_376e1,
+            2.209_460_984_245_205e2,
+            -2.759_285_104_469_687e2,
+            1.383_577_518_672_69e2,
+            -3.066_479_806_614_716e1,
+            2.506_628_277_459_239,
+        ];
+        const B: [FLOAT; 5] = [
+            -5.447_609_879_822_406e1,
+            1.615_858_368_580_409e2,
+            -1.556_989_798_598_866e2,
+            6.680_131_188_771_972e1,
+            -1.328_068_155_288_572e1,
+        ];
+        const C: [FLOAT; 6] = [
+            -7.784_894_002_430_293e-3,
+            -3.223_964_580_411_365e-1,
+            -2.400_758_277_161_838,
+            -2.549_732_539_343_734,
+            4.374_664_141_464_968,
+            2.938_163_982_698_783,
+        ];
+        const D: [FLOAT; 4] = [
+            7.784_695_709_041_462e-3,
+            3.224_671_290_700_398e-1,
+            2.445_134_137_142_996,
+            3.754_408_661_907_416,
+        ];
+        if p <= 0.0 {
+            return FLOAT::NEG_INFINITY;
+        }
+        if p >= 1.0 {
+            return FLOAT::INFINITY;
+        }
+        let tail = |q: FLOAT| {
+            let q = (-2.0 * q.ln()).sqrt();
+            (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+                / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+        };
+        let x = if p < 0.02425 {
+            tail(p)
+        } else if p > 1.0 - 0.02425 {
+            -tail(1.0 - p)
+        } else {
+            let q = p - 0.5;
+            let r = q * q;
+            (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+                / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+        };
+        let e = normal_cdf(x) - p;
+        let u = e * (2.0 * std::f64::consts::PI).sqrt() * (x * x / 2.0).exp();
+        x - u / (1.0 + x * u / 2.0)
+    }
+}
+
+/// A probability distribution with its parameters, as used by `pdf`, `cdf`, `icdf`, `mean`,
+/// `variance` and `random`.
+/// ```
+/// use rhai_sci::Distribution;
+/// let normal = Distribution::new("normal", &[0.0, 1.0]).unwrap();
+/// assert!((normal.cdf(1.96) - 0.975).abs() < 1e-4);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution {
+    /// Normal distribution with mean `mu` and standard deviation `sigma`.
+    Normal {
+        /// Mean.
+        mu: rhai::FLOAT,
+        /// Standard deviation.
+        sigma: rhai::FLOAT,
+    },
+    /// Lognormal distribution, whose logarithm has mean `mu` and standard deviation `sigma`.
+    Lognormal {
+        /// Mean of the logarithm.
+        mu: rhai::FLOAT,
+        /// Standard deviation of the logarithm.
+        sigma: rhai::FLOAT,
+    },
+    /// Continuous uniform distribution on `[a, b]`.
+    Uniform {
+        /// Lower bound.
+        a: rhai::FLOAT,
+        /// Upper bound.
+        b: rhai::FLOAT,
+    },
+    /// Exponential distribution with mean `mu`.
+    Exponential {
+        /// Mean.
+        mu: rhai::FLOAT,
+    },
+    /// Gamma distribution with shape `a` and scale `b`.
+    Gamma {
+        /// Shape.
+        a: rhai::FLOAT,
+        /// Scale.
+        b: rhai::FLOAT,
+    },
+    /// Beta distribution with shapes `a` and `b`.
+    Beta {
+        /// First shape.
+        a: rhai::FLOAT,
+        /// Second shape.
+        b: rhai::FLOAT,
+    },
+    /// Student's t distribution with `nu` degrees of freedom.
+    StudentT {
+        /// Degrees of freedom.
+        nu: rhai::FLOAT,
+    },
+    /// Chi-squared distribution with `nu` degrees of freedom.
+    ChiSquared {
+        /// Degrees of freedom.
+        nu: rhai::FLOAT,
+    },
+    /// F distribution with `nu1` and `nu2` degrees of freedom.
+    F {
+        /// Degrees of freedom of the numerator.
+        nu1: rhai::FLOAT,
+        /// Degrees of freedom of the denominator.
+        nu2: rhai::FLOAT,
+    },
+    /// Weibull distribution with scale `a` and shape `b`.
+    Weibull {
+        /// Scale.
+        a: rhai::FLOAT,
+        /// Shape.
+        b: rhai::FLOAT,
+    },
+    /// Binomial distribution of the number of successes in `n` trials with probability `p`.
+    Binomial {
+        /// Number of trials.
+        n: rhai::FLOAT,
+        /// Probability of success.
+        p: rhai::FLOAT,
+    },
+    /// Poisson distribution with mean `lambda`.
+    Poisson {
+        /// Mean.
+        lambda: rhai::FLOAT,
+    },
+    /// Geometric distribution of the number of failures before the first success, with
+    /// probability of success `p`.
+    Geometric {
+        /// Probability of success.
+        p: rhai::FLOAT,
+    },
+}
+
+impl Distribution {
+    /// Construct a [`Distribution`] from its name and parameters, in the order used by MATLAB.
+    /// Names are case-insensitive, and MATLAB's abbreviations such as `"norm"`, `"wbl"` or
+    /// `"bino"` are accepted.
+    pub fn new(name: &str, params: &[rhai::FLOAT]) -> Result<Self, Box<rhai::EvalAltResult>> {
+        use Distribution::*;
+        let name = name.to_lowercase();
+        let distribution = match (name.as_str(), params) {
+            ("normal" | "norm", [mu, sigma]) => Normal {
+                mu: *mu,
+                sigma: *sigma,
+            },
+            ("lognormal" | "logn", [mu, sigma]) => Lognormal {
+                mu: *mu,
+                sigma: *sigma,
+            },
+            ("uniform" | "unif", [a, b]) => Uniform { a: *a, b: *b },
+            ("exponential" | "exp", [mu]) => Exponential { mu: *mu },
+            ("gamma" | "gam", [a, b]) => Gamma { a: *a, b: *b },
+            ("beta", [a, b]) => Beta { a: *a, b: *b },
+            ("t", [nu]) => StudentT { nu: *nu },
+            ("chi2", [nu]) => ChiSquared { nu: *nu },
+            ("f", [nu1, nu2]) => F {
+                nu1: *nu1,
+                nu2: *nu2,
+            },
+            ("weibull" | "wbl", [a, b]) => Weibull { a: *a, b: *b },
+            ("binomial" | "bino", [n, p]) => Binomial { n: *n, p: *p },
+            ("poisson" | "poiss", [lambda]) => Poisson { lambda: *lambda },
+            ("geometric" | "geo", [p]) => Geometric { p: *p },
+            (name, _) => {
+                let expected = match name {
+                    "exponential" | "exp" | "t" | "chi2" | "poisson" | "poiss" | "geometric"
+                    | "geo" => 1,
+                    "normal" | "norm" | "lognormal" | "logn" | "uniform" | "unif" | "gamma"
+                    | "gam" | "beta" | "f" | "weibull" | "wbl" | "binomial" | "bino" => 2,
+                    _ => {
+                        return Err(crate::arithmetic_error(format!(
+                            "Unknown distribution '{name}'"
+                        )))
+                    }
+                };
+                return Err(crate::arithmetic_error(format!(
+                    "The {name} distribution takes {expected} parameter{}, but {} were given",
+                    if expected == 1 { "" } else { "s" },
+                    params.len()
+                )));
+            }
+        };
+        distribution.check()?;
+        Ok(distribution)
+    }
+
+    /// Checks that the parameters are valid.
+    fn check(&self) -> Result<(), Box<rhai::EvalAltResult>> {
+        use Distribution::*;
+        let positive = |v: rhai::FLOAT| v > 0.0 && v.is_finite();
+        let probability = |p: rhai::FLOAT| (0.0..=1.0).contains(&p);
+        let (valid, requirement) = match *self {
+            Normal { mu, sigma } | Lognormal { mu, sigma } => {
+                (mu.is_finite() && positive(sigma), "sigma must be positive")
+            }
+            Uniform { a, b } => (
+                a.is_finite() && b.is_finite() && a < b,
+                "a must be less than b",
+            ),
+            Exponential { mu } => (positive(mu), "mu must be positive"),
+            Gamma { a, b } | Beta { a, b } | Weibull { a, b } => (
+                positive(a) && positive(b),
+                "both parameters must be positive",
+            ),
+            StudentT { nu } | ChiSquared { nu } => (positive(nu), "nu must be positive"),
+            F { nu1, nu2 } => (
+                positive(nu1) && positive(nu2),
+                "nu1 and nu2 must be positive",
+            ),
+            Binomial { n, p } => (
+                n >= 0.0 && n.fract() == 0.0 && n.is_finite() && probability(p),
+                "n must be a non-negative integer and p a probability",
+            ),
+            Poisson { lambda } => (positive(lambda), "lambda must be positive"),
+            Geometric { p } => (p > 0.0 && p <= 1.0, "p must be in (0, 1]"),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(crate::arithmetic_error(format!(
+                "Invalid parameters for the {} distribution: {requirement}",
+                self.name()
+            )))
+        }
+    }
+
+    /// Returns the name of the distribution.
+    fn name(&self) -> &'static str {
+        use Distribution::*;
+        match self {
+            Normal { .. } => "normal",
+            Lognormal { .. } => "lognormal",
+            Uniform { .. } => "uniform",
+            Exponential { .. } => "exponential",
+            Gamma { .. } => "gamma",
+            Beta { .. } => "beta",
+            StudentT { .. } => "t",
+            ChiSquared { .. } => "chi2",
+            F { .. } => "F",
+            Weibull { .. } => "Weibull",
+            Binomial { .. } => "binomial",
+            Poisson { .. } => "Poisson",
+            Geometric { .. } => "geometric",
+        }
+    }
+
+    /// Returns whether the distribution is discrete, with support on the non-negative integers.
+    pub fn is_discrete(&self) -> bool {
+        matches!(
+            self,
+            Distribution::Binomial { .. }
+                | Distribution::Poisson { .. }
+                | Distribution::Geometric { .. }
+        )
+    }
+
+    /// Returns the probability density at `x`, or the probability mass for discrete
+    /// distributions.
+    pub fn pdf(&self, x: rhai::FLOAT) -> rhai::FLOAT {
+        use special::{ln_beta, ln_gamma};
+        use Distribution::*;
+        if x.is_nan() {
+            return x;
+        }
+        if self.is_discrete() && (x < 0.0 || x.fract() != 0.0) {
+            return 0.0;
+        }
+        match *self {
+            Normal { mu, sigma } => {
+                let z = (x - mu) / sigma;
+                (-0.5 * z * z).exp() / (sigma * (2.0 * std::f64::consts::PI).sqrt())
+            }
+            Lognormal { mu, sigma } => {
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    Normal { mu, sigma }.pdf(x.ln()) / x
+                }
+            }
+            Uniform { a, b } => {
+                if (a..=b).contains(&x) {
+                    1.0 / (b - a)
+                } else {
+                    0.0
+                }
+            }
+            Exponential { mu } => {
+                if x < 0.0 {
+                    0.0
+                } else {
+                    (-x / mu).exp() / mu
+                }
+            }
+            Gamma { a, b } => {
+                if x < 0.0 {
+                    0.0
+                } else if x == 0.0 {
+                    match a.partial_cmp(&1.0) {
+                        Some(std::cmp::Ordering::Less) => rhai::FLOAT::INFINITY,
+                        Some(std::cmp::Ordering::Equal) => 1.0 / b,
+                        _ => 0.0,
+                    }
+                } else {
+                    ((a - 1.0) * x.ln() - x / b - ln_gamma(a) - a * b.ln()).exp()
+                }
+            }
+            Beta { a, b } => {
+                if !(0.0..=1.0).contains(&x) {
+                    0.0
+                } else if (x == 0.0 && a < 1.0) || (x == 1.0 && b < 1.0) {
+                    rhai::FLOAT::INFINITY
+                } else if (x == 0.0 && a == 1.0) || (x == 1.0 && b == 1.0) {
+                    (-ln_beta(a, b)).exp()
+                } else if x == 0.0 || x == 1.0 {
+                    0.0
+                } else {
+                    ((a - 1.0) * x.ln() + (b - 1.0) * (1.0 - x).ln() - ln_beta(a, b)).exp()
+                }
+            }
+            StudentT { nu } => (-(nu + 1.0) / 2.0 * (1.0 + x * x / nu).ln()
+                - 0.5 * nu.ln()
+                - ln_beta(0.5, nu / 2.0))
+            .exp(),
+            ChiSquared { nu } => Gamma {
+                a: nu / 2.0,
+                b: 2.0,
+            }
+            .pdf(x),
+            F { nu1, nu2 } => {
+                if x < 0.0 {
+                    0.0
+                } else if x == 0.0 {
+                    Beta {
+                        a: nu1 / 2.0,
+                        b: nu2 / 2.0,
+                    }
+                    .pdf(0.0)
+                        * nu1
+                        / nu2
+                } else {
+                    let y = nu1 * x / (nu1 * x + nu2);
+                    Beta {
+                        a: nu1 / 2.0,
+                        b: nu2 / 2.0,
+                    }
+                    .pdf(y)
+                        * nu1
+                        * nu2
+                        / (nu1 * x + nu2).powi(2)
+                }
+            }
+            Weibull { a, b } => {
+                if x < 0.0 {
+                    0.0
+                } else {
+                    b / a * (x / a).powf(b - 1.0) * (-(x / a).powf(b)).exp()
+                }
+            }
+            Binomial { n, p } => {
+                if x > n {
+                    0.0
+                } else if p == 0.0 || p == 1.0 {
+                    if x == n * p {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                } else {
+                    (ln_gamma(n + 1.0) - ln_gamma(x + 1.0) - ln_gamma(n - x + 1.0)
+                        + x * p.ln()
+                        + (n - x) * (1.0 - p).ln())
+                    .exp()
+                }
+            }
+            Poisson { lambda } => (x * lambda.ln() - lambda - ln_gamma(x + 1.0)).exp(),
+            Geometric { p } => p * (1.0 - p).powf(x),
+        }
+    }
+
+    /// Returns the cumulative distribution function at `x`.
+    pub fn cdf(&self, x: rhai::FLOAT) -> rhai::FLOAT {
+        use special::{beta_inc, gamma_p, gamma_q, normal_cdf};
+        use Distribution::*;
+        if x.is_nan() {
+            return x;
+        }
+        if self.is_discrete() && x < 0.0 {
+            return 0.0;
+        }
+        match *self {
+            Normal { mu, sigma } => normal_cdf((x - mu) / sigma),
+            Lognormal { mu, sigma } => {
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    normal_cdf((x.ln() - mu) / sigma)
+                }
+            }
+            Uniform { a, b } => ((x - a) / (b - a)).clamp(0.0, 1.0),
+            Exponential { mu } => {
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    -(-x / mu).exp_m1()
+                }
+            }
+            Gamma { a, b } => gamma_p(a, x / b),
+            Beta { a, b } => beta_inc(a, b, x),
+            StudentT { nu } => {
+                let tail = 0.5 * beta_inc(nu / 2.0, 0.5, nu / (nu + x * x));
+                if x > 0.0 {
+                    1.0 - tail
+                } else {
+                    tail
+                }
+            }
+            ChiSquared { nu } => gamma_p(nu / 2.0, x / 2.0),
+            F { nu1, nu2 } => {
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    beta_inc(nu1 / 2.0, nu2 / 2.0, nu1 * x / (nu1 * x + nu2))
+                }
+            }
+            Weibull { a, b } => {
+                if x <= 0.0 {
+                    0.0
+                } else {
+                    -(-(x / a).powf(b)).exp_m1()
+                }
+            }
+            Binomial { n, p } => {
+                let k = x.floor();
+                if k >= n {
+                    1.0
+                } else {
+                    beta_inc(n - k, k + 1.0, 1.0 - p)
+                }
+            }
+            Poisson { lambda } => gamma_q(x.floor() + 1.0, lambda),
+            Geometric { p } => -((x.floor() + 1.0) * (-p).ln_1p()).exp_m1(),
+        }
+    }
+
+    /// Returns the quantile function (the inverse of the cumulative distribution function) at
+    /// probability `p`, which must be between 0 and 1. For discrete distributions this is the
+    /// smallest value whose cumulative probability is at least `p`.
+    pub fn icdf(&self, p: rhai::FLOAT) -> rhai::FLOAT {
+        use special::normal_quantile;
+        use Distribution::*;
+        match *self {
+            Normal { mu, sigma } => mu + sigma * normal_quantile(p),
+            Lognormal { mu, sigma } => (mu + sigma * normal_quantile(p)).exp(),
+            Uniform { a, b } => a + p * (b - a),
+            Exponential { mu } => -mu * (-p).ln_1p(),
+            Weibull { a, b } => a * (-(-p).ln_1p()).powf(1.0 / b),
+            Geometric { p: q } => {
+                if p >= 1.0 {
+                    rhai::FLOAT::INFINITY
+                } else if q == 1.0 {
+                    0.0
+                } else {
+                    self.discrete_search(p, ((-p).ln_1p() / (-q).ln_1p() - 1.0).ceil())
+                }
+            }
+            Binomial { n, .. } | Poisson { lambda: n } => {
+                if p >= 1.0 {
+                    return if let Binomial { .. } = self {
+                        n
+                    } else {
+                        rhai::FLOAT::INFINITY
+                    };
+                }
+                let guess = self.mean() + self.variance().sqrt() * normal_quantile(p);
+                self.discrete_search(p, guess.floor())
+            }
+            Beta { .. } => {
+                if p <= 0.0 || p >= 1.0 {
+                    p.clamp(0.0, 1.0)
+                } else {
+                    self.invert(p, 0.0, 1.0)
+                }
+            }
+            StudentT { .. } => {
+                if p <= 0.0 || p >= 1.0 {
+                    return if p <= 0.0 {
+                        rhai::FLOAT::NEG_INFINITY
+                    } else {
+                        rhai::FLOAT::INFINITY
+                    };
+                }
+                let (mut lo, mut hi) = (-1.0, 1.0);
+                while self.cdf(lo) > p {
+                    (lo, hi) = (2.0 * lo, lo);
+                }
+                while self.cdf(hi) < p {
+                    (lo, hi) = (hi, 2.0 * hi);
+                }
+                self.invert(p, lo, hi)
+            }
+            Gamma { .. } | ChiSquared { .. } | F { .. } => {
+                if p <= 0.0 || p >= 1.0 {
+                    return if p <= 0.0 { 0.0 } else { rhai::FLOAT::INFINITY };
+                }
+                let (mut lo, mut hi) = (0.0, 1.0);
+                while self.cdf(hi) < p {
+                    (lo, hi) = (hi, 2.0 * hi);
+                }
+                self.invert(p, lo, hi)
+            }
+        }
+    }
+
+    /// Returns the smallest non-negative integer whose cumulative probability is at least `p`,
+    /// bracketing it with steps that double away from `guess` and then bisecting the bracket.
+    fn discrete_search(&self, p: rhai::FLOAT, guess: rhai::FLOAT) -> rhai::FLOAT {
+        // Allow for rounding in the cumulative probabilities
+        let target = p * (1.0 - 64.0 * rhai::FLOAT::EPSILON);
+        // The answer lies in (lo, hi], where lo = -1 stands for no integer at all
+        let mut hi = guess.max(0.0);
+        let mut lo = -1.0;
+        let mut step = 1.0;
+        while self.cdf(hi) < target {
+            if !hi.is_finite() {
+                return hi;
+            }
+            lo = hi;
+            hi += step;
+            step *= 2.0;
+        }
+        if lo < 0.0 {
+            step = 1.0;
+            lo = hi - step;
+            while lo >= 0.0 && self.cdf(lo) >= target {
+                hi = lo;
+                step *= 2.0;
+                lo = hi - step;
+            }
+            lo = lo.max(-1.0);
+        }
+        while hi - lo > 1.0 {
+            let mid = ((lo + hi) / 2.0).floor();
+            // Beyond 2^53 not every integer is a float, so the bracket may not narrow further
+            if mid <= lo || mid >= hi {
+                break;
+            }
+            if self.cdf(mid) >= target {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        hi
+    }
+
+    /// Solves `cdf(x) = p` for `x` in the bracket `[lo, hi]` by Newton's method, falling back
+    /// to bisection whenever a step would leave the bracket.
+    fn invert(&self, p: rhai::FLOAT, mut lo: rhai::FLOAT, mut hi: rhai::FLOAT) -> rhai::FLOAT {
+        let mut x = 0.5 * (lo + hi);
+        for _ in 0..200 {
+            let residual = self.cdf(x) - p;
+            if residual == 0.0 {
+                return x;
+            }
+            if residual < 0.0 {
+                lo = x;
+            } else {
+                hi = x;
+            }
+            let step = residual / self.pdf(x);
+            let newton = x - step;
+            let next = if step.is_finite() && newton > lo && newton < hi {
+                newton
+            } else {
+                0.5 * (lo + hi)
+            };
+            if (next - x).abs() <= 4.0 * rhai::FLOAT::EPSILON * x.abs()
+                || hi - lo <= rhai::FLOAT::MIN_POSITIVE
+            {
+                return next;
+            }
+            x = next;
+        }
+        x
+    }
+
+    /// Returns the mean of the distribution, which is infinite or NaN if it does not exist.
+    pub fn mean(&self) -> rhai::FLOAT {
+        use special::ln_gamma;
+        use Distribution::*;
+        match *self {
+            Normal { mu, .. } => mu,
+            Lognormal { mu, sigma } => (mu + sigma * sigma / 2.0).exp(),
+            Uniform { a, b } => (a + b) / 2.0,
+            Exponential { mu } => mu,
+            Gamma { a, b } => a * b,
+            Beta { a, b } => a / (a + b),
+            StudentT { nu } => {
+                if nu > 1.0 {
+                    0.0
+                } else {
+                    rhai::FLOAT::NAN
+                }
+            }
+            ChiSquared { nu } => nu,
+            F { nu2, .. } => {
+                if nu2 > 2.0 {
+                    nu2 / (nu2 - 2.0)
+                } else {
+                    rhai::FLOAT::INFINITY
+                }
+            }
+            Weibull { a, b } => a * ln_gamma(1.0 + 1.0 / b).exp(),
+            Binomial { n, p } => n * p,
+            Poisson { lambda } => lambda,
+            Geometric { p } => (1.0 - p) / p,
+        }
+    }
+
+    /// Returns the variance of the distribution, which is infinite or NaN if it does not
+    /// exist.
+    pub fn variance(&self) -> rhai::FLOAT {
+        use special::ln_gamma;
+        use Distribution::*;
+        match *self {
+            Normal { sigma, .. } => sigma * sigma,
+            Lognormal { mu, sigma } => (sigma * sigma).exp_m1() * (2.0 * mu + sigma * sigma).exp(),
+            Uniform { a, b } => (b - a).powi(2) / 12.0,
+            Exponential { mu } => mu * mu,
+            Gamma { a, b } => a * b * b,
+            Beta { a, b } => a * b / ((a + b).powi(2) * (a + b + 1.0)),
+            StudentT { nu } => {
+                if nu > 2.0 {
+                    nu / (nu - 2.0)
+                } else if nu > 1.0 {
+                    rhai::FLOAT::INFINITY
+                } else {
+                    rhai::FLOAT::NAN
+                }
+            }
+            ChiSquared { nu } => 2.0 * nu,
+            F { nu1, nu2 } => {
+                if nu2 > 4.0 {
+                    2.0 * nu2 * nu2 * (nu1 + nu2 - 2.0) / (nu1 * (nu2 - 2.0).powi(2) * (nu2 - 4.0))
+                } else if nu2 > 2.0 {
+                    rhai::FLOAT::INFINITY
+                } else {
+                    rhai::FLOAT::NAN
+                }
+            }
+            Weibull { a, b } => {
+                let m = ln_gamma(1.0 + 1.0 / b).exp();
+                a * a * (ln_gamma(1.0 + 2.0 / b).exp() - m * m)
+            }
+            Binomial { n, p } => n * p * (1.0 - p),
+            Poisson { lambda } => lambda,
+            Geometric { p } => (1.0 - p) / (p * p),
+        }
+    }
+
+    /// Draws a random number from the distribution.
+    #[cfg(feature = "rand")]
+    pub fn sample<R: randlib::Rng + ?Sized>(&self, rng: &mut R) -> rhai::FLOAT {
+        use Distribution::*;
+        match *self {
+            Gamma { a, b } => b * standard_gamma(a, rng),
+            ChiSquared { nu } => 2.0 * standard_gamma(nu / 2.0, rng),
+            Beta { a, b } => {
+                let x = standard_gamma(a, rng);
+                x / (x + standard_gamma(b, rng))
+            }
+            StudentT { nu } => {
+                standard_normal(rng) / (2.0 * standard_gamma(nu / 2.0, rng) / nu).sqrt()
+            }
+            F { nu1, nu2 } => {
+                (standard_gamma(nu1 / 2.0, rng) / nu1) / (standard_gamma(nu2 / 2.0, rng) / nu2)
+            }
+            _ => self.icdf(open_unit(rng)),
+        }
+    }
+}
+
+/// Returns a uniform random number in `(0, 1)`.
+#[cfg(feature = "rand")]
+fn open_unit<R: randlib::Rng + ?Sized>(rng: &mut R) -> rhai::FLOAT {
+    loop {
+        let u = rng.random::<rhai::FLOAT>();
+        if u > 0.0 {
+            return u;
+        }
+    }
+}
+
+/// Returns a standard normal random number.
+#[cfg(feature = "rand")]
+fn standard_normal<R: randlib::Rng + ?Sized>(rng: &mut R) -> rhai::FLOAT {
+    special::normal_quantile(open_unit(rng))
+}
+
+/// Returns a gamma random number with shape `a` and unit scale, by the method of Marsaglia and
+/// Tsang.
+#[cfg(feature = "rand")]
+fn standard_gamma<R: randlib::Rng + ?Sized>(a: rhai::FLOAT, rng: &mut R) -> rhai::FLOAT {
+    if a < 1.0 {
+        return standard_gamma(a + 1.0, rng) * open_unit(rng).powf(1.0 / a);
+    }
+    let d = a - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let z = standard_normal(rng);
+        let v = (1.0 + c * z).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = open_unit(rng);
+        if u.ln() < 0.5 * z * z + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Applies `f` to a number, or to every element of an array or matrix.
+fn elementwise(
+    x: rhai::Dynamic,
+    f: &impl Fn(rhai::FLOAT) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>>,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    if x.is_array() {
+        return x
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|el| elementwise(el, f))
+            .collect::<Result<rhai::Array, _>>()
+            .map(rhai::Dynamic::from_array);
+    }
+    let x = crate::if_int_convert_to_float_and_do(x, Ok)
+        .map_err(|_| crate::arithmetic_error("Expected a number or an array of numbers"))?;
+    f(x).map(rhai::Dynamic::from_float)
+}
+
+#[export_module]
+pub mod distribution_functions {
+    use super::elementwise;
+    use crate::{arithmetic_error, Distribution};
+    #[cfg(feature = "rand")]
+    use rhai::{Array, INT};
+    use rhai::{Dynamic, EvalAltResult, ImmutableString, FLOAT};
+
+    /// Reads a distribution from its name and parameters.
+    fn distribution(
+        name: ImmutableString,
+        params: Dynamic,
+    ) -> Result<Distribution, Box<EvalAltResult>> {
+        Distribution::new(name.as_str(), &crate::dynamic_to_vec_float(params)?)
+    }
+
+    /// Returns the probability density function of the distribution `name` with parameters
+    /// `params` at `x`, which may be a number or an array. For discrete distributions this is
+    /// the probability mass function. The distributions and their parameters, in order, are:
+    ///
+    /// | Name | Parameters |
+    /// |------|------------|
+    /// | `"normal"` | mean `mu`, standard deviation `sigma` |
+    /// | `"lognormal"` | `mu` and `sigma` of the logarithm |
+    /// | `"uniform"` | bounds `a`, `b` |
+    /// | `"exponential"` | mean `mu` |
+    /// | `"gamma"` | shape `a`, scale `b` |
+    /// | `"beta"` | shapes `a`, `b` |
+    /// | `"t"` | degrees of freedom `nu` |
+    /// | `"chi2"` | degrees of freedom `nu` |
+    /// | `"f"` | degrees of freedom `nu1`, `nu2` |
+    /// | `"weibull"` | scale `a`, shape `b` |
+    /// | `"binomial"` | trials `n`, probability `p` |
+    /// | `"poisson"` | mean `lambda` |
+    /// | `"geometric"` | probability `p`, counting failures before the first success |
+    /// ```typescript
+    /// assert_approx_eq(pdf("normal", 0.0, [0, 1]), 1.0 / sqrt(2.0 * pi), 1e-15);
+    /// ```
+    /// ```typescript
+    /// let p = pdf("binomial", [0, 1, 2, 3], [3, 0.5]);
+    /// assert_approx_eq(p, [0.125, 0.375, 0.375, 0.125], 1e-15);
+    /// ```
+    /// ```typescript
+    /// assert_approx_eq(pdf("gamma", 2.0, [2, 3]), 2.0 / 9.0 * exp(-2.0 / 3.0), 1e-15);
+    /// ```
+    #[rhai_fn(name = "pdf", return_raw)]
+    pub fn pdf(
+        name: ImmutableString,
+        x: Dynamic,
+        params: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let d = distribution(name, params)?;
+        elementwise(x, &|x| Ok(d.pdf(x)))
+    }
+
+    /// Returns the cumulative distribution function of the distribution `name` with parameters
+    /// `params` at `x`, which may be a number or an array. See `pdf` for the distributions.
+    /// ```typescript
+    /// assert_approx_eq(cdf("normal", 1.96, [0, 1]), 0.9750021048517795, 1e-15);
+    /// ```
+    /// ```typescript
+    /// assert_approx_eq(cdf("t", 2.0, 5), 0.9490302605850709, 1e-14);
+    /// ```
+    /// ```typescript
+    /// assert_approx_eq(cdf("poisson", [0, 1, 2], 2), [exp(-2.0), 3.0 * exp(-2.0), 5.0 * exp(-2.0)], 1e-15);
+    /// ```
+    #[rhai_fn(name = "cdf", return_raw)]
+    pub fn cdf(
+        name: ImmutableString,
+        x: Dynamic,
+        params: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let d = distribution(name, params)?;
+        elementwise(x, &|x| Ok(d.cdf(x)))
+    }
+
+    /// Returns the inverse cumulative distribution function (quantile function) of the
+    /// distribution `name` with parameters `params` at probability `p`, which may be a number or
+    /// an array of values between 0 and 1. For discrete distributions this is the smallest value
+    /// whose cumulative probability is at least `p`. See `pdf` for the distributions.
+    /// ```typescript
+    /// assert_approx_eq(icdf("normal", 0.975, [0, 1]), 1.959963984540054, 1e-14);
+    /// ```
+    /// ```typescript
+    /// assert_approx_eq(icdf("chi2", 0.95, 3), 7.814727903251178, 1e-12);
+    /// ```
+    /// ```typescript
+    /// assert_eq(icdf("binomial", [0.1, 0.5, 0.9], [10, 0.3]), [1.0, 3.0, 5.0]);
+    /// ```
+    #[rhai_fn(name = "icdf", return_raw)]
+    pub fn icdf(
+        name: ImmutableString,
+        p: Dynamic,
+        params: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let d = distribution(name, params)?;
+        elementwise(p, &|p| {
+            if (0.0..=1.0).contains(&p) {
+                Ok(d.icdf(p))
+            } else {
+                Err(arithmetic_error(format!(
+                    "Probabilities must be between 0 and 1, not {p}"
+                )))
+            }
+        })
+    }
+
+    /// Returns the mean of the distribution `name` with parameters `params`. See `pdf` for the
+    /// distributions.
+    /// ```typescript
+    /// assert_eq(mean("gamma", [2, 3]), 6.0);
+    /// ```
+    /// ```typescript
+    /// assert_approx_eq(mean("weibull", [2, 1]), 2.0, 1e-14);
+    /// ```
+    #[rhai_fn(name = "mean", return_raw)]
+    pub fn distribution_mean(
+        name: ImmutableString,
+        params: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        Ok(distribution(name, params)?.mean())
+    }
+
+    /// Returns the variance of the distribution `name` with parameters `params`. See `pdf` for
+    /// the distributions.
+    /// ```typescript
+    /// assert_eq(variance("binomial", [10, 0.5]), 2.5);
+    /// ```
+    /// ```typescript
+    /// assert_eq(variance("t", 4), 2.0);
+    /// ```
+    #[rhai_fn(name = "variance", return_raw)]
+    pub fn distribution_variance(
+        name: ImmutableString,
+        params: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        Ok(distribution(name, params)?.variance())
+    }
+
+    /// Returns a random number drawn from the distribution `name` with parameters `params`. See
+    /// `pdf` for the distributions.
+    /// ```typescript
+    /// let x = random("uniform", [2, 3]);
+    /// assert(x >= 2.0 && x <= 3.0);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "random", return_raw)]
+    pub fn random(name: ImmutableString, params: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        let d = distribution(name, params)?;
+        Ok(crate::with_rng(|rng| d.sample(rng)))
+    }
+
+    /// Returns an array of `n` random numbers drawn from the distribution `name` with
+    /// parameters `params`. See `pdf` for the distributions.
+    /// ```typescript
+    /// let x = random("poisson", 4, 20000);
+    /// assert_eq(x.len(), 20000);
+    /// assert_approx_eq(mean(x), 4.0, 0.1);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "random", return_raw)]
+    pub fn random_array(
+        name: ImmutableString,
+        params: Dynamic,
+        n: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let d = distribution(name, params)?;
+        Ok(crate::with_rng(|rng| {
+            (0..n.max(0))
+                .map(|_| Dynamic::from_float(d.sample(rng)))
+                .collect()
+        }))
+    }
+}
@@ -0,0 +1,903 @@
+use rhai::plugin::*;
+
+/// A dual number for forward-mode automatic differentiation: a value together with its
+/// gradient with respect to a set of seeded variables. Gradients of different lengths are
+/// combined as if the shorter one were padded with zeros, so constants can have an empty
+/// gradient.
+/// ```
+/// use rhai_sci::Dual;
+/// let x = Dual::variable(2.0, 0, 1);
+/// assert_eq!(x.gradient, vec![1.0]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dual {
+    /// The value of the number.
+    pub value: rhai::FLOAT,
+    /// The partial derivatives of the value with respect to each variable.
+    pub gradient: Vec<rhai::FLOAT>,
+}
+
+impl Dual {
+    /// Construct a [`Dual`] for a constant, whose gradient is empty.
+    pub fn constant(value: rhai::FLOAT) -> Self {
+        Self {
+            value,
+            gradient: vec![],
+        }
+    }
+
+    /// Construct a [`Dual`] for variable `index` of `count`, whose gradient is the unit vector
+    /// in that direction.
+    pub fn variable(value: rhai::FLOAT, index: usize, count: usize) -> Self {
+        let mut gradient = vec![0.0; count];
+        gradient[index] = 1.0;
+        Self { value, gradient }
+    }
+
+    /// Applies a function with the given value and derivative at `self.value`, by the chain
+    /// rule.
+    fn chain(&self, value: rhai::FLOAT, slope: rhai::FLOAT) -> Self {
+        Self {
+            value,
+            gradient: self.gradient.iter().map(|g| slope * g).collect(),
+        }
+    }
+
+    /// Combines two numbers into one with the given value and partial derivatives with
+    /// respect to each of them.
+    fn combine(
+        &self,
+        other: &Self,
+        value: rhai::FLOAT,
+        slopes: (rhai::FLOAT, rhai::FLOAT),
+    ) -> Self {
+        let n = self.gradient.len().max(other.gradient.len());
+        let component = |g: &[rhai::FLOAT], i: usize| g.get(i).copied().unwrap_or(0.0);
+        Self {
+            value,
+            gradient: (0..n)
+                .map(|i| {
+                    slopes.0 * component(&self.gradient, i)
+                        + slopes.1 * component(&other.gradient, i)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[export_module]
+pub mod dual_functions {
+    use crate::{arithmetic_error, if_int_convert_to_float_and_do, trig_functions as trig};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, NativeCallContext, FLOAT};
+
+    /// A dual number: a value and its gradient.
+    pub type Dual = crate::Dual;
+
+    /// Degrees to radians.
+    const DEG: FLOAT = std::f64::consts::PI / 180.0;
+
+    /// Reads a number or a dual number as a dual number.
+    fn to_dual(x: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        if x.is::<Dual>() {
+            return Ok(x.cast::<Dual>());
+        }
+        if_int_convert_to_float_and_do(x, |v| Ok(Dual::constant(v)))
+            .map_err(|_| arithmetic_error("Dual numbers can only be combined with numbers"))
+    }
+
+    /// Creates a dual number for a constant, with an empty gradient.
+    /// ```typescript
+    /// let c = dual(2.5);
+    /// assert_eq(c.value, 2.5);
+    /// assert_eq(c.gradient, []);
+    /// ```
+    #[rhai_fn(name = "dual", return_raw)]
+    pub fn dual_constant(value: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        to_dual(value)
+    }
+
+    /// Creates a dual number with the given value and gradient.
+    /// ```typescript
+    /// let x = dual(3.0, [1.0, 0.0]);
+    /// let y = dual(2.0, [0.0, 1.0]);
+    /// let z = x * y + x;
+    /// assert_eq(z.value, 9.0);
+    /// assert_eq(z.gradient, [3.0, 3.0]);
+    /// ```
+    /// ```typescript
+    /// // Powers with dual or constant bases and exponents
+    /// let x = dual(2.0, [1.0]);
+    /// assert_approx_eq((x ** x).gradient, [4.0 * (ln(2.0) + 1.0)], 1e-14);
+    /// assert_eq((x ** 3).gradient, [12.0]);
+    /// assert_approx_eq((2.0 ** x).gradient, [4.0 * ln(2.0)], 1e-14);
+    /// ```
+    /// ```typescript
+    /// // Remainders with respect to the dividend and the divisor
+    /// let z = dual(7.5, [1.0, 0.0]) % dual(2.0, [0.0, 1.0]);
+    /// assert_eq(z.value, 1.5);
+    /// assert_eq(z.gradient, [1.0, -3.0]);
+    /// ```
+    #[rhai_fn(name = "dual", return_raw)]
+    pub fn dual(value: Dynamic, gradient: Array) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(Dual {
+            value: to_dual(value)?.value,
+            gradient: crate::dynamic_to_vec_float(Dynamic::from_array(gradient))?,
+        })
+    }
+
+    /// Returns the value of a dual number.
+    #[rhai_fn(get = "value", pure)]
+    pub fn get_value(x: &mut Dual) -> FLOAT {
+        x.value
+    }
+
+    /// Returns the gradient of a dual number.
+    #[rhai_fn(get = "gradient", pure)]
+    pub fn get_gradient(x: &mut Dual) -> Array {
+        x.gradient.iter().map(|g| Dynamic::from_float(*g)).collect()
+    }
+
+    /// Returns a string describing a dual number.
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(x: &mut Dual) -> String {
+        format!("{x:?}")
+    }
+
+    /// Returns the gradient of `f` at `x`, computed exactly by forward-mode automatic
+    /// differentiation: `f` is called once with dual numbers seeded with the unit vectors, so
+    /// it may use arithmetic, comparisons, `max`, `min`, `sum` and the math and trigonometry
+    /// functions. Rounding functions such as `floor` have a zero derivative. If `x` is a number,
+    /// `f` is called with a single dual number and the derivative is returned.
+    /// ```typescript
+    /// let f = |x| x[0] ** 2 * sin(x[1]) + exp(x[0] / x[1]);
+    /// let g = grad(f, [1.0, 2.0]);
+    /// assert_approx_eq(g, [2.0 * sin(2.0) + 0.5 * exp(0.5), cos(2.0) - 0.25 * exp(0.5)], 1e-14);
+    /// ```
+    /// ```typescript
+    /// let d = grad(|x| if x > 0.0 { sqrt(x) * sind(x) } else { -x }, 30.0);
+    /// assert_approx_eq(d, 0.5 / sqrt(30.0) * sind(30.0) + sqrt(30.0) * pi / 180.0 * cosd(30.0), 1e-14);
+    /// ```
+    #[rhai_fn(name = "grad", return_raw)]
+    pub fn grad(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let scalar = x.is_int() || x.is_float();
+        let point = crate::dynamic_to_vec_float(x)?;
+        let n = point.len();
+        let argument = if scalar {
+            Dynamic::from(Dual::variable(point[0], 0, 1))
+        } else {
+            Dynamic::from_array(
+                point
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| Dynamic::from(Dual::variable(*v, i, n)))
+                    .collect(),
+            )
+        };
+        let result = f.call_raw(&ctx, None, [argument])?;
+        let mut gradient = if result.is::<Dual>() {
+            result.cast::<Dual>().gradient
+        } else if result.is_int() || result.is_float() {
+            vec![]
+        } else {
+            return Err(arithmetic_error("The function must return a number"));
+        };
+        gradient.resize(n, 0.0);
+        Ok(if scalar {
+            Dynamic::from_float(gradient[0])
+        } else {
+            Dynamic::from_array(gradient.into_iter().map(Dynamic::from_float).collect())
+        })
+    }
+
+    /// Adds two dual numbers.
+    #[rhai_fn(name = "+")]
+    pub fn add(x: Dual, y: Dual) -> Dual {
+        x.combine(&y, x.value + y.value, (1.0, 1.0))
+    }
+
+    /// Adds a dual number and a number.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(add(x, to_dual(y)?))
+    }
+
+    /// Adds a number and a dual number.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn number_add(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(add(to_dual(x)?, y))
+    }
+
+    /// Subtracts two dual numbers.
+    #[rhai_fn(name = "-")]
+    pub fn subtract(x: Dual, y: Dual) -> Dual {
+        x.combine(&y, x.value - y.value, (1.0, -1.0))
+    }
+
+    /// Subtracts a number from a dual number.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(subtract(x, to_dual(y)?))
+    }
+
+    /// Subtracts a dual number from a number.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn number_subtract(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(subtract(to_dual(x)?, y))
+    }
+
+    /// Negates a dual number.
+    #[rhai_fn(name = "-")]
+    pub fn negate(x: Dual) -> Dual {
+        x.chain(-x.value, -1.0)
+    }
+
+    /// Multiplies two dual numbers.
+    #[rhai_fn(name = "*")]
+    pub fn multiply(x: Dual, y: Dual) -> Dual {
+        x.combine(&y, x.value * y.value, (y.value, x.value))
+    }
+
+    /// Multiplies a dual number by a number.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(multiply(x, to_dual(y)?))
+    }
+
+    /// Multiplies a number by a dual number.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn number_multiply(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(multiply(to_dual(x)?, y))
+    }
+
+    /// Divides two dual numbers.
+    #[rhai_fn(name = "/")]
+    pub fn divide(x: Dual, y: Dual) -> Dual {
+        let value = x.value / y.value;
+        x.combine(&y, value, (1.0 / y.value, -value / y.value))
+    }
+
+    /// Divides a dual number by a number.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(divide(x, to_dual(y)?))
+    }
+
+    /// Divides a number by a dual number.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn number_divide(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(divide(to_dual(x)?, y))
+    }
+
+    /// Raises a dual number to a dual power.
+    #[rhai_fn(name = "**")]
+    pub fn power(x: Dual, y: Dual) -> Dual {
+        let value = x.value.powf(y.value);
+        let exponent_slope = if y.gradient.iter().all(|g| *g == 0.0) {
+            0.0
+        } else {
+            value * x.value.ln()
+        };
+        let base_slope = if y.value == 0.0 {
+            0.0
+        } else {
+            y.value * x.value.powf(y.value - 1.0)
+        };
+        x.combine(&y, value, (base_slope, exponent_slope))
+    }
+
+    /// Raises a dual number to a power.
+    #[rhai_fn(name = "**", return_raw)]
+    pub fn power_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(power(x, to_dual(y)?))
+    }
+
+    /// Raises a number to a dual power.
+    #[rhai_fn(name = "**", return_raw)]
+    pub fn number_power(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(power(to_dual(x)?, y))
+    }
+
+    /// Returns the remainder of dividing two dual numbers, with the sign of the dividend.
+    #[rhai_fn(name = "%")]
+    pub fn remainder(x: Dual, y: Dual) -> Dual {
+        let quotient = (x.value / y.value).trunc();
+        x.combine(&y, x.value % y.value, (1.0, -quotient))
+    }
+
+    /// Returns the remainder of dividing a dual number by a number.
+    #[rhai_fn(name = "%", return_raw)]
+    pub fn remainder_number(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(remainder(x, to_dual(y)?))
+    }
+
+    /// Returns the remainder of dividing a number by a dual number.
+    #[rhai_fn(name = "%", return_raw)]
+    pub fn number_remainder(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(remainder(to_dual(x)?, y))
+    }
+
+    /// Compares the values of two numbers, either of which may be dual.
+    fn compare(x: Dynamic, y: Dynamic) -> Result<std::cmp::Ordering, Box<EvalAltResult>> {
+        let (x, y) = (to_dual(x)?.value, to_dual(y)?.value);
+        x.partial_cmp(&y)
+            .ok_or_else(|| arithmetic_error("Cannot compare NaN values"))
+    }
+
+    /// Returns whether the value of a dual number is less than another value.
+    #[rhai_fn(name = "<", return_raw)]
+    pub fn less(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_lt())
+    }
+
+    /// Returns whether a value is less than the value of a dual number.
+    #[rhai_fn(name = "<", return_raw)]
+    pub fn number_less(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_lt())
+    }
+
+    /// Returns whether the value of a dual number is at most another value.
+    #[rhai_fn(name = "<=", return_raw)]
+    pub fn less_equal(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_le())
+    }
+
+    /// Returns whether a value is at most the value of a dual number.
+    #[rhai_fn(name = "<=", return_raw)]
+    pub fn number_less_equal(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_le())
+    }
+
+    /// Returns whether the value of a dual number is greater than another value.
+    #[rhai_fn(name = ">", return_raw)]
+    pub fn greater(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_gt())
+    }
+
+    /// Returns whether a value is greater than the value of a dual number.
+    #[rhai_fn(name = ">", return_raw)]
+    pub fn number_greater(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_gt())
+    }
+
+    /// Returns whether the value of a dual number is at least another value.
+    #[rhai_fn(name = ">=", return_raw)]
+    pub fn greater_equal(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_ge())
+    }
+
+    /// Returns whether a value is at least the value of a dual number.
+    #[rhai_fn(name = ">=", return_raw)]
+    pub fn number_greater_equal(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_ge())
+    }
+
+    /// Returns whether the value of a dual number equals another value.
+    #[rhai_fn(name = "==", return_raw)]
+    pub fn equal(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_eq())
+    }
+
+    /// Returns whether a value equals the value of a dual number.
+    #[rhai_fn(name = "==", return_raw)]
+    pub fn number_equal(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_eq())
+    }
+
+    /// Returns whether the value of a dual number differs from another value.
+    #[rhai_fn(name = "!=", return_raw)]
+    pub fn not_equal(x: Dual, y: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(Dynamic::from(x), y)?.is_ne())
+    }
+
+    /// Returns whether a value differs from the value of a dual number.
+    #[rhai_fn(name = "!=", return_raw)]
+    pub fn number_not_equal(x: Dynamic, y: Dual) -> Result<bool, Box<EvalAltResult>> {
+        Ok(compare(x, Dynamic::from(y))?.is_ne())
+    }
+
+    /// Returns the larger of two numbers, either of which may be dual, together with its
+    /// gradient. Ties go to the first number.
+    /// ```typescript
+    /// assert_eq(grad(|x| max(x, 1.0), 2.0), 1.0);
+    /// assert_eq(grad(|x| max(x, 1.0), 0.5), 0.0);
+    /// assert_eq(grad(|x| max(x[0], 2.0 * x[1]), [1.0, 3.0]), [0.0, 2.0]);
+    /// ```
+    #[rhai_fn(name = "max", return_raw)]
+    pub fn max(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        let y = to_dual(y)?;
+        Ok(if y.value > x.value { y } else { x })
+    }
+
+    /// Returns the larger of a number and a dual number.
+    #[rhai_fn(name = "max", return_raw)]
+    pub fn number_max(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        max(to_dual(x)?, Dynamic::from(y))
+    }
+
+    /// Returns the smaller of two numbers, either of which may be dual, together with its
+    /// gradient. Ties go to the first number.
+    /// ```typescript
+    /// assert_eq(grad(|x| min(x, 1.0), 0.5), 1.0);
+    /// assert_eq(grad(|x| min(3.0 * x, 1.0), 2.0), 0.0);
+    /// ```
+    #[rhai_fn(name = "min", return_raw)]
+    pub fn min(x: Dual, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        let y = to_dual(y)?;
+        Ok(if y.value < x.value { y } else { x })
+    }
+
+    /// Returns the smaller of a number and a dual number.
+    #[rhai_fn(name = "min", return_raw)]
+    pub fn number_min(x: Dynamic, y: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        min(to_dual(x)?, Dynamic::from(y))
+    }
+
+    /// Returns the largest integer at most a dual number, whose gradient is zero.
+    /// ```typescript
+    /// let x = dual(2.5, [1.0]);
+    /// assert_eq(x.floor().value, 2.0);
+    /// assert_eq(grad(|x| x.floor() + x, 2.5), 1.0);
+    /// ```
+    #[rhai_fn(name = "floor")]
+    pub fn floor(x: Dual) -> Dual {
+        x.chain(x.value.floor(), 0.0)
+    }
+
+    /// Returns the smallest integer at least a dual number, whose gradient is zero.
+    #[rhai_fn(name = "ceiling")]
+    pub fn ceiling(x: Dual) -> Dual {
+        x.chain(x.value.ceil(), 0.0)
+    }
+
+    /// Returns a dual number rounded to the nearest integer, whose gradient is zero.
+    #[rhai_fn(name = "round")]
+    pub fn round(x: Dual) -> Dual {
+        x.chain(x.value.round(), 0.0)
+    }
+
+    /// Returns the absolute value of a dual number.
+    #[rhai_fn(name = "abs")]
+    pub fn abs(x: Dual) -> Dual {
+        x.chain(x.value.abs(), x.value.signum())
+    }
+
+    /// Returns the square root of a dual number.
+    #[rhai_fn(name = "sqrt")]
+    pub fn sqrt(x: Dual) -> Dual {
+        let value = x.value.sqrt();
+        x.chain(value, 0.5 / value)
+    }
+
+    /// Returns the exponential of a dual number.
+    #[rhai_fn(name = "exp")]
+    pub fn exp(x: Dual) -> Dual {
+        let value = x.value.exp();
+        x.chain(value, value)
+    }
+
+    /// Returns the natural logarithm of a dual number.
+    #[rhai_fn(name = "ln")]
+    pub fn ln(x: Dual) -> Dual {
+        x.chain(x.value.ln(), 1.0 / x.value)
+    }
+
+    /// Returns the base-10 logarithm of a dual number.
+    #[rhai_fn(name = "log")]
+    pub fn log10(x: Dual) -> Dual {
+        x.chain(x.value.log10(), 1.0 / (x.value * std::f64::consts::LN_10))
+    }
+
+    /// Returns the logarithm of a dual number to the given base.
+    #[rhai_fn(name = "log", return_raw)]
+    pub fn log(x: Dual, base: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(divide(ln(x), ln(to_dual(base)?)))
+    }
+
+    /// Returns the sine of a dual number.
+    #[rhai_fn(name = "sin")]
+    pub fn sin(x: Dual) -> Dual {
+        x.chain(x.value.sin(), x.value.cos())
+    }
+
+    /// Returns the cosine of a dual number.
+    #[rhai_fn(name = "cos")]
+    pub fn cos(x: Dual) -> Dual {
+        x.chain(x.value.cos(), -x.value.sin())
+    }
+
+    /// Returns the tangent of a dual number.
+    #[rhai_fn(name = "tan")]
+    pub fn tan(x: Dual) -> Dual {
+        x.chain(x.value.tan(), 1.0 / x.value.cos().powi(2))
+    }
+
+    /// Returns the inverse sine of a dual number.
+    #[rhai_fn(name = "asin")]
+    pub fn asin(x: Dual) -> Dual {
+        x.chain(x.value.asin(), 1.0 / (1.0 - x.value.powi(2)).sqrt())
+    }
+
+    /// Returns the inverse cosine of a dual number.
+    #[rhai_fn(name = "acos")]
+    pub fn acos(x: Dual) -> Dual {
+        x.chain(x.value.acos(), -1.0 / (1.0 - x.value.powi(2)).sqrt())
+    }
+
+    /// Returns the inverse tangent of a dual number.
+    #[rhai_fn(name = "atan")]
+    pub fn atan(x: Dual) -> Dual {
+        x.chain(x.value.atan(), 1.0 / (1.0 + x.value.powi(2)))
+    }
+
+    /// Returns the four-quadrant inverse tangent of `y / x` for dual numbers.
+    /// ```typescript
+    /// let g = grad(|v| atan(v[1], v[0]), [1.0, 1.0]);
+    /// assert_approx_eq(g, [-0.5, 0.5], 1e-15);
+    /// ```
+    #[rhai_fn(name = "atan")]
+    pub fn atan2(y: Dual, x: Dual) -> Dual {
+        let r2 = x.value.powi(2) + y.value.powi(2);
+        y.combine(&x, y.value.atan2(x.value), (x.value / r2, -y.value / r2))
+    }
+
+    /// Returns the four-quadrant inverse tangent of a dual number over a number.
+    #[rhai_fn(name = "atan", return_raw)]
+    pub fn atan2_number(y: Dual, x: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(atan2(y, to_dual(x)?))
+    }
+
+    /// Returns the four-quadrant inverse tangent of a number over a dual number.
+    #[rhai_fn(name = "atan", return_raw)]
+    pub fn number_atan2(y: Dynamic, x: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(atan2(to_dual(y)?, x))
+    }
+
+    /// Returns the hyperbolic sine of a dual number.
+    #[rhai_fn(name = "sinh")]
+    pub fn sinh(x: Dual) -> Dual {
+        x.chain(x.value.sinh(), x.value.cosh())
+    }
+
+    /// Returns the hyperbolic cosine of a dual number.
+    #[rhai_fn(name = "cosh")]
+    pub fn cosh(x: Dual) -> Dual {
+        x.chain(x.value.cosh(), x.value.sinh())
+    }
+
+    /// Returns the hyperbolic tangent of a dual number.
+    #[rhai_fn(name = "tanh")]
+    pub fn tanh(x: Dual) -> Dual {
+        let value = x.value.tanh();
+        x.chain(value, 1.0 - value * value)
+    }
+
+    /// Returns the inverse hyperbolic sine of a dual number.
+    #[rhai_fn(name = "asinh")]
+    pub fn asinh(x: Dual) -> Dual {
+        x.chain(x.value.asinh(), 1.0 / (x.value.powi(2) + 1.0).sqrt())
+    }
+
+    /// Returns the inverse hyperbolic cosine of a dual number.
+    #[rhai_fn(name = "acosh")]
+    pub fn acosh(x: Dual) -> Dual {
+        x.chain(x.value.acosh(), 1.0 / (x.value.powi(2) - 1.0).sqrt())
+    }
+
+    /// Returns the inverse hyperbolic tangent of a dual number.
+    #[rhai_fn(name = "atanh")]
+    pub fn atanh(x: Dual) -> Dual {
+        x.chain(x.value.atanh(), 1.0 / (1.0 - x.value.powi(2)))
+    }
+
+    /// Returns the length of the hypotenuse with dual sides.
+    #[rhai_fn(name = "hypot", return_raw)]
+    pub fn hypot(x: Dynamic, y: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        let (x, y) = (to_dual(x)?, to_dual(y)?);
+        Ok(sqrt(add(multiply(x.clone(), x), multiply(y.clone(), y))))
+    }
+
+    /// Returns the distance from the origin of a point with dual coordinates in 3D space.
+    #[rhai_fn(name = "hypot", return_raw)]
+    pub fn hypot3(x: Dynamic, y: Dynamic, z: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        let z = to_dual(z)?;
+        let xy = hypot(x, y)?;
+        Ok(sqrt(add(multiply(xy.clone(), xy), multiply(z.clone(), z))))
+    }
+
+    /// Converts a dual number from degrees to radians.
+    #[rhai_fn(name = "deg2rad")]
+    pub fn deg2rad(x: Dual) -> Dual {
+        x.chain(trig::deg2rad(x.value), DEG)
+    }
+
+    /// Converts a dual number from radians to degrees.
+    #[rhai_fn(name = "rad2deg")]
+    pub fn rad2deg(x: Dual) -> Dual {
+        x.chain(trig::rad2deg(x.value), 1.0 / DEG)
+    }
+
+    /// Returns the sine of a dual number given in degrees.
+    #[rhai_fn(name = "sind")]
+    pub fn sind(x: Dual) -> Dual {
+        x.chain(trig::sind(x.value), DEG * trig::cosd(x.value))
+    }
+
+    /// Returns the cosine of a dual number given in degrees.
+    #[rhai_fn(name = "cosd")]
+    pub fn cosd(x: Dual) -> Dual {
+        x.chain(trig::cosd(x.value), -DEG * trig::sind(x.value))
+    }
+
+    /// Returns the tangent of a dual number given in degrees.
+    #[rhai_fn(name = "tand")]
+    pub fn tand(x: Dual) -> Dual {
+        x.chain(trig::tand(x.value), DEG * trig::secd(x.value).powi(2))
+    }
+
+    /// Returns the inverse sine of a dual number in degrees.
+    #[rhai_fn(name = "asind")]
+    pub fn asind(x: Dual) -> Dual {
+        x.chain(
+            trig::asind(x.value),
+            1.0 / (DEG * (1.0 - x.value.powi(2)).sqrt()),
+        )
+    }
+
+    /// Returns the inverse cosine of a dual number in degrees.
+    #[rhai_fn(name = "acosd")]
+    pub fn acosd(x: Dual) -> Dual {
+        x.chain(
+            trig::acosd(x.value),
+            -1.0 / (DEG * (1.0 - x.value.powi(2)).sqrt()),
+        )
+    }
+
+    /// Returns the inverse tangent of a dual number in degrees.
+    #[rhai_fn(name = "atand")]
+    pub fn atand(x: Dual) -> Dual {
+        x.chain(trig::atand(x.value), 1.0 / (DEG * (1.0 + x.value.powi(2))))
+    }
+
+    /// Returns the four-quadrant inverse tangent of `y / x` in degrees for dual numbers.
+    /// ```typescript
+    /// let a = atand(dual(0.5, [1.0]), dual(1.0, [0.0]));
+    /// assert_approx_eq(a.value, atand(0.5, 1.0), 1e-12);
+    /// assert_approx_eq(a.gradient, [0.8 / (pi / 180.0)], 1e-12);
+    /// ```
+    #[rhai_fn(name = "atand")]
+    pub fn atand2(y: Dual, x: Dual) -> Dual {
+        rad2deg(atan2(y, x))
+    }
+
+    /// Returns the four-quadrant inverse tangent in degrees of a dual number over a number.
+    #[rhai_fn(name = "atand", return_raw)]
+    pub fn atand2_number(y: Dual, x: Dynamic) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(atand2(y, to_dual(x)?))
+    }
+
+    /// Returns the four-quadrant inverse tangent in degrees of a number over a dual number.
+    #[rhai_fn(name = "atand", return_raw)]
+    pub fn number_atand2(y: Dynamic, x: Dual) -> Result<Dual, Box<EvalAltResult>> {
+        Ok(atand2(to_dual(y)?, x))
+    }
+
+    /// Returns the hyperbolic sine of a dual number given in degrees.
+    #[rhai_fn(name = "sinhd")]
+    pub fn sinhd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::sinhd(v), DEG * trig::coshd(v))
+    }
+
+    /// Returns the hyperbolic cosine of a dual number given in degrees.
+    #[rhai_fn(name = "coshd")]
+    pub fn coshd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::coshd(v), DEG * trig::sinhd(v))
+    }
+
+    /// Returns the hyperbolic tangent of a dual number given in degrees.
+    #[rhai_fn(name = "tanhd")]
+    pub fn tanhd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::tanhd(v), DEG * trig::sechd(v).powi(2))
+    }
+
+    /// Returns the inverse hyperbolic sine of a dual number in degrees.
+    #[rhai_fn(name = "asinhd")]
+    pub fn asinhd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::asinhd(v), 1.0 / (DEG * (v * v + 1.0).sqrt()))
+    }
+
+    /// Returns the inverse hyperbolic cosine of a dual number in degrees.
+    #[rhai_fn(name = "acoshd")]
+    pub fn acoshd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acoshd(v), 1.0 / (DEG * (v * v - 1.0).sqrt()))
+    }
+
+    /// Returns the inverse hyperbolic tangent of a dual number in degrees.
+    #[rhai_fn(name = "atanhd")]
+    pub fn atanhd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::atanhd(v), 1.0 / (DEG * (1.0 - v * v)))
+    }
+
+    /// Returns the cosecant of a dual number.
+    #[rhai_fn(name = "csc")]
+    pub fn csc(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::csc(v), -trig::csc(v) * trig::cot(v))
+    }
+
+    /// Returns the secant of a dual number.
+    #[rhai_fn(name = "sec")]
+    pub fn sec(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::sec(v), trig::sec(v) * v.tan())
+    }
+
+    /// Returns the cotangent of a dual number.
+    #[rhai_fn(name = "cot")]
+    pub fn cot(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::cot(v), -trig::csc(v).powi(2))
+    }
+
+    /// Returns the cosecant of a dual number given in degrees.
+    #[rhai_fn(name = "cscd")]
+    pub fn cscd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::cscd(v), -DEG * trig::cscd(v) * trig::cotd(v))
+    }
+
+    /// Returns the secant of a dual number given in degrees.
+    #[rhai_fn(name = "secd")]
+    pub fn secd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::secd(v), DEG * trig::secd(v) * trig::tand(v))
+    }
+
+    /// Returns the cotangent of a dual number given in degrees.
+    #[rhai_fn(name = "cotd")]
+    pub fn cotd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::cotd(v), -DEG * trig::cscd(v).powi(2))
+    }
+
+    /// Returns the inverse cosecant of a dual number.
+    #[rhai_fn(name = "acsc")]
+    pub fn acsc(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acsc(v), -1.0 / (v.abs() * (v * v - 1.0).sqrt()))
+    }
+
+    /// Returns the inverse secant of a dual number.
+    #[rhai_fn(name = "asec")]
+    pub fn asec(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::asec(v), 1.0 / (v.abs() * (v * v - 1.0).sqrt()))
+    }
+
+    /// Returns the inverse cotangent of a dual number.
+    #[rhai_fn(name = "acot")]
+    pub fn acot(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acot(v), -1.0 / (1.0 + v * v))
+    }
+
+    /// Returns the inverse cosecant of a dual number in degrees.
+    #[rhai_fn(name = "acscd")]
+    pub fn acscd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(
+            trig::acscd(v),
+            -1.0 / (DEG * v.abs() * (v * v - 1.0).sqrt()),
+        )
+    }
+
+    /// Returns the inverse secant of a dual number in degrees.
+    #[rhai_fn(name = "asecd")]
+    pub fn asecd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::asecd(v), 1.0 / (DEG * v.abs() * (v * v - 1.0).sqrt()))
+    }
+
+    /// Returns the inverse cotangent of a dual number in degrees.
+    #[rhai_fn(name = "acotd")]
+    pub fn acotd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acotd(v), -1.0 / (DEG * (1.0 + v * v)))
+    }
+
+    /// Returns the hyperbolic cosecant of a dual number.
+    #[rhai_fn(name = "csch")]
+    pub fn csch(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::csch(v), -trig::csch(v) * trig::coth(v))
+    }
+
+    /// Returns the hyperbolic secant of a dual number.
+    #[rhai_fn(name = "sech")]
+    pub fn sech(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::sech(v), -trig::sech(v) * v.tanh())
+    }
+
+    /// Returns the hyperbolic cotangent of a dual number.
+    #[rhai_fn(name = "coth")]
+    pub fn coth(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::coth(v), -trig::csch(v).powi(2))
+    }
+
+    /// Returns the hyperbolic cosecant of a dual number given in degrees.
+    #[rhai_fn(name = "cschd")]
+    pub fn cschd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::cschd(v), -DEG * trig::cschd(v) * trig::cothd(v))
+    }
+
+    /// Returns the hyperbolic secant of a dual number given in degrees.
+    #[rhai_fn(name = "sechd")]
+    pub fn sechd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::sechd(v), -DEG * trig::sechd(v) * trig::tanhd(v))
+    }
+
+    /// Returns the hyperbolic cotangent of a dual number given in degrees.
+    #[rhai_fn(name = "cothd")]
+    pub fn cothd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::cothd(v), -DEG * trig::cschd(v).powi(2))
+    }
+
+    /// Returns the inverse hyperbolic cosecant of a dual number.
+    #[rhai_fn(name = "acsch")]
+    pub fn acsch(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acsch(v), -1.0 / (v.abs() * (v * v + 1.0).sqrt()))
+    }
+
+    /// Returns the inverse hyperbolic secant of a dual number.
+    #[rhai_fn(name = "asech")]
+    pub fn asech(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::asech(v), -1.0 / (v * (1.0 - v * v).sqrt()))
+    }
+
+    /// Returns the inverse hyperbolic cotangent of a dual number.
+    #[rhai_fn(name = "acoth")]
+    pub fn acoth(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acoth(v), 1.0 / (1.0 - v * v))
+    }
+
+    /// Returns the inverse hyperbolic cosecant of a dual number in degrees.
+    #[rhai_fn(name = "acschd")]
+    pub fn acschd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(
+            trig::acschd(v),
+            -1.0 / (DEG * v.abs() * (v * v + 1.0).sqrt()),
+        )
+    }
+
+    /// Returns the inverse hyperbolic secant of a dual number in degrees.
+    #[rhai_fn(name = "asechd")]
+    pub fn asechd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::asechd(v), -1.0 / (DEG * v * (1.0 - v * v).sqrt()))
+    }
+
+    /// Returns the inverse hyperbolic cotangent of a dual number in degrees.
+    #[rhai_fn(name = "acothd")]
+    pub fn acothd(x: Dual) -> Dual {
+        let v = x.value;
+        x.chain(trig::acothd(v), 1.0 / (DEG * (1.0 - v * v)))
+    }
+}
@@ -0,0 +1,477 @@
+use rhai::plugin::*;
+
+/// Returns the first `count` prime numbers.
+pub fn primes(count: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = vec![];
+    let mut candidate = 2;
+    while primes.len() < count {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Reflects the digits of `i` in the given base about the radix point.
+pub fn radical_inverse(mut i: u64, base: u64) -> rhai::FLOAT {
+    let mut value = 0.0;
+    let mut scale = 1.0 / base as rhai::FLOAT;
+    while i > 0 {
+        value += (i % base) as rhai::FLOAT * scale;
+        i /= base;
+        scale /= base as rhai::FLOAT;
+    }
+    value
+}
+
+/// Primitive polynomials and initial direction numbers of the Sobol sequence in dimensions 2
+/// to 21, from the `new-joe-kuo-6.21201` table of Joe and Kuo: the degree `s`, the
+/// coefficients `a` and the initial numbers `m`.
+const SOBOL_DIRECTIONS: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+/// Number of bits of the Sobol points.
+const SOBOL_BITS: usize = 32;
+
+/// Returns the direction numbers `v[k] = m[k] 2^(32 - k)` of the Sobol sequence in the
+/// dimension with index `dimension`, counting from zero.
+fn sobol_direction_numbers(dimension: usize) -> Vec<u32> {
+    if dimension == 0 {
+        return (1..=SOBOL_BITS).map(|k| 1 << (SOBOL_BITS - k)).collect();
+    }
+    let (s, a, m) = SOBOL_DIRECTIONS[dimension - 1];
+    let s = s as usize;
+    let mut v: Vec<u32> = m
+        .iter()
+        .enumerate()
+        .map(|(k, m)| m << (SOBOL_BITS - 1 - k))
+        .collect();
+    for k in s..SOBOL_BITS {
+        let mut next = v[k - s] ^ (v[k - s] >> s);
+        for j in 1..s {
+            if (a >> (s - 1 - j)) & 1 == 1 {
+                next ^= v[k - j];
+            }
+        }
+        v.push(next);
+    }
+    v
+}
+
+/// Returns the coded levels (-1 or 1) of the full two-level factorial design in `k` factors,
+/// with the last factor changing fastest.
+fn two_level_factorial(k: usize) -> Vec<Vec<rhai::FLOAT>> {
+    (0..1_usize << k)
+        .map(|row| {
+            (0..k)
+                .map(|j| {
+                    if (row >> (k - 1 - j)) & 1 == 1 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Converts rows of numbers to a matrix.
+fn design_matrix(rows: Vec<Vec<rhai::FLOAT>>) -> rhai::Array {
+    rows.into_iter()
+        .map(|row| {
+            rhai::Dynamic::from_array(row.into_iter().map(rhai::Dynamic::from_float).collect())
+        })
+        .collect()
+}
+
+/// Checks the number of points `n` and the dimension `d` of a design.
+fn check_design_size(n: rhai::INT, d: rhai::INT) -> Result<(), Box<rhai::EvalAltResult>> {
+    if n < 0 || d < 1 {
+        Err(crate::arithmetic_error(
+            "The number of points must be non-negative and the dimension positive",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[export_module]
+pub mod experiment_functions {
+    use super::{
+        check_design_size, design_matrix, sobol_direction_numbers, two_level_factorial, SOBOL_BITS,
+        SOBOL_DIRECTIONS,
+    };
+    use crate::arithmetic_error;
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT, INT};
+
+    /// Returns the first `n` points of the `d`-dimensional Halton sequence, as a matrix with
+    /// one point per row. Coordinate `j` of point `i` is the radical inverse of `i` in the
+    /// `j`-th prime base, and the sequence starts at the origin.
+    /// ```typescript
+    /// let x = halton(4, 2);
+    /// assert_eq(x[0], [0.0, 0.0]);
+    /// assert_eq(x[1], [0.5, 1.0 / 3.0]);
+    /// assert_eq(x[2], [0.25, 2.0 / 3.0]);
+    /// assert_approx_eq(x[3], [0.75, 1.0 / 9.0], 1e-15);
+    /// ```
+    #[rhai_fn(name = "halton", return_raw)]
+    pub fn halton(n: INT, d: INT) -> Result<Array, Box<EvalAltResult>> {
+        check_design_size(n, d)?;
+        let bases = crate::primes(d as usize);
+        Ok(design_matrix(
+            (0..n as u64)
+                .map(|i| {
+                    bases
+                        .iter()
+                        .map(|base| crate::radical_inverse(i, *base))
+                        .collect()
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns the first `n` points of the `d`-dimensional Sobol sequence, as a matrix with one
+    /// point per row, using the direction numbers of Joe and Kuo. The sequence starts at the
+    /// origin, so that the first `2^m` points fill every interval `[k / 2^m, (k + 1) / 2^m)`
+    /// of each coordinate exactly once. Up to 21 dimensions are available.
+    /// ```typescript
+    /// let x = sobol(4, 2);
+    /// assert_eq(x, [[0.0, 0.0], [0.5, 0.5], [0.75, 0.25], [0.25, 0.75]]);
+    /// ```
+    #[rhai_fn(name = "sobol", return_raw)]
+    pub fn sobol(n: INT, d: INT) -> Result<Array, Box<EvalAltResult>> {
+        check_design_size(n, d)?;
+        let max_dimension = SOBOL_DIRECTIONS.len() + 1;
+        if d as usize > max_dimension {
+            return Err(arithmetic_error(format!(
+                "Sobol sequences are available in up to {max_dimension} dimensions"
+            )));
+        }
+        if n as u64 > 1 << SOBOL_BITS {
+            return Err(arithmetic_error(format!(
+                "At most 2^{SOBOL_BITS} Sobol points are available"
+            )));
+        }
+        let directions: Vec<Vec<u32>> = (0..d as usize).map(sobol_direction_numbers).collect();
+        let scale = 1.0 / (1_u64 << SOBOL_BITS) as FLOAT;
+        let mut point = vec![0_u32; d as usize];
+        let mut rows = Vec::with_capacity(n as usize);
+        for i in 0..n as u64 {
+            if i > 0 {
+                // Gray code order: flip the direction number of the lowest zero bit of i - 1
+                let bit = (i - 1).trailing_ones() as usize;
+                for (x, v) in point.iter_mut().zip(&directions) {
+                    *x ^= v[bit];
+                }
+            }
+            rows.push(point.iter().map(|x| *x as FLOAT * scale).collect());
+        }
+        Ok(design_matrix(rows))
+    }
+
+    /// Returns the full factorial design for factors with the given numbers of levels, as a
+    /// matrix with one run per row and one column per factor. Levels are numbered from 1 and
+    /// the first factor changes fastest, as in MATLAB.
+    /// ```typescript
+    /// let design = fullfact([2, 3]);
+    /// assert_eq(design, [[1, 1], [2, 1], [1, 2], [2, 2], [1, 3], [2, 3]]);
+    /// ```
+    #[rhai_fn(name = "fullfact", return_raw)]
+    pub fn fullfact(levels: Array) -> Result<Array, Box<EvalAltResult>> {
+        let levels = levels
+            .into_iter()
+            .map(|level| match level.as_int() {
+                Ok(level) if level >= 1 => Ok(level),
+                _ => Err(arithmetic_error("The levels must be positive integers")),
+            })
+            .collect::<Result<Vec<INT>, _>>()?;
+        if levels.is_empty() {
+            return Err(arithmetic_error("At least one factor is needed"));
+        }
+        let runs: INT = levels.iter().product();
+        Ok((0..runs)
+            .map(|mut run| {
+                Dynamic::from_array(
+                    levels
+                        .iter()
+                        .map(|level| {
+                            let value = run % level + 1;
+                            run /= level;
+                            Dynamic::from_int(value)
+                        })
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+
+    /// Returns the full two-level factorial design in `k` factors, with coded levels -1 and 1,
+    /// as a matrix with one run per row. The last factor changes fastest.
+    /// ```typescript
+    /// assert_eq(ff2n(2), [[-1.0, -1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]]);
+    /// ```
+    #[rhai_fn(name = "ff2n", return_raw)]
+    pub fn ff2n(k: INT) -> Result<Array, Box<EvalAltResult>> {
+        if !(1..=30).contains(&k) {
+            return Err(arithmetic_error(
+                "The number of factors must be between 1 and 30",
+            ));
+        }
+        Ok(design_matrix(two_level_factorial(k as usize)))
+    }
+
+    /// Returns the two-level fractional factorial design given by a string of generators, as in
+    /// MATLAB. Each generator is a word of factor letters separated by spaces: single letters
+    /// are the basic factors, which form a full factorial design, and longer words are columns
+    /// confounded with the product of their letters, optionally negated with a leading `-`.
+    /// Levels are coded -1 and 1.
+    /// ```typescript
+    /// let design = fracfact("a b ab");
+    /// assert_eq(design, [[-1.0, -1.0, 1.0], [-1.0, 1.0, -1.0], [1.0, -1.0, -1.0], [1.0, 1.0, 1.0]]);
+    /// ```
+    /// ```typescript
+    /// // A 2^(5-2) design with eight runs
+    /// let design = fracfact("a b c -ab ac");
+    /// assert_eq(size(design), [8, 5]);
+    /// assert_eq(sum(design.map(|run| run[3] * run[0] * run[1])), -8.0);
+    /// ```
+    #[rhai_fn(name = "fracfact", return_raw)]
+    pub fn fracfact(generators: ImmutableString) -> Result<Array, Box<EvalAltResult>> {
+        let words: Vec<&str> = generators.split_whitespace().collect();
+        let basic: Vec<char> = words
+            .iter()
+            .filter(|word| word.chars().count() == 1)
+            .filter_map(|word| word.chars().next())
+            .collect();
+        if basic.is_empty() || basic.iter().any(|c| !c.is_ascii_alphabetic()) {
+            return Err(arithmetic_error(
+                "The generators must include at least one basic factor, given by a single letter",
+            ));
+        }
+        if basic.len() > 30 {
+            return Err(arithmetic_error("There can be at most 30 basic factors"));
+        }
+        let mut columns = Vec::with_capacity(words.len());
+        for word in &words {
+            let (sign, letters) = match word.strip_prefix('-') {
+                Some(letters) => (-1.0, letters),
+                None => (1.0, word.strip_prefix('+').unwrap_or(word)),
+            };
+            let mut factors = Vec::new();
+            for letter in letters.chars() {
+                let Some(factor) = basic.iter().position(|c| *c == letter) else {
+                    return Err(arithmetic_error(format!(
+                        "The generator '{word}' uses '{letter}', which is not a basic factor"
+                    )));
+                };
+                if factors.contains(&factor) {
+                    return Err(arithmetic_error(format!(
+                        "The generator '{word}' repeats the factor '{letter}'"
+                    )));
+                }
+                factors.push(factor);
+            }
+            if factors.is_empty() {
+                return Err(arithmetic_error(format!("The generator '{word}' is empty")));
+            }
+            columns.push((sign, factors));
+        }
+        if basic
+            .iter()
+            .enumerate()
+            .any(|(i, c)| basic[..i].contains(c))
+        {
+            return Err(arithmetic_error("Each basic factor can only be given once"));
+        }
+        Ok(design_matrix(
+            two_level_factorial(basic.len())
+                .into_iter()
+                .map(|run| {
+                    columns
+                        .iter()
+                        .map(|(sign, factors)| {
+                            sign * factors.iter().map(|f| run[*f]).product::<FLOAT>()
+                        })
+                        .collect()
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns the Box-Behnken design in `n` factors (at least 3) with coded levels -1, 0 and
+    /// 1, as a matrix with one run per row. Every pair of factors takes the four combinations of
+    /// -1 and 1 while the other factors are at 0, followed by `center` center points.
+    /// ```typescript
+    /// let design = bbdesign(3, 3);
+    /// assert_eq(size(design), [15, 3]);
+    /// assert_eq(design[0], [-1.0, -1.0, 0.0]);
+    /// assert_eq(design[14], [0.0, 0.0, 0.0]);
+    /// ```
+    #[rhai_fn(name = "bbdesign", return_raw)]
+    pub fn bbdesign_with_center(n: INT, center: INT) -> Result<Array, Box<EvalAltResult>> {
+        if n < 3 {
+            return Err(arithmetic_error(
+                "A Box-Behnken design needs at least 3 factors",
+            ));
+        }
+        if center < 0 {
+            return Err(arithmetic_error(
+                "The number of center points must be non-negative",
+            ));
+        }
+        let n = n as usize;
+        let mut rows = vec![];
+        for i in 0..n {
+            for j in i + 1..n {
+                for (a, b) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    let mut row = vec![0.0; n];
+                    row[i] = a;
+                    row[j] = b;
+                    rows.push(row);
+                }
+            }
+        }
+        rows.extend((0..center).map(|_| vec![0.0; n]));
+        Ok(design_matrix(rows))
+    }
+
+    /// Returns the Box-Behnken design in `n` factors with a single center point. See
+    /// `bbdesign(n, center)`.
+    /// ```typescript
+    /// assert_eq(size(bbdesign(4)), [25, 4]);
+    /// ```
+    #[rhai_fn(name = "bbdesign", return_raw)]
+    pub fn bbdesign(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        bbdesign_with_center(n, 1)
+    }
+
+    /// Returns the central composite design in `n` factors, as a matrix with one run per row:
+    /// the full two-level factorial runs, then the axial runs `-alpha` and `alpha` of each
+    /// factor in turn, then the center points. The options are:
+    ///
+    /// - `type`: `"circumscribed"` (default) puts the factorial runs at -1 and 1 and the axial
+    ///   runs at `-alpha` and `alpha`; `"inscribed"` scales that design so that the axial runs
+    ///   are at -1 and 1; `"faced"` puts the axial runs at -1 and 1, on the faces of the cube.
+    /// - `alpha`: the axial distance, by default `(2^n)^(1/4)`, which makes the design
+    ///   rotatable.
+    /// - `center`: the number of center points (default 1).
+    /// ```typescript
+    /// let design = ccdesign(2, #{center: 2});
+    /// assert_eq(size(design), [10, 2]);
+    /// assert_approx_eq(design[4], [-sqrt(2.0), 0.0], 1e-15);
+    /// ```
+    /// ```typescript
+    /// let design = ccdesign(3, #{type: "faced"});
+    /// assert_eq(design[8], [-1.0, 0.0, 0.0]);
+    /// assert_eq(max(design.map(|run| max(run))), 1.0);
+    /// ```
+    #[rhai_fn(name = "ccdesign", return_raw)]
+    pub fn ccdesign_with_options(n: INT, options: Map) -> Result<Array, Box<EvalAltResult>> {
+        if !(1..=30).contains(&n) {
+            return Err(arithmetic_error(
+                "The number of factors must be between 1 and 30",
+            ));
+        }
+        let n = n as usize;
+        let kind = match options.get("type") {
+            Some(kind) => kind
+                .clone()
+                .into_immutable_string()
+                .map_err(|_| arithmetic_error("The option 'type' must be a string"))?,
+            None => "circumscribed".into(),
+        };
+        let rotatable = ((1_u64 << n) as FLOAT).powf(0.25);
+        let alpha = crate::float_option(&options, "alpha", rotatable)?;
+        let center = crate::int_option(&options, "center", 1)?;
+        if alpha.is_nan() || alpha <= 0.0 || center < 0 {
+            return Err(arithmetic_error(
+                "The option 'alpha' must be positive and 'center' non-negative",
+            ));
+        }
+        let (cube, axial) = match kind.as_str() {
+            "circumscribed" => (1.0, alpha),
+            "inscribed" => (1.0 / alpha, 1.0),
+            "faced" => (1.0, 1.0),
+            _ => {
+                return Err(arithmetic_error(format!(
+                    "Unknown type '{kind}'; expected \"circumscribed\", \"inscribed\" or \"faced\""
+                )))
+            }
+        };
+        let mut rows: Vec<Vec<FLOAT>> = two_level_factorial(n)
+            .into_iter()
+            .map(|run| run.into_iter().map(|x| cube * x).collect())
+            .collect();
+        for i in 0..n {
+            for sign in [-1.0, 1.0] {
+                let mut row = vec![0.0; n];
+                row[i] = sign * axial;
+                rows.push(row);
+            }
+        }
+        rows.extend((0..center).map(|_| vec![0.0; n]));
+        Ok(design_matrix(rows))
+    }
+
+    /// Returns the circumscribed, rotatable central composite design in `n` factors with a
+    /// single center point. See `ccdesign(n, options)`.
+    /// ```typescript
+    /// assert_eq(size(ccdesign(3)), [15, 3]);
+    /// ```
+    #[rhai_fn(name = "ccdesign", return_raw)]
+    pub fn ccdesign(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        ccdesign_with_options(n, Map::new())
+    }
+
+    /// Returns a Latin hypercube sample of `n` points in the unit cube of dimension `d`, as a
+    /// matrix with one point per row. Each column has exactly one point in each of the
+    /// intervals `[k / n, (k + 1) / n)`, at a random position. The numbers are drawn from the
+    /// generator seeded by `rng_seed`.
+    /// ```typescript
+    /// let x = lhsdesign(5, 3);
+    /// assert_eq(size(x), [5, 3]);
+    /// let strata = x.map(|point| point[0] * 5.0).map(|v| floor(v));
+    /// strata.sort();
+    /// assert_eq(strata, [0.0, 1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "lhsdesign", return_raw)]
+    pub fn lhsdesign(n: INT, d: INT) -> Result<Array, Box<EvalAltResult>> {
+        use randlib::{seq::SliceRandom, Rng};
+        check_design_size(n, d)?;
+        let (n, d) = (n as usize, d as usize);
+        let mut rows = vec![vec![0.0; d]; n];
+        crate::with_rng(|rng| {
+            let mut strata: Vec<usize> = (0..n).collect();
+            for j in 0..d {
+                strata.shuffle(rng);
+                for (row, stratum) in rows.iter_mut().zip(&strata) {
+                    row[j] = (*stratum as FLOAT + rng.random::<FLOAT>()) / n as FLOAT;
+                }
+            }
+        });
+        Ok(design_matrix(rows))
+    }
+}
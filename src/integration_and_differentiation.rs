@@ -1,9 +1,1196 @@
 use rhai::plugin::*;
 
+/// Adaptive Gauss-Kronrod quadrature of Rhai functions, shared by `integral`, `quadgk`,
+/// `integral2` and `integral3`, and the integrands and bounds shared with the cubature rules.
+mod quadrature {
+    use crate::OperationCounter;
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, if_int_convert_to_float_and_do,
+        int_option,
+    };
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+    use std::cell::Cell;
+
+    /// Positive nodes of the 15-point Kronrod rule; the odd entries are the nodes of the 7-point
+    /// Gauss rule.
+    const NODES: [FLOAT; 8] = [
+        0.991_455_371_120_812_6,
+        0.949_107_912_342_758_5,
+        0.864_864_423_359_769_1,
+        0.741_531_185_599_394_4,
+        0.586_087_235_467_691_1,
+        0.405_845_151_377_397_2,
+        0.207_784_955_007_898_5,
+        0.0,
+    ];
+
+    /// Weights of the 15-point Kronrod rule, matching `NODES`.
+    const KRONROD_WEIGHTS: [FLOAT; 8] = [
+        0.022_935_322_010_529_22,
+        0.063_092_092_629_978_55,
+        0.104_790_010_322_250_2,
+        0.140_653_259_715_525_9,
+        0.169_004_726_639_267_9,
+        0.190_350_578_064_785_4,
+        0.204_432_940_075_298_9,
+        0.209_482_141_084_727_8,
+    ];
+
+    /// Weights of the 7-point Gauss rule, for the odd entries of `NODES`.
+    const GAUSS_WEIGHTS: [FLOAT; 4] = [
+        0.129_484_966_168_869_7,
+        0.279_705_391_489_276_7,
+        0.381_830_050_505_118_9,
+        0.417_959_183_673_469_4,
+    ];
+
+    /// Change of variable `x(t)` mapping a finite `t` interval onto a piece of the domain. The
+    /// finite case also weakens singularities at the ends of the piece.
+    #[derive(Clone, Copy)]
+    enum Transform {
+        /// `[a, b]` from `t` in `[-1, 1]`.
+        Finite(FLOAT, FLOAT),
+        /// `[a, inf)` from `t` in `[0, 1)`.
+        Above(FLOAT),
+        /// `(-inf, b]` from `t` in `(-1, 0]`.
+        Below(FLOAT),
+        /// The whole real line from `t` in `(-1, 1)`.
+        Whole,
+    }
+
+    impl Transform {
+        /// Range of `t`.
+        fn range(self) -> (FLOAT, FLOAT) {
+            match self {
+                Transform::Finite(..) | Transform::Whole => (-1.0, 1.0),
+                Transform::Above(_) => (0.0, 1.0),
+                Transform::Below(_) => (-1.0, 0.0),
+            }
+        }
+
+        /// Returns `x(t)` and `dx/dt`.
+        fn map(self, t: FLOAT) -> (FLOAT, FLOAT) {
+            match self {
+                Transform::Finite(a, b) => (
+                    0.25 * (b - a) * t * (3.0 - t * t) + 0.5 * (a + b),
+                    0.75 * (b - a) * (1.0 - t * t),
+                ),
+                Transform::Above(a) => {
+                    let s = t / (1.0 - t);
+                    (a + s * s, 2.0 * t / (1.0 - t).powi(3))
+                }
+                Transform::Below(b) => {
+                    let s = t / (1.0 + t);
+                    (b - s * s, -2.0 * t / (1.0 + t).powi(3))
+                }
+                Transform::Whole => {
+                    let d = 1.0 - t * t;
+                    (t / d, (1.0 + t * t) / (d * d))
+                }
+            }
+        }
+    }
+
+    /// A subinterval in the transformed variable with its integral and error estimates.
+    struct Interval {
+        /// Change of variable of the piece the subinterval belongs to.
+        transform: Transform,
+        /// Start of the subinterval.
+        start: FLOAT,
+        /// End of the subinterval.
+        end: FLOAT,
+        /// Kronrod estimate of the integral.
+        value: FLOAT,
+        /// Difference between the Kronrod and Gauss estimates.
+        error: FLOAT,
+    }
+
+    /// Tolerances of the adaptive quadrature.
+    pub(super) struct Settings {
+        /// Absolute error tolerance.
+        abs_tol: FLOAT,
+        /// Relative error tolerance.
+        rel_tol: FLOAT,
+        /// Maximum number of subintervals of each one-dimensional integral.
+        max_intervals: INT,
+    }
+
+    impl Settings {
+        /// Reads the `abs_tol`, `rel_tol` and `max_intervals` options.
+        pub(super) fn from_options(options: &Map) -> Result<Self, Box<EvalAltResult>> {
+            let abs_tol = float_option(options, "abs_tol", 1e-10)?;
+            let rel_tol = float_option(options, "rel_tol", 1e-6)?;
+            let max_intervals = int_option(options, "max_intervals", 650)?;
+            if abs_tol < 0.0 || rel_tol < 0.0 || max_intervals < 1 {
+                return Err(arithmetic_error(
+                    "The tolerances must be non-negative and 'max_intervals' positive",
+                ));
+            }
+            Ok(Self {
+                abs_tol,
+                rel_tol,
+                max_intervals,
+            })
+        }
+    }
+
+    /// Result of the adaptive quadrature.
+    pub(super) struct Quadrature {
+        /// Estimate of the integral.
+        pub(super) value: FLOAT,
+        /// Estimate of the absolute error.
+        pub(super) error: FLOAT,
+        /// Number of subintervals used.
+        pub(super) intervals: INT,
+        /// Whether the error estimate meets the tolerances.
+        pub(super) converged: bool,
+    }
+
+    impl Quadrature {
+        /// Returns the result as an object map with entries `value`, `error`, `intervals`,
+        /// `evaluations` and `converged`.
+        pub(super) fn into_map(self, evaluations: INT) -> Map {
+            let mut result = Map::new();
+            result.insert("value".into(), Dynamic::from_float(self.value));
+            result.insert("error".into(), Dynamic::from_float(self.error));
+            result.insert("intervals".into(), Dynamic::from_int(self.intervals));
+            result.insert("evaluations".into(), Dynamic::from_int(evaluations));
+            result.insert("converged".into(), Dynamic::from_bool(self.converged));
+            result
+        }
+
+        /// Returns the value, or fails if the tolerance was not met.
+        pub(super) fn checked_value(self) -> Result<FLOAT, Box<EvalAltResult>> {
+            if !self.converged {
+                return Err(arithmetic_error(format!(
+                    "The integral did not reach the requested tolerance; the error estimate is {:e}",
+                    self.error
+                )));
+            }
+            Ok(self.value)
+        }
+    }
+
+    /// The integrand, implemented by a Rhai function pointer.
+    pub(super) struct Integrand<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for the integrand.
+        f: &'a FnPtr,
+        /// Whether the coordinates are passed as one array rather than as separate arguments.
+        packed: bool,
+        /// Counter of the calls to the integrand.
+        counter: OperationCounter,
+        /// Number of evaluations of the integrand so far.
+        pub(super) evaluations: Cell<INT>,
+    }
+
+    impl<'a> Integrand<'a> {
+        /// Wraps a function pointer as an integrand.
+        pub(super) fn new(ctx: &'a NativeCallContext<'a>, f: &'a FnPtr, packed: bool) -> Self {
+            Self {
+                ctx,
+                f,
+                packed,
+                counter: OperationCounter::new(ctx),
+                evaluations: Cell::new(0),
+            }
+        }
+
+        /// Evaluates the integrand at `point`, where it must be finite.
+        pub(super) fn eval(&self, point: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            self.evaluations.set(self.evaluations.get() + 1);
+            let value = self.call(self.f, point)?;
+            let value = if_int_convert_to_float_and_do(value, Ok)
+                .map_err(|_| arithmetic_error("The integrand must return an INT or FLOAT"))?;
+            if !value.is_finite() {
+                let at = match point {
+                    [x] => format!("x = {x}"),
+                    _ => format!("{point:?}"),
+                };
+                return Err(arithmetic_error(format!(
+                    "The integrand is not finite at {at}"
+                )));
+            }
+            Ok(value)
+        }
+
+        /// Calls `f` with the coordinates of `point`.
+        fn call(&self, f: &FnPtr, point: &[FLOAT]) -> Result<Dynamic, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            if self.packed {
+                let x = point.iter().map(|v| Dynamic::from_float(*v)).collect();
+                f.call_raw(self.ctx, None, [Dynamic::from_array(x)])
+            } else {
+                let args: Vec<Dynamic> = point.iter().map(|v| Dynamic::from_float(*v)).collect();
+                f.call_raw(self.ctx, None, args)
+            }
+        }
+    }
+
+    /// Limit of an inner integral: a number, or a function of the outer variables.
+    pub(super) enum Limit {
+        /// A fixed limit.
+        Fixed(FLOAT),
+        /// A limit computed from the outer variables.
+        Function(FnPtr),
+    }
+
+    impl Limit {
+        /// Reads a limit given as a number or a function pointer.
+        pub(super) fn new(value: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+            if value.is_fnptr() {
+                return Ok(Limit::Function(value.cast::<FnPtr>()));
+            }
+            if_int_convert_to_float_and_do(value, |v| Ok(Limit::Fixed(v))).map_err(|_| {
+                arithmetic_error("The limits of integration must be numbers or function pointers")
+            })
+        }
+
+        /// Returns the limit for the given outer variables.
+        fn eval(
+            &self,
+            integrand: &Integrand,
+            outer: &[FLOAT],
+        ) -> Result<FLOAT, Box<EvalAltResult>> {
+            match self {
+                Limit::Fixed(value) => Ok(*value),
+                Limit::Function(f) => if_int_convert_to_float_and_do(integrand.call(f, outer)?, Ok)
+                    .map_err(|_| arithmetic_error("A limit function must return an INT or FLOAT")),
+            }
+        }
+    }
+
+    /// Applies the 15-point Kronrod and 7-point Gauss rules to `[start, end]` in the
+    /// transformed variable, returning the Kronrod estimate and the difference between the
+    /// two.
+    fn rule(
+        g: &mut impl FnMut(FLOAT) -> Result<FLOAT, Box<EvalAltResult>>,
+        transform: Transform,
+        (start, end): (FLOAT, FLOAT),
+    ) -> Result<(FLOAT, FLOAT), Box<EvalAltResult>> {
+        let center = 0.5 * (start + end);
+        let half = 0.5 * (end - start);
+        let mut kronrod = 0.0;
+        let mut gauss = 0.0;
+        for (k, node) in NODES.iter().enumerate() {
+            let offsets: &[FLOAT] = if *node == 0.0 {
+                &[0.0]
+            } else {
+                &[-node, *node]
+            };
+            for offset in offsets {
+                let (x, jacobian) = transform.map(center + half * offset);
+                let value = g(x)? * jacobian;
+                kronrod += KRONROD_WEIGHTS[k] * value;
+                if k % 2 == 1 {
+                    gauss += GAUSS_WEIGHTS[k / 2] * value;
+                }
+            }
+        }
+        Ok((kronrod * half, ((kronrod - gauss) * half).abs()))
+    }
+
+    /// Integrates `g` from `a` to `b`, either of which may be infinite, with globally adaptive
+    /// Gauss-Kronrod quadrature. The interval is split at the `waypoints`, each piece is mapped
+    /// to a finite interval and divided into `initial` subintervals, and the subinterval with
+    /// the largest error is bisected until the total error estimate is at most
+    /// `max(abs_tol, rel_tol * |value|)`.
+    pub(super) fn adaptive(
+        g: &mut impl FnMut(FLOAT) -> Result<FLOAT, Box<EvalAltResult>>,
+        (a, b): (FLOAT, FLOAT),
+        waypoints: &[FLOAT],
+        initial: usize,
+        settings: &Settings,
+    ) -> Result<Quadrature, Box<EvalAltResult>> {
+        if a.is_nan() || b.is_nan() {
+            return Err(arithmetic_error(
+                "The limits of integration must not be NaN",
+            ));
+        }
+        let (low, high, sign) = if a <= b { (a, b, 1.0) } else { (b, a, -1.0) };
+        if low == high {
+            return Ok(Quadrature {
+                value: 0.0,
+                error: 0.0,
+                intervals: 0,
+                converged: true,
+            });
+        }
+
+        let mut points = vec![low];
+        let mut inside: Vec<FLOAT> = waypoints
+            .iter()
+            .copied()
+            .filter(|w| *w > low && *w < high)
+            .collect();
+        inside.sort_by(|x, y| x.total_cmp(y));
+        points.extend(inside);
+        points.push(high);
+        let mut intervals = vec![];
+        for piece in points.windows(2) {
+            let transform = match (piece[0].is_finite(), piece[1].is_finite()) {
+                (true, true) => Transform::Finite(piece[0], piece[1]),
+                (true, false) => Transform::Above(piece[0]),
+                (false, true) => Transform::Below(piece[1]),
+                (false, false) => Transform::Whole,
+            };
+            let (start, end) = transform.range();
+            for k in 0..initial {
+                let split = (
+                    start + (end - start) * k as FLOAT / initial as FLOAT,
+                    start + (end - start) * (k + 1) as FLOAT / initial as FLOAT,
+                );
+                let (value, error) = rule(g, transform, split)?;
+                intervals.push(Interval {
+                    transform,
+                    start: split.0,
+                    end: split.1,
+                    value,
+                    error,
+                });
+            }
+        }
+
+        loop {
+            let value: FLOAT = intervals.iter().map(|i| i.value).sum();
+            let error: FLOAT = intervals.iter().map(|i| i.error).sum();
+            let converged = error <= FLOAT::max(settings.abs_tol, settings.rel_tol * value.abs());
+            let (worst, _) = intervals
+                .iter()
+                .enumerate()
+                .max_by(|(_, x), (_, y)| x.error.total_cmp(&y.error))
+                .unwrap();
+            let Interval {
+                transform,
+                start,
+                end,
+                ..
+            } = intervals[worst];
+            let middle = 0.5 * (start + end);
+            let too_narrow = middle <= start || middle >= end;
+            if converged || too_narrow || intervals.len() as INT >= settings.max_intervals {
+                return Ok(Quadrature {
+                    value: sign * value,
+                    error,
+                    intervals: intervals.len() as INT,
+                    converged,
+                });
+            }
+            intervals.swap_remove(worst);
+            for split in [(start, middle), (middle, end)] {
+                let (value, error) = rule(g, transform, split)?;
+                intervals.push(Interval {
+                    transform,
+                    start: split.0,
+                    end: split.1,
+                    value,
+                    error,
+                });
+            }
+        }
+    }
+
+    /// Integrates `f` from `a` to `b`, splitting the interval at the `waypoints` option.
+    pub(super) fn gauss_kronrod(
+        integrand: &Integrand,
+        (a, b): (FLOAT, FLOAT),
+        options: &Map,
+    ) -> Result<Quadrature, Box<EvalAltResult>> {
+        let settings = Settings::from_options(options)?;
+        let waypoints = match options.get("waypoints") {
+            Some(waypoints) => dynamic_to_vec_float(waypoints.clone())?,
+            None => vec![],
+        };
+        adaptive(
+            &mut |x| integrand.eval(&[x]),
+            (a, b),
+            &waypoints,
+            10,
+            &settings,
+        )
+    }
+
+    /// Computes an iterated integral over the variables in order, where the limits of each
+    /// variable may depend on the previous ones. Every one-dimensional integral is adaptive
+    /// and starts from a single subinterval. The result converges only if every inner integral
+    /// does.
+    pub(super) fn iterated(
+        integrand: &Integrand,
+        limits: &[(Limit, Limit)],
+        point: &mut Vec<FLOAT>,
+        settings: &Settings,
+    ) -> Result<Quadrature, Box<EvalAltResult>> {
+        let level = point.len();
+        let a = limits[level].0.eval(integrand, point)?;
+        let b = limits[level].1.eval(integrand, point)?;
+        let mut inner_converged = true;
+        let mut g = |x: FLOAT| {
+            point.push(x);
+            let value = if level + 1 == limits.len() {
+                integrand.eval(point)
+            } else {
+                iterated(integrand, limits, point, settings).map(|inner| {
+                    inner_converged &= inner.converged;
+                    inner.value
+                })
+            };
+            point.pop();
+            value
+        };
+        let mut result = adaptive(&mut g, (a, b), &[], 1, settings)?;
+        result.converged &= inner_converged;
+        Ok(result)
+    }
+
+    /// Reads the bounds of a box, a matrix with one row `[a, b]` per variable.
+    pub(super) fn box_bounds(bounds: Array) -> Result<Vec<(FLOAT, FLOAT)>, Box<EvalAltResult>> {
+        let error = || {
+            arithmetic_error(
+                "The bounds must be a matrix with one row [a, b] of finite numbers per variable",
+            )
+        };
+        if bounds.is_empty() {
+            return Err(error());
+        }
+        bounds
+            .into_iter()
+            .map(|row| match dynamic_to_vec_float(row).as_deref() {
+                Ok(&[a, b]) if a.is_finite() && b.is_finite() => Ok((a, b)),
+                _ => Err(error()),
+            })
+            .collect()
+    }
+}
+
+/// Reading the coordinate vectors and values of gridded data, as produced by `meshgrid`.
+mod grid_data {
+    use crate::{arithmetic_error, dynamic_to_vec_float};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT};
+
+    /// Reads the matrix `name` as a list of rows of equal length.
+    pub(super) fn matrix(value: Array, name: &str) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let rows = value
+            .into_iter()
+            .map(|row| {
+                if row.is_array() {
+                    dynamic_to_vec_float(row)
+                } else {
+                    Err(arithmetic_error(format!("'{name}' must be a matrix")))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if rows.is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err(arithmetic_error(format!(
+                "'{name}' must be a non-empty matrix with rows of equal length"
+            )));
+        }
+        Ok(rows)
+    }
+
+    /// Reads the coordinates along one axis, given as a list, a row or column vector, or a
+    /// grid from `meshgrid`, in which case they are the first row (`along_columns`) or the
+    /// first column of the grid.
+    pub(super) fn axis(
+        value: Array,
+        name: &str,
+        along_columns: bool,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        if !value.iter().all(|v| v.is_array()) {
+            return dynamic_to_vec_float(Dynamic::from_array(value));
+        }
+        let rows = matrix(value, name)?;
+        Ok(if rows.len() == 1 || rows[0].len() == 1 {
+            rows.concat()
+        } else if along_columns {
+            rows[0].clone()
+        } else {
+            rows.iter().map(|row| row[0]).collect()
+        })
+    }
+
+    /// Checks that the grid `z` has one row per `y` coordinate and one column per `x`
+    /// coordinate.
+    pub(super) fn check_shape(
+        x: &[FLOAT],
+        y: &[FLOAT],
+        z: &[Vec<FLOAT>],
+    ) -> Result<(), Box<EvalAltResult>> {
+        if z.len() != y.len() || z[0].len() != x.len() {
+            return Err(arithmetic_error(format!(
+                "The grid has {} rows and {} columns, but there are {} y and {} x coordinates",
+                z.len(),
+                z[0].len(),
+                y.len(),
+                x.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Finite differences of lists and grids, for `diff`, `gradient` and `del2`.
+mod finite_differences {
+    use super::grid_data;
+    use crate::arithmetic_error;
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT};
+
+    /// Returns `b - a`, which stays an integer if both are integers.
+    fn difference(a: &Dynamic, b: &Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        match (a.as_int(), b.as_int()) {
+            (Ok(a), Ok(b)) => Ok(Dynamic::from_int(b - a)),
+            _ => {
+                let float = |v: &Dynamic| {
+                    v.as_float()
+                        .or_else(|_| v.as_int().map(|v| v as FLOAT))
+                        .map_err(|_| arithmetic_error("diff expects numeric values"))
+                };
+                Ok(Dynamic::from_float(float(b)? - float(a)?))
+            }
+        }
+    }
+
+    /// Returns the differences between successive elements of `values`.
+    pub(super) fn successive(values: &[Dynamic]) -> Result<Array, Box<EvalAltResult>> {
+        values
+            .windows(2)
+            .map(|pair| difference(&pair[0], &pair[1]))
+            .collect()
+    }
+
+    /// Returns the differences between successive rows of the matrix `rows`.
+    pub(super) fn successive_rows(rows: &[Array]) -> Result<Array, Box<EvalAltResult>> {
+        rows.windows(2)
+            .map(|pair| {
+                pair[0]
+                    .iter()
+                    .zip(&pair[1])
+                    .map(|(a, b)| difference(a, b))
+                    .collect::<Result<Array, _>>()
+                    .map(Dynamic::from_array)
+            })
+            .collect()
+    }
+
+    /// Reads the coordinates of `n` points along one direction from `spacing`, which is either
+    /// the distance between neighbouring points or the coordinates themselves.
+    pub(super) fn coordinates(
+        spacing: Dynamic,
+        n: usize,
+        name: &str,
+        along_columns: bool,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        let x = if spacing.is_int() || spacing.is_float() {
+            let h = spacing
+                .as_float()
+                .unwrap_or_else(|_| spacing.as_int().unwrap() as FLOAT);
+            (0..n).map(|i| i as FLOAT * h).collect()
+        } else if spacing.is_array() {
+            let x = grid_data::axis(spacing.into_array().unwrap(), name, along_columns)?;
+            if x.len() != n {
+                return Err(arithmetic_error(format!(
+                    "'{name}' has {} coordinates, but there are {n} points in that direction",
+                    x.len()
+                )));
+            }
+            x
+        } else {
+            return Err(arithmetic_error(format!(
+                "'{name}' must be a number or an array of coordinates"
+            )));
+        };
+        if x.windows(2).any(|pair| pair[1] == pair[0]) {
+            return Err(arithmetic_error(format!(
+                "The coordinates in '{name}' must be distinct"
+            )));
+        }
+        Ok(x)
+    }
+
+    /// Returns the first derivative of `values` sampled at `x`, using central differences in
+    /// the interior and one-sided differences at the ends.
+    pub(super) fn derivative(values: &[FLOAT], x: &[FLOAT]) -> Vec<FLOAT> {
+        let n = values.len();
+        if n < 2 {
+            return vec![0.0; n];
+        }
+        let mut result = vec![(values[1] - values[0]) / (x[1] - x[0])];
+        for i in 1..n - 1 {
+            result.push((values[i + 1] - values[i - 1]) / (x[i + 1] - x[i - 1]));
+        }
+        result.push((values[n - 1] - values[n - 2]) / (x[n - 1] - x[n - 2]));
+        result
+    }
+
+    /// Returns the second derivative of `values` sampled at `x`, using the three-point formula
+    /// in the interior and linear extrapolation to the ends. Fewer than three points give zero.
+    pub(super) fn second_derivative(values: &[FLOAT], x: &[FLOAT]) -> Vec<FLOAT> {
+        let n = values.len();
+        if n < 3 {
+            return vec![0.0; n];
+        }
+        let mut result = vec![0.0];
+        for i in 1..n - 1 {
+            let (h0, h1) = (x[i] - x[i - 1], x[i + 1] - x[i]);
+            let slopes = (values[i + 1] - values[i]) / h1 - (values[i] - values[i - 1]) / h0;
+            result.push(2.0 * slopes / (h0 + h1));
+        }
+        result.push(0.0);
+        if n == 3 {
+            result[0] = result[1];
+            result[2] = result[1];
+        } else {
+            let extrapolate = |from: usize, to: usize, at: usize| {
+                result[from] + (result[to] - result[from]) * (x[at] - x[from]) / (x[to] - x[from])
+            };
+            let ends = (extrapolate(1, 2, 0), extrapolate(n - 2, n - 3, n - 1));
+            result[0] = ends.0;
+            result[n - 1] = ends.1;
+        }
+        result
+    }
+
+    /// Applies `f` to each column of the matrix `rows`.
+    pub(super) fn by_column<F>(rows: &[Vec<FLOAT>], mut f: F) -> Vec<Vec<FLOAT>>
+    where
+        F: FnMut(&[FLOAT]) -> Vec<FLOAT>,
+    {
+        let columns: Vec<Vec<FLOAT>> = (0..rows[0].len())
+            .map(|j| f(&rows.iter().map(|row| row[j]).collect::<Vec<_>>()))
+            .collect();
+        (0..rows.len())
+            .map(|i| columns.iter().map(|column| column[i]).collect())
+            .collect()
+    }
+
+    /// Converts a matrix of floats to a Rhai array of rows.
+    pub(super) fn to_array(rows: Vec<Vec<FLOAT>>) -> Array {
+        rows.into_iter()
+            .map(|row| Dynamic::from_array(row.into_iter().map(Dynamic::from_float).collect()))
+            .collect()
+    }
+
+    /// Sampled data, either along a line or on a grid whose rows follow `y` and whose columns
+    /// follow `x`.
+    pub(super) enum Samples {
+        /// Samples along a line.
+        List(Vec<FLOAT>),
+        /// Samples on a grid, as a list of rows.
+        Grid(Vec<Vec<FLOAT>>),
+    }
+
+    impl Samples {
+        /// Reads `value` as a grid if it is a list of rows, and as a list otherwise.
+        pub(super) fn new(value: Array, name: &str) -> Result<Self, Box<EvalAltResult>> {
+            if !value.is_empty() && value.iter().all(|v| v.is_array()) {
+                grid_data::matrix(value, name).map(Samples::Grid)
+            } else {
+                crate::dynamic_to_vec_float(Dynamic::from_array(value)).map(Samples::List)
+            }
+        }
+    }
+
+    /// Returns the gradient of sampled data `f` with coordinates `x` and, for grids, `y`.
+    pub(super) fn gradient(
+        f: Samples,
+        x: Dynamic,
+        y: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        match f {
+            Samples::List(values) => {
+                let x = coordinates(x, values.len(), "h", true)?;
+                Ok(Dynamic::from_array(
+                    derivative(&values, &x)
+                        .into_iter()
+                        .map(Dynamic::from_float)
+                        .collect(),
+                ))
+            }
+            Samples::Grid(rows) => {
+                let x = coordinates(x, rows[0].len(), "hx", true)?;
+                let y = coordinates(y, rows.len(), "hy", false)?;
+                let along_x = rows.iter().map(|row| derivative(row, &x)).collect();
+                let along_y = by_column(&rows, |column| derivative(column, &y));
+                let mut result = Map::new();
+                result.insert("x".into(), Dynamic::from_array(to_array(along_x)));
+                result.insert("y".into(), Dynamic::from_array(to_array(along_y)));
+                Ok(Dynamic::from_map(result))
+            }
+        }
+    }
+
+    /// Returns a quarter of the Laplacian of sampled data `f` with coordinates `x` and, for
+    /// grids, `y`.
+    pub(super) fn laplacian(
+        f: Samples,
+        x: Dynamic,
+        y: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        match f {
+            Samples::List(values) => {
+                let x = coordinates(x, values.len(), "h", true)?;
+                Ok(second_derivative(&values, &x)
+                    .into_iter()
+                    .map(|d| Dynamic::from_float(d / 4.0))
+                    .collect())
+            }
+            Samples::Grid(rows) => {
+                let x = coordinates(x, rows[0].len(), "hx", true)?;
+                let y = coordinates(y, rows.len(), "hy", false)?;
+                let along_y = by_column(&rows, |column| second_derivative(column, &y));
+                let total = rows
+                    .iter()
+                    .zip(along_y)
+                    .map(|(row, dyy)| {
+                        second_derivative(row, &x)
+                            .into_iter()
+                            .zip(dyy)
+                            .map(|(dxx, dyy)| (dxx + dyy) / 4.0)
+                            .collect()
+                    })
+                    .collect();
+                Ok(to_array(total))
+            }
+        }
+    }
+}
+
+/// Numerical differentiation of Rhai functions by Richardson-extrapolated central differences,
+/// for `derivative`, `jacobian` and `hessian`.
+mod differentiation {
+    use crate::{arithmetic_error, dynamic_to_vec_float, OperationCounter};
+    use rhai::{Dynamic, EvalAltResult, FnPtr, NativeCallContext, FLOAT, INT};
+
+    /// A function implemented by a Rhai function pointer, called with a number or an array
+    /// depending on how the point of differentiation was given.
+    pub(super) struct Function<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for the function.
+        f: &'a FnPtr,
+        /// Whether the function takes a number rather than an array.
+        scalar: bool,
+        /// Counter of the calls to the function.
+        counter: OperationCounter,
+    }
+
+    impl<'a> Function<'a> {
+        /// Wraps a function pointer, returning it together with the point `x` read as a list.
+        pub(super) fn new(
+            ctx: &'a NativeCallContext<'a>,
+            f: &'a FnPtr,
+            x: Dynamic,
+        ) -> Result<(Self, Vec<FLOAT>), Box<EvalAltResult>> {
+            let scalar = x.is_int() || x.is_float();
+            let x = dynamic_to_vec_float(x)?;
+            if x.is_empty() {
+                return Err(arithmetic_error("The point must not be empty"));
+            }
+            let function = Self {
+                ctx,
+                f,
+                scalar,
+                counter: OperationCounter::new(ctx),
+            };
+            Ok((function, x))
+        }
+
+        /// Evaluates the function at `x`, where all of its values must be finite.
+        pub(super) fn eval(&self, x: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            let argument = if self.scalar {
+                Dynamic::from_float(x[0])
+            } else {
+                Dynamic::from_array(x.iter().map(|v| Dynamic::from_float(*v)).collect())
+            };
+            let value = self.f.call_raw(self.ctx, None, [argument])?;
+            let values = dynamic_to_vec_float(value).map_err(|_| {
+                arithmetic_error("The function must return a number or a numeric array")
+            })?;
+            if values.iter().any(|v| !v.is_finite()) {
+                let at = match x {
+                    [x] => format!("x = {x}"),
+                    _ => format!("{x:?}"),
+                };
+                return Err(arithmetic_error(format!(
+                    "The function is not finite at {at}"
+                )));
+            }
+            Ok(values)
+        }
+
+        /// Evaluates a function that must return a single number at `x`.
+        pub(super) fn eval_scalar(&self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            match self.eval(x)?[..] {
+                [value] => Ok(value),
+                _ => Err(arithmetic_error("The function must return a number")),
+            }
+        }
+    }
+
+    /// Returns the step for a coordinate `x`, scaled so that it is relative for large `x`.
+    fn step(x: FLOAT, base: FLOAT) -> FLOAT {
+        base * x.abs().max(1.0)
+    }
+
+    /// Returns the central difference approximation of the derivative of order `order` at `x`
+    /// with step `h`, whose error is a series in even powers of `h`.
+    fn central_difference(
+        g: &Function,
+        x: FLOAT,
+        order: INT,
+        h: FLOAT,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let mut total = 0.0;
+        let mut binomial = 1.0;
+        for k in 0..=order {
+            let offset = (order as FLOAT / 2.0 - k as FLOAT) * h;
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            total += sign * binomial * g.eval_scalar(&[x + offset])?;
+            binomial *= (order - k) as FLOAT / (k + 1) as FLOAT;
+        }
+        Ok(total / h.powi(order as i32))
+    }
+
+    /// Returns the derivative of order `order` at `x` by Ridders' method: central differences
+    /// with shrinking steps are extrapolated to zero step size in a Neville tableau, stopping
+    /// once the error estimate starts to grow.
+    pub(super) fn ridders(g: &Function, x: FLOAT, order: INT) -> Result<FLOAT, Box<EvalAltResult>> {
+        const SHRINK: FLOAT = 1.4;
+        const TABLE_SIZE: usize = 10;
+        let mut h = step(x, 0.1);
+        let mut table = vec![vec![central_difference(g, x, order, h)?]];
+        let (mut best, mut error) = (table[0][0], FLOAT::INFINITY);
+        for i in 1..TABLE_SIZE {
+            h /= SHRINK;
+            let mut row = vec![central_difference(g, x, order, h)?];
+            let mut factor = SHRINK * SHRINK;
+            for j in 1..=i {
+                let previous = &table[i - 1];
+                let value = (row[j - 1] * factor - previous[j - 1]) / (factor - 1.0);
+                factor *= SHRINK * SHRINK;
+                let estimate = (value - row[j - 1])
+                    .abs()
+                    .max((value - previous[j - 1]).abs());
+                if estimate <= error {
+                    (best, error) = (value, estimate);
+                }
+                row.push(value);
+            }
+            let growth = (row[i] - table[i - 1][i - 1]).abs();
+            table.push(row);
+            if growth >= 2.0 * error {
+                break;
+            }
+        }
+        Ok(best)
+    }
+
+    /// Returns the Jacobian of the vector-valued function `g` at `x`, one row per output and
+    /// one column per variable, from central differences with one Richardson extrapolation.
+    pub(super) fn jacobian(
+        g: &Function,
+        x: &[FLOAT],
+    ) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let outputs = g.eval(x)?.len();
+        let mut point = x.to_vec();
+        let mut difference = |j: usize, h: FLOAT| -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            point[j] = x[j] + h;
+            let forward = g.eval(&point)?;
+            point[j] = x[j] - h;
+            let backward = g.eval(&point)?;
+            point[j] = x[j];
+            if forward.len() != outputs || backward.len() != outputs {
+                return Err(arithmetic_error(
+                    "The function must return the same number of values at every point",
+                ));
+            }
+            Ok(forward
+                .iter()
+                .zip(backward)
+                .map(|(f, b)| (f - b) / (2.0 * h))
+                .collect())
+        };
+        let mut columns = vec![];
+        for (j, xj) in x.iter().enumerate() {
+            let h = step(*xj, FLOAT::EPSILON.powf(0.2));
+            let coarse = difference(j, h)?;
+            let fine = difference(j, h / 2.0)?;
+            columns.push(
+                fine.iter()
+                    .zip(coarse)
+                    .map(|(f, c)| (4.0 * f - c) / 3.0)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        Ok((0..outputs)
+            .map(|i| columns.iter().map(|column| column[i]).collect())
+            .collect())
+    }
+
+    /// Returns the Hessian of the scalar function `g` at `x` from central differences with one
+    /// Richardson extrapolation, symmetric by construction.
+    pub(super) fn hessian(
+        g: &Function,
+        x: &[FLOAT],
+    ) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let n = x.len();
+        let center = g.eval_scalar(x)?;
+        let mut point = x.to_vec();
+        let mut second = |i: usize, j: usize, scale: FLOAT| -> Result<FLOAT, Box<EvalAltResult>> {
+            let hi = step(x[i], FLOAT::EPSILON.powf(1.0 / 6.0)) * scale;
+            let hj = step(x[j], FLOAT::EPSILON.powf(1.0 / 6.0)) * scale;
+            let mut at = |di: FLOAT, dj: FLOAT| {
+                point[i] += di;
+                point[j] += dj;
+                let value = g.eval_scalar(&point);
+                point[i] = x[i];
+                point[j] = x[j];
+                value
+            };
+            if i == j {
+                Ok((at(hi, 0.0)? - 2.0 * center + at(-hi, 0.0)?) / (hi * hi))
+            } else {
+                let corners = at(hi, hj)? - at(hi, -hj)? - at(-hi, hj)? + at(-hi, -hj)?;
+                Ok(corners / (4.0 * hi * hj))
+            }
+        };
+        let mut upper = vec![];
+        for i in 0..n {
+            let mut row = vec![];
+            for j in i..n {
+                let coarse = second(i, j, 1.0)?;
+                let fine = second(i, j, 0.5)?;
+                row.push((4.0 * fine - coarse) / 3.0);
+            }
+            upper.push(row);
+        }
+        Ok((0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| upper[i.min(j)][i.max(j) - i.min(j)])
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// Smolyak sparse grids built from nested Clenshaw-Curtis rules, for `sparsegrid`.
+mod sparse_grid {
+    use super::quadrature::Integrand;
+    use crate::arithmetic_error;
+    use rhai::{Dynamic, EvalAltResult, Map, FLOAT, INT};
+    use std::collections::BTreeMap;
+    use std::f64::consts::PI;
+
+    /// Highest supported level. The one-dimensional rule of this level has 4097 nodes.
+    const MAX_LEVEL: INT = 12;
+
+    /// Nodes and weights of the Clenshaw-Curtis rule of level `i` on `[-1, 1]`, which has a
+    /// single node for `i = 0` and `2^i + 1` nodes otherwise. The node `cos(pi k / 2^scale)` is
+    /// identified by the integer `k`, so that the rules of all levels up to `scale - 1` share
+    /// their common nodes.
+    fn clenshaw_curtis(i: u32, scale: u32) -> Vec<(u64, FLOAT)> {
+        if i == 0 {
+            return vec![(1 << (scale - 1), 2.0)];
+        }
+        let n = 1_u64 << i;
+        (0..=n)
+            .map(|j| {
+                let theta = PI * j as FLOAT / n as FLOAT;
+                let sum: FLOAT = (1..=n / 2)
+                    .map(|k| {
+                        let b = if k == n / 2 { 1.0 } else { 2.0 };
+                        b * (2.0 * k as FLOAT * theta).cos() / (4 * k * k - 1) as FLOAT
+                    })
+                    .sum();
+                let c = if j == 0 || j == n { 1.0 } else { 2.0 };
+                (j << (scale - i), c / n as FLOAT * (1.0 - sum))
+            })
+            .collect()
+    }
+
+    /// Calls `visit` with every multi-index of length `index.len()` whose entries from `k` on
+    /// sum to at most `remaining`.
+    fn multi_indices(
+        index: &mut Vec<usize>,
+        k: usize,
+        remaining: usize,
+        visit: &mut impl FnMut(&[usize]),
+    ) {
+        if k == index.len() {
+            visit(index);
+            return;
+        }
+        for i in 0..=remaining {
+            index[k] = i;
+            multi_indices(index, k + 1, remaining - i, visit);
+        }
+    }
+
+    /// Weights of the Smolyak rule of the given level on `[-1, 1]^d`, keyed by their nodes,
+    /// from the combination technique: the sum over the multi-indices `i` with
+    /// `level - d < |i| <= level` of `(-1)^(level - |i|) C(d - 1, level - |i|)` times the tensor
+    /// product of the rules `rules[i[0]]`, ..., `rules[i[d - 1]]`.
+    fn smolyak(d: usize, level: usize, rules: &[Vec<(u64, FLOAT)>]) -> BTreeMap<Vec<u64>, FLOAT> {
+        let mut weights = BTreeMap::new();
+        let mut index = vec![0; d];
+        multi_indices(&mut index, 0, level, &mut |index| {
+            let j = level - index.iter().sum::<usize>();
+            if j >= d {
+                return;
+            }
+            let binomial = (0..j).fold(1.0, |c, m| c * (d - 1 - m) as FLOAT / (m + 1) as FLOAT);
+            let coefficient = if j.is_multiple_of(2) {
+                binomial
+            } else {
+                -binomial
+            };
+            let mut position = vec![0; d];
+            'tensor: loop {
+                let mut key = Vec::with_capacity(d);
+                let mut weight = coefficient;
+                for (k, &i) in index.iter().enumerate() {
+                    let (node, w) = rules[i][position[k]];
+                    key.push(node);
+                    weight *= w;
+                }
+                *weights.entry(key).or_insert(0.0) += weight;
+                for (k, &i) in index.iter().enumerate() {
+                    position[k] += 1;
+                    if position[k] < rules[i].len() {
+                        continue 'tensor;
+                    }
+                    position[k] = 0;
+                }
+                break;
+            }
+        });
+        weights
+    }
+
+    /// Integrates `f(x)` over the box with the Smolyak rule of the given level. The rule of
+    /// the level below uses a subset of the nodes, and the difference of the two estimates
+    /// serves as the error estimate.
+    pub(super) fn integrate(
+        integrand: &Integrand,
+        bounds: &[(FLOAT, FLOAT)],
+        level: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if !(1..=MAX_LEVEL).contains(&level) {
+            return Err(arithmetic_error(format!(
+                "The level must be between 1 and {MAX_LEVEL}"
+            )));
+        }
+        let (d, level) = (bounds.len(), level as usize);
+        let scale = level as u32 + 1;
+        let rules: Vec<_> = (0..=level as u32)
+            .map(|i| clenshaw_curtis(i, scale))
+            .collect();
+        let fine = smolyak(d, level, &rules);
+        let coarse = smolyak(d, level - 1, &rules);
+
+        let jacobian: FLOAT = bounds.iter().map(|(a, b)| 0.5 * (b - a)).product();
+        let mut values = BTreeMap::new();
+        let mut x = vec![0.0; d];
+        let mut value = 0.0;
+        for (key, weight) in &fine {
+            for (k, (a, b)) in bounds.iter().enumerate() {
+                let t = (PI * key[k] as FLOAT / (1_u64 << scale) as FLOAT).cos();
+                x[k] = 0.5 * (a + b) + 0.5 * (b - a) * t;
+            }
+            let fx = integrand.eval(&x)?;
+            value += weight * fx;
+            values.insert(key, fx);
+        }
+        let estimate: FLOAT = coarse.iter().map(|(key, w)| w * values[key]).sum();
+
+        let mut result = Map::new();
+        result.insert("value".into(), Dynamic::from_float(jacobian * value));
+        result.insert(
+            "error".into(),
+            Dynamic::from_float((jacobian * (value - estimate)).abs()),
+        );
+        result.insert(
+            "evaluations".into(),
+            Dynamic::from_int(integrand.evaluations.get()),
+        );
+        Ok(result)
+    }
+}
+
+/// Randomized quasi-Monte Carlo integration over a box, for `mcintegrate`.
+#[cfg(feature = "rand")]
+mod monte_carlo {
+    use super::quadrature::Integrand;
+    use crate::{arithmetic_error, primes, radical_inverse};
+    use randlib::{rngs::StdRng, Rng};
+    use rhai::{Dynamic, EvalAltResult, Map, FLOAT, INT};
+
+    /// Number of independently shifted copies of the point set.
+    const REPLICATES: usize = 10;
+
+    /// Estimates the integral of `f(x)` over the box with `n` points of the Halton sequence,
+    /// split into ten copies with independent random shifts modulo one (Cranley-Patterson
+    /// rotations). The spread of the copies gives the error estimate.
+    pub(super) fn shifted_halton(
+        integrand: &Integrand,
+        bounds: &[(FLOAT, FLOAT)],
+        n: INT,
+        rng: &mut StdRng,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if n < REPLICATES as INT {
+            return Err(arithmetic_error(format!(
+                "The number of points must be at least {REPLICATES}"
+            )));
+        }
+        let per_replicate = n as usize / REPLICATES;
+        let bases = primes(bounds.len());
+        let volume: FLOAT = bounds.iter().map(|(a, b)| b - a).product();
+        let mut means = Vec::with_capacity(REPLICATES);
+        let mut x = vec![0.0; bounds.len()];
+        for _ in 0..REPLICATES {
+            let shift: Vec<FLOAT> = bounds.iter().map(|_| rng.random::<FLOAT>()).collect();
+            let mut sum = 0.0;
+            for i in 1..=per_replicate {
+                for (j, (a, b)) in bounds.iter().enumerate() {
+                    let u = (radical_inverse(i as u64, bases[j]) + shift[j]).fract();
+                    x[j] = a + (b - a) * u;
+                }
+                sum += integrand.eval(&x)?;
+            }
+            means.push(sum / per_replicate as FLOAT);
+        }
+        let mean = means.iter().sum::<FLOAT>() / REPLICATES as FLOAT;
+        let variance = means.iter().map(|m| (m - mean).powi(2)).sum::<FLOAT>()
+            / (REPLICATES * (REPLICATES - 1)) as FLOAT;
+        let mut result = Map::new();
+        result.insert("value".into(), Dynamic::from_float(volume * mean));
+        result.insert(
+            "error".into(),
+            Dynamic::from_float(volume.abs() * variance.sqrt()),
+        );
+        result.insert(
+            "evaluations".into(),
+            Dynamic::from_int(integrand.evaluations.get()),
+        );
+        Ok(result)
+    }
+}
+
 #[export_module]
 pub mod int_and_diff {
-    use crate::if_list_convert_to_vec_float_and_do;
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT};
+    use super::differentiation;
+    use super::finite_differences;
+    use super::grid_data;
+    #[cfg(feature = "rand")]
+    use super::monte_carlo;
+    use super::quadrature::{box_bounds, gauss_kronrod, iterated, Integrand, Limit, Settings};
+    use super::sparse_grid;
+    use crate::{
+        arithmetic_error, if_int_convert_to_float_and_do, if_list_convert_to_vec_float_and_do,
+    };
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
 
     /// Returns the approximate integral of the curve defined by `y` and `x` using the trapezoidal method.
     /// ```typescript
@@ -21,11 +1208,7 @@ pub mod int_and_diff {
     #[rhai_fn(name = "trapz", return_raw)]
     pub fn trapz(x: Array, y: Array) -> Result<Dynamic, Box<EvalAltResult>> {
         if x.len() != y.len() {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have the same length".to_string(),
-                Position::NONE,
-            )
-            .into())
+            Err(arithmetic_error("The arrays must have the same length"))
         } else {
             if_list_convert_to_vec_float_and_do(&mut y.clone(), |yf| {
                 if_list_convert_to_vec_float_and_do(&mut x.clone(), |xf| {
@@ -62,6 +1245,116 @@ pub mod int_and_diff {
         })
     }
 
+    /// Returns the approximate integral of the curve defined by `y` and `x` using Simpson's
+    /// rule, which is exact for quadratics. Each pair of intervals is integrated with the rule
+    /// for non-uniform spacing; with an odd number of intervals, the last one is integrated with
+    /// the quadratic through the last three samples. Two samples fall back to the trapezoidal
+    /// method.
+    /// ```typescript
+    /// let x = [0.0, 0.5, 1.0, 1.5, 2.0];
+    /// let A = simpson(x, x.map(|x| x ** 3));
+    /// assert_eq(A, 4.0);
+    /// ```
+    /// ```typescript
+    /// // Non-uniform spacing and an even number of samples
+    /// let x = [0.0, 0.3, 1.0, 1.2, 2.0, 2.1];
+    /// let A = simpson(x, x.map(|x| x ** 2 - x));
+    /// assert_approx_eq(A, 2.1 ** 3 / 3.0 - 2.1 ** 2 / 2.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "simpson", return_raw)]
+    pub fn simpson(x: Array, y: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        if x.len() != y.len() {
+            return Err(arithmetic_error("The arrays must have the same length"));
+        }
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |y| {
+            if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+                let h: Vec<FLOAT> = x.windows(2).map(|w| w[1] - w[0]).collect();
+                if h.contains(&0.0) {
+                    return Err(arithmetic_error("The x values must be distinct"));
+                }
+                let n = y.len();
+                if n < 3 {
+                    return Ok(h.first().map_or(0.0, |h| h * (y[0] + y[1]) / 2.0));
+                }
+                let mut total = 0.0;
+                for i in (0..n - 2).step_by(2) {
+                    let (h0, h1) = (h[i], h[i + 1]);
+                    total += (h0 + h1) / 6.0
+                        * ((2.0 - h1 / h0) * y[i]
+                            + (h0 + h1).powi(2) / (h0 * h1) * y[i + 1]
+                            + (2.0 - h0 / h1) * y[i + 2]);
+                }
+                if n % 2 == 0 {
+                    let (h0, h1) = (h[n - 3], h[n - 2]);
+                    total += (2.0 * h1 * h1 + 3.0 * h0 * h1) / (6.0 * (h0 + h1)) * y[n - 1]
+                        + (h1 * h1 + 3.0 * h0 * h1) / (6.0 * h0) * y[n - 2]
+                        - h1.powi(3) / (6.0 * h0 * (h0 + h1)) * y[n - 3];
+                }
+                Ok(total)
+            })
+        })
+    }
+
+    /// Returns the approximate integral of the curve defined by `y` using Simpson's rule.
+    /// Assumes that x-values have unit spacing.
+    /// ```typescript
+    /// let A = simpson([1, 4, 9, 16, 25]);
+    /// assert_approx_eq(A, 124.0 / 3.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "simpson", return_raw, pure)]
+    pub fn simpson_unit(y: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let x = (0..y.len()).map(|i| Dynamic::from_int(i as INT)).collect();
+        simpson(x, y.clone())
+    }
+
+    /// Returns the approximate integral of uniformly sampled data `y` with spacing `dx` using
+    /// Romberg's method: trapezoidal estimates with step sizes `dx`, `2 dx`, `4 dx`, ... are
+    /// combined by Richardson extrapolation. The number of samples must be one more than a
+    /// power of two.
+    /// ```typescript
+    /// let x = linspace(0, pi, 17);
+    /// let A = romberg(x.map(|x| sin(x)), x[1] - x[0]);
+    /// assert_approx_eq(A, 2.0, 1e-8);
+    /// ```
+    #[rhai_fn(name = "romberg", return_raw)]
+    pub fn romberg(y: Array, dx: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        let dx = if_int_convert_to_float_and_do(dx, Ok)?;
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |y| {
+            let intervals = y.len().saturating_sub(1);
+            if !intervals.is_power_of_two() {
+                return Err(arithmetic_error(format!(
+                    "Romberg integration needs 2^k + 1 samples, but there are {}",
+                    y.len()
+                )));
+            }
+            let levels = intervals.trailing_zeros() as usize;
+            let mut table: Vec<FLOAT> = vec![];
+            for level in 0..=levels {
+                let stride = intervals >> level;
+                let h = dx * stride as FLOAT;
+                let interior: FLOAT = (stride..intervals).step_by(stride).map(|i| y[i]).sum();
+                let mut row = vec![h * ((y[0] + y[intervals]) / 2.0 + interior)];
+                for k in 1..=level {
+                    let factor = (4.0 as FLOAT).powi(k as i32);
+                    row.push(row[k - 1] + (row[k - 1] - table[k - 1]) / (factor - 1.0));
+                }
+                table = row;
+            }
+            Ok(table[levels])
+        })
+    }
+
+    /// Returns the approximate integral of uniformly sampled data `y` with unit spacing using
+    /// Romberg's method.
+    /// ```typescript
+    /// let A = romberg([0, 1, 8, 27, 64]);
+    /// assert_eq(A, 64.0);
+    /// ```
+    #[rhai_fn(name = "romberg", return_raw, pure)]
+    pub fn romberg_unit(y: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        romberg(y.clone(), Dynamic::FLOAT_ONE)
+    }
+
     /// Returns the difference between successive elements of a 1-D array.
     /// ```typescript
     /// let arr = [2, 5, 1, 7, 8];
@@ -92,4 +1385,616 @@ pub mod int_and_diff {
             },
         )
     }
+
+    /// Returns the `n`-th order differences of an array. For a 1-D array the differences are
+    /// taken between successive elements; for a matrix they are taken between successive rows.
+    /// Integers stay integers.
+    /// ```typescript
+    /// let d = diff([1, 4, 9, 16, 25], 2);
+    /// assert_eq(d, [2, 2, 2]);
+    /// ```
+    /// ```typescript
+    /// let d = diff([[1, 2], [4, 8], [9, 18]], 1);
+    /// assert_eq(d, [[3, 6], [5, 10]]);
+    /// ```
+    #[rhai_fn(name = "diff", return_raw)]
+    pub fn diff_order(arr: Array, n: INT) -> Result<Array, Box<EvalAltResult>> {
+        let is_matrix = !arr.is_empty() && arr.iter().all(|row| row.is_array());
+        diff_along(arr, n, if is_matrix { 1 } else { 2 })
+    }
+
+    /// Returns the `n`-th order differences of a matrix along dimension `dim`: `1` takes
+    /// differences between successive rows and `2` between successive columns. A 1-D array
+    /// is treated as a row vector, so it can only be differenced along dimension `2`.
+    /// ```typescript
+    /// let d = diff([[1, 4, 9], [2, 3, 5]], 1, 2);
+    /// assert_eq(d, [[3, 5], [1, 2]]);
+    /// ```
+    /// ```typescript
+    /// let d = diff([1.0, 0.5, 0.0, 2.0], 3, 2);
+    /// assert_eq(d, [2.5]);
+    /// ```
+    #[rhai_fn(name = "diff", return_raw)]
+    pub fn diff_along(arr: Array, n: INT, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        if n < 0 {
+            return Err(arithmetic_error(format!(
+                "The order of the differences must be non-negative, not {n}"
+            )));
+        }
+        if dim != 1 && dim != 2 {
+            return Err(arithmetic_error(format!(
+                "The dimension must be 1 or 2, not {dim}"
+            )));
+        }
+        let is_matrix = !arr.is_empty() && arr.iter().all(|row| row.is_array());
+        let mut result = arr;
+        for _ in 0..n {
+            result = match (is_matrix, dim) {
+                (false, 1) => {
+                    return Err(arithmetic_error(
+                        "A 1-D array can only be differenced along dimension 2".to_string(),
+                    ))
+                }
+                (false, _) => finite_differences::successive(&result)?,
+                (true, 1) => {
+                    let rows = result
+                        .into_iter()
+                        .map(|row| row.into_array().unwrap())
+                        .collect::<Vec<_>>();
+                    finite_differences::successive_rows(&rows)?
+                }
+                (true, _) => result
+                    .into_iter()
+                    .map(|row| {
+                        finite_differences::successive(&row.into_array().unwrap())
+                            .map(Dynamic::from_array)
+                    })
+                    .collect::<Result<Array, _>>()?,
+            };
+        }
+        Ok(result)
+    }
+
+    /// Returns the numerical gradient of sampled data `F` with unit spacing, using central
+    /// differences in the interior and one-sided differences at the ends. For a 1-D array the
+    /// result is an array; for a matrix, whose rows correspond to `y` and columns to `x` as
+    /// for the grids produced by `meshgrid`, it is an object map with the derivatives `x`
+    /// along each row and `y` along each column.
+    /// ```typescript
+    /// let g = gradient([1, 4, 9, 16, 25]);
+    /// assert_eq(g, [3.0, 4.0, 6.0, 8.0, 9.0]);
+    /// ```
+    /// ```typescript
+    /// let g = gradient([[1, 2, 4], [3, 5, 8]]);
+    /// assert_eq(g.x, [[1.0, 1.5, 2.0], [2.0, 2.5, 3.0]]);
+    /// assert_eq(g.y, [[2.0, 3.0, 4.0], [2.0, 3.0, 4.0]]);
+    /// ```
+    #[rhai_fn(name = "gradient", return_raw)]
+    pub fn gradient_unit(f: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        gradient(f, Dynamic::ONE)
+    }
+
+    /// Returns the numerical gradient of sampled data `F` with spacing `h`, which is either the
+    /// distance between neighbouring points or an array of coordinates. For a matrix the same
+    /// spacing is used in both directions.
+    /// ```typescript
+    /// let x = [0.0, 0.5, 1.5, 2.0];
+    /// let g = gradient(x.map(|x| 3.0 * x + 1.0), x);
+    /// assert_eq(g, [3.0, 3.0, 3.0, 3.0]);
+    /// ```
+    #[rhai_fn(name = "gradient", return_raw)]
+    pub fn gradient(f: Array, h: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        let f = finite_differences::Samples::new(f, "F")?;
+        finite_differences::gradient(f, h.clone(), h)
+    }
+
+    /// Returns the numerical gradient of gridded data `F` as an object map with the
+    /// derivatives `x` and `y`, with the spacing in each direction given either as a distance
+    /// between neighbouring points or as coordinates, which may be the grids from `meshgrid`.
+    /// ```typescript
+    /// let y = [0.0, 0.1, 0.4, 1.0];
+    /// let g = meshgrid(linspace(0, 2, 5), y);
+    /// let F = g.x.map(|row, i| row.map(|x| x * x + 3.0 * y[i]));
+    /// let dF = gradient(F, g.x, g.y);
+    /// assert_approx_eq(dF.x[2], [0.5, 1.0, 2.0, 3.0, 3.5], 1e-12);
+    /// assert_approx_eq(dF.y[3], [3.0, 3.0, 3.0, 3.0, 3.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "gradient", return_raw)]
+    pub fn gradient_xy(f: Array, hx: Dynamic, hy: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        match finite_differences::Samples::new(f, "F")? {
+            finite_differences::Samples::List(_) => Err(arithmetic_error(
+                "A 1-D array takes a single spacing".to_string(),
+            )),
+            f => Ok(finite_differences::gradient(f, hx, hy)?.cast::<Map>()),
+        }
+    }
+
+    /// Returns the discrete Laplacian of sampled data `F` with unit spacing, scaled by a
+    /// quarter as in MATLAB, so that `4 * del2(F)` approximates `F_xx + F_yy` on a grid (or
+    /// `F_xx` for a 1-D array). Second differences are taken in the interior and extrapolated
+    /// linearly to the edges.
+    /// ```typescript
+    /// let L = del2([1, 4, 9, 16, 25]);
+    /// assert_eq(L, [0.5, 0.5, 0.5, 0.5, 0.5]);
+    /// ```
+    /// ```typescript
+    /// let L = del2([[0, 1, 4], [1, 2, 5], [4, 5, 8]]);
+    /// assert_eq(L, [[1.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 1.0]]);
+    /// ```
+    #[rhai_fn(name = "del2", return_raw)]
+    pub fn del2_unit(f: Array) -> Result<Array, Box<EvalAltResult>> {
+        del2(f, Dynamic::ONE)
+    }
+
+    /// Returns the discrete Laplacian of sampled data `F`, scaled by a quarter, with spacing
+    /// `h` in every direction given as a distance or as coordinates.
+    /// ```typescript
+    /// let x = linspace(0, 1, 11);
+    /// let L = del2(x.map(|x| x ** 3), x);
+    /// assert_approx_eq(L, x.map(|x| 1.5 * x), 1e-12);
+    /// ```
+    #[rhai_fn(name = "del2", return_raw)]
+    pub fn del2(f: Array, h: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        let f = finite_differences::Samples::new(f, "F")?;
+        finite_differences::laplacian(f, h.clone(), h)
+    }
+
+    /// Returns the discrete Laplacian of gridded data `F`, scaled by a quarter, with the
+    /// spacing in each direction given as a distance or as coordinates.
+    /// ```typescript
+    /// let y = linspace(0, 2, 5);
+    /// let g = meshgrid(linspace(-1, 1, 9), y);
+    /// let F = g.x.map(|row, i| row.map(|x| x * x - 2.0 * y[i] ** 2));
+    /// let L = del2(F, g.x, g.y);
+    /// // F_xx + F_yy = -2 everywhere
+    /// assert(L.all(|row| row.all(|v| abs(v + 0.5) < 1e-12)));
+    /// ```
+    #[rhai_fn(name = "del2", return_raw)]
+    pub fn del2_xy(f: Array, hx: Dynamic, hy: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        match finite_differences::Samples::new(f, "F")? {
+            finite_differences::Samples::List(_) => Err(arithmetic_error(
+                "A 1-D array takes a single spacing".to_string(),
+            )),
+            f => finite_differences::laplacian(f, hx, hy),
+        }
+    }
+
+    /// Returns the derivative of the function `f` at `x`, computed by Ridders' method: central
+    /// differences with steps shrinking from `0.1 * max(|x|, 1)` are extrapolated to zero step
+    /// size, which typically gives about ten significant digits.
+    /// ```typescript
+    /// let d = derivative(|x| exp(x) * sin(x), 1.0);
+    /// assert_approx_eq(d, exp(1.0) * (sin(1.0) + cos(1.0)), 1e-10);
+    /// ```
+    #[rhai_fn(name = "derivative", return_raw)]
+    pub fn derivative_first(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        derivative(ctx, f, x, 1)
+    }
+
+    /// Returns the derivative of order `order`, from 1 to 4, of the function `f` at `x`,
+    /// computed by Ridders' method. Higher orders lose more digits to rounding.
+    /// ```typescript
+    /// let d2 = derivative(|x| x ** 4 - 3.0 * x, 2.0, 2);
+    /// assert_approx_eq(d2, 48.0, 1e-8);
+    /// ```
+    /// ```typescript
+    /// let d3 = derivative(|x| sin(x), 0.5, 3);
+    /// assert_approx_eq(d3, -cos(0.5), 1e-6);
+    /// ```
+    #[rhai_fn(name = "derivative", return_raw)]
+    pub fn derivative(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x: Dynamic,
+        order: INT,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        if !(1..=4).contains(&order) {
+            return Err(arithmetic_error(format!(
+                "The order of the derivative must be between 1 and 4, not {order}"
+            )));
+        }
+        if !(x.is_int() || x.is_float()) {
+            return Err(arithmetic_error(
+                "The point of differentiation must be a number",
+            ));
+        }
+        let (g, x) = differentiation::Function::new(&ctx, &f, x)?;
+        differentiation::ridders(&g, x[0], order)
+    }
+
+    /// Returns the Jacobian matrix of the function `F` at `x`, with one row per value returned
+    /// by `F` and one column per variable. `x` may be a number or an array, and is passed to
+    /// `F` in the same form. The derivatives are central differences improved by one step of
+    /// Richardson extrapolation.
+    /// ```typescript
+    /// let F = |x| [x[0] * x[1], sin(x[0]) + x[1] ** 2, exp(x[1])];
+    /// let J = jacobian(F, [1.0, 2.0]);
+    /// assert_approx_eq(J[0], [2.0, 1.0], 1e-9);
+    /// assert_approx_eq(J[1], [cos(1.0), 4.0], 1e-9);
+    /// assert_approx_eq(J[2], [0.0, exp(2.0)], 1e-9);
+    /// ```
+    #[rhai_fn(name = "jacobian", return_raw)]
+    pub fn jacobian(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let (g, x) = differentiation::Function::new(&ctx, &f, x)?;
+        differentiation::jacobian(&g, &x).map(finite_differences::to_array)
+    }
+
+    /// Returns the Hessian matrix of the scalar function `f` at `x`, computed by central
+    /// differences improved by one step of Richardson extrapolation. The result is symmetric.
+    /// ```typescript
+    /// let f = |x| x[0] ** 2 * x[1] + exp(x[0] * x[1]);
+    /// let H = hessian(f, [0.5, 1.0]);
+    /// let e = exp(0.5);
+    /// assert_approx_eq(H[0], [2.0 + e, 1.0 + 1.5 * e], 1e-7);
+    /// assert_approx_eq(H[1], [1.0 + 1.5 * e, 0.25 * e], 1e-7);
+    /// ```
+    #[rhai_fn(name = "hessian", return_raw)]
+    pub fn hessian(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let (g, x) = differentiation::Function::new(&ctx, &f, x)?;
+        differentiation::hessian(&g, &x).map(finite_differences::to_array)
+    }
+
+    /// Returns the integral of the function `f` from `a` to `b`, either of which may be
+    /// infinite, computed with adaptive Gauss-Kronrod quadrature to an absolute error of
+    /// `1e-10` or a relative error of `1e-6`, whichever is larger. Fails if the integrand is
+    /// not finite at a quadrature node or the tolerance cannot be met.
+    /// ```typescript
+    /// let I = integral(|x| x ** 2, 0, 3);
+    /// assert_approx_eq(I, 9.0, 1e-12);
+    /// ```
+    /// ```typescript
+    /// // Gaussian integral
+    /// let I = integral(|x| exp(-x * x), -inf, inf);
+    /// assert_approx_eq(I, sqrt(pi), 1e-8);
+    /// ```
+    #[rhai_fn(name = "integral", return_raw)]
+    pub fn integral(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        integral_with_options(ctx, f, a, b, Map::new())
+    }
+
+    /// Returns the integral of the function `f` from `a` to `b` with adaptive Gauss-Kronrod
+    /// quadrature. The following options are supported:
+    /// - `abs_tol`: absolute error tolerance (default `1e-10`).
+    /// - `rel_tol`: relative error tolerance (default `1e-6`).
+    /// - `max_intervals`: maximum number of subintervals (default `650`).
+    /// - `waypoints`: array of points inside the interval where the integrand has
+    ///   discontinuities or kinks, which become edges of the subintervals.
+    ///
+    /// Each call of `f` counts as one operation towards the engine's operation limit.
+    /// ```typescript
+    /// // Expected value of an exponential distribution with rate 2
+    /// let mean = integral(|x| x * 2.0 * exp(-2.0 * x), 0, inf, #{rel_tol: 1e-12});
+    /// assert_approx_eq(mean, 0.5, 1e-12);
+    /// ```
+    /// ```typescript
+    /// let I = integral(|x| if x < 1.0 { 1.0 } else { 3.0 }, 0, 2, #{waypoints: [1.0]});
+    /// assert_approx_eq(I, 4.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "integral", return_raw)]
+    pub fn integral_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+        options: Map,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let a = if_int_convert_to_float_and_do(a, Ok)?;
+        let b = if_int_convert_to_float_and_do(b, Ok)?;
+        let integrand = Integrand::new(&ctx, &f, false);
+        gauss_kronrod(&integrand, (a, b), &options)?.checked_value()
+    }
+
+    /// Integrates the function `f` from `a` to `b`, either of which may be infinite, with
+    /// adaptive Gauss-Kronrod quadrature, and returns an object map with the integral `value`,
+    /// the estimate of its absolute `error`, the number of subintervals `intervals` and
+    /// function `evaluations`, and whether the tolerance was met (`converged`). Unlike
+    /// `integral`, it does not fail when the tolerance cannot be met.
+    /// ```typescript
+    /// let result = quadgk(|x| sin(x), 0, pi);
+    /// assert(result.converged);
+    /// assert_approx_eq(result.value, 2.0, 1e-12);
+    /// assert(result.error < 1e-10);
+    /// ```
+    #[rhai_fn(name = "quadgk", return_raw)]
+    pub fn quadgk(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        quadgk_with_options(ctx, f, a, b, Map::new())
+    }
+
+    /// Integrates the function `f` from `a` to `b` with adaptive Gauss-Kronrod quadrature,
+    /// supporting the options of `integral`.
+    /// ```typescript
+    /// // An integrable singularity at the lower limit
+    /// let result = quadgk(|x| 1.0 / sqrt(x), 0, 1, #{abs_tol: 1e-12, rel_tol: 1e-12});
+    /// assert_approx_eq(result.value, 2.0, 1e-10);
+    /// ```
+    /// ```typescript
+    /// let result = quadgk(|x| sin(1.0 / x), 0.001, 1, #{max_intervals: 20});
+    /// assert(!result.converged);
+    /// assert_eq(result.intervals, 20);
+    /// ```
+    #[rhai_fn(name = "quadgk", return_raw)]
+    pub fn quadgk_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let a = if_int_convert_to_float_and_do(a, Ok)?;
+        let b = if_int_convert_to_float_and_do(b, Ok)?;
+        let integrand = Integrand::new(&ctx, &f, false);
+        let result = gauss_kronrod(&integrand, (a, b), &options)?;
+        Ok(result.into_map(integrand.evaluations.get()))
+    }
+
+    /// Returns the integral of `f(x, y)` over `xa <= x <= xb` and `ya <= y <= yb`, computed
+    /// as an iterated integral with adaptive Gauss-Kronrod quadrature in each variable. The
+    /// inner limits `ya` and `yb` may be numbers or functions of `x`, and any limit may be
+    /// infinite. The tolerances are the same as for `integral`.
+    /// ```typescript
+    /// let I = integral2(|x, y| x * y ** 2, 0, 2, 0, 1);
+    /// assert_approx_eq(I, 2.0 / 3.0, 1e-10);
+    /// ```
+    /// ```typescript
+    /// // Area of the unit disk
+    /// let I = integral2(|x, y| 1.0, -1, 1, |x| -sqrt(1.0 - x * x), |x| sqrt(1.0 - x * x));
+    /// assert_approx_eq(I, pi, 1e-8);
+    /// ```
+    #[rhai_fn(name = "integral2", return_raw)]
+    pub fn integral2(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        xa: Dynamic,
+        xb: Dynamic,
+        ya: Dynamic,
+        yb: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        integral2_with_options(ctx, f, xa, xb, ya, yb, Map::new())
+    }
+
+    /// Returns the integral of `f(x, y)` over the region as above, supporting the `abs_tol`,
+    /// `rel_tol` and `max_intervals` options of `integral`, which apply to every
+    /// one-dimensional integral. Each call of `f` or of a limit function counts as one
+    /// operation towards the engine's operation limit.
+    /// ```typescript
+    /// let I = integral2(|x, y| exp(-x * x - y * y), -inf, inf, -inf, inf, #{rel_tol: 1e-8});
+    /// assert_approx_eq(I, pi, 1e-7);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    #[rhai_fn(name = "integral2", return_raw)]
+    pub fn integral2_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        xa: Dynamic,
+        xb: Dynamic,
+        ya: Dynamic,
+        yb: Dynamic,
+        options: Map,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let limits = [
+            (Limit::new(xa)?, Limit::new(xb)?),
+            (Limit::new(ya)?, Limit::new(yb)?),
+        ];
+        if matches!(limits[0], (Limit::Function(_), _) | (_, Limit::Function(_))) {
+            return Err(arithmetic_error("The limits of x must be numbers"));
+        }
+        let integrand = Integrand::new(&ctx, &f, false);
+        let settings = Settings::from_options(&options)?;
+        iterated(&integrand, &limits, &mut vec![], &settings)?.checked_value()
+    }
+
+    /// Returns the integral of `f(x, y, z)` over `xa <= x <= xb`, `ya <= y <= yb` and
+    /// `za <= z <= zb`, computed as an iterated integral like `integral2`. The limits `ya` and
+    /// `yb` may be functions of `x`, and `za` and `zb` functions of `x` and `y`.
+    /// ```typescript
+    /// let I = integral3(|x, y, z| x + y * z, 0, 1, 0, 2, 0, 3);
+    /// assert_approx_eq(I, 12.0, 1e-10);
+    /// ```
+    /// ```typescript
+    /// // Volume of the tetrahedron x, y, z >= 0, x + y + z <= 1
+    /// let I = integral3(|x, y, z| 1.0, 0, 1, 0, |x| 1.0 - x, 0, |x, y| 1.0 - x - y);
+    /// assert_approx_eq(I, 1.0 / 6.0, 1e-10);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    #[rhai_fn(name = "integral3", return_raw)]
+    pub fn integral3(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        xa: Dynamic,
+        xb: Dynamic,
+        ya: Dynamic,
+        yb: Dynamic,
+        za: Dynamic,
+        zb: Dynamic,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        integral3_with_options(ctx, f, xa, xb, ya, yb, za, zb, Map::new())
+    }
+
+    /// Returns the integral of `f(x, y, z)` over the region as above, supporting the options
+    /// of `integral2`.
+    /// ```typescript
+    /// // Volume of the unit ball
+    /// let r = |x, y| sqrt(max(1.0 - x * x - y * y, 0.0));
+    /// let I = integral3(|x, y, z| 1.0, -1, 1, |x| -sqrt(1.0 - x * x), |x| sqrt(1.0 - x * x),
+    ///                   |x, y| -r.call(x, y), r, #{rel_tol: 1e-5});
+    /// assert_approx_eq(I, 4.0 * pi / 3.0, 1e-4);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    #[rhai_fn(name = "integral3", return_raw)]
+    pub fn integral3_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        xa: Dynamic,
+        xb: Dynamic,
+        ya: Dynamic,
+        yb: Dynamic,
+        za: Dynamic,
+        zb: Dynamic,
+        options: Map,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let limits = [
+            (Limit::new(xa)?, Limit::new(xb)?),
+            (Limit::new(ya)?, Limit::new(yb)?),
+            (Limit::new(za)?, Limit::new(zb)?),
+        ];
+        if matches!(limits[0], (Limit::Function(_), _) | (_, Limit::Function(_))) {
+            return Err(arithmetic_error("The limits of x must be numbers"));
+        }
+        let integrand = Integrand::new(&ctx, &f, false);
+        let settings = Settings::from_options(&options)?;
+        iterated(&integrand, &limits, &mut vec![], &settings)?.checked_value()
+    }
+
+    /// Returns the approximate integral of gridded data `z` using the trapezoidal method in
+    /// both directions. The rows of `z` correspond to the `y` coordinates and its columns to
+    /// the `x` coordinates, as for the grids produced by `meshgrid`. The coordinates may be
+    /// given as lists or as the grids themselves, and need not be uniformly spaced.
+    /// ```typescript
+    /// let g = meshgrid(linspace(0, 2, 21), linspace(0, 1, 11));
+    /// let z = zeros(11, 21);
+    /// for i in 0..11 {
+    ///     for j in 0..21 {
+    ///         z[i][j] = g.x[i][j] + g.y[i][j];
+    ///     }
+    /// }
+    /// assert_approx_eq(trapz2(g.x, g.y, z), 3.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "trapz2", return_raw)]
+    pub fn trapz2(x: Array, y: Array, z: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let x = grid_data::axis(x, "x", true)?;
+        let y = grid_data::axis(y, "y", false)?;
+        let z = grid_data::matrix(z, "z")?;
+        grid_data::check_shape(&x, &y, &z)?;
+        let mut total = 0.0;
+        for i in 1..y.len() {
+            for j in 1..x.len() {
+                let corners = z[i - 1][j - 1] + z[i - 1][j] + z[i][j - 1] + z[i][j];
+                total += 0.25 * corners * (x[j] - x[j - 1]) * (y[i] - y[i - 1]);
+            }
+        }
+        Ok(total)
+    }
+
+    /// Returns the approximate integral of gridded data `z` using the trapezoidal method in
+    /// both directions, assuming unit spacing.
+    /// ```typescript
+    /// let z = [[1, 2, 3],
+    ///          [3, 4, 5]];
+    /// assert_eq(trapz2(z), 6.0);
+    /// ```
+    #[rhai_fn(name = "trapz2", return_raw)]
+    pub fn trapz2_unit(z: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let rows = grid_data::matrix(z.clone(), "z")?;
+        let x = (0..rows[0].len()).map(|j| Dynamic::from_int(j as rhai::INT));
+        let y = (0..rows.len()).map(|i| Dynamic::from_int(i as rhai::INT));
+        trapz2(x.collect(), y.collect(), z)
+    }
+
+    /// Integrates `f(x)`, where `x` is an array, over the box given by `bounds`, a matrix with one
+    /// row `[a, b]` per variable, with the Smolyak sparse-grid rule of the given `level` built
+    /// from nested Clenshaw-Curtis rules. The rule integrates polynomials of total degree up to
+    /// `2 * level + 1` exactly, with far fewer points than a tensor product grid of the same
+    /// accuracy in several dimensions, and suits smooth integrands. The level ranges from 1 to
+    /// 12. Returns an object map with the estimate `value`, an `error` estimate, which is the
+    /// difference from the rule of the level below, and the number of function `evaluations`.
+    /// ```typescript
+    /// // Polynomials of total degree five are integrated exactly from level 2 on
+    /// let result = sparsegrid(|x| x[0] ** 2 * x[1] ** 3, [[0, 2], [0, 1]], 2);
+    /// assert_approx_eq(result.value, 2.0 / 3.0, 1e-14);
+    /// assert_eq(result.evaluations, 13);
+    /// ```
+    /// ```typescript
+    /// let bounds = [[0, 1], [0, 1], [0, 1], [0, 1], [0, 1]];
+    /// let result = sparsegrid(|x| exp(sum(x)), bounds, 4);
+    /// assert_approx_eq(result.value, (exp(1.0) - 1.0) ** 5, 1e-5);
+    /// assert(result.error < 1e-4);
+    /// assert_eq(result.evaluations, 801);
+    /// ```
+    #[rhai_fn(name = "sparsegrid", return_raw)]
+    pub fn sparsegrid(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        bounds: Array,
+        level: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let bounds = box_bounds(bounds)?;
+        let integrand = Integrand::new(&ctx, &f, true);
+        sparse_grid::integrate(&integrand, &bounds, level)
+    }
+
+    /// Estimates the integral of `f(x)`, where `x` is an array, over the box given by `bounds`,
+    /// a matrix with one row `[a, b]` per variable, with `n` points of a randomized quasi-Monte
+    /// Carlo rule. The points come from the Halton sequence, split into ten copies with
+    /// independent random shifts; the seed makes the estimate reproducible. Returns an object
+    /// map with the estimate `value`, its standard `error` estimated from the spread of the
+    /// copies, and the number of function `evaluations`.
+    /// ```typescript
+    /// // The integral of the sum of the coordinates over the unit cube in five dimensions
+    /// let bounds = [[0, 1], [0, 1], [0, 1], [0, 1], [0, 1]];
+    /// let result = mcintegrate(|x| sum(x), bounds, 2000, 42);
+    /// assert_approx_eq(result.value, 2.5, 0.01);
+    /// assert(result.error < 0.01);
+    /// assert_eq(result.evaluations, 2000);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "mcintegrate", return_raw)]
+    pub fn mcintegrate_with_seed(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        bounds: Array,
+        n: INT,
+        seed: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        use randlib::SeedableRng;
+        let bounds = box_bounds(bounds)?;
+        let integrand = Integrand::new(&ctx, &f, true);
+        let mut rng = randlib::rngs::StdRng::seed_from_u64(seed as u64);
+        monte_carlo::shifted_halton(&integrand, &bounds, n, &mut rng)
+    }
+
+    /// Estimates the integral of `f(x)` over the box given by `bounds` with `n` points of a
+    /// randomized quasi-Monte Carlo rule, as above, seeded from the generator of `rng_seed`.
+    /// ```typescript
+    /// // Unseeded estimates change from call to call; a seed makes them reproducible
+    /// let result = mcintegrate(|x| x[0] * x[1], [[0, 2], [0, 3]], 10000);
+    /// assert_eq(result.evaluations, 10000);
+    /// let seeded = mcintegrate(|x| x[0] * x[1], [[0, 2], [0, 3]], 10000, 7);
+    /// assert_approx_eq(seeded.value, 9.0, 0.01);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "mcintegrate", return_raw)]
+    pub fn mcintegrate(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        bounds: Array,
+        n: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        use randlib::SeedableRng;
+        let bounds = box_bounds(bounds)?;
+        let integrand = Integrand::new(&ctx, &f, true);
+        let mut rng = crate::with_rng(randlib::rngs::StdRng::from_rng);
+        monte_carlo::shifted_halton(&integrand, &bounds, n, &mut rng)
+    }
 }
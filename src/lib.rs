@@ -32,6 +32,24 @@ mod validate;
 pub use validate::validation_functions;
 mod trig;
 pub use trig::trig_functions;
+mod signal;
+pub use signal::signal_functions;
+mod ode;
+pub use ode::ode_functions;
+mod roots;
+pub use roots::roots_functions;
+mod optimization;
+pub use optimization::optimization_functions;
+mod dual;
+pub use dual::{dual_functions, Dual};
+mod distributions;
+pub use distributions::{distribution_functions, Distribution};
+mod random;
+pub use random::random_functions;
+#[cfg(feature = "rand")]
+pub use random::{rng_seed, with_rng};
+mod experiments;
+pub use experiments::{experiment_functions, primes, radical_inverse};
 
 def_package! {
     /// Package for scientific computing
@@ -48,6 +66,37 @@ def_package! {
         combine_with_exported_module!(lib, "rhai_sci_moving", moving_functions);
         combine_with_exported_module!(lib, "rhai_sci_validation", validation_functions);
         combine_with_exported_module!(lib, "rhai_sci_trig", trig_functions);
+        combine_with_exported_module!(lib, "rhai_sci_signal", signal_functions);
+        combine_with_exported_module!(lib, "rhai_sci_ode", ode_functions);
+        combine_with_exported_module!(lib, "rhai_sci_roots", roots_functions);
+        combine_with_exported_module!(lib, "rhai_sci_optimization", optimization_functions);
+        combine_with_exported_module!(lib, "rhai_sci_dual", dual_functions);
+        combine_with_exported_module!(lib, "rhai_sci_distributions", distribution_functions);
+        combine_with_exported_module!(lib, "rhai_sci_random", random_functions);
+        combine_with_exported_module!(lib, "rhai_sci_experiments", experiment_functions);
+    }
+}
+
+#[cfg(feature = "rand")]
+impl SciPackage {
+    /// Reseeds the random number generator of the current thread with `seed`, as
+    /// [`rng_seed`] does, and creates the package. The generator is not part of the package:
+    /// every package on the thread draws from it, so building another package this way
+    /// restarts the sequence of every engine on the thread, and an engine evaluating on another
+    /// thread draws from that thread's generator, which must be seeded there.
+    /// ```
+    /// use rhai::{packages::Package, Engine, Array};
+    /// use rhai_sci::SciPackage;
+    /// let mut engine = Engine::new();
+    /// engine.register_global_module(SciPackage::reseeding_thread_rng(7).as_shared_module());
+    /// let first = engine.eval::<Array>("randperm(10)").unwrap();
+    /// engine.register_global_module(SciPackage::reseeding_thread_rng(7).as_shared_module());
+    /// let second = engine.eval::<Array>("randperm(10)").unwrap();
+    /// assert_eq!(format!("{first:?}"), format!("{second:?}"));
+    /// ```
+    pub fn reseeding_thread_rng(seed: u64) -> Self {
+        rng_seed(seed);
+        Self::new()
     }
 }
 
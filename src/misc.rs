@@ -9,7 +9,8 @@ pub mod misc_functions {
     #[allow(non_upper_case_globals)]
     pub const inf: FLOAT = FLOAT::INFINITY;
 
-    /// Returns a random number between zero and one.
+    /// Returns a random number between zero and one, drawn from the generator seeded by
+    /// `rng_seed`.
     /// ```typescript
     /// let r = rand();
     /// assert(r >= 0.0 && r <= 1.0);
@@ -17,7 +18,7 @@ pub mod misc_functions {
     #[cfg(feature = "rand")]
     #[rhai_fn(name = "rand")]
     pub fn rand_float() -> FLOAT {
-        randlib::random()
+        crate::with_rng(randlib::Rng::random)
     }
 
     /// Returns an array of the unique elements in an array.
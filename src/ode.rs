@@ -0,0 +1,2045 @@
+use rhai::plugin::*;
+
+/// Shared machinery for the initial value problem solvers: right-hand side evaluation, step
+/// size control and output recording.
+mod ivp {
+    use crate::{arithmetic_error, dynamic_to_vec_float, float_option, OperationCounter};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// Converts a state vector to a Rhai value: a FLOAT for scalar problems and an array
+    /// otherwise.
+    pub(super) fn state_to_dynamic(y: &[FLOAT], scalar: bool) -> Dynamic {
+        if scalar {
+            Dynamic::from_float(y[0])
+        } else {
+            Dynamic::from_array(y.iter().map(|v| Dynamic::from_float(*v)).collect())
+        }
+    }
+
+    /// The right-hand side `f(t, y)` of an ODE, implemented by a Rhai function pointer.
+    pub(super) struct Rhs<'a> {
+        /// Context used to call back into the script.
+        pub(super) ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `f(t, y)`.
+        pub(super) f: &'a FnPtr,
+        /// Whether the state is a scalar rather than an array.
+        pub(super) scalar: bool,
+        /// Number of state variables.
+        pub(super) dim: usize,
+        /// Counter of the calls to `f`.
+        pub(super) counter: OperationCounter,
+    }
+
+    impl Rhs<'_> {
+        /// Evaluates `f(t, y)`, checking that the result has one entry per state variable.
+        pub(super) fn eval(&self, t: FLOAT, y: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            let result = self.f.call_raw(
+                self.ctx,
+                None,
+                [Dynamic::from_float(t), state_to_dynamic(y, self.scalar)],
+            )?;
+            let dydt = dynamic_to_vec_float(result).map_err(|_| {
+                arithmetic_error("The ODE function must return a number or a numeric array")
+            })?;
+            if dydt.len() != self.dim {
+                return Err(arithmetic_error(format!(
+                    "The ODE function returned {} values but the state has {}",
+                    dydt.len(),
+                    self.dim
+                )));
+            }
+            Ok(dydt)
+        }
+    }
+
+    /// Start time, end time and (if requested) output times of an integration.
+    pub(super) type TimeSpan = (FLOAT, FLOAT, Option<Vec<FLOAT>>);
+
+    /// Reads the time span of an integration. Two entries give the start and end times; more
+    /// entries additionally request output at exactly those times.
+    pub(super) fn time_span(tspan: &Array) -> Result<TimeSpan, Box<EvalAltResult>> {
+        let times = dynamic_to_vec_float(Dynamic::from_array(tspan.clone()))?;
+        if times.len() < 2 {
+            return Err(arithmetic_error(
+                "The time span must contain at least two times",
+            ));
+        }
+        let (t0, tf) = (times[0], times[times.len() - 1]);
+        if t0 == tf {
+            return Err(arithmetic_error("The start and end times must differ"));
+        }
+        let direction = (tf - t0).signum();
+        if times.windows(2).any(|w| (w[1] - w[0]) * direction <= 0.0) {
+            return Err(arithmetic_error("The time span must be strictly monotonic"));
+        }
+        Ok((t0, tf, (times.len() > 2).then_some(times)))
+    }
+
+    /// Reads the initial state, which may be a number or a numeric array.
+    pub(super) fn initial_state(y0: Dynamic) -> Result<(Vec<FLOAT>, bool), Box<EvalAltResult>> {
+        let scalar = y0.is_int() || y0.is_float();
+        let y0 = dynamic_to_vec_float(y0)?;
+        if y0.is_empty() {
+            return Err(arithmetic_error("The initial state must not be empty"));
+        }
+        Ok((y0, scalar))
+    }
+
+    /// Error control and step size settings of the adaptive solvers.
+    pub(super) struct Tolerances {
+        /// Relative error tolerance.
+        pub(super) rtol: FLOAT,
+        /// Absolute error tolerance.
+        pub(super) atol: FLOAT,
+        /// Largest allowed step size.
+        pub(super) max_step: FLOAT,
+        /// First step size to try, if given.
+        pub(super) initial_step: Option<FLOAT>,
+    }
+
+    impl Tolerances {
+        /// Reads the `rtol`, `atol`, `max_step` and `initial_step` options.
+        pub(super) fn from_options(options: &Map, span: FLOAT) -> Result<Self, Box<EvalAltResult>> {
+            let rtol = float_option(options, "rtol", 1e-3)?;
+            let atol = float_option(options, "atol", 1e-6)?;
+            let max_step = float_option(options, "max_step", span.abs() / 10.0)?;
+            if rtol <= 0.0 || atol <= 0.0 || max_step <= 0.0 {
+                return Err(arithmetic_error(
+                    "The options 'rtol', 'atol' and 'max_step' must be positive",
+                ));
+            }
+            let initial_step = match options.get("initial_step") {
+                Some(_) => Some(float_option(options, "initial_step", 0.0)?),
+                None => None,
+            };
+            if initial_step.is_some_and(|h| h <= 0.0) {
+                return Err(arithmetic_error(
+                    "The option 'initial_step' must be positive",
+                ));
+            }
+            Ok(Self {
+                rtol,
+                atol,
+                max_step,
+                initial_step,
+            })
+        }
+
+        /// Root-mean-square norm of `e` scaled by the mixed tolerance of `y` and `y_new`.
+        pub(super) fn error_norm(&self, e: &[FLOAT], y: &[FLOAT], y_new: &[FLOAT]) -> FLOAT {
+            let sum = e
+                .iter()
+                .zip(y.iter().zip(y_new))
+                .map(|(e, (a, b))| (e / (self.atol + self.rtol * a.abs().max(b.abs()))).powi(2))
+                .sum::<FLOAT>();
+            (sum / e.len() as FLOAT).sqrt()
+        }
+
+        /// Chooses the first step size from the scale of the initial state and its derivative.
+        pub(super) fn first_step(&self, y0: &[FLOAT], f0: &[FLOAT], span: FLOAT) -> FLOAT {
+            if let Some(h) = self.initial_step {
+                return h.min(self.max_step).min(span.abs());
+            }
+            let zeros = vec![0.0; y0.len()];
+            let d0 = self.error_norm(y0, y0, &zeros);
+            let d1 = self.error_norm(f0, y0, &zeros);
+            let h = if d0 < 1e-5 || d1 < 1e-5 {
+                1e-6
+            } else {
+                0.01 * d0 / d1
+            };
+            h.min(self.max_step).min(span.abs())
+        }
+    }
+
+    /// Cubic Hermite interpolation of the state between two accepted steps.
+    pub(super) fn hermite(
+        (t0, y0, f0): (FLOAT, &[FLOAT], &[FLOAT]),
+        (t1, y1, f1): (FLOAT, &[FLOAT], &[FLOAT]),
+        t: FLOAT,
+    ) -> Vec<FLOAT> {
+        let h = t1 - t0;
+        let s = (t - t0) / h;
+        let h00 = (1.0 + 2.0 * s) * (1.0 - s).powi(2);
+        let h10 = s * (1.0 - s).powi(2);
+        let h01 = s * s * (3.0 - 2.0 * s);
+        let h11 = s * s * (s - 1.0);
+        (0..y0.len())
+            .map(|i| h00 * y0[i] + h10 * h * f0[i] + h01 * y1[i] + h11 * h * f1[i])
+            .collect()
+    }
+
+    /// Zero-crossing detection for user-supplied event functions `g(t, y)`.
+    pub(super) struct Events<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `g(t, y)`, returning a number or an array of numbers.
+        g: FnPtr,
+        /// Whether the state is a scalar rather than an array.
+        scalar: bool,
+        /// The `terminal` option, expanded once the number of events is known.
+        terminal_option: Option<Dynamic>,
+        /// The `direction` option, expanded once the number of events is known.
+        direction_option: Option<Dynamic>,
+        /// Whether each event stops the integration.
+        terminal: Vec<bool>,
+        /// Crossing direction of each event: positive for rising, negative for falling and zero
+        /// for both.
+        direction: Vec<FLOAT>,
+        /// Event function values at the last accepted point.
+        previous: Vec<FLOAT>,
+        /// Times of the located events.
+        te: Vec<FLOAT>,
+        /// States at the located events.
+        ye: Vec<Vec<FLOAT>>,
+        /// Indices of the located events.
+        ie: Vec<usize>,
+        /// Counter of the calls to `g`.
+        counter: OperationCounter,
+    }
+
+    impl<'a> Events<'a> {
+        /// Reads the `events`, `terminal` and `direction` options. Returns `None` if no event
+        /// function was given.
+        pub(super) fn from_options(
+            ctx: &'a NativeCallContext<'a>,
+            options: &Map,
+            scalar: bool,
+        ) -> Result<Option<Self>, Box<EvalAltResult>> {
+            let Some(g) = options.get("events") else {
+                return Ok(None);
+            };
+            let g = g.clone().try_cast::<FnPtr>().ok_or_else(|| {
+                arithmetic_error("The option 'events' must be a function pointer")
+            })?;
+            Ok(Some(Self {
+                ctx,
+                g,
+                scalar,
+                terminal_option: options.get("terminal").cloned(),
+                direction_option: options.get("direction").cloned(),
+                terminal: vec![],
+                direction: vec![],
+                previous: vec![],
+                te: vec![],
+                ye: vec![],
+                ie: vec![],
+                counter: OperationCounter::new(ctx),
+            }))
+        }
+
+        /// Evaluates the event functions at `(t, y)`.
+        fn eval(&self, t: FLOAT, y: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            let result = self.g.call_raw(
+                self.ctx,
+                None,
+                [Dynamic::from_float(t), state_to_dynamic(y, self.scalar)],
+            )?;
+            let values = dynamic_to_vec_float(result).map_err(|_| {
+                arithmetic_error("The event function must return a number or a numeric array")
+            })?;
+            if !self.previous.is_empty() && values.len() != self.previous.len() {
+                return Err(arithmetic_error(
+                    "The event function must always return the same number of values",
+                ));
+            }
+            Ok(values)
+        }
+
+        /// Evaluates the event functions at the initial point and expands the `terminal` and
+        /// `direction` options to one entry per event.
+        fn start(&mut self, t0: FLOAT, y0: &[FLOAT]) -> Result<(), Box<EvalAltResult>> {
+            self.previous = self.eval(t0, y0)?;
+            let n = self.previous.len();
+            self.terminal = match self.terminal_option.take() {
+                None => vec![false; n],
+                Some(value) if value.is_bool() => vec![value.as_bool().unwrap(); n],
+                Some(value) => value
+                    .into_array()
+                    .ok()
+                    .filter(|flags| flags.len() == n && flags.iter().all(|f| f.is_bool()))
+                    .map(|flags| flags.iter().map(|f| f.as_bool().unwrap()).collect())
+                    .ok_or_else(|| {
+                        arithmetic_error(
+                            "The option 'terminal' must be a bool or an array of bools with one entry per event",
+                        )
+                    })?,
+            };
+            self.direction = match self.direction_option.take() {
+                None => vec![0.0; n],
+                Some(value) => {
+                    let direction = dynamic_to_vec_float(value)?;
+                    match direction.len() {
+                        1 => vec![direction[0]; n],
+                        len if len == n => direction,
+                        _ => {
+                            return Err(arithmetic_error(
+                                "The option 'direction' must be a number or an array with one entry per event",
+                            ))
+                        }
+                    }
+                }
+            };
+            Ok(())
+        }
+
+        /// Locates the zero of event `i` within a step, where it changes sign from `ga` to `gb`,
+        /// with the Illinois variant of regula falsi on the interpolated solution.
+        fn locate(
+            &self,
+            i: usize,
+            start: (FLOAT, &[FLOAT], &[FLOAT]),
+            end: (FLOAT, &[FLOAT], &[FLOAT]),
+            ga: FLOAT,
+            gb: FLOAT,
+        ) -> Result<FLOAT, Box<EvalAltResult>> {
+            let (mut a, mut fa, mut b, mut fb) = (start.0, ga, end.0, gb);
+            if fb == 0.0 {
+                return Ok(b);
+            }
+            let tol = 4.0 * FLOAT::EPSILON * a.abs().max(b.abs()).max(1.0);
+            let mut side = 0;
+            for _ in 0..100 {
+                if (b - a).abs() <= tol {
+                    break;
+                }
+                let mut c = (a * fb - b * fa) / (fb - fa);
+                if c.is_nan() || (c - a) * (b - c) <= 0.0 {
+                    c = 0.5 * (a + b);
+                }
+                let fc = self.eval(c, &hermite(start, end, c))?[i];
+                if fc == 0.0 {
+                    return Ok(c);
+                }
+                if fc.signum() == fb.signum() {
+                    (b, fb) = (c, fc);
+                    if side == -1 {
+                        fa /= 2.0;
+                    }
+                    side = -1;
+                } else {
+                    (a, fa) = (c, fc);
+                    if side == 1 {
+                        fb /= 2.0;
+                    }
+                    side = 1;
+                }
+            }
+            Ok(b)
+        }
+
+        /// Records the events that occur during an accepted step. Returns the time and state of
+        /// the first terminal event, if any.
+        fn check(
+            &mut self,
+            start: (FLOAT, &[FLOAT], &[FLOAT]),
+            end: (FLOAT, &[FLOAT], &[FLOAT]),
+        ) -> Result<Option<(FLOAT, Vec<FLOAT>)>, Box<EvalAltResult>> {
+            let values = self.eval(end.0, end.1)?;
+            let mut found = vec![];
+            for (i, (ga, gb)) in self.previous.iter().zip(&values).enumerate() {
+                let direction = self.direction[i];
+                let rising = *ga < 0.0 && *gb >= 0.0 && direction >= 0.0;
+                let falling = *ga > 0.0 && *gb <= 0.0 && direction <= 0.0;
+                if rising || falling {
+                    found.push((self.locate(i, start, end, *ga, *gb)?, i));
+                }
+            }
+            self.previous = values;
+
+            let forward = end.0 > start.0;
+            found.sort_by(|(a, _), (b, _)| {
+                if forward {
+                    a.total_cmp(b)
+                } else {
+                    b.total_cmp(a)
+                }
+            });
+            for (t, i) in found {
+                let y = if t == end.0 {
+                    end.1.to_vec()
+                } else {
+                    hermite(start, end, t)
+                };
+                self.te.push(t);
+                self.ye.push(y.clone());
+                self.ie.push(i);
+                if self.terminal[i] {
+                    return Ok(Some((t, y)));
+                }
+            }
+            Ok(None)
+        }
+    }
+
+    /// Collects the solution, either at every step or at requested output times, along with
+    /// any events.
+    pub(super) struct Recorder<'a> {
+        /// Requested output times, if any.
+        output_times: Option<Vec<FLOAT>>,
+        /// Index of the next requested output time.
+        next_output: usize,
+        /// Recorded times.
+        t: Vec<FLOAT>,
+        /// Recorded states.
+        y: Vec<Vec<FLOAT>>,
+        /// Event detection, if requested.
+        events: Option<Events<'a>>,
+    }
+
+    impl<'a> Recorder<'a> {
+        /// Starts a recording at the initial point.
+        pub(super) fn new(
+            t0: FLOAT,
+            y0: &[FLOAT],
+            output_times: Option<Vec<FLOAT>>,
+            mut events: Option<Events<'a>>,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            if let Some(events) = &mut events {
+                events.start(t0, y0)?;
+            }
+            let mut recorder = Self {
+                output_times,
+                next_output: 0,
+                t: vec![],
+                y: vec![],
+                events,
+            };
+            match &recorder.output_times {
+                Some(times) if times[0] != t0 => (),
+                _ => {
+                    recorder.t.push(t0);
+                    recorder.y.push(y0.to_vec());
+                    recorder.next_output = 1;
+                }
+            }
+            Ok(recorder)
+        }
+
+        /// Records an accepted step from `(t0, y0)` to `(t1, y1)`, with derivatives `f0` and `f1`.
+        /// Returns `true` if a terminal event occurred, in which case the recording ends at the
+        /// event.
+        pub(super) fn step(
+            &mut self,
+            start: (FLOAT, &[FLOAT], &[FLOAT]),
+            end: (FLOAT, &[FLOAT], &[FLOAT]),
+        ) -> Result<bool, Box<EvalAltResult>> {
+            let stop = match &mut self.events {
+                Some(events) => events.check(start, end)?,
+                None => None,
+            };
+            let (t_end, y_end) = match &stop {
+                Some((t, y)) => (*t, y.clone()),
+                None => (end.0, end.1.to_vec()),
+            };
+            match &self.output_times {
+                None => {
+                    self.t.push(t_end);
+                    self.y.push(y_end);
+                }
+                Some(times) => {
+                    let direction = (end.0 - start.0).signum();
+                    while self.next_output < times.len()
+                        && (times[self.next_output] - t_end) * direction <= 0.0
+                    {
+                        let t = times[self.next_output];
+                        let y = if t == end.0 {
+                            end.1.to_vec()
+                        } else {
+                            hermite(start, end, t)
+                        };
+                        self.t.push(t);
+                        self.y.push(y);
+                        self.next_output += 1;
+                    }
+                }
+            }
+            Ok(stop.is_some())
+        }
+
+        /// Returns the recording as an object map with entries `t` and `y`. The states in `y` are
+        /// numbers for scalar problems and arrays (one row per time) otherwise. If events were
+        /// requested, the map also holds their times `te`, states `ye` and indices `ie`.
+        pub(super) fn into_map(self, scalar: bool) -> Map {
+            let states = |y: Vec<Vec<FLOAT>>| {
+                Dynamic::from_array(y.iter().map(|y| state_to_dynamic(y, scalar)).collect())
+            };
+            let mut result = Map::new();
+            result.insert(
+                "t".into(),
+                Dynamic::from_array(self.t.into_iter().map(Dynamic::from_float).collect()),
+            );
+            result.insert("y".into(), states(self.y));
+            if let Some(events) = self.events {
+                result.insert(
+                    "te".into(),
+                    Dynamic::from_array(events.te.into_iter().map(Dynamic::from_float).collect()),
+                );
+                result.insert("ye".into(), states(events.ye));
+                result.insert(
+                    "ie".into(),
+                    Dynamic::from_array(
+                        events
+                            .ie
+                            .into_iter()
+                            .map(|i| Dynamic::from_int(i as INT))
+                            .collect(),
+                    ),
+                );
+            }
+            result
+        }
+    }
+
+    /// Butcher tableau of an explicit embedded Runge-Kutta pair with the first-same-as-last
+    /// property.
+    pub(super) struct Tableau {
+        /// Nodes.
+        pub(super) c: &'static [FLOAT],
+        /// Lower-triangular coupling coefficients, one row per stage after the first.
+        pub(super) a: &'static [&'static [FLOAT]],
+        /// Weights of the propagated solution.
+        pub(super) b: &'static [FLOAT],
+        /// Differences between the weights of the propagated and embedded solutions.
+        pub(super) e: &'static [FLOAT],
+        /// Order of the embedded error estimate plus one, used for step size control.
+        pub(super) order: i32,
+    }
+
+    /// Dormand-Prince 5(4) pair.
+    pub(super) const DORMAND_PRINCE: Tableau = Tableau {
+        c: &[0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0],
+        a: &[
+            &[1.0 / 5.0],
+            &[3.0 / 40.0, 9.0 / 40.0],
+            &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+            &[
+                19372.0 / 6561.0,
+                -25360.0 / 2187.0,
+                64448.0 / 6561.0,
+                -212.0 / 729.0,
+            ],
+            &[
+                9017.0 / 3168.0,
+                -355.0 / 33.0,
+                46732.0 / 5247.0,
+                49.0 / 176.0,
+                -5103.0 / 18656.0,
+            ],
+            &[
+                35.0 / 384.0,
+                0.0,
+                500.0 / 1113.0,
+                125.0 / 192.0,
+                -2187.0 / 6784.0,
+                11.0 / 84.0,
+            ],
+        ],
+        b: &[
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+            0.0,
+        ],
+        e: &[
+            71.0 / 57600.0,
+            0.0,
+            -71.0 / 16695.0,
+            71.0 / 1920.0,
+            -17253.0 / 339200.0,
+            22.0 / 525.0,
+            -1.0 / 40.0,
+        ],
+        order: 5,
+    };
+
+    /// Bogacki-Shampine 3(2) pair.
+    pub(super) const BOGACKI_SHAMPINE: Tableau = Tableau {
+        c: &[0.0, 1.0 / 2.0, 3.0 / 4.0, 1.0],
+        a: &[
+            &[1.0 / 2.0],
+            &[0.0, 3.0 / 4.0],
+            &[2.0 / 9.0, 1.0 / 3.0, 4.0 / 9.0],
+        ],
+        b: &[2.0 / 9.0, 1.0 / 3.0, 4.0 / 9.0, 0.0],
+        e: &[-5.0 / 72.0, 1.0 / 12.0, 1.0 / 9.0, -1.0 / 8.0],
+        order: 3,
+    };
+
+    /// Returns `y + h * sum(weights[j] * k[j])`.
+    pub(super) fn combine(
+        y: &[FLOAT],
+        h: FLOAT,
+        weights: &[FLOAT],
+        k: &[Vec<FLOAT>],
+    ) -> Vec<FLOAT> {
+        (0..y.len())
+            .map(|i| y[i] + h * weights.iter().zip(k).map(|(w, k)| w * k[i]).sum::<FLOAT>())
+            .collect()
+    }
+
+    /// Integrates `rhs` from `t0` to `tf` with an adaptive explicit Runge-Kutta pair.
+    pub(super) fn integrate_explicit(
+        rhs: &Rhs,
+        tableau: &Tableau,
+        (t0, tf): (FLOAT, FLOAT),
+        y0: Vec<FLOAT>,
+        tolerances: &Tolerances,
+        recorder: &mut Recorder,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let direction = (tf - t0).signum();
+        let stages = tableau.c.len();
+        let mut t = t0;
+        let mut y = y0;
+        let mut f = rhs.eval(t, &y)?;
+        let mut h = tolerances.first_step(&y, &f, tf - t0);
+
+        while (tf - t) * direction > 0.0 {
+            let min_step = 16.0 * FLOAT::EPSILON * t.abs().max(1.0);
+            if h < min_step {
+                return Err(arithmetic_error(format!(
+                    "Unable to meet the integration tolerances without reducing the step size below the smallest value allowed at t = {t}"
+                )));
+            }
+            h = h.min(tolerances.max_step);
+            let last = h >= (tf - t).abs();
+            if last {
+                h = (tf - t).abs();
+            }
+            let signed_h = direction * h;
+
+            let mut k = Vec::with_capacity(stages);
+            k.push(f.clone());
+            for s in 1..stages {
+                let ys = combine(&y, signed_h, tableau.a[s - 1], &k);
+                k.push(rhs.eval(t + tableau.c[s] * signed_h, &ys)?);
+            }
+            let y_new = combine(&y, signed_h, tableau.b, &k[..stages - 1]);
+            let f_new = if tableau.b[stages - 1] == 0.0 && tableau.c[stages - 1] == 1.0 {
+                k[stages - 1].clone()
+            } else {
+                rhs.eval(t + signed_h, &y_new)?
+            };
+            let e = combine(&vec![0.0; y.len()], signed_h, tableau.e, &k);
+            let err = tolerances.error_norm(&e, &y, &y_new);
+            if !err.is_finite() {
+                h *= 0.5;
+                continue;
+            }
+
+            if err <= 1.0 {
+                let t_new = if last { tf } else { t + signed_h };
+                if recorder.step((t, &y, &f), (t_new, &y_new, &f_new))? {
+                    return Ok(());
+                }
+                t = t_new;
+                y = y_new;
+                f = f_new;
+            }
+            let factor = if err == 0.0 {
+                5.0
+            } else {
+                (0.9 * err.powf(-1.0 / tableau.order as FLOAT)).clamp(0.2, 5.0)
+            };
+            h *= if err <= 1.0 { factor } else { factor.min(1.0) };
+        }
+        Ok(())
+    }
+
+    /// Integrates `rhs` through the given times with the classical fourth-order Runge-Kutta
+    /// method, taking one step between consecutive times.
+    pub(super) fn integrate_rk4(
+        rhs: &Rhs,
+        times: &[FLOAT],
+        y0: Vec<FLOAT>,
+        recorder: &mut Recorder,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let mut y = y0;
+        let mut f = rhs.eval(times[0], &y)?;
+        for w in times.windows(2) {
+            let (t, h) = (w[0], w[1] - w[0]);
+            let k1 = f.clone();
+            let k2 = rhs.eval(
+                t + h / 2.0,
+                &combine(&y, h / 2.0, &[1.0], std::slice::from_ref(&k1)),
+            )?;
+            let k3 = rhs.eval(
+                t + h / 2.0,
+                &combine(&y, h / 2.0, &[1.0], std::slice::from_ref(&k2)),
+            )?;
+            let k4 = rhs.eval(t + h, &combine(&y, h, &[1.0], std::slice::from_ref(&k3)))?;
+            let y_new = combine(
+                &y,
+                h,
+                &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+                &[k1, k2, k3, k4],
+            );
+            let f_new = rhs.eval(w[1], &y_new)?;
+            if recorder.step((w[0], &y, &f), (w[1], &y_new, &f_new))? {
+                return Ok(());
+            }
+            y = y_new;
+            f = f_new;
+        }
+        Ok(())
+    }
+
+    /// Reads the `output_times` option, falling back to the extra entries of the time span.
+    pub(super) fn output_times(
+        options: &Map,
+        (t0, tf, span_outputs): TimeSpan,
+    ) -> Result<Option<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let output_times = match options.get("output_times") {
+            Some(times) => Some(dynamic_to_vec_float(times.clone())?),
+            None => span_outputs,
+        };
+        if let Some(times) = &output_times {
+            let (low, high) = (t0.min(tf), t0.max(tf));
+            if times.iter().any(|t| *t < low || *t > high) {
+                return Err(arithmetic_error(
+                    "The output times must lie within the time span",
+                ));
+            }
+            if times.windows(2).any(|w| (w[1] - w[0]) * (tf - t0) <= 0.0) {
+                return Err(arithmetic_error(
+                    "The output times must be strictly monotonic in the direction of integration",
+                ));
+            }
+        }
+        Ok(output_times)
+    }
+
+    /// An initial value problem together with its solver settings, as read from the arguments
+    /// of an adaptive solver.
+    pub(super) struct Problem<'a> {
+        /// The right-hand side.
+        pub(super) rhs: Rhs<'a>,
+        /// Start time.
+        pub(super) t0: FLOAT,
+        /// End time.
+        pub(super) tf: FLOAT,
+        /// Initial state.
+        pub(super) y0: Vec<FLOAT>,
+        /// Error tolerances and step size limits.
+        pub(super) tolerances: Tolerances,
+        /// Solution output and event detection.
+        pub(super) recorder: Recorder<'a>,
+    }
+
+    impl<'a> Problem<'a> {
+        /// Reads the arguments shared by all adaptive solvers.
+        pub(super) fn new(
+            ctx: &'a NativeCallContext<'a>,
+            f: &'a FnPtr,
+            tspan: &Array,
+            y0: Dynamic,
+            options: &Map,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            let span = time_span(tspan)?;
+            let (t0, tf) = (span.0, span.1);
+            let (y0, scalar) = initial_state(y0)?;
+            let tolerances = Tolerances::from_options(options, tf - t0)?;
+            let output_times = output_times(options, span)?;
+            let events = Events::from_options(ctx, options, scalar)?;
+            let recorder = Recorder::new(t0, &y0, output_times, events)?;
+            let rhs = Rhs {
+                ctx,
+                f,
+                scalar,
+                dim: y0.len(),
+                counter: OperationCounter::new(ctx),
+            };
+            Ok(Self {
+                rhs,
+                t0,
+                tf,
+                y0,
+                tolerances,
+                recorder,
+            })
+        }
+    }
+
+    /// Runs an adaptive explicit solver with the given tableau.
+    pub(super) fn solve_explicit(
+        ctx: &NativeCallContext,
+        tableau: &Tableau,
+        f: &FnPtr,
+        tspan: &Array,
+        y0: Dynamic,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut problem = Problem::new(ctx, f, tspan, y0, options)?;
+        integrate_explicit(
+            &problem.rhs,
+            tableau,
+            (problem.t0, problem.tf),
+            problem.y0,
+            &problem.tolerances,
+            &mut problem.recorder,
+        )?;
+        Ok(problem.recorder.into_map(problem.rhs.scalar))
+    }
+}
+
+/// Implicit solvers for stiff problems and the Jacobians they need.
+#[cfg(feature = "nalgebra")]
+mod stiff {
+    use super::ivp::{Problem, Rhs, Tolerances};
+    use crate::{arithmetic_error, dynamic_to_vec_float, OperationCounter};
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT};
+
+    /// Source of the Jacobian `df/dy`: a user-supplied function or finite differences.
+    pub(super) struct Jacobian<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `J(t, y)`, if given.
+        jac: Option<FnPtr>,
+        /// Counter of the calls to `J`.
+        counter: OperationCounter,
+    }
+
+    impl<'a> Jacobian<'a> {
+        /// Reads the `jacobian` option.
+        pub(super) fn from_options(
+            ctx: &'a NativeCallContext<'a>,
+            options: &Map,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            let jac = match options.get("jacobian") {
+                Some(jac) => Some(jac.clone().try_cast::<FnPtr>().ok_or_else(|| {
+                    arithmetic_error("The option 'jacobian' must be a function pointer")
+                })?),
+                None => None,
+            };
+            Ok(Self {
+                ctx,
+                jac,
+                counter: OperationCounter::new(ctx),
+            })
+        }
+
+        /// Evaluates the Jacobian at `(t, y)`, where `f = f(t, y)`.
+        pub(super) fn eval(
+            &self,
+            rhs: &Rhs,
+            t: FLOAT,
+            y: &[FLOAT],
+            f: &[FLOAT],
+        ) -> Result<DMatrix<FLOAT>, Box<EvalAltResult>> {
+            let n = y.len();
+            match &self.jac {
+                Some(jac) => {
+                    self.counter.tick()?;
+                    let result = jac.call_raw(
+                        self.ctx,
+                        None,
+                        [
+                            Dynamic::from_float(t),
+                            super::ivp::state_to_dynamic(y, rhs.scalar),
+                        ],
+                    )?;
+                    let shape_error = || {
+                        arithmetic_error(format!(
+                            "The Jacobian function must return a {n} x {n} matrix"
+                        ))
+                    };
+                    let rows = match result.clone().into_array() {
+                        Ok(rows) if rows.iter().all(|row| row.is_array()) => rows
+                            .into_iter()
+                            .map(dynamic_to_vec_float)
+                            .collect::<Result<Vec<_>, _>>()?,
+                        _ => vec![dynamic_to_vec_float(result).map_err(|_| shape_error())?],
+                    };
+                    if rows.len() != n || rows.iter().any(|row| row.len() != n) {
+                        return Err(shape_error());
+                    }
+                    Ok(DMatrix::from_fn(n, n, |i, j| rows[i][j]))
+                }
+                None => {
+                    let mut jac = DMatrix::zeros(n, n);
+                    let mut yp = y.to_vec();
+                    for j in 0..n {
+                        let delta = FLOAT::EPSILON.sqrt() * y[j].abs().max(1.0);
+                        yp[j] = y[j] + delta;
+                        let fp = rhs.eval(t, &yp)?;
+                        yp[j] = y[j];
+                        for i in 0..n {
+                            jac[(i, j)] = (fp[i] - f[i]) / delta;
+                        }
+                    }
+                    Ok(jac)
+                }
+            }
+        }
+    }
+
+    /// Solves `(I - c J) x = b`, returning `None` if the matrix is singular.
+    fn solve_shifted(jac: &DMatrix<FLOAT>, c: FLOAT, b: &[FLOAT]) -> Option<Vec<FLOAT>> {
+        let n = b.len();
+        let m = DMatrix::identity(n, n) - jac * c;
+        m.lu()
+            .solve(&DVector::from_column_slice(b))
+            .filter(|x| x.iter().all(|v| v.is_finite()))
+            .map(|x| x.as_slice().to_vec())
+    }
+
+    /// Error raised when the step size underflows.
+    fn step_too_small(t: FLOAT) -> Box<EvalAltResult> {
+        arithmetic_error(format!(
+            "Unable to meet the integration tolerances without reducing the step size below the smallest value allowed at t = {t}"
+        ))
+    }
+
+    /// Integrates a stiff problem with the modified Rosenbrock 2(3) pair of Shampine and Reichelt,
+    /// evaluating the Jacobian once per step.
+    pub(super) fn integrate_rosenbrock(
+        problem: &mut Problem,
+        jacobian: &Jacobian,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let (rhs, tolerances, recorder) =
+            (&problem.rhs, &problem.tolerances, &mut problem.recorder);
+        let (t0, tf) = (problem.t0, problem.tf);
+        let d = 1.0 / (2.0 + FLOAT::sqrt(2.0));
+        let e32 = 6.0 + FLOAT::sqrt(2.0);
+        let direction = (tf - t0).signum();
+        let n = problem.y0.len();
+
+        let mut t = t0;
+        let mut y = problem.y0.clone();
+        let mut f = rhs.eval(t, &y)?;
+        let mut h = tolerances.first_step(&y, &f, tf - t0);
+        let mut jac = jacobian.eval(rhs, t, &y, &f)?;
+        let mut dfdt = time_derivative(rhs, t, &y, &f, direction)?;
+
+        while (tf - t) * direction > 0.0 {
+            if h < 16.0 * FLOAT::EPSILON * t.abs().max(1.0) {
+                return Err(step_too_small(t));
+            }
+            h = h.min(tolerances.max_step);
+            let last = h >= (tf - t).abs();
+            if last {
+                h = (tf - t).abs();
+            }
+            let sh = direction * h;
+            let t_new = if last { tf } else { t + sh };
+
+            let stages = (|| -> Result<Option<_>, Box<EvalAltResult>> {
+                let b1: Vec<FLOAT> = (0..n).map(|i| f[i] + sh * d * dfdt[i]).collect();
+                let Some(k1) = solve_shifted(&jac, sh * d, &b1) else {
+                    return Ok(None);
+                };
+                let y1: Vec<FLOAT> = (0..n).map(|i| y[i] + 0.5 * sh * k1[i]).collect();
+                let f1 = rhs.eval(t + 0.5 * sh, &y1)?;
+                let b2: Vec<FLOAT> = (0..n).map(|i| f1[i] - k1[i]).collect();
+                let Some(k2) = solve_shifted(&jac, sh * d, &b2) else {
+                    return Ok(None);
+                };
+                let k2: Vec<FLOAT> = (0..n).map(|i| k2[i] + k1[i]).collect();
+                let y_new: Vec<FLOAT> = (0..n).map(|i| y[i] + sh * k2[i]).collect();
+                let f_new = rhs.eval(t_new, &y_new)?;
+                let b3: Vec<FLOAT> = (0..n)
+                    .map(|i| {
+                        f_new[i] - e32 * (k2[i] - f1[i]) - 2.0 * (k1[i] - f[i]) + sh * d * dfdt[i]
+                    })
+                    .collect();
+                let Some(k3) = solve_shifted(&jac, sh * d, &b3) else {
+                    return Ok(None);
+                };
+                let e: Vec<FLOAT> = (0..n)
+                    .map(|i| sh / 6.0 * (k1[i] - 2.0 * k2[i] + k3[i]))
+                    .collect();
+                Ok(Some((y_new, f_new, e)))
+            })()?;
+
+            let Some((y_new, f_new, e)) = stages else {
+                h *= 0.5;
+                continue;
+            };
+            let err = tolerances.error_norm(&e, &y, &y_new);
+            if !err.is_finite() {
+                h *= 0.5;
+                continue;
+            }
+            if err <= 1.0 {
+                if recorder.step((t, &y, &f), (t_new, &y_new, &f_new))? {
+                    return Ok(());
+                }
+                t = t_new;
+                y = y_new;
+                f = f_new;
+                if (tf - t) * direction > 0.0 {
+                    jac = jacobian.eval(rhs, t, &y, &f)?;
+                    dfdt = time_derivative(rhs, t, &y, &f, direction)?;
+                }
+            }
+            let factor = if err == 0.0 {
+                5.0
+            } else {
+                (0.8 * err.powf(-1.0 / 3.0)).clamp(0.2, 5.0)
+            };
+            h *= if err <= 1.0 { factor } else { factor.min(1.0) };
+        }
+        Ok(())
+    }
+
+    /// Forward-difference approximation of `df/dt` at `(t, y)`, where `f = f(t, y)`.
+    fn time_derivative(
+        rhs: &Rhs,
+        t: FLOAT,
+        y: &[FLOAT],
+        f: &[FLOAT],
+        direction: FLOAT,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        let delta = direction * FLOAT::EPSILON.sqrt() * t.abs().max(1.0);
+        let ft = rhs.eval(t + delta, y)?;
+        Ok(ft.iter().zip(f).map(|(a, b)| (a - b) / delta).collect())
+    }
+
+    /// Highest order of the backward differentiation formulas.
+    const MAX_ORDER: usize = 5;
+
+    /// Maximum number of simplified Newton iterations per step.
+    const NEWTON_MAXITER: usize = 4;
+
+    /// Returns the matrix that rescales the backward differences of an interpolating polynomial
+    /// of the given order when the step size is multiplied by `factor`.
+    fn rescaling_matrix(order: usize, factor: FLOAT) -> DMatrix<FLOAT> {
+        let mut m = DMatrix::zeros(order + 1, order + 1);
+        for j in 0..=order {
+            m[(0, j)] = 1.0;
+        }
+        for i in 1..=order {
+            for j in 1..=order {
+                m[(i, j)] = (i as FLOAT - 1.0 - factor * j as FLOAT) / i as FLOAT;
+            }
+        }
+        for i in 1..=order {
+            for j in 0..=order {
+                m[(i, j)] *= m[(i - 1, j)];
+            }
+        }
+        m
+    }
+
+    /// Rescales the backward differences `d` (one row per difference) after the step size is
+    /// multiplied by `factor`.
+    fn change_differences(d: &mut DMatrix<FLOAT>, order: usize, factor: FLOAT) {
+        let ru = rescaling_matrix(order, factor) * rescaling_matrix(order, 1.0);
+        let rows = d.rows(0, order + 1).into_owned();
+        d.rows_mut(0, order + 1).copy_from(&(ru.transpose() * rows));
+    }
+
+    /// RMS norm of `x` scaled elementwise by `scale`.
+    fn scaled_norm(x: &[FLOAT], scale: &[FLOAT]) -> FLOAT {
+        let sum = x
+            .iter()
+            .zip(scale)
+            .map(|(x, s)| (x / s).powi(2))
+            .sum::<FLOAT>();
+        (sum / x.len() as FLOAT).sqrt()
+    }
+
+    /// Integrates a stiff problem with variable-order (1 to 5) numerical differentiation
+    /// formulas in backward-difference form, solving the implicit equations with a simplified
+    /// Newton iteration.
+    pub(super) fn integrate_ndf(
+        problem: &mut Problem,
+        jacobian: &Jacobian,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let (rhs, recorder) = (&problem.rhs, &mut problem.recorder);
+        let Tolerances {
+            rtol,
+            atol,
+            max_step,
+            ..
+        } = problem.tolerances;
+        let rtol = rtol.max(100.0 * FLOAT::EPSILON);
+        let (t0, tf) = (problem.t0, problem.tf);
+        let direction = (tf - t0).signum();
+        let n = problem.y0.len();
+
+        let kappa: [FLOAT; MAX_ORDER + 1] = [0.0, -0.1850, -1.0 / 9.0, -0.0823, -0.0415, 0.0];
+        let mut gamma = [0.0; MAX_ORDER + 1];
+        for k in 1..=MAX_ORDER {
+            gamma[k] = gamma[k - 1] + 1.0 / k as FLOAT;
+        }
+        let alpha: Vec<FLOAT> = (0..=MAX_ORDER)
+            .map(|k| (1.0 - kappa[k]) * gamma[k])
+            .collect();
+        let error_const: Vec<FLOAT> = (0..=MAX_ORDER)
+            .map(|k| kappa[k] * gamma[k] + 1.0 / (k + 1) as FLOAT)
+            .collect();
+        let newton_tol = (10.0 * FLOAT::EPSILON / rtol).max(rtol.sqrt().min(0.03));
+
+        let mut t = t0;
+        let mut y = problem.y0.clone();
+        let mut f = rhs.eval(t, &y)?;
+        let mut h_abs = problem.tolerances.first_step(&y, &f, tf - t0);
+        let mut jac = jacobian.eval(rhs, t, &y, &f)?;
+        let mut d = DMatrix::zeros(MAX_ORDER + 3, n);
+        for i in 0..n {
+            d[(0, i)] = y[i];
+            d[(1, i)] = f[i] * h_abs * direction;
+        }
+        let mut order = 1;
+        let mut n_equal_steps = 0;
+
+        while (tf - t) * direction > 0.0 {
+            let min_step = 16.0 * FLOAT::EPSILON * t.abs().max(1.0);
+            if h_abs > max_step {
+                change_differences(&mut d, order, max_step / h_abs);
+                h_abs = max_step;
+                n_equal_steps = 0;
+            }
+            let mut current_jac = false;
+            let (t_new, y_new, diff, safety, scale, error_norm) = loop {
+                if h_abs < min_step {
+                    return Err(step_too_small(t));
+                }
+                let mut t_new = t + direction * h_abs;
+                if (t_new - tf) * direction >= 0.0 {
+                    t_new = tf;
+                    change_differences(&mut d, order, (t_new - t).abs() / h_abs);
+                    n_equal_steps = 0;
+                }
+                let h = t_new - t;
+                h_abs = h.abs();
+
+                let y_predict: Vec<FLOAT> = (0..n)
+                    .map(|i| (0..=order).map(|k| d[(k, i)]).sum())
+                    .collect();
+                let scale: Vec<FLOAT> = y_predict.iter().map(|v| atol + rtol * v.abs()).collect();
+                let psi: Vec<FLOAT> = (0..n)
+                    .map(|i| {
+                        (1..=order).map(|k| d[(k, i)] * gamma[k]).sum::<FLOAT>() / alpha[order]
+                    })
+                    .collect();
+                let c = h / alpha[order];
+
+                let newton = loop {
+                    let result = newton(rhs, t_new, &y_predict, c, &psi, &jac, &scale, newton_tol)?;
+                    if result.is_some() || current_jac {
+                        break result;
+                    }
+                    let f_predict = rhs.eval(t_new, &y_predict)?;
+                    jac = jacobian.eval(rhs, t_new, &y_predict, &f_predict)?;
+                    current_jac = true;
+                };
+                let Some((n_iter, y_new, diff)) = newton else {
+                    h_abs *= 0.5;
+                    change_differences(&mut d, order, 0.5);
+                    n_equal_steps = 0;
+                    continue;
+                };
+
+                let safety = 0.9 * (2 * NEWTON_MAXITER + 1) as FLOAT
+                    / (2 * NEWTON_MAXITER + n_iter) as FLOAT;
+                let scale: Vec<FLOAT> = y_new.iter().map(|v| atol + rtol * v.abs()).collect();
+                let error: Vec<FLOAT> = diff.iter().map(|v| error_const[order] * v).collect();
+                let error_norm = scaled_norm(&error, &scale);
+                if error_norm > 1.0 {
+                    let factor = (safety * error_norm.powf(-1.0 / (order + 1) as FLOAT)).max(0.2);
+                    h_abs *= factor;
+                    change_differences(&mut d, order, factor);
+                    n_equal_steps = 0;
+                    continue;
+                }
+                break (t_new, y_new, diff, safety, scale, error_norm);
+            };
+
+            let f_new = rhs.eval(t_new, &y_new)?;
+            if recorder.step((t, &y, &f), (t_new, &y_new, &f_new))? {
+                return Ok(());
+            }
+            t = t_new;
+            y = y_new;
+            f = f_new;
+            n_equal_steps += 1;
+
+            for i in 0..n {
+                d[(order + 2, i)] = diff[i] - d[(order + 1, i)];
+                d[(order + 1, i)] = diff[i];
+            }
+            for k in (0..=order).rev() {
+                for i in 0..n {
+                    d[(k, i)] += d[(k + 1, i)];
+                }
+            }
+
+            if n_equal_steps < order + 1 {
+                continue;
+            }
+            let row_norm = |k: usize, constant: FLOAT| {
+                let row: Vec<FLOAT> = (0..n).map(|i| constant * d[(k, i)]).collect();
+                scaled_norm(&row, &scale)
+            };
+            let error_m_norm = if order > 1 {
+                row_norm(order, error_const[order - 1])
+            } else {
+                FLOAT::INFINITY
+            };
+            let error_p_norm = if order < MAX_ORDER {
+                row_norm(order + 2, error_const[order + 1])
+            } else {
+                FLOAT::INFINITY
+            };
+            let factors = [error_m_norm, error_norm, error_p_norm]
+                .iter()
+                .enumerate()
+                .map(|(k, norm)| norm.powf(-1.0 / (order + k) as FLOAT))
+                .collect::<Vec<FLOAT>>();
+            let best = (0..3)
+                .max_by(|a, b| factors[*a].total_cmp(&factors[*b]))
+                .unwrap();
+            order = order + best - 1;
+            let factor = (safety * factors[best]).min(10.0);
+            h_abs *= factor;
+            change_differences(&mut d, order, factor);
+            n_equal_steps = 0;
+        }
+        Ok(())
+    }
+
+    /// Number of iterations, new state and its difference from the prediction.
+    type NewtonStep = (usize, Vec<FLOAT>, Vec<FLOAT>);
+
+    /// Solves the implicit equations of a backward differentiation step with a simplified Newton
+    /// iteration. Returns the number of iterations, the new state and its difference from the
+    /// prediction, or `None` if the iteration does not converge.
+    #[allow(clippy::too_many_arguments)]
+    fn newton(
+        rhs: &Rhs,
+        t_new: FLOAT,
+        y_predict: &[FLOAT],
+        c: FLOAT,
+        psi: &[FLOAT],
+        jac: &DMatrix<FLOAT>,
+        scale: &[FLOAT],
+        tol: FLOAT,
+    ) -> Result<Option<NewtonStep>, Box<EvalAltResult>> {
+        let n = y_predict.len();
+        let lu = (DMatrix::identity(n, n) - jac * c).lu();
+        let mut y = y_predict.to_vec();
+        let mut diff = vec![0.0; n];
+        let mut dy_norm_old: Option<FLOAT> = None;
+        for k in 0..NEWTON_MAXITER {
+            let f = rhs.eval(t_new, &y)?;
+            if f.iter().any(|v| !v.is_finite()) {
+                break;
+            }
+            let b = DVector::from_iterator(n, (0..n).map(|i| c * f[i] - psi[i] - diff[i]));
+            let Some(dy) = lu.solve(&b) else {
+                break;
+            };
+            let dy_norm = scaled_norm(dy.as_slice(), scale);
+            let rate = dy_norm_old.map(|old| dy_norm / old);
+            if let Some(rate) = rate {
+                if rate >= 1.0
+                    || rate.powi((NEWTON_MAXITER - k) as i32) / (1.0 - rate) * dy_norm > tol
+                {
+                    break;
+                }
+            }
+            for i in 0..n {
+                y[i] += dy[i];
+                diff[i] += dy[i];
+            }
+            if dy_norm == 0.0 || rate.is_some_and(|rate| rate / (1.0 - rate) * dy_norm < tol) {
+                return Ok(Some((k + 1, y, diff)));
+            }
+            dy_norm_old = Some(dy_norm);
+        }
+        Ok(None)
+    }
+
+    /// Runs a stiff solver on the arguments of `ode15s` or `ode23s`.
+    pub(super) fn solve_stiff(
+        ctx: &NativeCallContext,
+        ndf: bool,
+        f: &FnPtr,
+        tspan: &rhai::Array,
+        y0: Dynamic,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut problem = Problem::new(ctx, f, tspan, y0, options)?;
+        let jacobian = Jacobian::from_options(ctx, options)?;
+        if ndf {
+            integrate_ndf(&mut problem, &jacobian)?;
+        } else {
+            integrate_rosenbrock(&mut problem, &jacobian)?;
+        }
+        Ok(problem.recorder.into_map(problem.rhs.scalar))
+    }
+}
+
+/// Collocation solver for two-point boundary value problems.
+#[cfg(feature = "nalgebra")]
+mod boundary {
+    use super::ivp::{hermite, state_to_dynamic, Rhs};
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, int_option, OperationCounter,
+    };
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// Maximum number of damped Newton iterations on a fixed mesh.
+    const MAX_NEWTON: usize = 20;
+
+    /// Maximum number of mesh refinements.
+    const MAX_REFINEMENTS: usize = 20;
+
+    /// A boundary value problem `y' = f(x, y)` with boundary conditions `bc(y(a), y(b)) = 0`.
+    struct Problem<'a> {
+        /// The right-hand side.
+        rhs: Rhs<'a>,
+        /// Function pointer for the boundary conditions.
+        bc: &'a FnPtr,
+    }
+
+    /// Flattened collocation and boundary residuals, with the derivative at each interval
+    /// midpoint.
+    type Residuals = (Vec<FLOAT>, Vec<Vec<FLOAT>>);
+
+    /// Solution values on a mesh.
+    struct Mesh {
+        /// Mesh nodes.
+        x: Vec<FLOAT>,
+        /// State at each node.
+        y: Vec<Vec<FLOAT>>,
+    }
+
+    /// Derivative of the cubic Hermite interpolant of an interval at `x`.
+    fn hermite_derivative(
+        (x0, y0, f0): (FLOAT, &[FLOAT], &[FLOAT]),
+        (x1, y1, f1): (FLOAT, &[FLOAT], &[FLOAT]),
+        x: FLOAT,
+    ) -> Vec<FLOAT> {
+        let h = x1 - x0;
+        let s = (x - x0) / h;
+        let d00 = 6.0 * s * s - 6.0 * s;
+        let d10 = 3.0 * s * s - 4.0 * s + 1.0;
+        let d11 = 3.0 * s * s - 2.0 * s;
+        (0..y0.len())
+            .map(|i| (d00 * (y0[i] - y1[i])) / h + d10 * f0[i] + d11 * f1[i])
+            .collect()
+    }
+
+    impl Problem<'_> {
+        /// Evaluates the boundary conditions.
+        fn bc_eval(&self, ya: &[FLOAT], yb: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let scalar = self.rhs.scalar;
+            let result = self.bc.call_raw(
+                self.rhs.ctx,
+                None,
+                [state_to_dynamic(ya, scalar), state_to_dynamic(yb, scalar)],
+            )?;
+            let residual = dynamic_to_vec_float(result).map_err(|_| {
+                arithmetic_error(
+                    "The boundary condition function must return a number or a numeric array",
+                )
+            })?;
+            if residual.len() != self.rhs.dim {
+                return Err(arithmetic_error(format!(
+                    "The boundary condition function returned {} values but the state has {}",
+                    residual.len(),
+                    self.rhs.dim
+                )));
+            }
+            Ok(residual)
+        }
+
+        /// Evaluates `f` at every node.
+        fn derivatives(&self, mesh: &Mesh) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+            mesh.x
+                .iter()
+                .zip(&mesh.y)
+                .map(|(x, y)| self.rhs.eval(*x, y))
+                .collect()
+        }
+
+        /// Collocation residual of interval `i` (Simpson's rule on the cubic interpolant) and the
+        /// derivative at its midpoint.
+        fn interval_residual(
+            &self,
+            mesh: &Mesh,
+            f: &[Vec<FLOAT>],
+            i: usize,
+        ) -> Result<(Vec<FLOAT>, Vec<FLOAT>), Box<EvalAltResult>> {
+            let (x0, x1) = (mesh.x[i], mesh.x[i + 1]);
+            let h = x1 - x0;
+            let x_mid = 0.5 * (x0 + x1);
+            let start = (x0, mesh.y[i].as_slice(), f[i].as_slice());
+            let end = (x1, mesh.y[i + 1].as_slice(), f[i + 1].as_slice());
+            let f_mid = self.rhs.eval(x_mid, &hermite(start, end, x_mid))?;
+            let residual = (0..self.rhs.dim)
+                .map(|k| {
+                    mesh.y[i + 1][k]
+                        - mesh.y[i][k]
+                        - h / 6.0 * (f[i][k] + 4.0 * f_mid[k] + f[i + 1][k])
+                })
+                .collect();
+            Ok((residual, f_mid))
+        }
+
+        /// Residuals of the collocation system: one block per interval followed by the boundary
+        /// conditions. Also returns the midpoint derivatives.
+        fn residuals(
+            &self,
+            mesh: &Mesh,
+            f: &[Vec<FLOAT>],
+        ) -> Result<Residuals, Box<EvalAltResult>> {
+            let m = mesh.x.len();
+            let mut residual = Vec::with_capacity(m * self.rhs.dim);
+            let mut f_mid = Vec::with_capacity(m - 1);
+            for i in 0..m - 1 {
+                let (r, fm) = self.interval_residual(mesh, f, i)?;
+                residual.extend(r);
+                f_mid.push(fm);
+            }
+            residual.extend(self.bc_eval(&mesh.y[0], &mesh.y[m - 1])?);
+            Ok((residual, f_mid))
+        }
+
+        /// Finite-difference Jacobian of the collocation residuals with respect to the states at
+        /// the nodes, exploiting the fact that each node only affects its neighbouring intervals
+        /// and, at the ends, the boundary conditions.
+        fn jacobian(
+            &self,
+            mesh: &mut Mesh,
+            f: &mut [Vec<FLOAT>],
+            residual: &[FLOAT],
+        ) -> Result<DMatrix<FLOAT>, Box<EvalAltResult>> {
+            let n = self.rhs.dim;
+            let m = mesh.x.len();
+            let mut jac = DMatrix::zeros(m * n, m * n);
+            for j in 0..m {
+                for k in 0..n {
+                    let original = mesh.y[j][k];
+                    let delta = FLOAT::EPSILON.sqrt() * original.abs().max(1.0);
+                    mesh.y[j][k] = original + delta;
+                    let f_original =
+                        std::mem::replace(&mut f[j], self.rhs.eval(mesh.x[j], &mesh.y[j])?);
+                    let column = j * n + k;
+                    for i in [j.wrapping_sub(1), j] {
+                        if i < m - 1 {
+                            let (r, _) = self.interval_residual(mesh, f, i)?;
+                            for (l, value) in r.iter().enumerate() {
+                                jac[(i * n + l, column)] = (value - residual[i * n + l]) / delta;
+                            }
+                        }
+                    }
+                    if j == 0 || j == m - 1 {
+                        let r = self.bc_eval(&mesh.y[0], &mesh.y[m - 1])?;
+                        let offset = (m - 1) * n;
+                        for (l, value) in r.iter().enumerate() {
+                            jac[(offset + l, column)] = (value - residual[offset + l]) / delta;
+                        }
+                    }
+                    mesh.y[j][k] = original;
+                    f[j] = f_original;
+                }
+            }
+            Ok(jac)
+        }
+
+        /// Whether the collocation and boundary residuals are small enough, relative to the
+        /// interval lengths and derivative magnitudes.
+        fn converged(
+            &self,
+            mesh: &Mesh,
+            residual: &[FLOAT],
+            f_mid: &[Vec<FLOAT>],
+            tol: FLOAT,
+            bc_tol: FLOAT,
+        ) -> bool {
+            let n = self.rhs.dim;
+            let m = mesh.x.len();
+            let collocation = (0..m - 1).all(|i| {
+                let h = mesh.x[i + 1] - mesh.x[i];
+                (0..n).all(|k| {
+                    1.5 * residual[i * n + k].abs() / h.abs()
+                        < 0.05 * tol * (1.0 + f_mid[i][k].abs())
+                })
+            });
+            collocation && residual[(m - 1) * n..].iter().all(|r| r.abs() < bc_tol)
+        }
+
+        /// Solves the collocation equations on a fixed mesh with a damped Newton iteration.
+        fn solve_collocation(
+            &self,
+            mesh: &mut Mesh,
+            tol: FLOAT,
+            bc_tol: FLOAT,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let n = self.rhs.dim;
+            let mut f = self.derivatives(mesh)?;
+            let (mut residual, mut f_mid) = self.residuals(mesh, &f)?;
+            for _ in 0..MAX_NEWTON {
+                if self.converged(mesh, &residual, &f_mid, tol, bc_tol) {
+                    break;
+                }
+                let jac = self.jacobian(mesh, &mut f, &residual)?;
+                let step = jac
+                    .lu()
+                    .solve(&-DVector::from_column_slice(&residual))
+                    .filter(|step| step.iter().all(|v| v.is_finite()))
+                    .ok_or_else(|| {
+                        arithmetic_error("The collocation system is singular; check the boundary conditions and the initial guess")
+                    })?;
+
+                let cost = residual.iter().map(|r| r * r).sum::<FLOAT>();
+                let start = mesh.y.clone();
+                let mut alpha = 1.0;
+                let mut improved = false;
+                for _ in 0..10 {
+                    for (j, y) in mesh.y.iter_mut().enumerate() {
+                        for (k, value) in y.iter_mut().enumerate() {
+                            *value = start[j][k] + alpha * step[j * n + k];
+                        }
+                    }
+                    f = self.derivatives(mesh)?;
+                    (residual, f_mid) = self.residuals(mesh, &f)?;
+                    let new_cost = residual.iter().map(|r| r * r).sum::<FLOAT>();
+                    if new_cost.is_finite() && new_cost < (1.0 - 2e-4 * alpha) * cost {
+                        improved = true;
+                        break;
+                    }
+                    alpha *= 0.5;
+                }
+                if !improved {
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        /// Estimates the RMS residual of the interpolated solution on each interval, relative to
+        /// `1 + |f|`, using Lobatto quadrature.
+        fn rms_residuals(&self, mesh: &Mesh) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let f = self.derivatives(mesh)?;
+            let mut rms = Vec::with_capacity(mesh.x.len() - 1);
+            for i in 0..mesh.x.len() - 1 {
+                let start = (mesh.x[i], mesh.y[i].as_slice(), f[i].as_slice());
+                let end = (mesh.x[i + 1], mesh.y[i + 1].as_slice(), f[i + 1].as_slice());
+                let x_mid = 0.5 * (mesh.x[i] + mesh.x[i + 1]);
+                let s = 0.5 * (mesh.x[i + 1] - mesh.x[i]) * (3.0 as FLOAT / 7.0).sqrt();
+                let mut squares = [0.0; 3];
+                for (square, x) in squares.iter_mut().zip([x_mid, x_mid - s, x_mid + s]) {
+                    let fx = self.rhs.eval(x, &hermite(start, end, x))?;
+                    *square = hermite_derivative(start, end, x)
+                        .iter()
+                        .zip(&fx)
+                        .map(|(dy, f)| ((dy - f) / (1.0 + f.abs())).powi(2))
+                        .sum();
+                }
+                rms.push(
+                    (0.5 * (32.0 / 45.0 * squares[0] + 49.0 / 90.0 * (squares[1] + squares[2])))
+                        .sqrt(),
+                );
+            }
+            Ok(rms)
+        }
+
+        /// Inserts one node in the middle of intervals with a residual above `tol`, or two
+        /// nodes if the residual exceeds `100 * tol`, interpolating the current solution.
+        fn refine(
+            &self,
+            mesh: &Mesh,
+            rms: &[FLOAT],
+            tol: FLOAT,
+        ) -> Result<Mesh, Box<EvalAltResult>> {
+            let f = self.derivatives(mesh)?;
+            let mut refined = Mesh {
+                x: vec![mesh.x[0]],
+                y: vec![mesh.y[0].clone()],
+            };
+            for (i, residual) in rms.iter().enumerate() {
+                let start = (mesh.x[i], mesh.y[i].as_slice(), f[i].as_slice());
+                let end = (mesh.x[i + 1], mesh.y[i + 1].as_slice(), f[i + 1].as_slice());
+                let h = mesh.x[i + 1] - mesh.x[i];
+                let fractions: &[FLOAT] = if *residual > 100.0 * tol {
+                    &[1.0 / 3.0, 2.0 / 3.0]
+                } else if *residual > tol {
+                    &[0.5]
+                } else {
+                    &[]
+                };
+                for fraction in fractions {
+                    let x = mesh.x[i] + fraction * h;
+                    refined.x.push(x);
+                    refined.y.push(hermite(start, end, x));
+                }
+                refined.x.push(mesh.x[i + 1]);
+                refined.y.push(mesh.y[i + 1].clone());
+            }
+            Ok(refined)
+        }
+    }
+
+    /// Builds the initial states from a guess: a constant state (number or flat array), one
+    /// state per node (array of arrays), or a function `guess(x)`.
+    fn initial_guess(
+        ctx: &NativeCallContext,
+        x: &[FLOAT],
+        guess: Dynamic,
+    ) -> Result<(Vec<Vec<FLOAT>>, bool), Box<EvalAltResult>> {
+        if let Some(guess) = guess.clone().try_cast::<FnPtr>() {
+            let mut scalar = true;
+            let mut y = Vec::with_capacity(x.len());
+            for xi in x {
+                let value = guess.call_raw(ctx, None, [Dynamic::from_float(*xi)])?;
+                scalar = value.is_int() || value.is_float();
+                y.push(dynamic_to_vec_float(value)?);
+            }
+            return Ok((y, scalar));
+        }
+        if guess.is_int() || guess.is_float() {
+            return Ok((vec![dynamic_to_vec_float(guess)?; x.len()], true));
+        }
+        let rows = guess.into_array().map_err(|_| {
+            arithmetic_error("The initial guess must be a number, an array or a function")
+        })?;
+        if !rows.is_empty() && rows.iter().all(|row| row.is_array()) {
+            if rows.len() != x.len() {
+                return Err(arithmetic_error(
+                    "An initial guess given per node must have one row per mesh point",
+                ));
+            }
+            let y = rows
+                .into_iter()
+                .map(dynamic_to_vec_float)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((y, false))
+        } else {
+            Ok((
+                vec![dynamic_to_vec_float(Dynamic::from_array(rows))?; x.len()],
+                false,
+            ))
+        }
+    }
+
+    /// Solves a boundary value problem, returning the solution map of `bvp`.
+    pub(super) fn solve_bvp(
+        ctx: &NativeCallContext,
+        f: &FnPtr,
+        bc: &FnPtr,
+        x: Array,
+        guess: Dynamic,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let tol = float_option(options, "tol", 1e-3)?;
+        let bc_tol = float_option(options, "bc_tol", tol)?;
+        let max_nodes = int_option(options, "max_nodes", 1000)?;
+        if tol <= 0.0 || bc_tol <= 0.0 {
+            return Err(arithmetic_error(
+                "The options 'tol' and 'bc_tol' must be positive",
+            ));
+        }
+
+        let x = dynamic_to_vec_float(Dynamic::from_array(x))?;
+        if x.len() < 2 || x.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(arithmetic_error(
+                "The initial mesh must have at least two strictly increasing points",
+            ));
+        }
+        let (y, scalar) = initial_guess(ctx, &x, guess)?;
+        let dim = y[0].len();
+        if dim == 0 || y.iter().any(|row| row.len() != dim) {
+            return Err(arithmetic_error(
+                "Every state in the initial guess must have the same, nonzero length",
+            ));
+        }
+
+        let problem = Problem {
+            rhs: Rhs {
+                ctx,
+                f,
+                scalar,
+                dim,
+                counter: OperationCounter::new(ctx),
+            },
+            bc,
+        };
+        let mut mesh = Mesh { x, y };
+        let mut converged = false;
+        let mut rms = vec![];
+        for _ in 0..MAX_REFINEMENTS {
+            problem.solve_collocation(&mut mesh, tol, bc_tol)?;
+            rms = problem.rms_residuals(&mesh)?;
+            if rms.iter().all(|r| *r < tol) {
+                converged = true;
+                break;
+            }
+            let refined = problem.refine(&mesh, &rms, tol)?;
+            if refined.x.len() as INT > max_nodes {
+                break;
+            }
+            mesh = refined;
+        }
+
+        let f = problem.derivatives(&mesh)?;
+        let states = |y: &[Vec<FLOAT>]| {
+            Dynamic::from_array(y.iter().map(|y| state_to_dynamic(y, scalar)).collect())
+        };
+        let floats =
+            |v: &[FLOAT]| Dynamic::from_array(v.iter().map(|v| Dynamic::from_float(*v)).collect());
+        let mut result = Map::new();
+        result.insert("x".into(), floats(&mesh.x));
+        result.insert("y".into(), states(&mesh.y));
+        result.insert("yp".into(), states(&f));
+        result.insert("residuals".into(), floats(&rms));
+        result.insert(
+            "max_residual".into(),
+            Dynamic::from_float(rms.iter().fold(0.0, |a: FLOAT, b| a.max(*b))),
+        );
+        result.insert("converged".into(), Dynamic::from_bool(converged));
+        Ok(result)
+    }
+}
+
+#[export_module]
+pub mod ode_functions {
+    #[cfg(feature = "nalgebra")]
+    use super::boundary::solve_bvp;
+    use super::ivp::{
+        initial_state, integrate_rk4, solve_explicit, time_span, Events, Recorder, Rhs,
+        BOGACKI_SHAMPINE, DORMAND_PRINCE,
+    };
+    #[cfg(feature = "nalgebra")]
+    use super::stiff::solve_stiff;
+    use crate::{arithmetic_error, float_option, OperationCounter};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT};
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the adaptive
+    /// Dormand-Prince 5(4) Runge-Kutta method. `f` is called with the time and the state (a
+    /// number if `y0` is a number, otherwise an array) and must return the derivative in the same
+    /// form. Returns an object map with the times `t` and states `y` (one entry per time). If
+    /// `tspan` has more than two entries, the solution is reported at exactly those times.
+    /// ```typescript
+    /// let sol = ode45(|t, y| -y, [0.0, 1.0], 1.0);
+    /// assert_approx_eq(sol.y[-1], exp(-1.0), 1e-4);
+    /// assert_eq(sol.t[-1], 1.0);
+    /// ```
+    /// ```typescript
+    /// let sol = ode45(|t, y| [y[1], -y[0]], [0.0, pi / 2.0, pi], [1.0, 0.0]);
+    /// assert_eq(sol.t, [0.0, pi / 2.0, pi]);
+    /// assert_approx_eq(sol.y[2], [-1.0, 0.0], 1e-3);
+    /// ```
+    #[rhai_fn(name = "ode45", return_raw)]
+    pub fn ode45(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_explicit(&ctx, &DORMAND_PRINCE, &f, &tspan, y0, &Map::new())
+    }
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the adaptive
+    /// Dormand-Prince 5(4) Runge-Kutta method. The following options are supported:
+    /// - `rtol`: relative error tolerance (default `1e-3`).
+    /// - `atol`: absolute error tolerance (default `1e-6`).
+    /// - `max_step`: largest step size (default a tenth of the time span).
+    /// - `initial_step`: first step size to try (chosen automatically by default).
+    /// - `output_times`: array of times at which to report the solution.
+    /// - `events`: function `g(t, y)` returning a number or an array of event values whose zero
+    ///   crossings are located.
+    /// - `terminal`: bool, or array of bools per event, stopping the integration at the event
+    ///   (default `false`).
+    /// - `direction`: `1` to locate only rising crossings, `-1` for falling ones and `0` for both
+    ///   (default), either for all events or as an array per event.
+    ///
+    /// Returns an object map with the times `t` and states `y`. When events are requested, the
+    /// map also holds the event times `te`, states `ye` and event indices `ie`.
+    /// ```typescript
+    /// fn decay(t, y) { -2.0 * y }
+    /// let sol = ode45(Fn("decay"), [0, 1], 3, #{rtol: 1e-10, atol: 1e-12});
+    /// assert_approx_eq(sol.y[-1], 3.0 * exp(-2.0), 1e-9);
+    /// ```
+    /// ```typescript
+    /// let k = 0.5;
+    /// let sol = ode45(|t, y| [-k * y[0]], [0.0, 2.0], [1.0], #{output_times: [0.5, 1.0, 1.5]});
+    /// assert_eq(sol.t, [0.5, 1.0, 1.5]);
+    /// assert_approx_eq(sol.y[1][0], exp(-0.5), 1e-4);
+    /// ```
+    /// ```typescript
+    /// // A ball dropped from 10 m, stopping when it hits the ground
+    /// let sol = ode45(|t, y| [y[1], -9.81], [0.0, 5.0], [10.0, 0.0],
+    ///                 #{events: |t, y| y[0], terminal: true, rtol: 1e-8, atol: 1e-10});
+    /// assert_approx_eq(sol.te[0], sqrt(20.0 / 9.81), 1e-8);
+    /// assert_eq(sol.t[-1], sol.te[0]);
+    /// assert_eq(sol.ie, [0]);
+    /// ```
+    #[rhai_fn(name = "ode45", return_raw)]
+    pub fn ode45_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_explicit(&ctx, &DORMAND_PRINCE, &f, &tspan, y0, &options)
+    }
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the adaptive
+    /// Bogacki-Shampine 3(2) Runge-Kutta method, which can be more efficient than `ode45` at
+    /// loose tolerances. Returns an object map with the times `t` and states `y`.
+    /// ```typescript
+    /// let sol = ode23(|t, y| t, [0.0, 2.0], 0.0);
+    /// assert_approx_eq(sol.y[-1], 2.0, 1e-10);
+    /// ```
+    #[rhai_fn(name = "ode23", return_raw)]
+    pub fn ode23(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_explicit(&ctx, &BOGACKI_SHAMPINE, &f, &tspan, y0, &Map::new())
+    }
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the adaptive
+    /// Bogacki-Shampine 3(2) Runge-Kutta method. Accepts the same options as `ode45`, including
+    /// event location.
+    /// ```typescript
+    /// let sol = ode23(|t, y| [y[1], -y[0]], [0.0, 2.0 * pi], [0.0, 1.0], #{rtol: 1e-8, atol: 1e-10});
+    /// assert_approx_eq(sol.y[-1], [0.0, 1.0], 1e-6);
+    /// ```
+    #[rhai_fn(name = "ode23", return_raw)]
+    pub fn ode23_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_explicit(&ctx, &BOGACKI_SHAMPINE, &f, &tspan, y0, &options)
+    }
+
+    /// Solves the stiff initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with
+    /// variable-order (1 to 5) numerical differentiation formulas, in the style of MATLAB's
+    /// `ode15s`. The Jacobian `df/dy` is approximated by finite differences. Returns an object
+    /// map with the times `t` and states `y`, in the same form as `ode45`.
+    /// ```typescript
+    /// let sol = ode15s(|t, y| -1000.0 * (y - cos(t)), [0.0, 1.0], 0.0);
+    /// assert_approx_eq(sol.y[-1], cos(1.0), 1e-2);
+    /// assert(len(sol.t) < 200);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "ode15s", return_raw)]
+    pub fn ode15s(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_stiff(&ctx, true, &f, &tspan, y0, &Map::new())
+    }
+
+    /// Solves the stiff initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with
+    /// variable-order numerical differentiation formulas. Accepts the same options as `ode45`
+    /// (including event location), plus `jacobian`: a function `J(t, y)` returning the matrix
+    /// `df/dy` as an array of rows (or a number for scalar problems).
+    /// ```typescript
+    /// // Robertson's chemical kinetics problem
+    /// fn robertson(t, y) {
+    ///     [-0.04 * y[0] + 1e4 * y[1] * y[2],
+    ///      0.04 * y[0] - 1e4 * y[1] * y[2] - 3e7 * y[1] ** 2,
+    ///      3e7 * y[1] ** 2]
+    /// }
+    /// let sol = ode15s(Fn("robertson"), [0.0, 40.0], [1.0, 0.0, 0.0], #{rtol: 1e-6, atol: 1e-10});
+    /// assert_approx_eq(sol.y[-1][0], 0.7158, 1e-3);
+    /// assert_approx_eq(sum(sol.y[-1]), 1.0, 1e-6);
+    /// ```
+    /// ```typescript
+    /// let sol = ode15s(|t, y| [y[1], -100.0 * y[0] - 101.0 * y[1]], [0.0, 1.0], [1.0, 0.0],
+    ///                  #{jacobian: |t, y| [[0.0, 1.0], [-100.0, -101.0]], rtol: 1e-6, atol: 1e-9});
+    /// let exact = (100.0 * exp(-1.0) - exp(-100.0)) / 99.0;
+    /// assert_approx_eq(sol.y[-1][0], exact, 1e-5);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "ode15s", return_raw)]
+    pub fn ode15s_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_stiff(&ctx, true, &f, &tspan, y0, &options)
+    }
+
+    /// Solves the stiff initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with a modified
+    /// Rosenbrock method of order 2, in the style of MATLAB's `ode23s`. The Jacobian `df/dy` is
+    /// approximated by finite differences. Returns an object map with the times `t` and states
+    /// `y`, in the same form as `ode45`.
+    /// ```typescript
+    /// let sol = ode23s(|t, y| -1000.0 * (y - cos(t)), [0.0, 1.0], 0.0);
+    /// assert_approx_eq(sol.y[-1], cos(1.0), 1e-2);
+    /// assert(len(sol.t) < 200);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "ode23s", return_raw)]
+    pub fn ode23s(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_stiff(&ctx, false, &f, &tspan, y0, &Map::new())
+    }
+
+    /// Solves the stiff initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with a modified
+    /// Rosenbrock method of order 2. Accepts the same options as `ode15s`, including `jacobian`
+    /// and event location.
+    /// ```typescript
+    /// // Stop when the fast species has decayed below 1%
+    /// let sol = ode23s(|t, y| [-500.0 * y[0], 500.0 * y[0] - y[1]], [0.0, 1.0], [1.0, 0.0],
+    ///                  #{events: |t, y| y[0] - 0.01, terminal: true, direction: -1, rtol: 1e-6});
+    /// assert_approx_eq(sol.te[0], ln(100.0) / 500.0, 1e-4);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "ode23s", return_raw)]
+    pub fn ode23s_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_stiff(&ctx, false, &f, &tspan, y0, &options)
+    }
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the classical
+    /// fixed-step fourth-order Runge-Kutta method. If `tspan` has two entries the interval is
+    /// divided into 100 equal steps; otherwise one step is taken between each pair of
+    /// consecutive times. Returns an object map with the times `t` and states `y`.
+    /// ```typescript
+    /// let sol = rk4(|t, y| -y, [0.0, 1.0], 1.0);
+    /// assert_eq(len(sol.t), 101);
+    /// assert_approx_eq(sol.y[-1], exp(-1.0), 1e-9);
+    /// ```
+    /// ```typescript
+    /// let sol = rk4(|t, y| [1.0, t], linspace(0.0, 1.0, 5), [0.0, 0.0]);
+    /// assert_approx_eq(sol.y[-1], [1.0, 0.5], 1e-12);
+    /// ```
+    #[rhai_fn(name = "rk4", return_raw)]
+    pub fn rk4(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        rk4_with_options(ctx, f, tspan, y0, Map::new())
+    }
+
+    /// Solves the initial value problem `y' = f(t, y)`, `y(tspan[0]) = y0` with the classical
+    /// fixed-step fourth-order Runge-Kutta method. When `tspan` has two entries, the `step`
+    /// option sets the step size; the last step is shortened to end exactly at `tspan[1]`. The
+    /// `events`, `terminal` and `direction` options of `ode45` are also supported.
+    /// ```typescript
+    /// let sol = rk4(|t, y| 2.0 * t, [0.0, 1.0], 0.0, #{step: 0.3});
+    /// assert_eq(len(sol.t), 5);
+    /// assert_approx_eq(sol.y[-1], 1.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "rk4", return_raw)]
+    pub fn rk4_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        tspan: Array,
+        y0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let (t0, tf, span_outputs) = time_span(&tspan)?;
+        let (y0, scalar) = initial_state(y0)?;
+        let times = match span_outputs {
+            Some(times) => times,
+            None => {
+                let default_step = (tf - t0).abs() / 100.0;
+                let step = float_option(&options, "step", default_step)?;
+                if step <= 0.0 {
+                    return Err(arithmetic_error("The option 'step' must be positive"));
+                }
+                let n = ((tf - t0).abs() / step - 1e-9).ceil().max(1.0) as usize;
+                let mut times = (0..n)
+                    .map(|i| t0 + (tf - t0).signum() * step * i as FLOAT)
+                    .collect::<Vec<FLOAT>>();
+                times.push(tf);
+                times
+            }
+        };
+
+        let rhs = Rhs {
+            ctx: &ctx,
+            f: &f,
+            scalar,
+            dim: y0.len(),
+            counter: OperationCounter::new(&ctx),
+        };
+        let events = Events::from_options(&ctx, &options, scalar)?;
+        let mut recorder = Recorder::new(t0, &y0, None, events)?;
+        integrate_rk4(&rhs, &times, y0, &mut recorder)?;
+        Ok(recorder.into_map(scalar))
+    }
+
+    /// Solves the two-point boundary value problem `y' = f(x, y)` on `[x[0], x[-1]]` with
+    /// boundary conditions `bc(y(a), y(b)) = 0`, using fourth-order collocation with adaptive
+    /// mesh refinement in the style of MATLAB's `bvp4c`. `bc` must return one residual per state
+    /// variable. `x` is the initial mesh, and `guess` the initial guess for the solution: a
+    /// constant state (a number or array), an array with one state per mesh point, or a
+    /// function of `x`.
+    ///
+    /// Returns an object map with the final mesh `x`, the states `y` and derivatives `yp` at each
+    /// mesh point, the estimated RMS relative `residuals` on each interval, their maximum
+    /// `max_residual`, and whether all residuals are below the tolerance (`converged`).
+    /// ```typescript
+    /// // y'' = -y with y(0) = 0 and y(pi / 2) = 1 has the solution sin(x)
+    /// let sol = bvp(|x, y| [y[1], -y[0]], |ya, yb| [ya[0], yb[0] - 1.0], linspace(0.0, pi / 2.0, 5), [0.0, 1.0]);
+    /// assert(sol.converged);
+    /// assert_approx_eq(sol.y[-1][1], 0.0, 1e-3);
+    /// assert_approx_eq(sol.y.map(|y| y[0]), sol.x.map(|x| sin(x)), 1e-3);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "bvp", return_raw)]
+    pub fn bvp(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        bc: FnPtr,
+        x: Array,
+        guess: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_bvp(&ctx, &f, &bc, x, guess, &Map::new())
+    }
+
+    /// Solves a two-point boundary value problem with collocation and adaptive mesh refinement.
+    /// The following options are supported:
+    /// - `tol`: tolerance on the RMS relative residual of each interval (default `1e-3`).
+    /// - `bc_tol`: tolerance on the boundary condition residuals (default `tol`).
+    /// - `max_nodes`: largest number of mesh points (default `1000`).
+    /// ```typescript
+    /// // Temperature along a cooling fin with an insulated tip: theta'' = 4 theta
+    /// let sol = bvp(|x, y| [y[1], 4.0 * y[0]], |ya, yb| [ya[0] - 1.0, yb[1]],
+    ///               [0.0, 0.5, 1.0], |x| [1.0, 0.0], #{tol: 1e-6});
+    /// assert(sol.converged);
+    /// assert_approx_eq(sol.y[-1][0], 1.0 / cosh(2.0), 1e-6);
+    /// assert(sol.max_residual < 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "bvp", return_raw)]
+    pub fn bvp_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        bc: FnPtr,
+        x: Array,
+        guess: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        solve_bvp(&ctx, &f, &bc, x, guess, &options)
+    }
+}
@@ -0,0 +1,4463 @@
+use rhai::plugin::*;
+
+/// Objective evaluation, progress reporting and results shared by the local minimizers.
+mod minimization {
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, if_int_convert_to_float_and_do, OperationCounter,
+    };
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+    use std::cell::Cell;
+
+    /// Converts a point to the form expected by the script: a FLOAT for scalar problems and an
+    /// array otherwise.
+    pub(super) fn point_to_dynamic(x: &[FLOAT], scalar: bool) -> Dynamic {
+        if scalar {
+            Dynamic::from_float(x[0])
+        } else {
+            Dynamic::from_array(x.iter().map(|v| Dynamic::from_float(*v)).collect())
+        }
+    }
+
+    /// Reads the matrix `name` as a list of rows with `n` columns each. A flat list is a single
+    /// row, and an empty list has no rows.
+    pub(super) fn matrix_rows(
+        value: Dynamic,
+        name: &str,
+        n: usize,
+    ) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let rows = value
+            .into_array()
+            .map_err(|_| arithmetic_error(format!("'{name}' must be a matrix")))?;
+        let rows = if rows.iter().all(|row| row.is_array()) {
+            rows.into_iter()
+                .map(dynamic_to_vec_float)
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![dynamic_to_vec_float(Dynamic::from_array(rows))?]
+        };
+        if rows.iter().any(|row| row.len() != n) {
+            return Err(arithmetic_error(format!(
+                "Every row of '{name}' must have {n} columns, one per variable"
+            )));
+        }
+        Ok(rows)
+    }
+
+    /// Reads an optional bound, given as a number for all variables or as a list.
+    pub(super) fn bound_entry(
+        map: &Map,
+        key: &str,
+        n: usize,
+        default: FLOAT,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        match map.get(key) {
+            None => Ok(vec![default; n]),
+            Some(value) if value.is_int() || value.is_float() => {
+                Ok(vec![dynamic_to_vec_float(value.clone())?[0]; n])
+            }
+            Some(value) => {
+                let bound = dynamic_to_vec_float(value.clone())?;
+                if bound.len() != n {
+                    return Err(arithmetic_error(format!(
+                        "The bound '{key}' must have {n} entries, one per variable"
+                    )));
+                }
+                Ok(bound)
+            }
+        }
+    }
+
+    /// Reads an optional function pointer.
+    pub(super) fn function_entry(
+        map: &Map,
+        key: &str,
+    ) -> Result<Option<FnPtr>, Box<EvalAltResult>> {
+        match map.get(key) {
+            Some(value) => Ok(Some(value.clone().try_cast::<FnPtr>().ok_or_else(
+                || arithmetic_error(format!("'{key}' must be a function pointer")),
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// A real-valued objective function, implemented by a Rhai function pointer.
+    pub(super) struct Objective<'a> {
+        /// Context used to call back into the script.
+        pub(super) ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `f(x)`.
+        pub(super) f: &'a FnPtr,
+        /// Whether the variable is a scalar rather than an array.
+        pub(super) scalar: bool,
+        /// Number of evaluations so far.
+        pub(super) evaluations: Cell<INT>,
+        /// Counter of the calls to the objective.
+        pub(super) counter: OperationCounter,
+    }
+
+    impl<'a> Objective<'a> {
+        /// Wraps a function pointer as an objective.
+        pub(super) fn new(ctx: &'a NativeCallContext<'a>, f: &'a FnPtr, scalar: bool) -> Self {
+            Self {
+                ctx,
+                f,
+                scalar,
+                evaluations: Cell::new(0),
+                counter: OperationCounter::new(ctx),
+            }
+        }
+
+        /// Evaluates `f(x)`.
+        pub(super) fn eval(&self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            self.evaluations.set(self.evaluations.get() + 1);
+            let value = self
+                .f
+                .call_raw(self.ctx, None, [point_to_dynamic(x, self.scalar)])?;
+            if_int_convert_to_float_and_do(value, Ok)
+                .map_err(|_| arithmetic_error("The objective function must return an INT or FLOAT"))
+        }
+    }
+
+    /// Calls the optional per-iteration `callback` option.
+    pub(super) struct Monitor<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for the callback, if given.
+        callback: Option<FnPtr>,
+    }
+
+    impl<'a> Monitor<'a> {
+        /// Reads the `callback` option.
+        pub(super) fn from_options(
+            ctx: &'a NativeCallContext<'a>,
+            options: &Map,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            let callback = match options.get("callback") {
+                Some(callback) => Some(callback.clone().try_cast::<FnPtr>().ok_or_else(|| {
+                    arithmetic_error("The option 'callback' must be a function pointer")
+                })?),
+                None => None,
+            };
+            Ok(Self { ctx, callback })
+        }
+
+        /// Reports the state after an iteration as an object map with entries `iteration`, `x`,
+        /// `fval` and `evaluations`. Returns `true` if the callback asked to stop by returning
+        /// `true`.
+        pub(super) fn report(
+            &self,
+            iteration: INT,
+            x: &[FLOAT],
+            fval: FLOAT,
+            objective: &Objective,
+        ) -> Result<bool, Box<EvalAltResult>> {
+            let Some(callback) = &self.callback else {
+                return Ok(false);
+            };
+            objective.counter.tick()?;
+            let mut state = Map::new();
+            state.insert("iteration".into(), Dynamic::from_int(iteration));
+            state.insert("x".into(), point_to_dynamic(x, objective.scalar));
+            state.insert("fval".into(), Dynamic::from_float(fval));
+            state.insert(
+                "evaluations".into(),
+                Dynamic::from_int(objective.evaluations.get()),
+            );
+            let stop = callback.call_raw(self.ctx, None, [state.into()])?;
+            Ok(stop.as_bool().unwrap_or(false))
+        }
+    }
+
+    /// Reason a minimizer stopped.
+    #[derive(Clone, Copy, PartialEq)]
+    pub(super) enum Status {
+        /// The tolerances were met.
+        Converged,
+        /// The iteration limit was reached.
+        MaxIterations,
+        /// The evaluation limit was reached.
+        MaxEvaluations,
+        /// The callback asked to stop.
+        Stopped,
+        /// The step became too small to make progress.
+        StepTolerance,
+        /// No step satisfying the line search conditions was found.
+        LineSearchFailed,
+        /// The constraints could not be satisfied.
+        Infeasible,
+    }
+
+    impl Status {
+        /// Name of the status as reported to scripts.
+        pub(super) fn name(self) -> &'static str {
+            match self {
+                Status::Converged => "converged",
+                Status::MaxIterations => "max_iterations",
+                Status::MaxEvaluations => "max_evaluations",
+                Status::Stopped => "stopped",
+                Status::StepTolerance => "step_tolerance",
+                Status::LineSearchFailed => "line_search_failed",
+                Status::Infeasible => "infeasible",
+            }
+        }
+    }
+
+    /// Outcome of a minimizer.
+    pub(super) struct Minimum {
+        /// Best point found.
+        pub(super) x: Vec<FLOAT>,
+        /// Objective value at `x`.
+        pub(super) fval: FLOAT,
+        /// Number of iterations performed.
+        pub(super) iterations: INT,
+        /// Why the minimizer stopped.
+        pub(super) status: Status,
+    }
+
+    impl Minimum {
+        /// Returns the result as an object map with entries `x`, `fval`, `iterations`,
+        /// `evaluations`, `status` and `converged`.
+        pub(super) fn into_map(self, objective: &Objective) -> Map {
+            let mut result = Map::new();
+            result.insert("x".into(), point_to_dynamic(&self.x, objective.scalar));
+            result.insert("fval".into(), Dynamic::from_float(self.fval));
+            result.insert("iterations".into(), Dynamic::from_int(self.iterations));
+            result.insert(
+                "evaluations".into(),
+                Dynamic::from_int(objective.evaluations.get()),
+            );
+            result.insert("status".into(), self.status.name().into());
+            result.insert(
+                "converged".into(),
+                Dynamic::from_bool(self.status == Status::Converged),
+            );
+            result
+        }
+    }
+
+    /// Minimizes `f` with the Nelder-Mead simplex method, using the standard reflection,
+    /// expansion, contraction and shrink coefficients and an initial simplex that perturbs each
+    /// coordinate of `x0` by 5%.
+    pub(super) fn nelder_mead(
+        f: &Objective,
+        x0: Vec<FLOAT>,
+        (tol_x, tol_f): (FLOAT, FLOAT),
+        (max_iterations, max_evaluations): (INT, INT),
+        monitor: &Monitor,
+    ) -> Result<Minimum, Box<EvalAltResult>> {
+        let n = x0.len();
+        let mut simplex = vec![x0.clone()];
+        for i in 0..n {
+            let mut vertex = x0.clone();
+            vertex[i] = if vertex[i] == 0.0 {
+                0.00025
+            } else {
+                1.05 * vertex[i]
+            };
+            simplex.push(vertex);
+        }
+        let mut values = simplex
+            .iter()
+            .map(|x| f.eval(x))
+            .collect::<Result<Vec<FLOAT>, _>>()?;
+        if !values[0].is_finite() {
+            return Err(arithmetic_error(
+                "The objective function is not finite at the starting point",
+            ));
+        }
+
+        // Maximum that propagates NaN, so that a simplex with NaN values never converges
+        let max = |m: FLOAT, v: FLOAT| if v.is_nan() || v > m { v } else { m };
+        let point = |a: &[FLOAT], b: &[FLOAT], t: FLOAT| -> Vec<FLOAT> {
+            a.iter().zip(b).map(|(a, b)| a + t * (b - a)).collect()
+        };
+        let mut iterations = 0;
+        let status = loop {
+            let mut order: Vec<usize> = (0..=n).collect();
+            order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+            simplex = order.iter().map(|i| simplex[*i].clone()).collect();
+            values = order.iter().map(|i| values[*i]).collect();
+
+            let spread_f = values.iter().map(|v| (v - values[0]).abs()).fold(0.0, max);
+            let spread_x = simplex[1..]
+                .iter()
+                .flat_map(|x| x.iter().zip(&simplex[0]).map(|(a, b)| (a - b).abs()))
+                .fold(0.0, max);
+            if spread_f <= tol_f && spread_x <= tol_x {
+                break Status::Converged;
+            }
+            if iterations >= max_iterations {
+                break Status::MaxIterations;
+            }
+            if f.evaluations.get() >= max_evaluations {
+                break Status::MaxEvaluations;
+            }
+            iterations += 1;
+
+            let centroid: Vec<FLOAT> = (0..n)
+                .map(|j| simplex[..n].iter().map(|x| x[j]).sum::<FLOAT>() / n as FLOAT)
+                .collect();
+            let worst = simplex[n].clone();
+            let reflected = point(&centroid, &worst, -1.0);
+            let f_reflected = f.eval(&reflected)?;
+            if f_reflected < values[0] {
+                let expanded = point(&centroid, &worst, -2.0);
+                let f_expanded = f.eval(&expanded)?;
+                if f_expanded < f_reflected {
+                    (simplex[n], values[n]) = (expanded, f_expanded);
+                } else {
+                    (simplex[n], values[n]) = (reflected, f_reflected);
+                }
+            } else if f_reflected < values[n - 1] {
+                (simplex[n], values[n]) = (reflected, f_reflected);
+            } else {
+                let (contracted, f_contracted) = if f_reflected < values[n] {
+                    let outside = point(&centroid, &worst, -0.5);
+                    let f_outside = f.eval(&outside)?;
+                    (outside, f_outside)
+                } else {
+                    let inside = point(&centroid, &worst, 0.5);
+                    let f_inside = f.eval(&inside)?;
+                    (inside, f_inside)
+                };
+                if f_contracted < f_reflected.min(values[n]) {
+                    (simplex[n], values[n]) = (contracted, f_contracted);
+                } else {
+                    for i in 1..=n {
+                        simplex[i] = point(&simplex[0], &simplex[i], 0.5);
+                        values[i] = f.eval(&simplex[i])?;
+                    }
+                }
+            }
+
+            let best = (0..=n)
+                .min_by(|a, b| values[*a].total_cmp(&values[*b]))
+                .unwrap();
+            if monitor.report(iterations, &simplex[best], values[best], f)? {
+                break Status::Stopped;
+            }
+        };
+
+        let best = (0..=n)
+            .min_by(|a, b| values[*a].total_cmp(&values[*b]))
+            .unwrap();
+        Ok(Minimum {
+            x: simplex[best].clone(),
+            fval: values[best],
+            iterations,
+            status,
+        })
+    }
+
+    /// Minimizes `f` on `[a, b]` with Brent's method, which combines golden-section search with
+    /// successive parabolic interpolation.
+    pub(super) fn brent_minimize(
+        f: &Objective,
+        (mut a, mut b): (FLOAT, FLOAT),
+        tol_x: FLOAT,
+        (max_iterations, max_evaluations): (INT, INT),
+        monitor: &Monitor,
+    ) -> Result<Minimum, Box<EvalAltResult>> {
+        let golden = 0.5 * (3.0 - FLOAT::sqrt(5.0));
+        let mut x = a + golden * (b - a);
+        let (mut w, mut v) = (x, x);
+        let mut fx = f.eval(&[x])?;
+        let (mut fw, mut fv) = (fx, fx);
+        let (mut d, mut e): (FLOAT, FLOAT) = (0.0, 0.0);
+        let mut iterations = 0;
+
+        let status = loop {
+            let mid = 0.5 * (a + b);
+            let tol1 = FLOAT::EPSILON.sqrt() * x.abs() + tol_x / 3.0;
+            let tol2 = 2.0 * tol1;
+            if (x - mid).abs() <= tol2 - 0.5 * (b - a) {
+                break Status::Converged;
+            }
+            if iterations >= max_iterations {
+                break Status::MaxIterations;
+            }
+            if f.evaluations.get() >= max_evaluations {
+                break Status::MaxEvaluations;
+            }
+            iterations += 1;
+
+            let mut golden_step = true;
+            if e.abs() > tol1 {
+                // Try a parabola through x, v and w
+                let r = (x - w) * (fx - fv);
+                let mut q = (x - v) * (fx - fw);
+                let mut p = (x - v) * q - (x - w) * r;
+                q = 2.0 * (q - r);
+                if q > 0.0 {
+                    p = -p;
+                }
+                q = q.abs();
+                let previous = e;
+                e = d;
+                if p.abs() < (0.5 * q * previous).abs() && p > q * (a - x) && p < q * (b - x) {
+                    d = p / q;
+                    let u = x + d;
+                    if u - a < tol2 || b - u < tol2 {
+                        d = tol1.copysign(mid - x);
+                    }
+                    golden_step = false;
+                }
+            }
+            if golden_step {
+                e = if x >= mid { a - x } else { b - x };
+                d = golden * e;
+            }
+            let u = if d.abs() >= tol1 {
+                x + d
+            } else {
+                x + tol1.copysign(d)
+            };
+            let fu = f.eval(&[u])?;
+            if fu <= fx {
+                if u >= x {
+                    a = x;
+                } else {
+                    b = x;
+                }
+                (v, fv) = (w, fw);
+                (w, fw) = (x, fx);
+                (x, fx) = (u, fu);
+            } else {
+                if u < x {
+                    a = u;
+                } else {
+                    b = u;
+                }
+                if fu <= fw || w == x {
+                    (v, fv) = (w, fw);
+                    (w, fw) = (u, fu);
+                } else if fu <= fv || v == x || v == w {
+                    (v, fv) = (u, fu);
+                }
+            }
+
+            if monitor.report(iterations, &[x], fx, f)? {
+                break Status::Stopped;
+            }
+        };
+        Ok(Minimum {
+            x: vec![x],
+            fval: fx,
+            iterations,
+            status,
+        })
+    }
+}
+
+/// Gradients, the Wolfe line search and the search directions of `fminunc`.
+mod quasi_newton {
+    use super::minimization::{point_to_dynamic, Minimum, Monitor, Objective, Status};
+    use crate::{arithmetic_error, dynamic_to_vec_float, float_option, int_option};
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+    use std::collections::VecDeque;
+
+    /// Dot product of two vectors.
+    fn dot(a: &[FLOAT], b: &[FLOAT]) -> FLOAT {
+        a.iter().zip(b).map(|(a, b)| a * b).sum()
+    }
+
+    /// Euclidean norm of a vector.
+    pub(super) fn norm(a: &[FLOAT]) -> FLOAT {
+        dot(a, a).sqrt()
+    }
+
+    /// Largest absolute component of a vector.
+    fn max_abs(a: &[FLOAT]) -> FLOAT {
+        a.iter().fold(0.0, |m, v| m.max(v.abs()))
+    }
+
+    /// Lower and upper bounds on the variables, infinite where absent.
+    pub(super) struct Bounds {
+        /// Lower bounds.
+        pub(super) lower: Vec<FLOAT>,
+        /// Upper bounds.
+        pub(super) upper: Vec<FLOAT>,
+    }
+
+    impl Bounds {
+        /// No bounds on `n` variables.
+        pub(super) fn unbounded(n: usize) -> Self {
+            Self {
+                lower: vec![FLOAT::NEG_INFINITY; n],
+                upper: vec![FLOAT::INFINITY; n],
+            }
+        }
+
+        /// The point of the box closest to `x`.
+        pub(super) fn project(&self, x: &[FLOAT]) -> Vec<FLOAT> {
+            x.iter()
+                .zip(self.lower.iter().zip(&self.upper))
+                .map(|(x, (l, u))| x.max(*l).min(*u))
+                .collect()
+        }
+
+        /// Whether moving the variable `i` of a point `x` of the box in the direction `d` leaves
+        /// the box.
+        fn blocks(&self, x: &[FLOAT], i: usize, d: FLOAT) -> bool {
+            (d < 0.0 && x[i] <= self.lower[i]) || (d > 0.0 && x[i] >= self.upper[i])
+        }
+
+        /// The gradient `g` at `x` without the components whose descent direction leaves the
+        /// box. It vanishes at the minima of the box.
+        fn projected_gradient(&self, x: &[FLOAT], g: &[FLOAT]) -> Vec<FLOAT> {
+            g.iter()
+                .enumerate()
+                .map(|(i, g)| if self.blocks(x, i, -g) { 0.0 } else { *g })
+                .collect()
+        }
+
+        /// Longest step along `d` from `x` that stays in the box.
+        fn max_step(&self, x: &[FLOAT], d: &[FLOAT]) -> FLOAT {
+            (0..x.len())
+                .map(|i| match d[i] {
+                    d if d < 0.0 => (self.lower[i] - x[i]) / d,
+                    d if d > 0.0 => (self.upper[i] - x[i]) / d,
+                    _ => FLOAT::INFINITY,
+                })
+                .fold(FLOAT::INFINITY, FLOAT::min)
+        }
+
+        /// The point `x + alpha * d`, with the variables that reach a bound on the way set to
+        /// that bound.
+        fn point(&self, x: &[FLOAT], d: &[FLOAT], alpha: FLOAT) -> Vec<FLOAT> {
+            (0..x.len())
+                .map(|i| {
+                    let bound = if d[i] < 0.0 {
+                        self.lower[i]
+                    } else {
+                        self.upper[i]
+                    };
+                    if d[i] != 0.0 && (bound - x[i]) / d[i] <= alpha {
+                        bound
+                    } else {
+                        x[i] + alpha * d[i]
+                    }
+                })
+                .collect()
+        }
+
+        /// Points below and above the variable `i` at `x` at which a central difference samples
+        /// a function, moved onto the bounds if they lie outside the box.
+        pub(super) fn difference_points(&self, x: FLOAT, i: usize) -> (FLOAT, FLOAT) {
+            let h = FLOAT::EPSILON.cbrt() * x.abs().max(1.0);
+            ((x - h).max(self.lower[i]), (x + h).min(self.upper[i]))
+        }
+    }
+
+    /// A smooth objective together with its gradient, which is either given by a Rhai function
+    /// pointer or approximated by central differences inside the bounds.
+    pub(super) struct Smooth<'a> {
+        /// The objective function.
+        pub(super) objective: &'a Objective<'a>,
+        /// Function pointer for the gradient, if given.
+        pub(super) gradient: Option<&'a FnPtr>,
+        /// Bounds on the variables.
+        pub(super) bounds: &'a Bounds,
+    }
+
+    /// A function whose value and gradient can be evaluated by the line search methods.
+    pub(super) trait Differentiable {
+        /// Evaluates the function at `x`.
+        fn value(&self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>>;
+        /// Evaluates the gradient at `x`.
+        fn gradient(&self, x: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>>;
+        /// Number of objective evaluations so far.
+        fn evaluations(&self) -> INT;
+    }
+
+    impl Differentiable for Smooth<'_> {
+        fn value(&self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            self.objective.eval(x)
+        }
+
+        fn evaluations(&self) -> INT {
+            self.objective.evaluations.get()
+        }
+
+        fn gradient(&self, x: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let f = self.objective;
+            match self.gradient {
+                Some(gradient) => {
+                    f.counter.tick()?;
+                    let value = gradient.call_raw(f.ctx, None, [point_to_dynamic(x, f.scalar)])?;
+                    let g = dynamic_to_vec_float(value)?;
+                    if g.len() != x.len() {
+                        return Err(arithmetic_error(format!(
+                            "The gradient function returned {} values, but there are {} variables",
+                            g.len(),
+                            x.len()
+                        )));
+                    }
+                    Ok(g)
+                }
+                None => {
+                    let mut point = x.to_vec();
+                    (0..x.len())
+                        .map(|i| {
+                            let (below, above) = self.bounds.difference_points(x[i], i);
+                            if below == above {
+                                return Ok(0.0);
+                            }
+                            point[i] = above;
+                            let forward = f.eval(&point)?;
+                            point[i] = below;
+                            let backward = f.eval(&point)?;
+                            point[i] = x[i];
+                            Ok((forward - backward) / (above - below))
+                        })
+                        .collect()
+                }
+            }
+        }
+    }
+
+    /// A point along the search direction, at distance `alpha`.
+    pub(super) struct Sample {
+        /// Step length.
+        alpha: FLOAT,
+        /// The point `x + alpha * d`.
+        pub(super) x: Vec<FLOAT>,
+        /// Objective value at the point.
+        pub(super) f: FLOAT,
+        /// Gradient at the point.
+        pub(super) g: Vec<FLOAT>,
+        /// Directional derivative along the search direction.
+        slope: FLOAT,
+    }
+
+    /// Evaluates the objective and its gradient at `x + alpha * d`.
+    fn sample(
+        f: &impl Differentiable,
+        x: &[FLOAT],
+        d: &[FLOAT],
+        alpha: FLOAT,
+        bounds: &Bounds,
+    ) -> Result<Sample, Box<EvalAltResult>> {
+        let point = bounds.point(x, d, alpha);
+        let value = f.value(&point)?;
+        let g = if value.is_finite() {
+            f.gradient(&point)?
+        } else {
+            vec![FLOAT::NAN; x.len()]
+        };
+        Ok(Sample {
+            alpha,
+            slope: dot(&g, d),
+            x: point,
+            f: value,
+            g,
+        })
+    }
+
+    /// Minimizer of the cubic interpolating the values and slopes at two samples, or `None` if the
+    /// cubic has no minimum.
+    fn cubic_minimizer(a: &Sample, b: &Sample) -> Option<FLOAT> {
+        let d1 = a.slope + b.slope - 3.0 * (a.f - b.f) / (a.alpha - b.alpha);
+        let d2 = (d1 * d1 - a.slope * b.slope).sqrt() * (b.alpha - a.alpha).signum();
+        let alpha =
+            b.alpha - (b.alpha - a.alpha) * (b.slope + d2 - d1) / (b.slope - a.slope + 2.0 * d2);
+        alpha.is_finite().then_some(alpha)
+    }
+
+    /// Finds a step along `d` satisfying the strong Wolfe conditions with sufficient decrease
+    /// parameter `1e-4` and curvature parameter `c2`, using the bracketing and zoom phases of
+    /// Nocedal and Wright (Algorithms 3.5 and 3.6). The step stops at the first bound it meets
+    /// if the objective still decreases there. Returns `None` if no step decreases the
+    /// objective.
+    pub(super) fn wolfe_line_search(
+        f: &impl Differentiable,
+        start: Sample,
+        d: &[FLOAT],
+        alpha0: FLOAT,
+        c2: FLOAT,
+        bounds: &Bounds,
+    ) -> Result<Option<Sample>, Box<EvalAltResult>> {
+        const C1: FLOAT = 1e-4;
+        let (f0, slope0) = (start.f, start.slope);
+        let x0 = start.x.clone();
+        let alpha_max = bounds.max_step(&x0, d);
+        let armijo = |s: &Sample| s.f <= f0 + C1 * s.alpha * slope0;
+        let curvature = |s: &Sample| s.slope.abs() <= -c2 * slope0;
+
+        // Bracketing phase: expand the step until the interval contains a Wolfe point
+        let mut previous = start;
+        let mut alpha = alpha0.min(alpha_max);
+        let (mut lo, mut hi) = 'bracket: {
+            for i in 0..20 {
+                let current = sample(f, &x0, d, alpha, bounds)?;
+                if !armijo(&current) || (i > 0 && current.f >= previous.f) {
+                    break 'bracket (previous, current);
+                }
+                if curvature(&current) {
+                    return Ok(Some(current));
+                }
+                if current.slope >= 0.0 {
+                    break 'bracket (current, previous);
+                }
+                if alpha >= alpha_max {
+                    return Ok(Some(current));
+                }
+                alpha = (2.0 * alpha).min(alpha_max);
+                previous = current;
+            }
+            return Ok((previous.alpha > 0.0).then_some(previous));
+        };
+
+        // Zoom phase: shrink the bracket, keeping `lo` as the best point satisfying the
+        // sufficient decrease condition
+        for _ in 0..30 {
+            let (left, right) = (lo.alpha.min(hi.alpha), lo.alpha.max(hi.alpha));
+            let margin = 0.1 * (right - left);
+            if margin <= FLOAT::EPSILON * right {
+                break;
+            }
+            let alpha = match cubic_minimizer(&lo, &hi) {
+                Some(alpha) if alpha > left + margin && alpha < right - margin => alpha,
+                _ => 0.5 * (left + right),
+            };
+            let current = sample(f, &x0, d, alpha, bounds)?;
+            if !armijo(&current) || current.f >= lo.f {
+                hi = current;
+            } else {
+                if curvature(&current) {
+                    return Ok(Some(current));
+                }
+                if current.slope * (hi.alpha - lo.alpha) >= 0.0 {
+                    hi = lo;
+                }
+                lo = current;
+            }
+        }
+        Ok((lo.alpha > 0.0).then_some(lo))
+    }
+
+    /// How search directions are computed.
+    #[derive(Clone)]
+    pub(super) enum Method {
+        /// BFGS with a dense approximation of the inverse Hessian, stored row by row.
+        Bfgs(Option<Vec<FLOAT>>),
+        /// Limited-memory BFGS keeping the given number of recent steps and gradient changes.
+        Lbfgs(usize, VecDeque<(Vec<FLOAT>, Vec<FLOAT>)>),
+        /// Polak-Ribière nonlinear conjugate gradient, keeping the previous gradient and
+        /// direction.
+        ConjugateGradient(Option<(Vec<FLOAT>, Vec<FLOAT>)>),
+    }
+
+    impl Method {
+        /// Whether no curvature information has been gathered yet.
+        fn is_fresh(&self) -> bool {
+            match self {
+                Method::Bfgs(h) => h.is_none(),
+                Method::Lbfgs(_, pairs) => pairs.is_empty(),
+                Method::ConjugateGradient(previous) => previous.is_none(),
+            }
+        }
+
+        /// Discards the curvature information, so that the next direction is steepest descent.
+        fn reset(&mut self) {
+            match self {
+                Method::Bfgs(h) => *h = None,
+                Method::Lbfgs(_, pairs) => pairs.clear(),
+                Method::ConjugateGradient(previous) => *previous = None,
+            }
+        }
+
+        /// Search direction at a point with gradient `g`.
+        fn direction(&self, g: &[FLOAT]) -> Vec<FLOAT> {
+            let n = g.len();
+            match self {
+                Method::Bfgs(Some(h)) => (0..n).map(|i| -dot(&h[i * n..(i + 1) * n], g)).collect(),
+                Method::Lbfgs(_, pairs) if !pairs.is_empty() => {
+                    // Two-loop recursion
+                    let mut q: Vec<FLOAT> = g.iter().map(|v| -v).collect();
+                    let mut alphas = Vec::with_capacity(pairs.len());
+                    for (s, y) in pairs.iter().rev() {
+                        let alpha = dot(s, &q) / dot(y, s);
+                        q.iter_mut().zip(y).for_each(|(q, y)| *q -= alpha * y);
+                        alphas.push(alpha);
+                    }
+                    let (s, y) = pairs.back().unwrap();
+                    let gamma = dot(s, y) / dot(y, y);
+                    q.iter_mut().for_each(|q| *q *= gamma);
+                    for ((s, y), alpha) in pairs.iter().zip(alphas.into_iter().rev()) {
+                        let beta = dot(y, &q) / dot(y, s);
+                        q.iter_mut()
+                            .zip(s)
+                            .for_each(|(q, s)| *q += (alpha - beta) * s);
+                    }
+                    q
+                }
+                Method::ConjugateGradient(Some((g_old, d_old))) => {
+                    let change: Vec<FLOAT> = g.iter().zip(g_old).map(|(g, o)| g - o).collect();
+                    let beta = (dot(g, &change) / dot(g_old, g_old)).max(0.0);
+                    g.iter().zip(d_old).map(|(g, d)| -g + beta * d).collect()
+                }
+                _ => g.iter().map(|v| -v).collect(),
+            }
+        }
+
+        /// Records a step `s` along direction `d`, taken from a point with gradient `g`, that
+        /// changed the gradient by `y`.
+        fn update(&mut self, s: Vec<FLOAT>, y: Vec<FLOAT>, g: &[FLOAT], d: Vec<FLOAT>) {
+            let sy = dot(&s, &y);
+            let curved = sy > FLOAT::EPSILON * norm(&s) * norm(&y);
+            match self {
+                Method::Bfgs(h) => {
+                    if !curved {
+                        return;
+                    }
+                    let n = s.len();
+                    let h = h.get_or_insert_with(|| {
+                        // Scale the initial approximation as in Nocedal and Wright (6.20)
+                        let gamma = sy / dot(&y, &y);
+                        let mut h = vec![0.0; n * n];
+                        (0..n).for_each(|i| h[i * n + i] = gamma);
+                        h
+                    });
+                    let rho = 1.0 / sy;
+                    let hy: Vec<FLOAT> = (0..n).map(|i| dot(&h[i * n..(i + 1) * n], &y)).collect();
+                    let scale = rho * rho * dot(&y, &hy) + rho;
+                    for i in 0..n {
+                        for j in 0..n {
+                            h[i * n + j] +=
+                                scale * s[i] * s[j] - rho * (hy[i] * s[j] + s[i] * hy[j]);
+                        }
+                    }
+                }
+                Method::Lbfgs(memory, pairs) => {
+                    if !curved {
+                        return;
+                    }
+                    if pairs.len() == *memory {
+                        pairs.pop_front();
+                    }
+                    pairs.push_back((s, y));
+                }
+                Method::ConjugateGradient(previous) => *previous = Some((g.to_vec(), d)),
+            }
+        }
+    }
+
+    /// Stopping criteria of `fminunc`.
+    pub(super) struct Criteria {
+        /// Tolerance on the norm of the gradient.
+        pub(super) tol_grad: FLOAT,
+        /// Relative tolerance on the size of a step.
+        pub(super) tol_x: FLOAT,
+        /// Maximum number of iterations.
+        pub(super) max_iterations: INT,
+        /// Maximum number of function evaluations.
+        pub(super) max_evaluations: INT,
+    }
+
+    /// Minimizes `f` over the box `bounds` from the projection of `x0` onto it, using line
+    /// searches along the directions of `method`, calling `report` after every iteration.
+    /// Variables at a bound that the gradient pushes outwards are held fixed, so `f` is only
+    /// evaluated inside the box. Returns the minimum together with the final gradient and the
+    /// objective value and projected gradient norm at every iteration.
+    pub(super) fn minimize(
+        f: &impl Differentiable,
+        x0: Vec<FLOAT>,
+        mut method: Method,
+        criteria: &Criteria,
+        bounds: &Bounds,
+        mut report: impl FnMut(INT, &[FLOAT], FLOAT) -> Result<bool, Box<EvalAltResult>>,
+    ) -> Result<(Minimum, Vec<FLOAT>, Map), Box<EvalAltResult>> {
+        let c2 = match method {
+            Method::ConjugateGradient(_) => 0.1,
+            _ => 0.9,
+        };
+        let x0 = bounds.project(&x0);
+        let fx = f.value(&x0)?;
+        if !fx.is_finite() {
+            return Err(arithmetic_error(
+                "The objective function is not finite at the starting point",
+            ));
+        }
+        let g = f.gradient(&x0)?;
+        let mut current = Sample {
+            alpha: 0.0,
+            x: x0,
+            f: fx,
+            g,
+            slope: 0.0,
+        };
+        let (mut fvals, mut norms) = (vec![Dynamic::from_float(fx)], vec![]);
+        let mut previous_f = None;
+        let mut small_step = false;
+        let mut iterations = 0;
+
+        let status = loop {
+            let free = bounds.projected_gradient(&current.x, &current.g);
+            let gnorm = norm(&free);
+            if norms.len() == iterations as usize {
+                norms.push(Dynamic::from_float(gnorm));
+            }
+            if gnorm <= criteria.tol_grad {
+                break Status::Converged;
+            }
+            if small_step {
+                break Status::StepTolerance;
+            }
+            if iterations >= criteria.max_iterations {
+                break Status::MaxIterations;
+            }
+            if f.evaluations() >= criteria.max_evaluations {
+                break Status::MaxEvaluations;
+            }
+
+            let descent = |method: &Method| -> Vec<FLOAT> {
+                let mut d = method.direction(&free);
+                for (i, d) in d.iter_mut().enumerate() {
+                    if bounds.blocks(&current.x, i, *d) {
+                        *d = 0.0;
+                    }
+                }
+                d
+            };
+            let mut d = descent(&method);
+            if dot(&current.g, &d) >= 0.0 {
+                method.reset();
+                d = descent(&method);
+            }
+            current.slope = dot(&current.g, &d);
+            let alpha0 = match (&method, previous_f) {
+                _ if method.is_fresh() => (1.0 / gnorm).min(1.0),
+                (Method::ConjugateGradient(_), Some(previous_f)) => {
+                    FLOAT::min(2.02 * (current.f - previous_f) / current.slope, 1.0)
+                }
+                _ => 1.0,
+            };
+
+            let start = Sample {
+                alpha: 0.0,
+                x: current.x.clone(),
+                f: current.f,
+                g: current.g.clone(),
+                slope: current.slope,
+            };
+            let next = match wolfe_line_search(f, start, &d, alpha0, c2, bounds)? {
+                Some(next) => next,
+                None if !method.is_fresh() => {
+                    method.reset();
+                    continue;
+                }
+                None => break Status::LineSearchFailed,
+            };
+            iterations += 1;
+
+            let s: Vec<FLOAT> = next.x.iter().zip(&current.x).map(|(a, b)| a - b).collect();
+            let y: Vec<FLOAT> = next.g.iter().zip(&current.g).map(|(a, b)| a - b).collect();
+            small_step = max_abs(&s) <= criteria.tol_x * (1.0 + max_abs(&current.x));
+            method.update(s, y, &free, d);
+            previous_f = Some(current.f);
+            current = next;
+            fvals.push(Dynamic::from_float(current.f));
+
+            if report(iterations, &current.x, current.f)? {
+                let free = bounds.projected_gradient(&current.x, &current.g);
+                norms.push(Dynamic::from_float(norm(&free)));
+                break Status::Stopped;
+            }
+        };
+
+        let mut history = Map::new();
+        history.insert("fval".into(), Dynamic::from_array(fvals));
+        history.insert("gradient_norm".into(), Dynamic::from_array(norms));
+        let minimum = Minimum {
+            x: current.x,
+            fval: current.f,
+            iterations,
+            status,
+        };
+        Ok((minimum, current.g, history))
+    }
+
+    /// Reads the `method` and `memory` options.
+    pub(super) fn method_option(options: &Map) -> Result<Method, Box<EvalAltResult>> {
+        let method = match options.get("method") {
+            Some(m) => m
+                .clone()
+                .into_string()
+                .map_err(|_| arithmetic_error("The option 'method' must be a string"))?,
+            None => "bfgs".to_string(),
+        };
+        match method.as_str() {
+            "bfgs" => Ok(Method::Bfgs(None)),
+            "lbfgs" => {
+                let memory = int_option(options, "memory", 10)?;
+                if memory < 1 {
+                    return Err(arithmetic_error("The option 'memory' must be positive"));
+                }
+                Ok(Method::Lbfgs(memory as usize, Default::default()))
+            }
+            "cg" => Ok(Method::ConjugateGradient(None)),
+            other => Err(arithmetic_error(format!(
+                "Unknown method '{other}', expected 'bfgs', 'lbfgs' or 'cg'"
+            ))),
+        }
+    }
+
+    /// Shared implementation of the `fminunc` overloads.
+    pub(super) fn minimize_smooth(
+        ctx: &NativeCallContext,
+        f: &FnPtr,
+        x0: Dynamic,
+        gradient: Option<&FnPtr>,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = x0.is_int() || x0.is_float();
+        let x0 = dynamic_to_vec_float(x0)?;
+        if x0.is_empty() {
+            return Err(arithmetic_error("The starting point must not be empty"));
+        }
+        let n = x0.len() as rhai::INT;
+        let method = method_option(&options)?;
+        let criteria = Criteria {
+            tol_grad: float_option(&options, "tol_grad", 1e-6)?,
+            tol_x: float_option(&options, "tol_x", 1e-10)?,
+            max_iterations: int_option(&options, "max_iterations", 400)?,
+            max_evaluations: int_option(&options, "max_evaluations", 1000 * n)?,
+        };
+        let monitor = Monitor::from_options(ctx, &options)?;
+        let objective = Objective::new(ctx, f, scalar);
+        let bounds = Bounds::unbounded(x0.len());
+        let smooth = Smooth {
+            objective: &objective,
+            gradient,
+            bounds: &bounds,
+        };
+        let (minimum, g, history) =
+            minimize(&smooth, x0, method, &criteria, &bounds, |i, x, fx| {
+                monitor.report(i, x, fx, &objective)
+            })?;
+        let mut result = minimum.into_map(&objective);
+        result.insert("gradient_norm".into(), Dynamic::from_float(norm(&g)));
+        result.insert("gradient".into(), point_to_dynamic(&g, scalar));
+        result.insert("history".into(), history.into());
+        Ok(result)
+    }
+}
+
+/// Constraint handling and the augmented Lagrangian method of `fmincon`.
+mod augmented_lagrangian {
+    use super::minimization::{
+        bound_entry, function_entry, matrix_rows, point_to_dynamic, Minimum, Monitor, Objective,
+        Status,
+    };
+    use super::quasi_newton::{
+        method_option, minimize, Bounds, Criteria, Differentiable, Method, Smooth,
+    };
+    use crate::{arithmetic_error, dynamic_to_vec_float, float_option, int_option};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+    use std::cell::RefCell;
+
+    /// Names of the constraint groups, in the order they are stored. The first four groups are
+    /// inequalities `g(x) <= 0` and the last two are equalities `h(x) = 0`. The bounds in the
+    /// first two groups are kept satisfied by the subproblem solver, so they do not enter the
+    /// augmented Lagrangian.
+    pub(super) const GROUPS: [&str; 6] = [
+        "lower",
+        "upper",
+        "ineqlin",
+        "ineqnonlin",
+        "eqlin",
+        "eqnonlin",
+    ];
+
+    /// Number of inequality groups at the start of [`GROUPS`].
+    const INEQUALITIES: usize = 4;
+
+    /// Number of bound groups at the start of [`GROUPS`].
+    const BOUNDS: usize = 2;
+
+    /// Values of the constraints (or their multipliers) in each group of [`GROUPS`].
+    type Groups = [Vec<FLOAT>; 6];
+
+    /// Reads an optional list of coefficient rows with `n` columns. A flat list is a single row.
+    fn rows_entry(
+        constraints: &Map,
+        key: &str,
+        n: usize,
+    ) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        match constraints.get(key) {
+            Some(value) => matrix_rows(value.clone(), key, n),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Reads an optional right-hand side with one entry per row of the matching matrix.
+    fn rhs_entry(
+        constraints: &Map,
+        key: &str,
+        rows: usize,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        let values = match constraints.get(key) {
+            Some(value) => dynamic_to_vec_float(value.clone())?,
+            None => vec![],
+        };
+        if values.len() != rows {
+            return Err(arithmetic_error(format!(
+                "The constraint '{key}' must have {rows} entries, one per row of the matrix"
+            )));
+        }
+        Ok(values)
+    }
+
+    /// Reads the bounds `lb` and `ub` for a problem with `n` variables.
+    pub(super) fn bounds_entry(constraints: &Map, n: usize) -> Result<Bounds, Box<EvalAltResult>> {
+        let lower = bound_entry(constraints, "lb", n, FLOAT::NEG_INFINITY)?;
+        let upper = bound_entry(constraints, "ub", n, FLOAT::INFINITY)?;
+        if lower.iter().chain(&upper).any(|v| v.is_nan()) {
+            return Err(arithmetic_error("The bounds must not be NaN"));
+        }
+        if lower.iter().zip(&upper).any(|(l, u)| l > u) {
+            return Err(arithmetic_error(
+                "The lower bounds must not exceed the upper bounds",
+            ));
+        }
+        Ok(Bounds { lower, upper })
+    }
+
+    /// A constrained minimization problem.
+    pub(super) struct Problem<'a> {
+        /// The objective, its gradient and the bounds.
+        pub(super) smooth: Smooth<'a>,
+        /// Rows of the linear inequalities `A x <= b`.
+        a: Vec<Vec<FLOAT>>,
+        /// Right-hand side of the linear inequalities.
+        b: Vec<FLOAT>,
+        /// Rows of the linear equalities `Aeq x = beq`.
+        aeq: Vec<Vec<FLOAT>>,
+        /// Right-hand side of the linear equalities.
+        beq: Vec<FLOAT>,
+        /// Nonlinear inequalities `c(x) <= 0`.
+        c: Option<FnPtr>,
+        /// Nonlinear equalities `ceq(x) = 0`.
+        ceq: Option<FnPtr>,
+    }
+
+    impl<'a> Problem<'a> {
+        /// Reads the constraints map for a problem with `n` variables.
+        pub(super) fn new(
+            smooth: Smooth<'a>,
+            constraints: &Map,
+            n: usize,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            let a = rows_entry(constraints, "A", n)?;
+            let b = rhs_entry(constraints, "b", a.len())?;
+            let aeq = rows_entry(constraints, "Aeq", n)?;
+            let beq = rhs_entry(constraints, "beq", aeq.len())?;
+            Ok(Self {
+                smooth,
+                a,
+                b,
+                aeq,
+                beq,
+                c: function_entry(constraints, "c")?,
+                ceq: function_entry(constraints, "ceq")?,
+            })
+        }
+
+        /// Evaluates a nonlinear constraint function, which may return a number or a list.
+        fn nonlinear(
+            &self,
+            function: &Option<FnPtr>,
+            x: &[FLOAT],
+        ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let Some(function) = function else {
+                return Ok(vec![]);
+            };
+            let objective = self.smooth.objective;
+            objective.counter.tick()?;
+            let value =
+                function.call_raw(objective.ctx, None, [point_to_dynamic(x, objective.scalar)])?;
+            dynamic_to_vec_float(value)
+        }
+
+        /// Evaluates all constraints at `x`.
+        pub(super) fn values(&self, x: &[FLOAT]) -> Result<Groups, Box<EvalAltResult>> {
+            let linear = |rows: &[Vec<FLOAT>], rhs: &[FLOAT]| -> Vec<FLOAT> {
+                rows.iter()
+                    .zip(rhs)
+                    .map(|(row, r)| row.iter().zip(x).map(|(a, x)| a * x).sum::<FLOAT>() - r)
+                    .collect()
+            };
+            let bounds = self.smooth.bounds;
+            Ok([
+                bounds.lower.iter().zip(x).map(|(l, x)| l - x).collect(),
+                x.iter().zip(&bounds.upper).map(|(x, u)| x - u).collect(),
+                linear(&self.a, &self.b),
+                self.nonlinear(&self.c, x)?,
+                linear(&self.aeq, &self.beq),
+                self.nonlinear(&self.ceq, x)?,
+            ])
+        }
+
+        /// Adds `weights^T J` to `gradient`, where `J` is the Jacobian of a nonlinear constraint
+        /// function at `x`, approximated by central differences inside the bounds.
+        fn add_nonlinear_gradient(
+            &self,
+            function: &Option<FnPtr>,
+            x: &[FLOAT],
+            weights: &[FLOAT],
+            gradient: &mut [FLOAT],
+        ) -> Result<(), Box<EvalAltResult>> {
+            if weights.iter().all(|w| *w == 0.0) {
+                return Ok(());
+            }
+            let mut point = x.to_vec();
+            for i in 0..x.len() {
+                let (below, above) = self.smooth.bounds.difference_points(x[i], i);
+                if below == above {
+                    continue;
+                }
+                point[i] = above;
+                let forward = self.nonlinear(function, &point)?;
+                point[i] = below;
+                let backward = self.nonlinear(function, &point)?;
+                point[i] = x[i];
+                if forward.len() != weights.len() || backward.len() != weights.len() {
+                    return Err(arithmetic_error(
+                        "The nonlinear constraints must return the same number of values everywhere",
+                    ));
+                }
+                gradient[i] += weights
+                    .iter()
+                    .zip(forward.iter().zip(&backward))
+                    .map(|(w, (f, b))| w * (f - b) / (above - below))
+                    .sum::<FLOAT>();
+            }
+            Ok(())
+        }
+
+        /// Adds `sum_j weights[j] * grad(constraint_j)` over all groups but the bounds to
+        /// `gradient`.
+        pub(super) fn add_constraint_gradient(
+            &self,
+            x: &[FLOAT],
+            weights: &Groups,
+            gradient: &mut [FLOAT],
+        ) -> Result<(), Box<EvalAltResult>> {
+            for (rows, w) in [(&self.a, &weights[2]), (&self.aeq, &weights[4])] {
+                for (row, w) in rows.iter().zip(w) {
+                    gradient.iter_mut().zip(row).for_each(|(g, a)| *g += w * a);
+                }
+            }
+            self.add_nonlinear_gradient(&self.c, x, &weights[3], gradient)?;
+            self.add_nonlinear_gradient(&self.ceq, x, &weights[5], gradient)
+        }
+    }
+
+    /// Largest violation of any constraint.
+    pub(super) fn max_violation(values: &Groups) -> FLOAT {
+        values
+            .iter()
+            .enumerate()
+            .flat_map(|(k, group)| {
+                group.iter().map(move |v| {
+                    if k < INEQUALITIES {
+                        v.max(0.0)
+                    } else {
+                        v.abs()
+                    }
+                })
+            })
+            .fold(0.0, FLOAT::max)
+    }
+
+    /// The Powell-Hestenes-Rockafellar augmented Lagrangian of a problem for fixed multipliers
+    /// and penalty parameter.
+    struct Lagrangian<'a> {
+        /// The problem.
+        problem: &'a Problem<'a>,
+        /// Current multiplier estimates.
+        multipliers: &'a Groups,
+        /// Penalty parameter.
+        rho: FLOAT,
+        /// The last point at which the constraints were evaluated, with their values.
+        cache: RefCell<Option<(Vec<FLOAT>, Groups)>>,
+    }
+
+    impl Lagrangian<'_> {
+        /// Constraint values at `x`, reusing the last evaluation if possible.
+        fn values(&self, x: &[FLOAT]) -> Result<Groups, Box<EvalAltResult>> {
+            if let Some((point, values)) = &*self.cache.borrow() {
+                if point == x {
+                    return Ok(values.clone());
+                }
+            }
+            let values = self.problem.values(x)?;
+            *self.cache.borrow_mut() = Some((x.to_vec(), values.clone()));
+            Ok(values)
+        }
+
+        /// Derivative of the penalty term of each constraint with respect to its value, which is
+        /// zero for the bounds.
+        fn weights(&self, values: &Groups) -> Groups {
+            std::array::from_fn(|k| {
+                values[k]
+                    .iter()
+                    .zip(&self.multipliers[k])
+                    .map(|(v, m)| {
+                        if k < BOUNDS {
+                            0.0
+                        } else if k < INEQUALITIES {
+                            (m + self.rho * v).max(0.0)
+                        } else {
+                            m + self.rho * v
+                        }
+                    })
+                    .collect()
+            })
+        }
+    }
+
+    impl Differentiable for Lagrangian<'_> {
+        fn value(&self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            let fx = self.problem.smooth.value(x)?;
+            let values = self.values(x)?;
+            let weights = self.weights(&values);
+            let mut total = fx;
+            for k in BOUNDS..GROUPS.len() {
+                for ((v, m), w) in values[k].iter().zip(&self.multipliers[k]).zip(&weights[k]) {
+                    total += if k < INEQUALITIES {
+                        (w * w - m * m) / (2.0 * self.rho)
+                    } else {
+                        m * v + 0.5 * self.rho * v * v
+                    };
+                }
+            }
+            Ok(total)
+        }
+
+        fn gradient(&self, x: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let mut gradient = self.problem.smooth.gradient(x)?;
+            let weights = self.weights(&self.values(x)?);
+            self.problem
+                .add_constraint_gradient(x, &weights, &mut gradient)?;
+            Ok(gradient)
+        }
+
+        fn evaluations(&self) -> INT {
+            self.problem.smooth.evaluations()
+        }
+    }
+
+    /// Settings of the augmented Lagrangian method.
+    pub(super) struct Settings {
+        /// Tolerance on the constraint violation and complementarity.
+        pub(super) tol_con: FLOAT,
+        /// Initial penalty parameter.
+        pub(super) penalty: FLOAT,
+        /// Maximum number of outer iterations.
+        pub(super) max_iterations: INT,
+        /// Stopping criteria of each unconstrained subproblem.
+        pub(super) inner: Criteria,
+    }
+
+    /// Minimizes the problem from `x0` with the augmented Lagrangian method, solving each
+    /// subproblem with `method` within the bounds. The penalty parameter grows tenfold whenever
+    /// an outer iteration fails to reduce the infeasibility by a factor of four. Returns the
+    /// minimum, the constraint values and the multipliers, where those of the bounds are the
+    /// components of the gradient of the Lagrangian that push against active bounds.
+    pub(super) fn solve(
+        problem: &Problem,
+        x0: Vec<FLOAT>,
+        method: &Method,
+        settings: &Settings,
+        monitor: &Monitor,
+    ) -> Result<(Minimum, Groups, Groups), Box<EvalAltResult>> {
+        let objective: &Objective = problem.smooth.objective;
+        let bounds = problem.smooth.bounds;
+        let mut x = bounds.project(&x0);
+        let mut values = problem.values(&x)?;
+        let mut multipliers: Groups = std::array::from_fn(|k| vec![0.0; values[k].len()]);
+        let mut rho = settings.penalty;
+        let mut previous_measure = FLOAT::INFINITY;
+        let mut iterations = 0;
+
+        let status = loop {
+            if iterations >= settings.max_iterations {
+                break Status::MaxIterations;
+            }
+            iterations += 1;
+            let lagrangian = Lagrangian {
+                problem,
+                multipliers: &multipliers,
+                rho,
+                cache: RefCell::new(None),
+            };
+            let (inner, gradient, _) = minimize(
+                &lagrangian,
+                x,
+                method.clone(),
+                &settings.inner,
+                bounds,
+                |_, _, _| Ok(false),
+            )?;
+            x = inner.x;
+            values = lagrangian.values(&x)?;
+
+            // Infeasibility and complementarity measure of Birgin and Martínez
+            let measure = values
+                .iter()
+                .zip(&multipliers)
+                .enumerate()
+                .skip(BOUNDS)
+                .flat_map(|(k, (group, m))| {
+                    group.iter().zip(m).map(move |(v, m)| {
+                        if k < INEQUALITIES {
+                            (-v).min(m / rho).abs()
+                        } else {
+                            v.abs()
+                        }
+                    })
+                })
+                .fold(0.0, FLOAT::max);
+            multipliers = lagrangian.weights(&values);
+            for (i, g) in gradient.iter().enumerate() {
+                if x[i] <= bounds.lower[i] {
+                    multipliers[0][i] = g.max(0.0);
+                }
+                if x[i] >= bounds.upper[i] {
+                    multipliers[1][i] = (-g).max(0.0);
+                }
+            }
+
+            let fx = objective.eval(&x)?;
+            if monitor.report(iterations, &x, fx, objective)? {
+                break Status::Stopped;
+            }
+            match inner.status {
+                Status::MaxEvaluations => break Status::MaxEvaluations,
+                Status::Converged | Status::StepTolerance if measure <= settings.tol_con => {
+                    break Status::Converged
+                }
+                _ => {}
+            }
+            if measure > 0.25 * previous_measure {
+                rho *= 10.0;
+                if rho > 1e12 {
+                    break if max_violation(&values) > settings.tol_con {
+                        Status::Infeasible
+                    } else {
+                        Status::StepTolerance
+                    };
+                }
+            }
+            previous_measure = measure;
+        };
+
+        let fval = objective.eval(&x)?;
+        let minimum = Minimum {
+            x,
+            fval,
+            iterations,
+            status,
+        };
+        Ok((minimum, values, multipliers))
+    }
+
+    /// Returns the multipliers as an object map with one array per constraint group.
+    pub(super) fn multipliers_to_map(multipliers: Groups) -> Map {
+        let mut map = Map::new();
+        for (name, group) in GROUPS.iter().zip(multipliers) {
+            map.insert(
+                (*name).into(),
+                Dynamic::from_array(
+                    group
+                        .into_iter()
+                        .map(Dynamic::from_float)
+                        .collect::<Array>(),
+                ),
+            );
+        }
+        map
+    }
+
+    /// Shared implementation of the `fmincon` overloads.
+    pub(super) fn fmincon(
+        ctx: &NativeCallContext,
+        f: &FnPtr,
+        x0: Dynamic,
+        constraints: &Map,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = x0.is_int() || x0.is_float();
+        let x0 = dynamic_to_vec_float(x0)?;
+        if x0.is_empty() {
+            return Err(arithmetic_error("The starting point must not be empty"));
+        }
+        let n = x0.len();
+        let gradient = function_entry(options, "gradient")?;
+        let settings = Settings {
+            tol_con: float_option(options, "tol_con", 1e-6)?,
+            penalty: float_option(options, "penalty", 10.0)?,
+            max_iterations: int_option(options, "max_iterations", 100)?,
+            inner: Criteria {
+                tol_grad: float_option(options, "tol_grad", 1e-6)?,
+                tol_x: float_option(options, "tol_x", 1e-10)?,
+                max_iterations: int_option(options, "max_inner_iterations", 400)?,
+                max_evaluations: int_option(options, "max_evaluations", 10000 * n as INT)?,
+            },
+        };
+        let method = method_option(options)?;
+        let monitor = Monitor::from_options(ctx, options)?;
+        let objective = Objective::new(ctx, f, scalar);
+        let bounds = bounds_entry(constraints, n)?;
+        let smooth = Smooth {
+            objective: &objective,
+            gradient: gradient.as_ref(),
+            bounds: &bounds,
+        };
+        let problem = Problem::new(smooth, constraints, n)?;
+        let (minimum, values, multipliers) = solve(&problem, x0, &method, &settings, &monitor)?;
+        let mut result = minimum.into_map(&objective);
+        result.insert(
+            "max_violation".into(),
+            Dynamic::from_float(max_violation(&values)),
+        );
+        result.insert("lambda".into(), multipliers_to_map(multipliers).into());
+        Ok(result)
+    }
+}
+
+/// Linear constraints and the two-phase simplex method of `linprog`.
+mod linear_programming {
+    use super::minimization::matrix_rows;
+    use crate::{arithmetic_error, dynamic_to_vec_float};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
+
+    /// Tolerance for pivots, reduced costs and feasibility.
+    const TOLERANCE: FLOAT = 1e-9;
+
+    /// Error for problems without feasible points.
+    pub(super) fn infeasible_error() -> Box<EvalAltResult> {
+        arithmetic_error("The problem is infeasible: no point satisfies all constraints")
+    }
+
+    /// Error for problems whose objective decreases without limit.
+    pub(super) fn unbounded_error() -> Box<EvalAltResult> {
+        arithmetic_error(
+            "The problem is unbounded: the objective decreases without limit over the feasible set",
+        )
+    }
+
+    /// Checks that the coefficients of `name` are finite.
+    pub(super) fn check_finite<'a>(
+        values: impl IntoIterator<Item = &'a FLOAT>,
+        name: &str,
+    ) -> Result<(), Box<EvalAltResult>> {
+        if values.into_iter().all(|v| v.is_finite()) {
+            Ok(())
+        } else {
+            Err(arithmetic_error(format!(
+                "'{name}' must contain only finite numbers"
+            )))
+        }
+    }
+
+    /// Reads the vector `name`, which must have `len` entries or, if `default` is given, may be
+    /// empty.
+    fn vector(
+        values: Array,
+        name: &str,
+        len: usize,
+        default: Option<FLOAT>,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        match default {
+            Some(default) if values.is_empty() => Ok(vec![default; len]),
+            _ => {
+                let values = dynamic_to_vec_float(Dynamic::from_array(values))?;
+                if values.len() != len {
+                    return Err(arithmetic_error(format!(
+                        "'{name}' must have {len} entries"
+                    )));
+                }
+                Ok(values)
+            }
+        }
+    }
+
+    /// Linear constraints `A x <= b`, `Aeq x = beq` and `lb <= x <= ub`.
+    pub(super) struct LinearConstraints {
+        /// Rows of the inequalities.
+        pub(super) a: Vec<Vec<FLOAT>>,
+        /// Right-hand side of the inequalities.
+        pub(super) b: Vec<FLOAT>,
+        /// Rows of the equalities.
+        pub(super) aeq: Vec<Vec<FLOAT>>,
+        /// Right-hand side of the equalities.
+        pub(super) beq: Vec<FLOAT>,
+        /// Lower bounds, `-inf` where absent.
+        pub(super) lower: Vec<FLOAT>,
+        /// Upper bounds, `inf` where absent.
+        pub(super) upper: Vec<FLOAT>,
+    }
+
+    impl LinearConstraints {
+        /// Reads the constraints on `n` variables. Every argument may be an empty array.
+        pub(super) fn new(
+            n: usize,
+            (a, b): (Array, Array),
+            (aeq, beq): (Array, Array),
+            (lb, ub): (Array, Array),
+        ) -> Result<Self, Box<EvalAltResult>> {
+            let a = matrix_rows(Dynamic::from_array(a), "A", n)?;
+            let b = vector(b, "b", a.len(), None)?;
+            let aeq = matrix_rows(Dynamic::from_array(aeq), "Aeq", n)?;
+            let beq = vector(beq, "beq", aeq.len(), None)?;
+            let lower = vector(lb, "lb", n, Some(FLOAT::NEG_INFINITY))?;
+            let upper = vector(ub, "ub", n, Some(FLOAT::INFINITY))?;
+            check_finite(a.iter().flatten(), "A")?;
+            check_finite(&b, "b")?;
+            check_finite(aeq.iter().flatten(), "Aeq")?;
+            check_finite(&beq, "beq")?;
+            if lower.iter().chain(&upper).any(|v| v.is_nan()) {
+                return Err(arithmetic_error("The bounds 'lb' and 'ub' must not be NaN"));
+            }
+            if lower.iter().zip(&upper).any(|(l, u)| l > u) {
+                return Err(infeasible_error());
+            }
+            Ok(Self {
+                a,
+                b,
+                aeq,
+                beq,
+                lower,
+                upper,
+            })
+        }
+    }
+
+    /// How a variable is expressed through the nonnegative variables of the standard form.
+    enum Substitution {
+        /// `x = offset + z[column]`.
+        Shifted(FLOAT, usize),
+        /// `x = offset - z[column]`.
+        Reflected(FLOAT, usize),
+        /// `x = z[positive] - z[negative]`.
+        Free(usize, usize),
+    }
+
+    /// A simplex tableau for a problem in the standard form `min c^T z` subject to `M z = r` and
+    /// `z >= 0`.
+    struct Tableau {
+        /// One row per constraint, holding the coefficients followed by the right-hand side.
+        rows: Vec<Vec<FLOAT>>,
+        /// Reduced costs, followed by the negated objective value.
+        cost: Vec<FLOAT>,
+        /// Basic variable of each row.
+        basis: Vec<usize>,
+        /// Number of pivots performed.
+        pivots: INT,
+    }
+
+    impl Tableau {
+        /// Makes column `k` basic in row `r`.
+        fn pivot(&mut self, r: usize, k: usize) {
+            let scale = self.rows[r][k];
+            self.rows[r].iter_mut().for_each(|v| *v /= scale);
+            let pivot_row = self.rows[r].clone();
+            let eliminate = |row: &mut Vec<FLOAT>| {
+                let factor = row[k];
+                if factor != 0.0 {
+                    row.iter_mut()
+                        .zip(&pivot_row)
+                        .for_each(|(v, p)| *v -= factor * p);
+                }
+            };
+            for (i, row) in self.rows.iter_mut().enumerate() {
+                if i != r {
+                    eliminate(row);
+                }
+            }
+            eliminate(&mut self.cost);
+            self.basis[r] = k;
+            self.pivots += 1;
+        }
+
+        /// Pivots until no column before `allowed` has a negative reduced cost. Uses Dantzig's
+        /// rule, switching to Bland's rule after a run of degenerate pivots to avoid cycling.
+        /// Returns `false` if the objective is unbounded below.
+        fn optimize(&mut self, allowed: usize) -> Result<bool, Box<EvalAltResult>> {
+            let rhs = self.cost.len() - 1;
+            let limit = 50 * (self.rows.len() + rhs) as INT + 1000;
+            let mut degenerate = 0;
+            loop {
+                let candidates = (0..allowed).filter(|k| self.cost[*k] < -TOLERANCE);
+                let entering = if degenerate > 20 {
+                    candidates.min()
+                } else {
+                    candidates.min_by(|a, b| self.cost[*a].total_cmp(&self.cost[*b]))
+                };
+                let Some(k) = entering else {
+                    return Ok(true);
+                };
+                let leaving = (0..self.rows.len())
+                    .filter(|i| self.rows[*i][k] > TOLERANCE)
+                    .min_by(|a, b| {
+                        let ratio = |i: usize| self.rows[i][rhs] / self.rows[i][k];
+                        ratio(*a)
+                            .total_cmp(&ratio(*b))
+                            .then(self.basis[*a].cmp(&self.basis[*b]))
+                    });
+                let Some(r) = leaving else {
+                    return Ok(false);
+                };
+                if self.rows[r][rhs] <= TOLERANCE {
+                    degenerate += 1;
+                } else {
+                    degenerate = 0;
+                }
+                if self.pivots >= limit {
+                    return Err(arithmetic_error(
+                        "The simplex method exceeded its iteration limit",
+                    ));
+                }
+                self.pivot(r, k);
+            }
+        }
+    }
+
+    /// Minimizes `c^T x` subject to the constraints with the two-phase simplex method. Returns
+    /// the minimizer and the number of pivots.
+    pub(super) fn simplex(
+        c: &[FLOAT],
+        constraints: &LinearConstraints,
+    ) -> Result<(Vec<FLOAT>, INT), Box<EvalAltResult>> {
+        // Express every variable through nonnegative ones
+        let mut columns = 0;
+        let mut next = || {
+            columns += 1;
+            columns - 1
+        };
+        let substitutions: Vec<Substitution> = constraints
+            .lower
+            .iter()
+            .zip(&constraints.upper)
+            .map(|(l, u)| {
+                if l.is_finite() {
+                    Substitution::Shifted(*l, next())
+                } else if u.is_finite() {
+                    Substitution::Reflected(*u, next())
+                } else {
+                    Substitution::Free(next(), next())
+                }
+            })
+            .collect();
+        let transform = |coefficients: &[FLOAT], rhs: FLOAT| -> (Vec<FLOAT>, FLOAT) {
+            let mut row = vec![0.0; columns];
+            let mut rhs = rhs;
+            for (a, substitution) in coefficients.iter().zip(&substitutions) {
+                match substitution {
+                    Substitution::Shifted(offset, k) => {
+                        row[*k] = *a;
+                        rhs -= a * offset;
+                    }
+                    Substitution::Reflected(offset, k) => {
+                        row[*k] = -a;
+                        rhs -= a * offset;
+                    }
+                    Substitution::Free(p, q) => {
+                        row[*p] = *a;
+                        row[*q] = -a;
+                    }
+                }
+            }
+            (row, rhs)
+        };
+
+        // Collect the constraints as (coefficients, right-hand side, is inequality)
+        let mut standard: Vec<(Vec<FLOAT>, FLOAT, bool)> = vec![];
+        for (row, b) in constraints.a.iter().zip(&constraints.b) {
+            let (row, rhs) = transform(row, *b);
+            standard.push((row, rhs, true));
+        }
+        for (substitution, u) in substitutions.iter().zip(&constraints.upper) {
+            if let Substitution::Shifted(offset, k) = substitution {
+                if u.is_finite() {
+                    let mut row = vec![0.0; columns];
+                    row[*k] = 1.0;
+                    standard.push((row, u - offset, true));
+                }
+            }
+        }
+        for (row, b) in constraints.aeq.iter().zip(&constraints.beq) {
+            let (row, rhs) = transform(row, *b);
+            standard.push((row, rhs, false));
+        }
+        let (cost, _) = transform(c, 0.0);
+
+        // Build the tableau, with slack variables for the inequalities and artificial
+        // variables for the rows without an obvious basic variable
+        let m = standard.len();
+        let slacks = standard
+            .iter()
+            .filter(|(_, _, inequality)| *inequality)
+            .count();
+        let first_artificial = columns + slacks;
+        let needs_artificial: Vec<bool> = standard
+            .iter()
+            .map(|(_, rhs, inequality)| !inequality || *rhs < 0.0)
+            .collect();
+        let width = first_artificial + needs_artificial.iter().filter(|a| **a).count();
+        let mut tableau = Tableau {
+            rows: Vec::with_capacity(m),
+            cost: vec![0.0; width + 1],
+            basis: Vec::with_capacity(m),
+            pivots: 0,
+        };
+        let (mut slack, mut artificial) = (columns, first_artificial);
+        for ((coefficients, rhs, inequality), needs_artificial) in
+            standard.into_iter().zip(needs_artificial)
+        {
+            let mut row = vec![0.0; width + 1];
+            row[..columns].copy_from_slice(&coefficients);
+            row[width] = rhs;
+            if inequality {
+                row[slack] = 1.0;
+            }
+            if rhs < 0.0 {
+                row.iter_mut().for_each(|v| *v = -*v);
+            }
+            if needs_artificial {
+                row[artificial] = 1.0;
+                tableau.basis.push(artificial);
+                artificial += 1;
+            } else {
+                tableau.basis.push(slack);
+            }
+            if inequality {
+                slack += 1;
+            }
+            tableau.rows.push(row);
+        }
+
+        // Phase 1: minimize the sum of the artificial variables
+        for k in first_artificial..width {
+            tableau.cost[k] = 1.0;
+        }
+        for (row, basic) in tableau.rows.iter().zip(&tableau.basis) {
+            if *basic >= first_artificial {
+                tableau.cost.iter_mut().zip(row).for_each(|(c, v)| *c -= v);
+            }
+        }
+        tableau.optimize(width)?;
+        let scale = tableau
+            .rows
+            .iter()
+            .fold(1.0, |s: FLOAT, row| s.max(row[width].abs()));
+        if -tableau.cost[width] > TOLERANCE * scale {
+            return Err(infeasible_error());
+        }
+
+        // Drive the artificial variables out of the basis, dropping redundant rows
+        let mut r = 0;
+        while r < tableau.rows.len() {
+            if tableau.basis[r] >= first_artificial {
+                match (0..first_artificial).find(|k| tableau.rows[r][*k].abs() > TOLERANCE) {
+                    Some(k) => tableau.pivot(r, k),
+                    None => {
+                        tableau.rows.remove(r);
+                        tableau.basis.remove(r);
+                        continue;
+                    }
+                }
+            }
+            r += 1;
+        }
+
+        // Phase 2: minimize the objective over the original and slack variables
+        tableau.cost = vec![0.0; width + 1];
+        tableau.cost[..columns].copy_from_slice(&cost);
+        for (row, basic) in tableau.rows.iter().zip(&tableau.basis) {
+            let factor = tableau.cost[*basic];
+            if factor != 0.0 {
+                tableau
+                    .cost
+                    .iter_mut()
+                    .zip(row)
+                    .for_each(|(c, v)| *c -= factor * v);
+            }
+        }
+        if !tableau.optimize(first_artificial)? {
+            return Err(unbounded_error());
+        }
+
+        let mut z = vec![0.0; width];
+        for (row, basic) in tableau.rows.iter().zip(&tableau.basis) {
+            z[*basic] = row[width];
+        }
+        let x = substitutions
+            .iter()
+            .map(|substitution| match substitution {
+                Substitution::Shifted(offset, k) => offset + z[*k],
+                Substitution::Reflected(offset, k) => offset - z[*k],
+                Substitution::Free(p, q) => z[*p] - z[*q],
+            })
+            .collect();
+        Ok((x, tableau.pivots))
+    }
+}
+
+/// The active-set method of `quadprog`.
+#[cfg(feature = "nalgebra")]
+mod quadratic_programming {
+    use super::linear_programming::{simplex, unbounded_error, LinearConstraints};
+    use crate::arithmetic_error;
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{EvalAltResult, FLOAT, INT};
+
+    /// Orthonormal basis of the null space of the rows `rows` of a matrix with `n` columns.
+    fn null_space(rows: &[&DVector<FLOAT>], n: usize) -> DMatrix<FLOAT> {
+        if rows.is_empty() {
+            return DMatrix::identity(n, n);
+        }
+        let a = DMatrix::from_fn(rows.len(), n, |i, j| rows[i][j]);
+        let eigen = (a.transpose() * a).symmetric_eigen();
+        let scale = eigen.eigenvalues.amax().max(1.0);
+        let columns: Vec<DVector<FLOAT>> = (0..n)
+            .filter(|k| eigen.eigenvalues[*k] <= 1e-10 * scale)
+            .map(|k| eigen.eigenvectors.column(k).into_owned())
+            .collect();
+        if columns.is_empty() {
+            DMatrix::zeros(n, 0)
+        } else {
+            DMatrix::from_columns(&columns)
+        }
+    }
+
+    /// Solution of a quadratic program.
+    pub(super) struct QpSolution {
+        /// The minimizer.
+        pub(super) x: Vec<FLOAT>,
+        /// Multipliers of the inequalities `A x <= b`.
+        pub(super) ineqlin: Vec<FLOAT>,
+        /// Multipliers of the equalities.
+        pub(super) eqlin: Vec<FLOAT>,
+        /// Multipliers of the lower bounds.
+        pub(super) lower: Vec<FLOAT>,
+        /// Multipliers of the upper bounds.
+        pub(super) upper: Vec<FLOAT>,
+        /// Number of iterations performed.
+        pub(super) iterations: INT,
+    }
+
+    /// Minimizes `x^T H x / 2 + f^T x` subject to the constraints with the primal active-set
+    /// method, starting from a feasible point found by the simplex method. Each step minimizes
+    /// the objective on the affine set of the working constraints, or follows a direction of
+    /// zero curvature when the objective has no minimum there.
+    pub(super) fn active_set(
+        h: &DMatrix<FLOAT>,
+        f: &DVector<FLOAT>,
+        constraints: &LinearConstraints,
+    ) -> Result<QpSolution, Box<EvalAltResult>> {
+        let n = f.len();
+        let h = (h + h.transpose()) * 0.5;
+        let eigenvalues = h.symmetric_eigenvalues();
+        if eigenvalues.min() < -1e-10 * eigenvalues.amax().max(1.0) {
+            return Err(arithmetic_error(
+                "The matrix H must be positive semidefinite",
+            ));
+        }
+
+        // All constraints as g^T x <= r, with the equalities first
+        let unit = |i: usize, sign: FLOAT| {
+            let mut e = DVector::zeros(n);
+            e[i] = sign;
+            e
+        };
+        let mut normals: Vec<DVector<FLOAT>> = vec![];
+        let mut rhs: Vec<FLOAT> = vec![];
+        for (row, b) in constraints.aeq.iter().zip(&constraints.beq) {
+            normals.push(DVector::from_column_slice(row));
+            rhs.push(*b);
+        }
+        let equalities = normals.len();
+        for (row, b) in constraints.a.iter().zip(&constraints.b) {
+            normals.push(DVector::from_column_slice(row));
+            rhs.push(*b);
+        }
+        let mut bounds = vec![];
+        for i in 0..n {
+            if constraints.lower[i].is_finite() {
+                bounds.push((i, false));
+                normals.push(unit(i, -1.0));
+                rhs.push(-constraints.lower[i]);
+            }
+            if constraints.upper[i].is_finite() {
+                bounds.push((i, true));
+                normals.push(unit(i, 1.0));
+                rhs.push(constraints.upper[i]);
+            }
+        }
+        let scale = |i: usize| 1e-9 * (1.0 + rhs[i].abs());
+
+        let (x0, _) = simplex(&vec![0.0; n], constraints)?;
+        let mut x = DVector::from_vec(x0);
+        let mut working: Vec<usize> = (0..normals.len())
+            .filter(|i| *i < equalities || (normals[*i].dot(&x) - rhs[*i]).abs() <= scale(*i))
+            .collect();
+        let limit = 50 * (n + normals.len()) as INT + 100;
+        let mut iterations = 0;
+
+        let multipliers = loop {
+            iterations += 1;
+            if iterations > limit {
+                return Err(arithmetic_error(
+                    "The active-set method exceeded its iteration limit",
+                ));
+            }
+            let gradient = &h * &x + f;
+            let rows: Vec<&DVector<FLOAT>> = working.iter().map(|i| &normals[*i]).collect();
+            let z = null_space(&rows, n);
+
+            // Step within the affine set of the working constraints
+            let (step, ray) = if z.ncols() == 0 {
+                (DVector::zeros(n), false)
+            } else {
+                let reduced = z.transpose() * &h * &z;
+                let g = z.transpose() * &gradient;
+                let eigen = reduced.symmetric_eigen();
+                let tolerance = 1e-10 * eigen.eigenvalues.amax().max(1.0);
+                let mut newton = DVector::zeros(z.ncols());
+                let mut flat = DVector::zeros(z.ncols());
+                for k in 0..z.ncols() {
+                    let v = eigen.eigenvectors.column(k);
+                    let component = v.dot(&g);
+                    if eigen.eigenvalues[k] > tolerance {
+                        newton -= v * (component / eigen.eigenvalues[k]);
+                    } else {
+                        flat -= v * component;
+                    }
+                }
+                if flat.norm() > 1e-9 * gradient.norm().max(1.0) {
+                    (&z * flat, true)
+                } else {
+                    (&z * newton, false)
+                }
+            };
+
+            if step.norm() <= 1e-12 * x.norm().max(1.0) {
+                // Stationary on the working set: check the signs of the multipliers
+                if working.is_empty() {
+                    break vec![];
+                }
+                let a = DMatrix::from_fn(n, working.len(), |i, j| rows[j][i]);
+                let lambda = a
+                    .svd(true, true)
+                    .solve(&(-&gradient), 1e-12)
+                    .map_err(|e| arithmetic_error(e.to_string()))?;
+                let most_negative = (0..working.len())
+                    .filter(|k| working[*k] >= equalities)
+                    .min_by(|a, b| lambda[*a].total_cmp(&lambda[*b]));
+                match most_negative {
+                    Some(k) if lambda[k] < -1e-9 * gradient.norm().max(1.0) => {
+                        working.remove(k);
+                    }
+                    _ => {
+                        break working
+                            .iter()
+                            .copied()
+                            .zip(lambda.iter().copied())
+                            .collect()
+                    }
+                }
+                continue;
+            }
+
+            // Move until the first constraint outside the working set blocks the step
+            let mut alpha = if ray { FLOAT::INFINITY } else { 1.0 };
+            let mut blocking = None;
+            for i in equalities..normals.len() {
+                if working.contains(&i) {
+                    continue;
+                }
+                let slope = normals[i].dot(&step);
+                if slope > 1e-12 * step.norm() * normals[i].norm() {
+                    let distance = ((rhs[i] - normals[i].dot(&x)) / slope).max(0.0);
+                    if distance < alpha {
+                        alpha = distance;
+                        blocking = Some(i);
+                    }
+                }
+            }
+            if alpha.is_infinite() {
+                return Err(unbounded_error());
+            }
+            x += step * alpha;
+            if let Some(i) = blocking {
+                working.push(i);
+            }
+        };
+
+        let m = constraints.a.len();
+        let mut solution = QpSolution {
+            x: x.iter().copied().collect(),
+            ineqlin: vec![0.0; m],
+            eqlin: vec![0.0; equalities],
+            lower: vec![0.0; n],
+            upper: vec![0.0; n],
+            iterations,
+        };
+        for (i, lambda) in multipliers {
+            if i < equalities {
+                solution.eqlin[i] = lambda;
+            } else if i < equalities + m {
+                solution.ineqlin[i - equalities] = lambda;
+            } else {
+                match bounds[i - equalities - m] {
+                    (j, false) => solution.lower[j] = lambda,
+                    (j, true) => solution.upper[j] = lambda,
+                }
+            }
+        }
+        Ok(solution)
+    }
+}
+
+/// Residual evaluation and the Levenberg-Marquardt method of `lsqcurvefit` and `lsqnonlin`.
+#[cfg(feature = "nalgebra")]
+mod least_squares {
+    use super::minimization::{bound_entry, function_entry, point_to_dynamic, Status};
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, int_option, OperationCounter,
+    };
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+    use std::cell::Cell;
+
+    /// Error for residual functions whose number of values changes.
+    fn inconsistent_error() -> Box<EvalAltResult> {
+        arithmetic_error("The residual function must return the same number of values everywhere")
+    }
+
+    /// Source of the residuals.
+    pub(super) enum Model {
+        /// A model `model(p, x)` evaluated at every data point, with residuals `model - y`.
+        Curve {
+            /// Function pointer for the model.
+            model: FnPtr,
+            /// Independent variable of each data point.
+            xdata: Array,
+            /// Observed values.
+            ydata: Vec<FLOAT>,
+        },
+        /// A function returning all residuals at once.
+        Residuals(FnPtr),
+    }
+
+    /// A vector of residuals depending on the parameters.
+    pub(super) struct Residuals<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Where the residuals come from.
+        model: Model,
+        /// Whether the parameter is a scalar rather than an array.
+        scalar: bool,
+        /// Function pointer for the Jacobian, if given.
+        jacobian: Option<FnPtr>,
+        /// Number of evaluations of the residual vector so far.
+        evaluations: Cell<INT>,
+        /// Counter of the calls to the residual function.
+        counter: OperationCounter,
+    }
+
+    impl<'a> Residuals<'a> {
+        /// Wraps a model, reading the `jacobian` option.
+        pub(super) fn new(
+            ctx: &'a NativeCallContext<'a>,
+            model: Model,
+            scalar: bool,
+            options: &Map,
+        ) -> Result<Self, Box<EvalAltResult>> {
+            Ok(Self {
+                ctx,
+                model,
+                scalar,
+                jacobian: function_entry(options, "jacobian")?,
+                evaluations: Cell::new(0),
+                counter: OperationCounter::new(ctx),
+            })
+        }
+
+        /// Evaluates the residuals for the parameters `p`.
+        fn eval(&self, p: &[FLOAT]) -> Result<DVector<FLOAT>, Box<EvalAltResult>> {
+            self.evaluations.set(self.evaluations.get() + 1);
+            let parameters = point_to_dynamic(p, self.scalar);
+            match &self.model {
+                Model::Curve {
+                    model,
+                    xdata,
+                    ydata,
+                } => {
+                    let mut residuals = DVector::zeros(ydata.len());
+                    for (i, (x, y)) in xdata.iter().zip(ydata).enumerate() {
+                        self.counter.tick()?;
+                        let value =
+                            model.call_raw(self.ctx, None, [parameters.clone(), x.clone()])?;
+                        let value = dynamic_to_vec_float(value)?;
+                        if value.len() != 1 {
+                            return Err(arithmetic_error(
+                                "The model must return a single number for each data point",
+                            ));
+                        }
+                        residuals[i] = value[0] - y;
+                    }
+                    Ok(residuals)
+                }
+                Model::Residuals(f) => {
+                    self.counter.tick()?;
+                    let value = f.call_raw(self.ctx, None, [parameters])?;
+                    Ok(DVector::from_vec(dynamic_to_vec_float(value)?))
+                }
+            }
+        }
+
+        /// Evaluates the Jacobian of the residuals at `p`, where the residuals are `r`. Forward
+        /// differences step away from the nearest bound.
+        fn jacobian(
+            &self,
+            p: &[FLOAT],
+            r: &DVector<FLOAT>,
+            (lower, upper): (&[FLOAT], &[FLOAT]),
+        ) -> Result<DMatrix<FLOAT>, Box<EvalAltResult>> {
+            let (m, n) = (r.len(), p.len());
+            if let Some(jacobian) = &self.jacobian {
+                self.counter.tick()?;
+                let value =
+                    jacobian.call_raw(self.ctx, None, [point_to_dynamic(p, self.scalar)])?;
+                let rows = value
+                    .into_array()
+                    .map_err(|_| arithmetic_error("The Jacobian function must return a matrix"))?;
+                let rows = rows
+                    .into_iter()
+                    .map(dynamic_to_vec_float)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if rows.len() != m || rows.iter().any(|row| row.len() != n) {
+                    return Err(arithmetic_error(format!(
+                        "The Jacobian function must return a {m} x {n} matrix"
+                    )));
+                }
+                return Ok(DMatrix::from_fn(m, n, |i, j| rows[i][j]));
+            }
+            let mut jacobian = DMatrix::zeros(m, n);
+            let mut point = p.to_vec();
+            for j in 0..n {
+                let mut h = FLOAT::EPSILON.sqrt() * p[j].abs().max(1.0);
+                if p[j] + h > upper[j] {
+                    h = -h;
+                }
+                point[j] = p[j] + h;
+                if point[j] < lower[j] {
+                    point[j] = p[j];
+                    continue;
+                }
+                let shifted = self.eval(&point)?;
+                point[j] = p[j];
+                if shifted.len() != m {
+                    return Err(inconsistent_error());
+                }
+                jacobian.set_column(j, &((shifted - r) / h));
+            }
+            Ok(jacobian)
+        }
+    }
+
+    /// Stopping criteria of the Levenberg-Marquardt method.
+    pub(super) struct Settings {
+        /// Tolerance on the relative size of a step.
+        pub(super) tol_x: FLOAT,
+        /// Tolerance on the relative reduction of the sum of squares.
+        pub(super) tol_f: FLOAT,
+        /// Tolerance on the largest component of the gradient.
+        pub(super) tol_grad: FLOAT,
+        /// Maximum number of iterations.
+        pub(super) max_iterations: INT,
+        /// Maximum number of residual evaluations.
+        pub(super) max_evaluations: INT,
+    }
+
+    impl Settings {
+        /// Reads the settings from the options, with `n` the number of parameters.
+        pub(super) fn from_options(options: &Map, n: usize) -> Result<Self, Box<EvalAltResult>> {
+            Ok(Self {
+                tol_x: float_option(options, "tol_x", 1e-10)?,
+                tol_f: float_option(options, "tol_f", 1e-12)?,
+                tol_grad: float_option(options, "tol_grad", 1e-10)?,
+                max_iterations: int_option(options, "max_iterations", 400)?,
+                max_evaluations: int_option(options, "max_evaluations", 100 * (n as INT + 1))?,
+            })
+        }
+    }
+
+    /// Converts a matrix into a nested array.
+    fn matrix_to_array(matrix: &DMatrix<FLOAT>) -> Dynamic {
+        Dynamic::from_array(
+            matrix
+                .row_iter()
+                .map(|row| {
+                    Dynamic::from_array(row.iter().map(|v| Dynamic::from_float(*v)).collect())
+                })
+                .collect(),
+        )
+    }
+
+    /// Converts a vector into an array.
+    fn vector_to_array(vector: &[FLOAT]) -> Dynamic {
+        Dynamic::from_array(vector.iter().map(|v| Dynamic::from_float(*v)).collect())
+    }
+
+    /// Minimizes the sum of squared residuals from `p0` within the bounds in `options` with the
+    /// Levenberg-Marquardt method, using Marquardt's diagonal scaling and Nielsen's update of
+    /// the damping parameter. Steps are projected onto the bounds. Returns an object map with
+    /// the `parameters`, their `standard_errors` and `covariance`, the `residuals`, the
+    /// `jacobian`, the sum of squared residuals `resnorm`, the number of `iterations` and
+    /// `evaluations`, the exit `status` and whether the fit `converged`.
+    pub(super) fn levenberg_marquardt(
+        residuals: &Residuals,
+        p0: Vec<FLOAT>,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let n = p0.len();
+        if n == 0 {
+            return Err(arithmetic_error("There must be at least one parameter"));
+        }
+        let settings = Settings::from_options(options, n)?;
+        let lower = bound_entry(options, "lb", n, FLOAT::NEG_INFINITY)?;
+        let upper = bound_entry(options, "ub", n, FLOAT::INFINITY)?;
+        if lower.iter().zip(&upper).any(|(l, u)| l > u) {
+            return Err(arithmetic_error(
+                "The lower bounds must not exceed the upper bounds",
+            ));
+        }
+        let clamp = |p: DVector<FLOAT>| -> Vec<FLOAT> {
+            p.iter()
+                .zip(lower.iter().zip(&upper))
+                .map(|(p, (l, u))| p.max(*l).min(*u))
+                .collect()
+        };
+
+        let mut p = clamp(DVector::from_vec(p0));
+        let mut r = residuals.eval(&p)?;
+        let m = r.len();
+        if m == 0 {
+            return Err(arithmetic_error("There must be at least one residual"));
+        }
+        if r.iter().any(|v| !v.is_finite()) {
+            return Err(arithmetic_error(
+                "The residuals are not finite at the starting point",
+            ));
+        }
+        let finite_jacobian = |p: &[FLOAT], r: &DVector<FLOAT>| {
+            let jacobian = residuals.jacobian(p, r, (&lower, &upper))?;
+            if jacobian.iter().all(|v| v.is_finite()) {
+                Ok(jacobian)
+            } else {
+                Err(arithmetic_error(format!(
+                    "The Jacobian of the residuals is not finite at {p:?}"
+                )))
+            }
+        };
+        let mut cost = r.norm_squared();
+        let mut jacobian = finite_jacobian(&p, &r)?;
+        let mut lambda: FLOAT = 1e-3;
+        let mut nu = 2.0;
+        let mut iterations = 0;
+        // Whether the last trial point had residuals that are not finite
+        let mut blocked = false;
+
+        let status = loop {
+            let jtj = jacobian.transpose() * &jacobian;
+            let gradient = jacobian.transpose() * &r;
+            if gradient.amax() <= settings.tol_grad {
+                break Status::Converged;
+            }
+            if iterations >= settings.max_iterations {
+                break Status::MaxIterations;
+            }
+            if residuals.evaluations.get() >= settings.max_evaluations {
+                break Status::MaxEvaluations;
+            }
+            // Every trial step was rejected until the damping overflowed
+            if !lambda.is_finite() {
+                break Status::StepTolerance;
+            }
+            iterations += 1;
+
+            let mut damped = jtj.clone();
+            for j in 0..n {
+                damped[(j, j)] += lambda * jtj[(j, j)].max(1e-12);
+            }
+            let Some(cholesky) = damped.cholesky() else {
+                lambda *= nu;
+                nu *= 2.0;
+                continue;
+            };
+            let step = cholesky.solve(&(-&gradient));
+            let trial = clamp(DVector::from_column_slice(&p) + step);
+            let step = DVector::from_column_slice(&trial) - DVector::from_column_slice(&p);
+            if step.norm()
+                <= settings.tol_x * (DVector::from_column_slice(&p).norm() + settings.tol_x)
+            {
+                // Steps that shrank only to avoid non-finite residuals are no convergence
+                break if blocked {
+                    Status::StepTolerance
+                } else {
+                    Status::Converged
+                };
+            }
+
+            let r_trial = residuals.eval(&trial)?;
+            if r_trial.len() != m {
+                return Err(inconsistent_error());
+            }
+            let cost_trial = r_trial.norm_squared();
+            blocked = !cost_trial.is_finite();
+            let predicted = cost - (&r + &jacobian * &step).norm_squared();
+            let ratio = (cost - cost_trial) / predicted;
+            if predicted > 0.0 && ratio > 0.0 {
+                let reduction = cost - cost_trial;
+                p = trial;
+                r = r_trial;
+                cost = cost_trial;
+                jacobian = finite_jacobian(&p, &r)?;
+                lambda *= FLOAT::max(1.0 / 3.0, 1.0 - (2.0 * ratio - 1.0).powi(3));
+                nu = 2.0;
+                if reduction <= settings.tol_f * cost {
+                    break Status::Converged;
+                }
+            } else {
+                lambda *= nu;
+                nu *= 2.0;
+            }
+        };
+
+        // Covariance of the parameters from the linearized model
+        let variance = if m > n {
+            cost / (m - n) as FLOAT
+        } else {
+            FLOAT::NAN
+        };
+        let covariance = (jacobian.transpose() * &jacobian)
+            .pseudo_inverse(1e-12)
+            .map_err(|e| arithmetic_error(e.to_string()))?
+            * variance;
+        let standard_errors: Vec<FLOAT> = covariance.diagonal().iter().map(|v| v.sqrt()).collect();
+
+        let mut result = Map::new();
+        result.insert("parameters".into(), point_to_dynamic(&p, residuals.scalar));
+        result.insert(
+            "standard_errors".into(),
+            point_to_dynamic(&standard_errors, residuals.scalar),
+        );
+        result.insert("covariance".into(), matrix_to_array(&covariance));
+        result.insert("residuals".into(), vector_to_array(r.as_slice()));
+        result.insert("jacobian".into(), matrix_to_array(&jacobian));
+        result.insert("resnorm".into(), Dynamic::from_float(cost));
+        result.insert("iterations".into(), Dynamic::from_int(iterations));
+        result.insert(
+            "evaluations".into(),
+            Dynamic::from_int(residuals.evaluations.get()),
+        );
+        result.insert("status".into(), status.name().into());
+        result.insert(
+            "converged".into(),
+            Dynamic::from_bool(status == Status::Converged),
+        );
+        Ok(result)
+    }
+}
+
+/// Dominance, crowding and hypervolume of objective vectors, all of which are minimized.
+mod pareto {
+    use super::minimization::matrix_rows;
+    use crate::arithmetic_error;
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT};
+
+    /// Reads a matrix with one row of objective values per point. A flat list is a single
+    /// point.
+    pub(super) fn objective_rows(f: Array) -> Result<Vec<Vec<FLOAT>>, Box<EvalAltResult>> {
+        let m = match f.first() {
+            None => return Ok(vec![]),
+            Some(row) if row.is_array() => row.clone().into_array().unwrap().len(),
+            Some(_) => f.len(),
+        };
+        matrix_rows(Dynamic::from_array(f), "F", m)
+    }
+
+    /// Whether `a` dominates `b`: `a` is no worse in every objective and better in at least
+    /// one.
+    pub(super) fn dominates(a: &[FLOAT], b: &[FLOAT]) -> bool {
+        a.iter().zip(b).all(|(x, y)| x <= y) && a.iter().zip(b).any(|(x, y)| x < y)
+    }
+
+    /// Sorts the points into successive non-dominated fronts with the fast non-dominated sort
+    /// of Deb et al. The first front holds the points no other point dominates.
+    pub(super) fn nondominated_sort(points: &[Vec<FLOAT>]) -> Vec<Vec<usize>> {
+        let n = points.len();
+        let mut dominated: Vec<Vec<usize>> = vec![vec![]; n];
+        let mut counts = vec![0; n];
+        for i in 0..n {
+            for j in i + 1..n {
+                if dominates(&points[i], &points[j]) {
+                    dominated[i].push(j);
+                    counts[j] += 1;
+                } else if dominates(&points[j], &points[i]) {
+                    dominated[j].push(i);
+                    counts[i] += 1;
+                }
+            }
+        }
+        let mut fronts = vec![];
+        let mut front: Vec<usize> = (0..n).filter(|&i| counts[i] == 0).collect();
+        while !front.is_empty() {
+            let mut next = vec![];
+            for &i in &front {
+                for &j in &dominated[i] {
+                    counts[j] -= 1;
+                    if counts[j] == 0 {
+                        next.push(j);
+                    }
+                }
+            }
+            next.sort_unstable();
+            fronts.push(front);
+            front = next;
+        }
+        fronts
+    }
+
+    /// Crowding distance of every point of `front`: the sum over the objectives of the gap
+    /// between its neighbours, relative to the range of the objective. The extreme points of
+    /// every objective have infinite distance.
+    pub(super) fn crowding_distance(points: &[Vec<FLOAT>], front: &[usize]) -> Vec<FLOAT> {
+        let mut distance = vec![0.0; front.len()];
+        if front.is_empty() {
+            return distance;
+        }
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        let columns: Vec<Vec<FLOAT>> = (0..points[front[0]].len())
+            .map(|k| front.iter().map(|&i| points[i][k]).collect())
+            .collect();
+        for column in columns {
+            order.sort_by(|&a, &b| column[a].total_cmp(&column[b]));
+            let low = column[order[0]];
+            let high = column[order[order.len() - 1]];
+            distance[order[0]] = FLOAT::INFINITY;
+            distance[order[order.len() - 1]] = FLOAT::INFINITY;
+            if high > low {
+                for w in order.windows(3) {
+                    distance[w[1]] += (column[w[2]] - column[w[0]]) / (high - low);
+                }
+            }
+        }
+        distance
+    }
+
+    /// Area dominated by points with two objectives and bounded by the reference point.
+    fn area(points: &mut [&[FLOAT]], reference: &[FLOAT]) -> FLOAT {
+        points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+        let mut level = reference[1];
+        let mut total = 0.0;
+        for p in points.iter() {
+            if p[1] < level {
+                total += (reference[0] - p[0]) * (level - p[1]);
+                level = p[1];
+            }
+        }
+        total
+    }
+
+    /// Hypervolume dominated by points with two or three objectives and bounded by the
+    /// reference point. Points that do not dominate the reference point do not contribute.
+    /// Three objectives are handled by slicing along the third one.
+    pub(super) fn hypervolume(
+        points: &[Vec<FLOAT>],
+        reference: &[FLOAT],
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        if !(2..=3).contains(&reference.len()) {
+            return Err(arithmetic_error(
+                "The hypervolume is only available for two or three objectives",
+            ));
+        }
+        if points.iter().any(|p| p.len() != reference.len()) {
+            return Err(arithmetic_error(format!(
+                "Every point must have {} objectives, like the reference point",
+                reference.len()
+            )));
+        }
+        let mut inside: Vec<&[FLOAT]> = points
+            .iter()
+            .filter(|p| p.iter().zip(reference).all(|(x, r)| x < r))
+            .map(|p| p.as_slice())
+            .collect();
+        if reference.len() == 2 {
+            return Ok(area(&mut inside, reference));
+        }
+        inside.sort_by(|a, b| a[2].total_cmp(&b[2]));
+        let mut total = 0.0;
+        for k in 0..inside.len() {
+            let top = inside.get(k + 1).map_or(reference[2], |p| p[2]);
+            if top > inside[k][2] {
+                total += area(&mut inside[..=k].to_vec(), reference) * (top - inside[k][2]);
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Population-based and stochastic global minimizers over a box: the genetic algorithm,
+/// particle swarm, simulated annealing and differential evolution, and the multi-objective
+/// NSGA-II algorithm.
+#[cfg(feature = "rand")]
+mod metaheuristics {
+    use super::minimization::{
+        function_entry, point_to_dynamic, Minimum, Monitor, Objective, Status,
+    };
+    use super::pareto::{crowding_distance, hypervolume, nondominated_sort};
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, int_option, OperationCounter,
+    };
+    use randlib::{rngs::StdRng, Rng, SeedableRng};
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// Global minimization method.
+    #[derive(Clone, Copy)]
+    pub(super) enum Method {
+        /// Real-coded genetic algorithm with elitism and tournament selection.
+        Genetic,
+        /// Particle swarm with a global neighbourhood.
+        ParticleSwarm,
+        /// Simulated annealing with exponential cooling.
+        Annealing,
+        /// Differential evolution with the `rand/1/bin` strategy.
+        DifferentialEvolution,
+    }
+
+    /// Search box, random number generator and method parameters.
+    struct Search {
+        /// Lower bounds.
+        lb: Vec<FLOAT>,
+        /// Upper bounds.
+        ub: Vec<FLOAT>,
+        /// Generator for every random decision of the search.
+        rng: StdRng,
+        /// Number of individuals, particles or moves per temperature level.
+        population_size: usize,
+    }
+
+    impl Search {
+        /// Reads the box `[lb, ub]`, whose bounds are numbers for a function of one variable or
+        /// arrays, together with the `population_size` and `seed` options. Also returns whether
+        /// the variable is a scalar.
+        fn new(
+            (lb, ub): (Dynamic, Dynamic),
+            options: &Map,
+            default_population: fn(INT) -> INT,
+            minimum_size: INT,
+        ) -> Result<(Self, bool), Box<EvalAltResult>> {
+            let scalar = lb.is_int() || lb.is_float();
+            let lb = dynamic_to_vec_float(lb)?;
+            let ub = dynamic_to_vec_float(ub)?;
+            if lb.is_empty() || lb.len() != ub.len() {
+                return Err(arithmetic_error(
+                    "The bounds must be non-empty and have one entry per variable",
+                ));
+            }
+            if lb
+                .iter()
+                .zip(&ub)
+                .any(|(l, u)| !(l.is_finite() && u.is_finite() && l <= u))
+            {
+                return Err(arithmetic_error(
+                    "Every bound must be finite, with the lower bound no greater than the upper bound",
+                ));
+            }
+            let population_size = int_option(
+                options,
+                "population_size",
+                default_population(lb.len() as INT),
+            )?;
+            if population_size < minimum_size {
+                return Err(arithmetic_error(format!(
+                    "The option 'population_size' must be at least {minimum_size}"
+                )));
+            }
+            let rng = if options.contains_key("seed") {
+                StdRng::seed_from_u64(int_option(options, "seed", 0)? as u64)
+            } else {
+                crate::with_rng(StdRng::from_rng)
+            };
+            let search = Self {
+                lb,
+                ub,
+                rng,
+                population_size: population_size as usize,
+            };
+            Ok((search, scalar))
+        }
+
+        /// Returns a point drawn uniformly from the box.
+        fn uniform_point(&mut self) -> Vec<FLOAT> {
+            let rng = &mut self.rng;
+            self.lb
+                .iter()
+                .zip(&self.ub)
+                .map(|(l, u)| l + rng.random::<FLOAT>() * (u - l))
+                .collect()
+        }
+
+        /// Draws from the standard normal distribution with the Box-Muller transform.
+        fn normal(&mut self) -> FLOAT {
+            let u1 = 1.0 - self.rng.random::<FLOAT>();
+            let u2 = self.rng.random::<FLOAT>();
+            (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+        }
+
+        /// Returns the index of the better of two individuals drawn at random.
+        fn tournament(&mut self, fitness: &[FLOAT]) -> usize {
+            let a = self.rng.random_range(0..fitness.len());
+            let b = self.rng.random_range(0..fitness.len());
+            if fitness[b] < fitness[a] {
+                b
+            } else {
+                a
+            }
+        }
+
+        /// Moves `x` into the box.
+        fn clamp(&self, x: &mut [FLOAT]) {
+            for ((v, l), u) in x.iter_mut().zip(&self.lb).zip(&self.ub) {
+                *v = v.clamp(*l, *u);
+            }
+        }
+
+        /// Width of the box along every coordinate.
+        fn range(&self) -> Vec<FLOAT> {
+            self.lb.iter().zip(&self.ub).map(|(l, u)| u - l).collect()
+        }
+    }
+
+    /// Tracks the best point, records the per-generation history and applies the stopping
+    /// rules.
+    struct Progress<'a> {
+        /// The function being minimized.
+        objective: Objective<'a>,
+        /// The optional per-generation callback.
+        monitor: Monitor<'a>,
+        /// Best point found so far.
+        x: Vec<FLOAT>,
+        /// Objective value at `x`.
+        fval: FLOAT,
+        /// Best value after every generation.
+        best: Vec<FLOAT>,
+        /// Name of the second history entry.
+        extra_name: &'static str,
+        /// Second history entry, recorded after every generation.
+        extra: Vec<FLOAT>,
+        /// Maximum number of generations.
+        generations: INT,
+        /// Number of generations over which the best value must improve.
+        stall_generations: INT,
+        /// Required improvement of the best value, relative to its magnitude.
+        tol_f: FLOAT,
+    }
+
+    impl Progress<'_> {
+        /// Evaluates the objective at `x`, keeping track of the best point. NaN values count as
+        /// infinitely bad.
+        fn evaluate(&mut self, x: &[FLOAT]) -> Result<FLOAT, Box<EvalAltResult>> {
+            let f = self.objective.eval(x)?;
+            let f = if f.is_nan() { FLOAT::INFINITY } else { f };
+            if self.x.is_empty() || f < self.fval {
+                self.x = x.to_vec();
+                self.fval = f;
+            }
+            Ok(f)
+        }
+
+        /// Records the end of a generation and returns the status if the search should stop.
+        fn end_generation(
+            &mut self,
+            generation: INT,
+            extra: FLOAT,
+        ) -> Result<Option<Status>, Box<EvalAltResult>> {
+            self.best.push(self.fval);
+            self.extra.push(extra);
+            if self
+                .monitor
+                .report(generation, &self.x, self.fval, &self.objective)?
+            {
+                return Ok(Some(Status::Stopped));
+            }
+            let stall = self.stall_generations.max(1) as usize;
+            if self.best.len() > stall {
+                let before = self.best[self.best.len() - 1 - stall];
+                if before - self.fval <= self.tol_f * FLOAT::max(self.fval.abs(), 1.0) {
+                    return Ok(Some(Status::Converged));
+                }
+            }
+            if generation >= self.generations {
+                return Ok(Some(Status::MaxIterations));
+            }
+            Ok(None)
+        }
+
+        /// Returns the result map of the search.
+        fn into_map(self, generation: INT, status: Status) -> Map {
+            let mut history = Map::new();
+            history.insert("fval".into(), float_array(&self.best));
+            history.insert(self.extra_name.into(), float_array(&self.extra));
+            let minimum = Minimum {
+                x: self.x,
+                fval: self.fval,
+                iterations: generation,
+                status,
+            };
+            let mut result = minimum.into_map(&self.objective);
+            result.insert("history".into(), history.into());
+            result
+        }
+    }
+
+    /// Converts a slice to an array of FLOATs.
+    fn float_array(values: &[FLOAT]) -> Dynamic {
+        Dynamic::from_array(values.iter().map(|v| Dynamic::from_float(*v)).collect())
+    }
+
+    /// Mean of the finite values, or infinity if there are none.
+    fn finite_mean(values: &[FLOAT]) -> FLOAT {
+        let finite: Vec<FLOAT> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        if finite.is_empty() {
+            FLOAT::INFINITY
+        } else {
+            finite.iter().sum::<FLOAT>() / finite.len() as FLOAT
+        }
+    }
+
+    /// Reads a FLOAT option that must lie in `[low, high]`.
+    fn ranged_option(
+        options: &Map,
+        key: &str,
+        default: FLOAT,
+        (low, high): (FLOAT, FLOAT),
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let value = float_option(options, key, default)?;
+        if !(low..=high).contains(&value) {
+            return Err(arithmetic_error(format!(
+                "The option '{key}' must be between {low} and {high}"
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Evolves a population with elitism, tournament selection, extended intermediate
+    /// crossover and Gaussian mutation whose scale shrinks linearly over the generations.
+    fn genetic_algorithm(
+        progress: &mut Progress,
+        search: &mut Search,
+        options: &Map,
+    ) -> Result<(INT, Status), Box<EvalAltResult>> {
+        let size = search.population_size;
+        let crossover_fraction = ranged_option(options, "crossover_fraction", 0.8, (0.0, 1.0))?;
+        let default_elites = (0.05 * size as FLOAT).ceil() as INT;
+        let elite_count =
+            (int_option(options, "elite_count", default_elites)?.max(0) as usize).min(size);
+        let mutation_scale = float_option(options, "mutation_scale", 1.0)?;
+        let mutation_shrink = ranged_option(options, "mutation_shrink", 1.0, (0.0, 1.0))?;
+        let range = search.range();
+
+        let mut population: Vec<Vec<FLOAT>> = (0..size).map(|_| search.uniform_point()).collect();
+        let mut fitness = population
+            .iter()
+            .map(|x| progress.evaluate(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut generation = 0;
+        loop {
+            if let Some(status) = progress.end_generation(generation, finite_mean(&fitness))? {
+                return Ok((generation, status));
+            }
+            generation += 1;
+            let mut order: Vec<usize> = (0..size).collect();
+            order.sort_by(|&a, &b| fitness[a].total_cmp(&fitness[b]));
+            let crossovers = ((size - elite_count) as FLOAT * crossover_fraction).round() as usize;
+            let shrink = FLOAT::max(
+                1.0 - mutation_shrink * generation as FLOAT / progress.generations.max(1) as FLOAT,
+                0.0,
+            );
+            let mut next = Vec::with_capacity(size);
+            let mut next_fitness = Vec::with_capacity(size);
+            for &i in &order[..elite_count] {
+                next.push(population[i].clone());
+                next_fitness.push(fitness[i]);
+            }
+            for k in elite_count..size {
+                let parent = &population[search.tournament(&fitness)];
+                let mut child = if k < elite_count + crossovers {
+                    let other = &population[search.tournament(&fitness)];
+                    parent
+                        .iter()
+                        .zip(other)
+                        .map(|(a, b)| a + (search.rng.random::<FLOAT>() * 1.5 - 0.25) * (b - a))
+                        .collect::<Vec<_>>()
+                } else {
+                    let mut child = parent.clone();
+                    for (v, width) in child.iter_mut().zip(&range) {
+                        *v += mutation_scale * shrink * width * search.normal();
+                    }
+                    child
+                };
+                search.clamp(&mut child);
+                next_fitness.push(progress.evaluate(&child)?);
+                next.push(child);
+            }
+            population = next;
+            fitness = next_fitness;
+        }
+    }
+
+    /// Moves a swarm of particles, each attracted to its own best point and to the best point of
+    /// the swarm, with the constriction coefficients of Clerc and Kennedy by default.
+    fn particle_swarm(
+        progress: &mut Progress,
+        search: &mut Search,
+        options: &Map,
+    ) -> Result<(INT, Status), Box<EvalAltResult>> {
+        let size = search.population_size;
+        let inertia = float_option(options, "inertia", 0.7298)?;
+        let self_adjustment = float_option(options, "self_adjustment", 1.49618)?;
+        let social_adjustment = float_option(options, "social_adjustment", 1.49618)?;
+        let range = search.range();
+
+        let mut positions: Vec<Vec<FLOAT>> = (0..size).map(|_| search.uniform_point()).collect();
+        let mut velocities: Vec<Vec<FLOAT>> = (0..size)
+            .map(|_| {
+                range
+                    .iter()
+                    .map(|w| w * (2.0 * search.rng.random::<FLOAT>() - 1.0))
+                    .collect()
+            })
+            .collect();
+        let mut values = positions
+            .iter()
+            .map(|x| progress.evaluate(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut personal = positions.clone();
+        let mut personal_values = values.clone();
+        let mut generation = 0;
+        loop {
+            if let Some(status) = progress.end_generation(generation, finite_mean(&values))? {
+                return Ok((generation, status));
+            }
+            generation += 1;
+            for i in 0..size {
+                for j in 0..range.len() {
+                    let r1 = search.rng.random::<FLOAT>();
+                    let r2 = search.rng.random::<FLOAT>();
+                    let v = inertia * velocities[i][j]
+                        + self_adjustment * r1 * (personal[i][j] - positions[i][j])
+                        + social_adjustment * r2 * (progress.x[j] - positions[i][j]);
+                    velocities[i][j] = v.clamp(-range[j], range[j]);
+                    let x = positions[i][j] + velocities[i][j];
+                    positions[i][j] = x.clamp(search.lb[j], search.ub[j]);
+                    if positions[i][j] != x {
+                        velocities[i][j] = 0.0;
+                    }
+                }
+                values[i] = progress.evaluate(&positions[i])?;
+                if values[i] < personal_values[i] {
+                    personal[i] = positions[i].clone();
+                    personal_values[i] = values[i];
+                }
+            }
+        }
+    }
+
+    /// Anneals a single point. Each generation is a temperature level with a fixed number of
+    /// Gaussian moves, whose size is proportional to the box and to the square root of the
+    /// temperature relative to its initial value; worse points are accepted with the Metropolis
+    /// probability.
+    fn simulated_annealing(
+        progress: &mut Progress,
+        search: &mut Search,
+        options: &Map,
+    ) -> Result<(INT, Status), Box<EvalAltResult>> {
+        let initial_temperature = float_option(options, "initial_temperature", 100.0)?;
+        if initial_temperature <= 0.0 {
+            return Err(arithmetic_error(
+                "The option 'initial_temperature' must be positive",
+            ));
+        }
+        let cooling = ranged_option(options, "cooling", 0.95, (0.0, 1.0))?;
+        let range = search.range();
+        let mut x = match options.get("x0") {
+            Some(x0) => {
+                let mut x0 = dynamic_to_vec_float(x0.clone())?;
+                if x0.len() != range.len() {
+                    return Err(arithmetic_error(format!(
+                        "The option 'x0' must have {} entries, one per variable",
+                        range.len()
+                    )));
+                }
+                search.clamp(&mut x0);
+                x0
+            }
+            None => search.uniform_point(),
+        };
+        let mut fx = progress.evaluate(&x)?;
+        let mut temperature = initial_temperature;
+        let mut generation = 0;
+        loop {
+            if let Some(status) = progress.end_generation(generation, temperature)? {
+                return Ok((generation, status));
+            }
+            generation += 1;
+            temperature *= cooling;
+            let scale = (temperature / initial_temperature).sqrt();
+            for _ in 0..search.population_size {
+                let mut y: Vec<FLOAT> = x
+                    .iter()
+                    .zip(&range)
+                    .map(|(v, w)| v + scale * w * search.normal())
+                    .collect();
+                search.clamp(&mut y);
+                let fy = progress.evaluate(&y)?;
+                let accept =
+                    fy <= fx || search.rng.random::<FLOAT>() < ((fx - fy) / temperature).exp();
+                if accept {
+                    x = y;
+                    fx = fy;
+                }
+            }
+        }
+    }
+
+    /// Evolves a population with the `rand/1/bin` strategy of Storn and Price. Mutant
+    /// components outside the box are moved halfway between the parent and the violated bound.
+    fn differential_evolution(
+        progress: &mut Progress,
+        search: &mut Search,
+        options: &Map,
+    ) -> Result<(INT, Status), Box<EvalAltResult>> {
+        let size = search.population_size;
+        let weight = ranged_option(options, "differential_weight", 0.8, (0.0, 2.0))?;
+        let crossover_probability =
+            ranged_option(options, "crossover_probability", 0.9, (0.0, 1.0))?;
+        let n = search.lb.len();
+
+        let mut population: Vec<Vec<FLOAT>> = (0..size).map(|_| search.uniform_point()).collect();
+        let mut fitness = population
+            .iter()
+            .map(|x| progress.evaluate(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut generation = 0;
+        loop {
+            if let Some(status) = progress.end_generation(generation, finite_mean(&fitness))? {
+                return Ok((generation, status));
+            }
+            generation += 1;
+            let mut trials = Vec::with_capacity(size);
+            for i in 0..size {
+                let mut picks = [i; 3];
+                for k in 0..3 {
+                    while picks[k] == i || picks[..k].contains(&picks[k]) {
+                        picks[k] = search.rng.random_range(0..size);
+                    }
+                }
+                let [a, b, c] = picks.map(|k| &population[k]);
+                let forced = search.rng.random_range(0..n);
+                let trial: Vec<FLOAT> = (0..n)
+                    .map(|j| {
+                        if j != forced && search.rng.random::<FLOAT>() >= crossover_probability {
+                            return population[i][j];
+                        }
+                        let v = a[j] + weight * (b[j] - c[j]);
+                        if v < search.lb[j] {
+                            0.5 * (population[i][j] + search.lb[j])
+                        } else if v > search.ub[j] {
+                            0.5 * (population[i][j] + search.ub[j])
+                        } else {
+                            v
+                        }
+                    })
+                    .collect();
+                trials.push(trial);
+            }
+            for (i, trial) in trials.into_iter().enumerate() {
+                let value = progress.evaluate(&trial)?;
+                if value <= fitness[i] {
+                    population[i] = trial;
+                    fitness[i] = value;
+                }
+            }
+        }
+    }
+
+    /// Minimizes `f` over the box `[lb, ub]` with the given method. Reads the options shared by
+    /// all methods (`population_size`, `generations`, `stall_generations`, `tol_f`, `seed` and
+    /// `callback`), with defaults depending on the method and the number of variables.
+    pub(super) fn global_minimize(
+        ctx: &NativeCallContext,
+        f: &FnPtr,
+        (lb, ub): (Dynamic, Dynamic),
+        options: &Map,
+        method: Method,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let (default_population, minimum_size): (fn(INT) -> INT, INT) = match method {
+            Method::Genetic => (|n| if n <= 5 { 50 } else { 200 }, 2),
+            Method::ParticleSwarm => (|n| (10 * n).min(100), 1),
+            Method::Annealing => (|n| 10 * n, 1),
+            Method::DifferentialEvolution => (|n| (15 * n).max(5), 4),
+        };
+        let (mut search, scalar) =
+            Search::new((lb, ub), options, default_population, minimum_size)?;
+        let n = search.lb.len() as INT;
+        let (generations, stall_generations) = match method {
+            Method::Genetic => (100 * n, 50),
+            Method::ParticleSwarm => (200 * n, 20),
+            Method::Annealing => (200, 50),
+            Method::DifferentialEvolution => (100 * n, 50),
+        };
+        let mut progress = Progress {
+            objective: Objective::new(ctx, f, scalar),
+            monitor: Monitor::from_options(ctx, options)?,
+            x: vec![],
+            fval: FLOAT::INFINITY,
+            best: vec![],
+            extra_name: match method {
+                Method::Annealing => "temperature",
+                _ => "mean",
+            },
+            extra: vec![],
+            generations: int_option(options, "generations", generations)?,
+            stall_generations: int_option(options, "stall_generations", stall_generations)?,
+            tol_f: float_option(options, "tol_f", 1e-6)?,
+        };
+        let (generation, status) = match method {
+            Method::Genetic => genetic_algorithm(&mut progress, &mut search, options)?,
+            Method::ParticleSwarm => particle_swarm(&mut progress, &mut search, options)?,
+            Method::Annealing => simulated_annealing(&mut progress, &mut search, options)?,
+            Method::DifferentialEvolution => {
+                differential_evolution(&mut progress, &mut search, options)?
+            }
+        };
+        Ok(progress.into_map(generation, status))
+    }
+
+    /// Vector-valued objective of `gamultiobj`, implemented by a Rhai function pointer.
+    struct Objectives<'a> {
+        /// Context used to call back into the script.
+        ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `f(x)`.
+        f: &'a FnPtr,
+        /// Whether the variable is a scalar rather than an array.
+        scalar: bool,
+        /// Number of objectives, known after the first evaluation.
+        count: Option<usize>,
+        /// Number of evaluations so far.
+        evaluations: INT,
+        /// Counter of the calls to the objectives.
+        counter: OperationCounter,
+    }
+
+    impl Objectives<'_> {
+        /// Evaluates `f(x)`. NaN values count as infinitely bad.
+        fn eval(&mut self, x: &[FLOAT]) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            self.evaluations += 1;
+            let value = self
+                .f
+                .call_raw(self.ctx, None, [point_to_dynamic(x, self.scalar)])?;
+            let values = dynamic_to_vec_float(value).map_err(|_| {
+                arithmetic_error("The objective function must return a number or a list of numbers")
+            })?;
+            if values.is_empty() || self.count.is_some_and(|count| count != values.len()) {
+                return Err(arithmetic_error(
+                    "The objective function must return the same, non-zero number of values everywhere",
+                ));
+            }
+            self.count = Some(values.len());
+            Ok(values
+                .into_iter()
+                .map(|v| if v.is_nan() { FLOAT::INFINITY } else { v })
+                .collect())
+        }
+    }
+
+    /// Simulated binary crossover of two parents, applied to each variable with probability
+    /// one half, with the distribution index `eta`.
+    fn simulated_binary_crossover(
+        search: &mut Search,
+        a: &[FLOAT],
+        b: &[FLOAT],
+        eta: FLOAT,
+    ) -> (Vec<FLOAT>, Vec<FLOAT>) {
+        let mut first = a.to_vec();
+        let mut second = b.to_vec();
+        for j in 0..a.len() {
+            if search.rng.random::<FLOAT>() < 0.5 {
+                continue;
+            }
+            let u = search.rng.random::<FLOAT>();
+            let beta = if u <= 0.5 {
+                (2.0 * u).powf(1.0 / (eta + 1.0))
+            } else {
+                (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (eta + 1.0))
+            };
+            first[j] = 0.5 * ((1.0 + beta) * a[j] + (1.0 - beta) * b[j]);
+            second[j] = 0.5 * ((1.0 - beta) * a[j] + (1.0 + beta) * b[j]);
+        }
+        search.clamp(&mut first);
+        search.clamp(&mut second);
+        (first, second)
+    }
+
+    /// Polynomial mutation of each variable with the given probability and distribution index
+    /// `eta`, scaled by the width of the box.
+    fn polynomial_mutation(search: &mut Search, x: &mut [FLOAT], probability: FLOAT, eta: FLOAT) {
+        for (j, v) in x.iter_mut().enumerate() {
+            if search.rng.random::<FLOAT>() >= probability {
+                continue;
+            }
+            let u = search.rng.random::<FLOAT>();
+            let delta = if u < 0.5 {
+                (2.0 * u).powf(1.0 / (eta + 1.0)) - 1.0
+            } else {
+                1.0 - (2.0 * (1.0 - u)).powf(1.0 / (eta + 1.0))
+            };
+            *v += delta * (search.ub[j] - search.lb[j]);
+        }
+        search.clamp(x);
+    }
+
+    /// Returns the rank of the front of every point and its crowding distance within the
+    /// front.
+    fn rank_and_crowding(values: &[Vec<FLOAT>]) -> (Vec<usize>, Vec<FLOAT>) {
+        let mut rank = vec![0; values.len()];
+        let mut crowding = vec![0.0; values.len()];
+        for (k, front) in nondominated_sort(values).iter().enumerate() {
+            for (&i, d) in front.iter().zip(crowding_distance(values, front)) {
+                rank[i] = k;
+                crowding[i] = d;
+            }
+        }
+        (rank, crowding)
+    }
+
+    /// Approximates the Pareto front of the objectives returned by `f` over the box
+    /// `[lb, ub]` with the NSGA-II algorithm of Deb et al.: binary tournaments on rank and
+    /// crowding distance, simulated binary crossover, polynomial mutation, and elitist survival
+    /// of the combined parents and children.
+    pub(super) fn nsga2(
+        ctx: &NativeCallContext,
+        f: &FnPtr,
+        bounds: (Dynamic, Dynamic),
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let (mut search, scalar) =
+            Search::new(bounds, options, |n| if n <= 5 { 50 } else { 200 }, 2)?;
+        let n = search.lb.len();
+        let size = search.population_size;
+        let generations = int_option(options, "generations", 100 * n as INT)?;
+        let crossover_probability =
+            ranged_option(options, "crossover_probability", 0.9, (0.0, 1.0))?;
+        let crossover_index = float_option(options, "crossover_index", 20.0)?;
+        let mutation_probability = ranged_option(
+            options,
+            "mutation_probability",
+            1.0 / n as FLOAT,
+            (0.0, 1.0),
+        )?;
+        let mutation_index = float_option(options, "mutation_index", 20.0)?;
+        let reference = match options.get("reference") {
+            Some(reference) => Some(dynamic_to_vec_float(reference.clone())?),
+            None => None,
+        };
+        let callback = function_entry(options, "callback")?;
+        let mut objectives = Objectives {
+            ctx,
+            f,
+            scalar,
+            count: None,
+            evaluations: 0,
+            counter: OperationCounter::new(ctx),
+        };
+
+        let mut population: Vec<Vec<FLOAT>> = (0..size).map(|_| search.uniform_point()).collect();
+        let mut values = population
+            .iter()
+            .map(|x| objectives.eval(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (mut rank, mut crowding) = rank_and_crowding(&values);
+        let mut front_sizes = vec![];
+        let mut volumes = vec![];
+        let mut generation = 0;
+        let status = loop {
+            let front: Vec<usize> = (0..size).filter(|&i| rank[i] == 0).collect();
+            front_sizes.push(Dynamic::from_int(front.len() as INT));
+            if let Some(reference) = &reference {
+                let points: Vec<Vec<FLOAT>> = front.iter().map(|&i| values[i].clone()).collect();
+                volumes.push(Dynamic::from_float(hypervolume(&points, reference)?));
+            }
+            if let Some(callback) = &callback {
+                objectives.counter.tick()?;
+                let mut state = Map::new();
+                state.insert("iteration".into(), Dynamic::from_int(generation));
+                state.insert(
+                    "x".into(),
+                    Dynamic::from_array(
+                        front
+                            .iter()
+                            .map(|&i| point_to_dynamic(&population[i], scalar))
+                            .collect(),
+                    ),
+                );
+                state.insert(
+                    "fval".into(),
+                    Dynamic::from_array(front.iter().map(|&i| float_array(&values[i])).collect()),
+                );
+                state.insert(
+                    "evaluations".into(),
+                    Dynamic::from_int(objectives.evaluations),
+                );
+                if callback
+                    .call_raw(ctx, None, [state.into()])?
+                    .as_bool()
+                    .unwrap_or(false)
+                {
+                    break Status::Stopped;
+                }
+            }
+            if generation >= generations {
+                break Status::MaxIterations;
+            }
+            generation += 1;
+
+            let tournament = |search: &mut Search| {
+                let a = search.rng.random_range(0..size);
+                let b = search.rng.random_range(0..size);
+                if rank[b] < rank[a] || (rank[b] == rank[a] && crowding[b] > crowding[a]) {
+                    b
+                } else {
+                    a
+                }
+            };
+            let mut children = Vec::with_capacity(size);
+            while children.len() < size {
+                let a = &population[tournament(&mut search)];
+                let b = &population[tournament(&mut search)];
+                let (mut first, mut second) =
+                    if search.rng.random::<FLOAT>() < crossover_probability {
+                        simulated_binary_crossover(&mut search, a, b, crossover_index)
+                    } else {
+                        (a.clone(), b.clone())
+                    };
+                polynomial_mutation(
+                    &mut search,
+                    &mut first,
+                    mutation_probability,
+                    mutation_index,
+                );
+                children.push(first);
+                if children.len() < size {
+                    polynomial_mutation(
+                        &mut search,
+                        &mut second,
+                        mutation_probability,
+                        mutation_index,
+                    );
+                    children.push(second);
+                }
+            }
+            for child in children {
+                values.push(objectives.eval(&child)?);
+                population.push(child);
+            }
+
+            let mut survivors = Vec::with_capacity(size);
+            for front in nondominated_sort(&values) {
+                if survivors.len() + front.len() <= size {
+                    survivors.extend(front);
+                    continue;
+                }
+                let distance = crowding_distance(&values, &front);
+                let mut order: Vec<usize> = (0..front.len()).collect();
+                order.sort_by(|&a, &b| distance[b].total_cmp(&distance[a]));
+                survivors.extend(order[..size - survivors.len()].iter().map(|&k| front[k]));
+                break;
+            }
+            population = survivors.iter().map(|&i| population[i].clone()).collect();
+            values = survivors.iter().map(|&i| values[i].clone()).collect();
+            (rank, crowding) = rank_and_crowding(&values);
+        };
+
+        let mut front: Vec<usize> = (0..size).filter(|&i| rank[i] == 0).collect();
+        front.sort_by(|&a, &b| values[a][0].total_cmp(&values[b][0]));
+        front.dedup_by(|a, b| values[*a] == values[*b] && population[*a] == population[*b]);
+        let mut history = Map::new();
+        history.insert("front_size".into(), front_sizes.into());
+        if reference.is_some() {
+            history.insert("hypervolume".into(), volumes.into());
+        }
+        let mut result = Map::new();
+        result.insert(
+            "x".into(),
+            Dynamic::from_array(
+                front
+                    .iter()
+                    .map(|&i| point_to_dynamic(&population[i], scalar))
+                    .collect(),
+            ),
+        );
+        result.insert(
+            "fval".into(),
+            Dynamic::from_array(front.iter().map(|&i| float_array(&values[i])).collect()),
+        );
+        result.insert("iterations".into(), Dynamic::from_int(generation));
+        result.insert(
+            "evaluations".into(),
+            Dynamic::from_int(objectives.evaluations),
+        );
+        result.insert("status".into(), status.name().into());
+        result.insert("history".into(), history.into());
+        Ok(result)
+    }
+}
+
+#[export_module]
+pub mod optimization_functions {
+    use super::augmented_lagrangian::fmincon as minimize_constrained;
+    #[cfg(feature = "nalgebra")]
+    use super::least_squares::{levenberg_marquardt, Model, Residuals};
+    use super::linear_programming::{check_finite, simplex, LinearConstraints};
+    #[cfg(feature = "rand")]
+    use super::metaheuristics::{global_minimize, nsga2, Method};
+    #[cfg(feature = "nalgebra")]
+    use super::minimization::matrix_rows;
+    use super::minimization::{brent_minimize, nelder_mead, Monitor, Objective};
+    use super::pareto::{
+        crowding_distance as front_crowding, hypervolume as front_hypervolume, nondominated_sort,
+        objective_rows,
+    };
+    #[cfg(feature = "nalgebra")]
+    use super::quadratic_programming::active_set;
+    use super::quasi_newton::minimize_smooth;
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, if_int_convert_to_float_and_do,
+        int_option,
+    };
+    #[cfg(feature = "nalgebra")]
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT};
+
+    /// Minimizes the function `f` with the derivative-free Nelder-Mead simplex method, starting
+    /// from `x0` (a number or an array). Returns an object map with the minimizer `x`, the
+    /// objective value `fval` there, the number of `iterations` and function `evaluations`, the
+    /// exit `status` (`"converged"`, `"max_iterations"`, `"max_evaluations"` or `"stopped"`),
+    /// and whether the tolerances were met (`converged`). The objective must be finite at `x0`,
+    /// and a simplex with NaN values never counts as converged.
+    /// ```typescript
+    /// // Rosenbrock's banana function
+    /// let result = fminsearch(|x| 100.0 * (x[1] - x[0] ** 2) ** 2 + (1.0 - x[0]) ** 2, [-1.2, 1.0]);
+    /// assert(result.converged);
+    /// assert_approx_eq(result.x, [1.0, 1.0], 1e-3);
+    /// ```
+    /// ```typescript
+    /// let result = fminsearch(|x| (x - 3.0) ** 2, 0);
+    /// assert_approx_eq(result.x, 3.0, 1e-3);
+    /// ```
+    #[rhai_fn(name = "fminsearch", return_raw)]
+    pub fn fminsearch(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        fminsearch_with_options(ctx, f, x0, Map::new())
+    }
+
+    /// Minimizes the function `f` with the Nelder-Mead simplex method, starting from `x0`. The
+    /// following options are supported (with `n` the number of variables):
+    /// - `tol_x`: tolerance on the size of the simplex (default `1e-4`).
+    /// - `tol_f`: tolerance on the spread of the objective over the simplex (default `1e-4`).
+    /// - `max_iterations`: maximum number of iterations (default `200 * n`).
+    /// - `max_evaluations`: maximum number of function evaluations (default `200 * n`).
+    /// - `callback`: function called after every iteration with an object map holding the
+    ///   `iteration`, the best point `x`, its value `fval`, and the number of `evaluations`. The
+    ///   search stops with status `"stopped"` if it returns `true`.
+    ///
+    /// Each call of `f` or `callback` counts as one operation towards the engine's operation
+    /// limit.
+    /// ```typescript
+    /// let result = fminsearch(|x| x[0] ** 2 + 4.0 * x[1] ** 2, [1.0, 1.0], #{tol_x: 1e-8, tol_f: 1e-12});
+    /// assert_approx_eq(result.x, [0.0, 0.0], 1e-7);
+    /// ```
+    /// ```typescript
+    /// let log = [];
+    /// let result = fminsearch(|x| (x[0] - 1.0) ** 2 + (x[1] + 2.0) ** 2, [0, 0],
+    ///                         #{callback: |state| { log.push(state.fval); state.iteration >= 5 }});
+    /// assert_eq(result.status, "stopped");
+    /// assert_eq(result.iterations, 5);
+    /// ```
+    #[rhai_fn(name = "fminsearch", return_raw)]
+    pub fn fminsearch_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = x0.is_int() || x0.is_float();
+        let x0 = dynamic_to_vec_float(x0)?;
+        if x0.is_empty() {
+            return Err(arithmetic_error("The starting point must not be empty"));
+        }
+        let n = x0.len() as rhai::INT;
+        let tol_x = float_option(&options, "tol_x", 1e-4)?;
+        let tol_f = float_option(&options, "tol_f", 1e-4)?;
+        let max_iterations = int_option(&options, "max_iterations", 200 * n)?;
+        let max_evaluations = int_option(&options, "max_evaluations", 200 * n)?;
+        let monitor = Monitor::from_options(&ctx, &options)?;
+        let objective = Objective::new(&ctx, &f, scalar);
+        let minimum = nelder_mead(
+            &objective,
+            x0,
+            (tol_x, tol_f),
+            (max_iterations, max_evaluations),
+            &monitor,
+        )?;
+        Ok(minimum.into_map(&objective))
+    }
+
+    /// Minimizes the function `f` of one variable on the interval `[a, b]` with Brent's method,
+    /// combining golden-section search and parabolic interpolation. Returns an object map in the
+    /// same form as `fminsearch`.
+    /// ```typescript
+    /// let result = fminbnd(|x| (x - 2.0) ** 2 + 1.0, 0, 5);
+    /// assert_approx_eq(result.x, 2.0, 1e-4);
+    /// assert_approx_eq(result.fval, 1.0, 1e-8);
+    /// ```
+    /// ```typescript
+    /// // The minimum of a monotonic function is at the boundary
+    /// let result = fminbnd(|x| exp(x), -1.0, 1.0);
+    /// assert_approx_eq(result.x, -1.0, 1e-3);
+    /// ```
+    #[rhai_fn(name = "fminbnd", return_raw)]
+    pub fn fminbnd(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        fminbnd_with_options(ctx, f, a, b, Map::new())
+    }
+
+    /// Minimizes the function `f` of one variable on the interval `[a, b]` with Brent's method.
+    /// Supports the `tol_x` (default `1e-4`), `max_iterations` (default `500`),
+    /// `max_evaluations` (default `500`) and `callback` options of `fminsearch`.
+    /// ```typescript
+    /// let result = fminbnd(|x| sin(x), 0, 2.0 * pi, #{tol_x: 1e-10});
+    /// assert_approx_eq(result.x, 1.5 * pi, 1e-8);
+    /// ```
+    #[rhai_fn(name = "fminbnd", return_raw)]
+    pub fn fminbnd_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let a = if_int_convert_to_float_and_do(a, Ok)?;
+        let b = if_int_convert_to_float_and_do(b, Ok)?;
+        if a >= b {
+            return Err(arithmetic_error(
+                "The lower bound must be smaller than the upper bound",
+            ));
+        }
+        let tol_x = float_option(&options, "tol_x", 1e-4)?;
+        let max_iterations = int_option(&options, "max_iterations", 500)?;
+        let max_evaluations = int_option(&options, "max_evaluations", 500)?;
+        let monitor = Monitor::from_options(&ctx, &options)?;
+        let objective = Objective::new(&ctx, &f, true);
+        let minimum = brent_minimize(
+            &objective,
+            (a, b),
+            tol_x,
+            (max_iterations, max_evaluations),
+            &monitor,
+        )?;
+        Ok(minimum.into_map(&objective))
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0` (a number or an array) with the
+    /// BFGS quasi-Newton method and a line search satisfying the strong Wolfe conditions. The
+    /// gradient is approximated by central differences. Returns an object map with the same
+    /// entries as `fminsearch`, where `status` may also be `"step_tolerance"` or
+    /// `"line_search_failed"`, together with the `gradient` at `x`, its Euclidean norm
+    /// `gradient_norm`, and a `history` map holding the arrays `fval` and `gradient_norm` with
+    /// one entry for the starting point and one per iteration.
+    /// ```typescript
+    /// let result = fminunc(|x| 100.0 * (x[1] - x[0] ** 2) ** 2 + (1.0 - x[0]) ** 2, [-1.2, 1.0]);
+    /// assert(result.converged);
+    /// assert_approx_eq(result.x, [1.0, 1.0], 1e-5);
+    /// assert(result.gradient_norm <= 1e-6);
+    /// ```
+    #[rhai_fn(name = "fminunc", return_raw)]
+    pub fn fminunc(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        fminunc_with_options(ctx, f, x0, Map::new())
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0`, using the analytic `gradient`
+    /// function, which takes the same argument as `f` and returns an array of partial
+    /// derivatives (or a number for scalar problems).
+    /// ```typescript
+    /// let f = |x| (x[0] - 1.0) ** 4 + (x[0] - x[1]) ** 2;
+    /// let g = |x| [4.0 * (x[0] - 1.0) ** 3 + 2.0 * (x[0] - x[1]), -2.0 * (x[0] - x[1])];
+    /// let result = fminunc(f, [3.0, -2.0], g);
+    /// assert_approx_eq(result.x, [1.0, 1.0], 1e-2);
+    /// assert_eq(len(result.history.fval), result.iterations + 1);
+    /// ```
+    #[rhai_fn(name = "fminunc", return_raw)]
+    pub fn fminunc_with_gradient(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        gradient: FnPtr,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        minimize_smooth(&ctx, &f, x0, Some(&gradient), Map::new())
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0`. The following options are supported
+    /// (with `n` the number of variables):
+    /// - `method`: `"bfgs"` (the default), `"lbfgs"` for limited-memory BFGS, which suits
+    ///   problems with many variables, or `"cg"` for the Polak-Ribière conjugate gradient method.
+    /// - `memory`: number of steps remembered by L-BFGS (default `10`).
+    /// - `tol_grad`: tolerance on the norm of the gradient (default `1e-6`).
+    /// - `tol_x`: stop when a step is smaller than `tol_x * (1 + max(abs(x)))` (default `1e-10`).
+    /// - `max_iterations`: maximum number of iterations (default `400`).
+    /// - `max_evaluations`: maximum number of evaluations of `f`, including those of the
+    ///   finite-difference gradient (default `1000 * n`).
+    /// - `callback`: function called after every iteration, as for `fminsearch`.
+    ///
+    /// Each call of `f`, the gradient function or `callback` counts as one operation towards the
+    /// engine's operation limit.
+    /// ```typescript
+    /// // A badly scaled quadratic with 20 variables
+    /// let f = |x| {
+    ///     let total = 0.0;
+    ///     for i in 0..len(x) { total += (i + 1) * (x[i] - 1.0) ** 2; }
+    ///     total
+    /// };
+    /// let result = fminunc(f, zeros(1, 20)[0], #{method: "lbfgs"});
+    /// assert(result.converged);
+    /// assert_approx_eq(result.x, ones(1, 20)[0], 1e-6);
+    /// ```
+    /// ```typescript
+    /// let result = fminunc(|x| x ** 4 - 3.0 * x, 0.0, #{method: "cg", tol_grad: 1e-10});
+    /// assert_approx_eq(result.x, (3.0 / 4.0) ** (1.0 / 3.0), 1e-8);
+    /// ```
+    #[rhai_fn(name = "fminunc", return_raw)]
+    pub fn fminunc_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        minimize_smooth(&ctx, &f, x0, None, options)
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0`, using the analytic `gradient`
+    /// function and the options described above.
+    /// ```typescript
+    /// let result = fminunc(|x| exp(x[0]) - x[0] + x[1] ** 2, [1, 1], |x| [exp(x[0]) - 1.0, 2.0 * x[1]],
+    ///                      #{method: "lbfgs", memory: 3});
+    /// assert_approx_eq(result.x, [0.0, 0.0], 1e-6);
+    /// assert_approx_eq(result.fval, 1.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "fminunc", return_raw)]
+    pub fn fminunc_with_gradient_and_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        gradient: FnPtr,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        minimize_smooth(&ctx, &f, x0, Some(&gradient), options)
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0` (a number or an array), subject to
+    /// the constraints in the object map `constraints`, which may contain:
+    /// - `lb` and `ub`: lower and upper bounds, as a number for all variables or an array.
+    /// - `A` and `b`: linear inequalities `A x <= b`, with `A` a matrix (or a single row).
+    /// - `Aeq` and `beq`: linear equalities `Aeq x = beq`.
+    /// - `c`: function of `x` returning a number or an array of nonlinear inequalities
+    ///   `c(x) <= 0`.
+    /// - `ceq`: function of `x` returning a number or an array of nonlinear equalities
+    ///   `ceq(x) = 0`.
+    ///
+    /// The problem is solved with the augmented Lagrangian method, minimizing each subproblem
+    /// with BFGS within the bounds, so that `f` and the constraint functions are never evaluated
+    /// outside them. Gradients are approximated by central differences, which are one-sided at
+    /// the bounds. Returns an object map with
+    /// the same entries as `fminsearch`, where `status` may also be `"infeasible"`, together with
+    /// the largest constraint violation `max_violation` and the Lagrange multipliers `lambda`, an
+    /// object map with the arrays `lower`, `upper`, `ineqlin`, `ineqnonlin`, `eqlin` and
+    /// `eqnonlin`. The multipliers of active inequalities are positive, and those of inactive
+    /// ones are zero.
+    /// ```typescript
+    /// // Minimize x + y on the unit disk
+    /// let result = fmincon(|x| x[0] + x[1], [0, 0], #{c: |x| x[0] ** 2 + x[1] ** 2 - 1.0});
+    /// assert(result.converged);
+    /// assert_approx_eq(result.x, [-sqrt(0.5), -sqrt(0.5)], 1e-5);
+    /// assert_approx_eq(result.lambda.ineqnonlin[0], sqrt(0.5), 1e-4);
+    /// ```
+    /// ```typescript
+    /// // Linear equality and bounds
+    /// let result = fmincon(|x| x[0] ** 2 + 2.0 * x[1] ** 2 + 3.0 * x[2] ** 2, [1, 1, 1],
+    ///                      #{Aeq: [[1, 1, 1]], beq: [6], ub: [2, 10, 10]});
+    /// assert_approx_eq(result.x, [2.0, 2.4, 1.6], 1e-5);
+    /// assert_approx_eq(result.lambda.upper, [5.6, 0.0, 0.0], 1e-4);
+    /// assert_approx_eq(result.lambda.eqlin, [-9.6], 1e-4);
+    /// ```
+    #[rhai_fn(name = "fmincon", return_raw)]
+    pub fn fmincon(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        constraints: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        minimize_constrained(&ctx, &f, x0, &constraints, &Map::new())
+    }
+
+    /// Minimizes the smooth function `f` starting from `x0` subject to `constraints`, as above.
+    /// The following options are supported (with `n` the number of variables):
+    /// - `gradient`: function returning the gradient of `f`, as for `fminunc`.
+    /// - `method`, `memory`, `tol_grad` and `tol_x`: settings of the subproblem solver, as for
+    ///   `fminunc`.
+    /// - `tol_con`: tolerance on the constraint violation (default `1e-6`).
+    /// - `penalty`: initial penalty parameter (default `10`).
+    /// - `max_iterations`: maximum number of outer iterations (default `100`).
+    /// - `max_inner_iterations`: maximum number of iterations per subproblem (default `400`).
+    /// - `max_evaluations`: maximum number of evaluations of `f` (default `10000 * n`).
+    /// - `callback`: function called after every outer iteration, as for `fminsearch`.
+    ///
+    /// Each call of `f`, the constraint functions, the gradient function or `callback` counts
+    /// as one operation towards the engine's operation limit.
+    /// ```typescript
+    /// let f = |x| (x[0] - 2.0) ** 2 + (x[1] - 1.0) ** 2;
+    /// let result = fmincon(f, [0, 0], #{A: [[1, 1]], b: [2], lb: 0},
+    ///                      #{gradient: |x| [2.0 * (x[0] - 2.0), 2.0 * (x[1] - 1.0)], tol_con: 1e-9});
+    /// assert_approx_eq(result.x, [1.5, 0.5], 1e-6);
+    /// assert_approx_eq(result.lambda.ineqlin, [1.0], 1e-5);
+    /// ```
+    #[rhai_fn(name = "fmincon", return_raw)]
+    pub fn fmincon_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        constraints: Map,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        minimize_constrained(&ctx, &f, x0, &constraints, &options)
+    }
+
+    /// Solves the linear program of minimizing `c^T x` subject to `A x <= b`, where `A` is a
+    /// matrix with one row per constraint, with the two-phase simplex method. Returns an object
+    /// map with the minimizer `x`, the objective value `fval` and the number of simplex pivots
+    /// `iterations`. Variables without bounds may take any value. Infeasible and unbounded
+    /// problems are reported as errors, as are coefficients that are not finite.
+    /// ```typescript
+    /// // Maximize 3x + 5y
+    /// let result = linprog([-3, -5], [[1, 0], [0, 2], [3, 2], [-1, 0], [0, -1]], [4, 12, 18, 0, 0]);
+    /// assert_approx_eq(result.x, [2.0, 6.0], 1e-10);
+    /// assert_approx_eq(result.fval, -36.0, 1e-10);
+    /// ```
+    /// ```typescript
+    /// let message = "";
+    /// try {
+    ///     linprog([-1, -1], [[1, -1]], [1]);
+    /// } catch (error) {
+    ///     message = `${error}`;
+    /// }
+    /// assert(message.contains("unbounded"));
+    /// ```
+    #[rhai_fn(name = "linprog", return_raw)]
+    pub fn linprog(c: Array, a: Array, b: Array) -> Result<Map, Box<EvalAltResult>> {
+        linprog_with_bounds(
+            c,
+            a,
+            b,
+            Array::new(),
+            Array::new(),
+            Array::new(),
+            Array::new(),
+        )
+    }
+
+    /// Solves the linear program of minimizing `c^T x` subject to `A x <= b` and
+    /// `Aeq x = beq`. Pass empty arrays for absent constraints.
+    /// ```typescript
+    /// let nonnegative = [[-1, 0, 0], [0, -1, 0], [0, 0, -1]];
+    /// let result = linprog([1, 2, 3], nonnegative, [0, 0, 0], [[1, 1, 1]], [1]);
+    /// assert_approx_eq(result.x, [1.0, 0.0, 0.0], 1e-10);
+    /// ```
+    /// ```typescript
+    /// let message = "";
+    /// try {
+    ///     linprog([1, 1], [[1, 1]], [1], [[1, 1]], [2]);
+    /// } catch (error) {
+    ///     message = `${error}`;
+    /// }
+    /// assert(message.contains("infeasible"));
+    /// ```
+    #[rhai_fn(name = "linprog", return_raw)]
+    pub fn linprog_with_equalities(
+        c: Array,
+        a: Array,
+        b: Array,
+        aeq: Array,
+        beq: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        linprog_with_bounds(c, a, b, aeq, beq, Array::new(), Array::new())
+    }
+
+    /// Solves the linear program of minimizing `c^T x` subject to `A x <= b`, `Aeq x = beq` and
+    /// `lb <= x <= ub`. Pass empty arrays for absent constraints, and `inf` or `-inf` for
+    /// individual variables without a bound.
+    /// ```typescript
+    /// // Cheapest diet meeting two nutrient requirements
+    /// let cost = [2.0, 3.0];
+    /// let nutrients = [[-1.0, -2.0], [-3.0, -1.0]];
+    /// let result = linprog(cost, nutrients, [-4, -6], [], [], [0, 0], [inf, 1]);
+    /// assert_approx_eq(result.x, [2.0, 1.0], 1e-10);
+    /// assert_approx_eq(result.fval, 7.0, 1e-10);
+    /// ```
+    #[rhai_fn(name = "linprog", return_raw)]
+    pub fn linprog_with_bounds(
+        c: Array,
+        a: Array,
+        b: Array,
+        aeq: Array,
+        beq: Array,
+        lb: Array,
+        ub: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let c = dynamic_to_vec_float(Dynamic::from_array(c))?;
+        if c.is_empty() {
+            return Err(arithmetic_error("The cost vector must not be empty"));
+        }
+        check_finite(&c, "c")?;
+        let constraints = LinearConstraints::new(c.len(), (a, b), (aeq, beq), (lb, ub))?;
+        let (x, iterations) = simplex(&c, &constraints)?;
+        let mut result = Map::new();
+        result.insert(
+            "fval".into(),
+            Dynamic::from_float(c.iter().zip(&x).map(|(c, x)| c * x).sum::<FLOAT>()),
+        );
+        result.insert(
+            "x".into(),
+            Dynamic::from_array(x.into_iter().map(Dynamic::from_float).collect()),
+        );
+        result.insert("iterations".into(), Dynamic::from_int(iterations));
+        Ok(result)
+    }
+
+    /// Solves the unconstrained convex quadratic program of minimizing `x^T H x / 2 + f^T x`,
+    /// where `H` is a symmetric positive semidefinite matrix. Returns an object map in the same
+    /// form as `quadprog` with constraints.
+    /// ```typescript
+    /// let result = quadprog([[2, 0], [0, 4]], [-2, -8]);
+    /// assert_approx_eq(result.x, [1.0, 2.0], 1e-10);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "quadprog", return_raw)]
+    pub fn quadprog(h: Array, f: Array) -> Result<Map, Box<EvalAltResult>> {
+        quadprog_with_bounds(
+            h,
+            f,
+            Array::new(),
+            Array::new(),
+            Array::new(),
+            Array::new(),
+            Array::new(),
+            Array::new(),
+        )
+    }
+
+    /// Solves the convex quadratic program of minimizing `x^T H x / 2 + f^T x` subject to
+    /// `A x <= b`. Returns an object map with the minimizer `x`, the objective value `fval`,
+    /// the number of active-set `iterations`, and the Lagrange multipliers `lambda`, an object
+    /// map with the arrays `ineqlin`, `eqlin`, `lower` and `upper`. Infeasible and unbounded
+    /// problems are reported as errors.
+    /// ```typescript
+    /// let H = [[1, -1], [-1, 2]];
+    /// let result = quadprog(H, [-2, -6], [[1, 1], [-1, 2], [2, 1], [-1, 0], [0, -1]], [2, 2, 3, 0, 0]);
+    /// assert_approx_eq(result.x, [2.0 / 3.0, 4.0 / 3.0], 1e-10);
+    /// assert_approx_eq(result.fval, -74.0 / 9.0, 1e-10);
+    /// assert_approx_eq(result.lambda.ineqlin, [28.0 / 9.0, 4.0 / 9.0, 0.0, 0.0, 0.0], 1e-10);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "quadprog", return_raw)]
+    pub fn quadprog_with_inequalities(
+        h: Array,
+        f: Array,
+        a: Array,
+        b: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        quadprog_with_bounds(
+            h,
+            f,
+            a,
+            b,
+            Array::new(),
+            Array::new(),
+            Array::new(),
+            Array::new(),
+        )
+    }
+
+    /// Solves the convex quadratic program of minimizing `x^T H x / 2 + f^T x` subject to
+    /// `A x <= b` and `Aeq x = beq`. Pass empty arrays for absent constraints.
+    /// ```typescript
+    /// // Closest point to the origin on a plane
+    /// let result = quadprog(eye(3), [0, 0, 0], [], [], [[1, 2, 2]], [9]);
+    /// assert_approx_eq(result.x, [1.0, 2.0, 2.0], 1e-10);
+    /// assert_approx_eq(result.lambda.eqlin, [-1.0], 1e-10);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "quadprog", return_raw)]
+    pub fn quadprog_with_equalities(
+        h: Array,
+        f: Array,
+        a: Array,
+        b: Array,
+        aeq: Array,
+        beq: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        quadprog_with_bounds(h, f, a, b, aeq, beq, Array::new(), Array::new())
+    }
+
+    /// Solves the convex quadratic program of minimizing `x^T H x / 2 + f^T x` subject to
+    /// `A x <= b`, `Aeq x = beq` and `lb <= x <= ub`. Pass empty arrays for absent
+    /// constraints, and `inf` or `-inf` for individual variables without a bound. A feasible
+    /// starting point is found with the simplex method and then improved with the primal
+    /// active-set method.
+    /// ```typescript
+    /// // Least-squares fit with nonnegative coefficients
+    /// let H = [[2, 1], [1, 2]];
+    /// let result = quadprog(H, [1, -4], [], [], [], [], [0, 0], []);
+    /// assert_approx_eq(result.x, [0.0, 2.0], 1e-10);
+    /// assert_approx_eq(result.lambda.lower, [3.0, 0.0], 1e-10);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "quadprog", return_raw)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn quadprog_with_bounds(
+        h: Array,
+        f: Array,
+        a: Array,
+        b: Array,
+        aeq: Array,
+        beq: Array,
+        lb: Array,
+        ub: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let f = dynamic_to_vec_float(Dynamic::from_array(f))?;
+        let n = f.len();
+        if n == 0 {
+            return Err(arithmetic_error("The vector f must not be empty"));
+        }
+        let rows = matrix_rows(Dynamic::from_array(h), "H", n)?;
+        if rows.len() != n {
+            return Err(arithmetic_error(format!("H must be a {n} x {n} matrix")));
+        }
+        check_finite(rows.iter().flatten(), "H")?;
+        check_finite(&f, "f")?;
+        let h = DMatrix::from_fn(n, n, |i, j| rows[i][j]);
+        let f = DVector::from_vec(f);
+        let constraints = LinearConstraints::new(n, (a, b), (aeq, beq), (lb, ub))?;
+        let solution = active_set(&h, &f, &constraints)?;
+
+        let x = DVector::from_column_slice(&solution.x);
+        let to_array =
+            |v: Vec<FLOAT>| Dynamic::from_array(v.into_iter().map(Dynamic::from_float).collect());
+        let mut lambda = Map::new();
+        lambda.insert("ineqlin".into(), to_array(solution.ineqlin));
+        lambda.insert("eqlin".into(), to_array(solution.eqlin));
+        lambda.insert("lower".into(), to_array(solution.lower));
+        lambda.insert("upper".into(), to_array(solution.upper));
+        let mut result = Map::new();
+        result.insert(
+            "fval".into(),
+            Dynamic::from_float(0.5 * x.dot(&(&h * &x)) + f.dot(&x)),
+        );
+        result.insert("x".into(), to_array(solution.x));
+        result.insert("iterations".into(), Dynamic::from_int(solution.iterations));
+        result.insert("lambda".into(), lambda.into());
+        Ok(result)
+    }
+
+    /// Fits the parameters `p` of `model(p, x)` to the data `xdata` and `ydata` in the least
+    /// squares sense, starting from `p0` (a number or an array), with the Levenberg-Marquardt
+    /// method. The model is called once per data point, with `x` the corresponding element of
+    /// `xdata` (a number, or an array for several independent variables), and must return a
+    /// number. Returns an object map with the `parameters`, their `standard_errors` and
+    /// `covariance` estimated from the Jacobian at the solution, the `residuals`
+    /// `model(p, x) - y`, the `jacobian` of the residuals, the sum of squared residuals
+    /// `resnorm`, the number of `iterations` and residual `evaluations`, the exit `status` and
+    /// whether the fit `converged`. The `status` is `"step_tolerance"` if no step reducing the
+    /// residuals can be found, for example because they are not finite around the current
+    /// parameters. The residuals must be finite at `p0`.
+    /// ```typescript
+    /// // Exponential decay
+    /// let t = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+    /// let y = t.map(|t| 5.0 * exp(-0.7 * t) + 1.0);
+    /// let fit = lsqcurvefit(|p, t| p[0] * exp(-p[1] * t) + p[2], [1.0, 1.0, 0.0], t, y);
+    /// assert(fit.converged);
+    /// assert_approx_eq(fit.parameters, [5.0, 0.7, 1.0], 1e-8);
+    /// assert(fit.resnorm < 1e-16);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "lsqcurvefit", return_raw)]
+    pub fn lsqcurvefit(
+        ctx: NativeCallContext,
+        model: FnPtr,
+        p0: Dynamic,
+        xdata: Array,
+        ydata: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        lsqcurvefit_with_options(ctx, model, p0, xdata, ydata, Map::new())
+    }
+
+    /// Fits the parameters of `model(p, x)` to the data as above. The following options are
+    /// supported (with `n` the number of parameters):
+    /// - `lb` and `ub`: lower and upper bounds on the parameters, as a number for all of them
+    ///   or an array.
+    /// - `jacobian`: function of `p` returning the Jacobian of the residuals, a matrix with one
+    ///   row per data point and one column per parameter. By default it is approximated by
+    ///   forward differences.
+    /// - `tol_x`: tolerance on the size of a step relative to the parameters (default `1e-10`).
+    /// - `tol_f`: tolerance on the relative reduction of the sum of squares (default `1e-12`).
+    /// - `tol_grad`: tolerance on the largest component of the gradient (default `1e-10`).
+    /// - `max_iterations`: maximum number of iterations (default `400`).
+    /// - `max_evaluations`: maximum number of evaluations of all residuals
+    ///   (default `100 * (n + 1)`).
+    ///
+    /// Each call of `model` or the Jacobian function counts as one operation towards the
+    /// engine's operation limit.
+    /// ```typescript
+    /// // Arrhenius law k = A exp(-Ea / (R T)), with a bound keeping the activation energy positive
+    /// let T = [300.0, 320.0, 340.0, 360.0, 380.0];
+    /// let k = [0.00162, 0.00936, 0.0440, 0.174, 0.596];
+    /// let fit = lsqcurvefit(|p, T| p[0] * 1e9 * exp(-p[1] * 1e4 / (8.314 * T)), [1.0, 1.0], T, k,
+    ///                       #{lb: [0.0, 0.0]});
+    /// assert(fit.converged);
+    /// assert_approx_eq(fit.parameters[1], 7.0, 0.1);
+    /// assert(fit.standard_errors[1] < 0.1);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "lsqcurvefit", return_raw)]
+    pub fn lsqcurvefit_with_options(
+        ctx: NativeCallContext,
+        model: FnPtr,
+        p0: Dynamic,
+        xdata: Array,
+        ydata: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = p0.is_int() || p0.is_float();
+        let p0 = dynamic_to_vec_float(p0)?;
+        let ydata = dynamic_to_vec_float(ydata)?;
+        if xdata.len() != ydata.len() {
+            return Err(arithmetic_error(format!(
+                "There are {} values in xdata but {} in ydata",
+                xdata.len(),
+                ydata.len()
+            )));
+        }
+        let model = Model::Curve {
+            model,
+            xdata,
+            ydata,
+        };
+        let residuals = Residuals::new(&ctx, model, scalar, &options)?;
+        levenberg_marquardt(&residuals, p0, &options)
+    }
+
+    /// Minimizes the sum of squares of the residuals returned by `f(p)` (a number or an array)
+    /// starting from `p0`, with the Levenberg-Marquardt method. Returns an object map in the
+    /// same form as `lsqcurvefit`.
+    /// ```typescript
+    /// // Rosenbrock's function as a least squares problem
+    /// let fit = lsqnonlin(|p| [10.0 * (p[1] - p[0] ** 2), 1.0 - p[0]], [-1.2, 1.0]);
+    /// assert(fit.converged);
+    /// assert_approx_eq(fit.parameters, [1.0, 1.0], 1e-8);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "lsqnonlin", return_raw)]
+    pub fn lsqnonlin(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        p0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        lsqnonlin_with_options(ctx, f, p0, Map::new())
+    }
+
+    /// Minimizes the sum of squares of the residuals returned by `f(p)` starting from `p0`,
+    /// with the options of `lsqcurvefit`. The Jacobian function has one row per residual.
+    /// ```typescript
+    /// // The bound excludes the unconstrained minimum at p = 2
+    /// let fit = lsqnonlin(|p| [p - 2.0, 0.5 * (p - 2.0)], 0.0, #{ub: 1.5});
+    /// assert_approx_eq(fit.parameters, 1.5, 1e-12);
+    /// assert_approx_eq(fit.residuals, [-0.5, -0.25], 1e-12);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "lsqnonlin", return_raw)]
+    pub fn lsqnonlin_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        p0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = p0.is_int() || p0.is_float();
+        let p0 = dynamic_to_vec_float(p0)?;
+        let residuals = Residuals::new(&ctx, Model::Residuals(f), scalar, &options)?;
+        levenberg_marquardt(&residuals, p0, &options)
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with a genetic algorithm. The bounds
+    /// are numbers for a function of one variable or arrays, and must be finite. Returns an
+    /// object map with the best point `x`, its value `fval`, the number of generations as
+    /// `iterations`, the number of function `evaluations`, the exit `status` (`"converged"` when
+    /// the best value stalls, `"max_iterations"` or `"stopped"`), whether it `converged`, and a
+    /// `history` map holding the arrays `fval` (best value) and `mean` (mean value of the
+    /// population) with one entry for the initial population and one per generation.
+    /// ```typescript
+    /// // Rastrigin's function has a grid of local minima around the global minimum at the origin
+    /// let f = |x| 20.0 + x[0] ** 2 + x[1] ** 2 - 10.0 * (cos(2.0 * pi * x[0]) + cos(2.0 * pi * x[1]));
+    /// let result = ga(f, [-5.12, -5.12], [5.12, 5.12], #{seed: 1});
+    /// assert_approx_eq(result.x, [0.0, 0.0], 1e-2);
+    /// assert_eq(result.history.fval.len(), result.iterations + 1);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "ga", return_raw)]
+    pub fn ga(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        ga_with_options(ctx, f, lb, ub, Map::new())
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with a genetic algorithm. Every
+    /// generation keeps the best individuals, creates a fraction of children by crossing parents
+    /// chosen by tournament, and creates the rest by Gaussian mutation. The following options are
+    /// supported by all the global minimizers (with `n` the number of variables):
+    /// - `population_size`: number of individuals (default `50` for `n <= 5`, `200` otherwise).
+    /// - `generations`: maximum number of generations (default `100 * n`).
+    /// - `stall_generations` and `tol_f`: the search converges when the best value improves by
+    ///   at most `tol_f` (default `1e-6`) relative to its magnitude over `stall_generations`
+    ///   generations (default `50`).
+    /// - `seed`: seed of the random number generator, making the search reproducible. Without it
+    ///   the generator is seeded from the one of `rng_seed`.
+    /// - `callback`: function called after every generation with an object map holding the
+    ///   `iteration`, the best point `x`, its value `fval`, and the number of `evaluations`. The
+    ///   search stops with status `"stopped"` if it returns `true`.
+    ///
+    /// The genetic algorithm also supports:
+    /// - `elite_count`: number of best individuals kept unchanged (default 5% of the population).
+    /// - `crossover_fraction`: fraction of the other children created by crossover
+    ///   (default `0.8`).
+    /// - `mutation_scale`: standard deviation of mutations relative to the box (default `1.0`).
+    /// - `mutation_shrink`: how much the mutations shrink by the last generation (default `1.0`).
+    ///
+    /// Each call of `f` or `callback` counts as one operation towards the engine's operation
+    /// limit.
+    /// ```typescript
+    /// let options = #{seed: 1, population_size: 20, generations: 30};
+    /// let first = ga(|x| (x - 1.0) ** 2, -10, 10, options);
+    /// let second = ga(|x| (x - 1.0) ** 2, -10, 10, options);
+    /// assert_eq(first.x, second.x);
+    /// assert_approx_eq(first.x, 1.0, 0.1);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "ga", return_raw)]
+    pub fn ga_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        global_minimize(&ctx, &f, (lb, ub), &options, Method::Genetic)
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with particle swarm optimization.
+    /// Returns an object map in the same form as `ga`.
+    /// ```typescript
+    /// // Ackley's function
+    /// let f = |x| -20.0 * exp(-0.2 * sqrt(0.5 * (x[0] ** 2 + x[1] ** 2)))
+    ///             - exp(0.5 * (cos(2.0 * pi * x[0]) + cos(2.0 * pi * x[1]))) + e + 20.0;
+    /// let result = particleswarm(f, [-5, -5], [5, 5], #{seed: 7});
+    /// assert_approx_eq(result.x, [0.0, 0.0], 1e-3);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "particleswarm", return_raw)]
+    pub fn particleswarm(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        particleswarm_with_options(ctx, f, lb, ub, Map::new())
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with particle swarm optimization.
+    /// Supports the options of `ga`, where `population_size` is the number of particles
+    /// (default `min(10 * n, 100)`), `generations` defaults to `200 * n` and
+    /// `stall_generations` to `20`, together with:
+    /// - `inertia`: weight of the previous velocity (default `0.7298`).
+    /// - `self_adjustment`: attraction to the best point of the particle (default `1.49618`).
+    /// - `social_adjustment`: attraction to the best point of the swarm (default `1.49618`).
+    /// ```typescript
+    /// let result = particleswarm(|x| (x[0] - 2.0) ** 2 + (x[1] + 1.0) ** 2, [-3, -3], [3, 3],
+    ///                            #{seed: 3, population_size: 15, tol_f: 1e-12});
+    /// assert(result.converged);
+    /// assert_approx_eq(result.x, [2.0, -1.0], 1e-4);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "particleswarm", return_raw)]
+    pub fn particleswarm_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        global_minimize(&ctx, &f, (lb, ub), &options, Method::ParticleSwarm)
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with simulated annealing. Returns an
+    /// object map in the same form as `ga`, except that the history holds the `temperature`
+    /// of every generation instead of the mean value.
+    /// ```typescript
+    /// let f = |x| x * sin(3.0 * x) + 0.2 * (x - 1.0) ** 2;
+    /// let result = simulannealbnd(f, -6, 6, #{seed: 0});
+    /// assert_approx_eq(result.x, 3.663, 0.01);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "simulannealbnd", return_raw)]
+    pub fn simulannealbnd(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        simulannealbnd_with_options(ctx, f, lb, ub, Map::new())
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with simulated annealing. Every
+    /// generation is a temperature level at which `population_size` random moves are tried
+    /// (default `10 * n`), and `generations` defaults to `200`. Supports the other options of
+    /// `ga`, together with:
+    /// - `x0`: starting point (by default drawn uniformly from the box).
+    /// - `initial_temperature`: temperature of the first generation (default `100`). Moves that
+    ///   increase `f` by `d` are accepted with probability `exp(-d / temperature)`.
+    /// - `cooling`: factor by which the temperature decreases every generation (default `0.95`).
+    ///   The size of the moves is proportional to the box and to the square root of the ratio
+    ///   of the temperature to the initial temperature.
+    /// ```typescript
+    /// let result = simulannealbnd(|x| abs(x[0] - 0.5) + abs(x[1] - 0.25), [0, 0], [1, 1],
+    ///                             #{seed: 5, x0: [1, 1], initial_temperature: 1.0});
+    /// assert_approx_eq(result.x, [0.5, 0.25], 1e-2);
+    /// assert_approx_eq(result.history.temperature[1], 0.95, 1e-12);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "simulannealbnd", return_raw)]
+    pub fn simulannealbnd_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        global_minimize(&ctx, &f, (lb, ub), &options, Method::Annealing)
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with differential evolution. Returns
+    /// an object map in the same form as `ga`.
+    /// ```typescript
+    /// // Rosenbrock's banana function
+    /// let f = |x| 100.0 * (x[1] - x[0] ** 2) ** 2 + (1.0 - x[0]) ** 2;
+    /// let result = differential_evolution(f, [-2, -2], [2, 2], #{seed: 9, tol_f: 1e-10});
+    /// assert_approx_eq(result.x, [1.0, 1.0], 1e-3);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "differential_evolution", return_raw)]
+    pub fn differential_evolution(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        differential_evolution_with_options(ctx, f, lb, ub, Map::new())
+    }
+
+    /// Minimizes the function `f` over the box `[lb, ub]` with differential evolution, using
+    /// the `rand/1/bin` strategy. Supports the options of `ga`, where `population_size`
+    /// defaults to `max(15 * n, 5)`, together with:
+    /// - `differential_weight`: scale of the difference vectors (default `0.8`).
+    /// - `crossover_probability`: probability of taking each component from the mutant
+    ///   (default `0.9`).
+    /// ```typescript
+    /// let result = differential_evolution(|x| (x[0] - 0.3) ** 2 + (x[1] - 0.7) ** 2, [0, 0], [1, 1],
+    ///                                     #{seed: 2, population_size: 10, generations: 40});
+    /// assert_eq(result.iterations, 40);
+    /// assert_eq(result.status, "max_iterations");
+    /// assert_approx_eq(result.x, [0.3, 0.7], 1e-2);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "differential_evolution", return_raw)]
+    pub fn differential_evolution_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        global_minimize(&ctx, &f, (lb, ub), &options, Method::DifferentialEvolution)
+    }
+
+    /// Returns the indices of the non-dominated rows of the matrix `F`, whose rows hold the
+    /// objective values of a set of points and whose objectives are all minimized. A row is
+    /// dominated if another row is no worse in every objective and better in at least one.
+    /// ```typescript
+    /// let F = [[1.0, 5.0],
+    ///          [2.0, 3.0],
+    ///          [3.0, 4.0],
+    ///          [4.0, 1.0],
+    ///          [2.0, 3.0]];
+    /// assert_eq(paretofront(F), [0, 1, 3, 4]);
+    /// ```
+    #[rhai_fn(name = "paretofront", return_raw)]
+    pub fn paretofront(f: Array) -> Result<Array, Box<EvalAltResult>> {
+        let points = objective_rows(f)?;
+        Ok(nondominated_sort(&points)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| Dynamic::from_int(i as rhai::INT))
+            .collect())
+    }
+
+    /// Returns the crowding distance of every row of the matrix `F` of objective values: the
+    /// sum over the objectives of the distance between the neighbouring rows, relative to the
+    /// range of the objective. The extreme rows of every objective have infinite distance.
+    /// ```typescript
+    /// let F = [[0.0, 4.0],
+    ///          [1.0, 2.0],
+    ///          [3.0, 1.0],
+    ///          [4.0, 0.0]];
+    /// assert_eq(crowding_distance(F), [inf, 1.5, 1.25, inf]);
+    /// ```
+    #[rhai_fn(name = "crowding_distance", return_raw)]
+    pub fn crowding_distance(f: Array) -> Result<Array, Box<EvalAltResult>> {
+        let points = objective_rows(f)?;
+        let rows: Vec<usize> = (0..points.len()).collect();
+        Ok(front_crowding(&points, &rows)
+            .into_iter()
+            .map(Dynamic::from_float)
+            .collect())
+    }
+
+    /// Returns the hypervolume dominated by the rows of the matrix `F` of objective values and
+    /// bounded by the `reference` point, for two or three minimized objectives. Rows that do
+    /// not dominate the reference point do not contribute.
+    /// ```typescript
+    /// let F = [[1.0, 3.0],
+    ///          [2.0, 2.0],
+    ///          [3.0, 1.0]];
+    /// assert_eq(hypervolume(F, [4.0, 4.0]), 6.0);
+    /// ```
+    /// ```typescript
+    /// let F = [[1.0, 2.0, 2.0],
+    ///          [2.0, 1.0, 1.0]];
+    /// assert_eq(hypervolume(F, [3.0, 3.0, 3.0]), 5.0);
+    /// ```
+    #[rhai_fn(name = "hypervolume", return_raw)]
+    pub fn hypervolume(f: Array, reference: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        let points = objective_rows(f)?;
+        front_hypervolume(&points, &dynamic_to_vec_float(reference)?)
+    }
+
+    /// Approximates the Pareto front of the objectives returned as an array by `f`, over the
+    /// box `[lb, ub]`, with the NSGA-II genetic algorithm. Returns an object map with the
+    /// points `x` of the final non-dominated front, ordered by the first objective, their
+    /// objective values `fval` (one row per point), the number of generations as
+    /// `iterations`, the number of function `evaluations`, the exit `status`
+    /// (`"max_iterations"` or `"stopped"`), and a `history` map holding the size of the front
+    /// in every generation as `front_size`.
+    /// ```typescript
+    /// // Schaffer's problem: the front is 0 <= x <= 2
+    /// let result = gamultiobj(|x| [x ** 2, (x - 2.0) ** 2], -5, 5, #{seed: 4, generations: 30});
+    /// assert(result.x.all(|x| x >= -1e-3 && x <= 2.001));
+    /// assert_eq(paretofront(result.fval).len(), result.fval.len());
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "gamultiobj", return_raw)]
+    pub fn gamultiobj(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        gamultiobj_with_options(ctx, f, lb, ub, Map::new())
+    }
+
+    /// Approximates the Pareto front of the objectives returned by `f` over the box `[lb, ub]`
+    /// with NSGA-II. Supports the `population_size` (default `50` for `n <= 5`, `200`
+    /// otherwise), `generations` (default `100 * n`), `seed` and `callback` options of `ga`,
+    /// where the callback receives the current front as `x` and `fval`, together with:
+    /// - `crossover_probability`: probability of crossing a pair of parents (default `0.9`).
+    /// - `crossover_index`: distribution index of the simulated binary crossover
+    ///   (default `20`).
+    /// - `mutation_probability`: probability of mutating each variable (default `1 / n`).
+    /// - `mutation_index`: distribution index of the polynomial mutation (default `20`).
+    /// - `reference`: reference point for two or three objectives. If given, the history also
+    ///   holds the `hypervolume` of the front in every generation.
+    /// ```typescript
+    /// // ZDT1 with three variables
+    /// let zdt1 = |x| {
+    ///     let g = 1.0 + 9.0 * (x[1] + x[2]) / 2.0;
+    ///     [x[0], g * (1.0 - sqrt(x[0] / g))]
+    /// };
+    /// let result = gamultiobj(zdt1, [0, 0, 0], [1, 1, 1],
+    ///                         #{seed: 8, population_size: 40, generations: 60, reference: [1.1, 1.1]});
+    /// let volumes = result.history.hypervolume;
+    /// assert(volumes[-1] > 0.8);
+    /// assert(volumes[-1] >= volumes[0]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "gamultiobj", return_raw)]
+    pub fn gamultiobj_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        lb: Dynamic,
+        ub: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        nsga2(&ctx, &f, (lb, ub), &options)
+    }
+}
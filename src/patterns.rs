@@ -1,5 +1,5 @@
 use crate::matrix::{RhaiMatrix, RhaiVector};
-use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT, INT};
+use rhai::{Array, Dynamic, EvalAltResult, Map, NativeCallContext, Position, FLOAT, INT};
 
 /// Matrix compatibility conditions
 #[allow(dead_code)]
@@ -154,6 +154,97 @@ where
     f(new_x)
 }
 
+/// Converts a number or a numeric list (including a row or column vector) into a `Vec<FLOAT>`.
+/// Unlike [`if_list_convert_to_vec_float_and_do`], INT and FLOAT elements may be mixed.
+pub fn dynamic_to_vec_float(value: Dynamic) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+    if value.is_int() || value.is_float() {
+        return if_int_convert_to_float_and_do(value, |x| Ok(vec![x]));
+    }
+    let mut arr = value
+        .into_array()
+        .map_err(|_| list_error("Input must be a number or a numeric list."))?;
+    if arr.is_empty() {
+        return Ok(vec![]);
+    }
+    if !crate::validation_functions::is_list(&mut arr) {
+        return Err(list_error(
+            "Input must be a 1-D array, row vector, or column vector.",
+        ));
+    }
+    crate::matrix_functions::flatten(&mut arr)
+        .into_iter()
+        .map(|el| if_int_convert_to_float_and_do(el, Ok))
+        .collect()
+}
+
+/// Reads a numeric entry from an options map, falling back to `default` if the key is absent.
+pub fn float_option(options: &Map, key: &str, default: FLOAT) -> Result<FLOAT, Box<EvalAltResult>> {
+    match options.get(key) {
+        Some(value) => if_int_convert_to_float_and_do(value.clone(), Ok).map_err(|_| {
+            EvalAltResult::ErrorArithmetic(
+                format!("The option '{key}' must either be INT or FLOAT"),
+                Position::NONE,
+            )
+            .into()
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Reads an integer entry from an options map, falling back to `default` if the key is absent.
+pub fn int_option(options: &Map, key: &str, default: INT) -> Result<INT, Box<EvalAltResult>> {
+    match options.get(key) {
+        Some(value) => value.as_int().map_err(|_| {
+            EvalAltResult::ErrorArithmetic(
+                format!("The option '{key}' must be an INT"),
+                Position::NONE,
+            )
+            .into()
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Counts the calls that a native function makes back into script code, so that iterative
+/// algorithms respect the engine's operation limit. Rhai does not carry the operations performed
+/// inside a function pointer call back to the caller, so each call is charged as one operation.
+/// The solvers keep one counter per problem and tick it before every call of a user function,
+/// so that a script cannot loop forever inside a native function when a limit is set.
+pub struct OperationCounter {
+    /// The engine's operation limit (0 for unlimited).
+    limit: u64,
+    /// Number of calls made so far.
+    count: std::cell::Cell<u64>,
+}
+
+impl OperationCounter {
+    /// Creates a counter for the engine of the given context.
+    pub fn new(ctx: &NativeCallContext) -> Self {
+        Self {
+            limit: ctx.engine().max_operations(),
+            count: std::cell::Cell::new(0),
+        }
+    }
+
+    /// Records a call, failing with [`EvalAltResult::ErrorTooManyOperations`] once the limit is
+    /// exceeded.
+    pub fn tick(&self) -> Result<(), Box<EvalAltResult>> {
+        let count = self.count.get() + 1;
+        self.count.set(count);
+        if self.limit > 0 && count > self.limit {
+            Err(EvalAltResult::ErrorTooManyOperations(Position::NONE).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Builds the arithmetic error with which the package reports invalid arguments and failed
+/// computations.
+pub fn arithmetic_error(message: impl Into<String>) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorArithmetic(message.into(), Position::NONE).into()
+}
+
 #[cfg(feature = "nalgebra")]
 pub fn if_matrix_do<T, F>(matrix: &mut Array, mut f: F) -> Result<T, Box<EvalAltResult>>
 where
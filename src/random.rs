@@ -0,0 +1,459 @@
+use rhai::plugin::*;
+
+#[cfg(feature = "rand")]
+thread_local! {
+    /// The random number generator shared by every package on the thread, seeded from the
+    /// operating system until `rng_seed` is called on the thread.
+    static RNG: std::cell::RefCell<randlib::rngs::StdRng> =
+        std::cell::RefCell::new(randlib::SeedableRng::from_os_rng());
+}
+
+/// Seeds the random number generator used by every random function of the package, so that
+/// scripts run afterwards on the current thread draw reproducible numbers. The generator is
+/// thread-local, so engines evaluating on other threads are not affected. This is the Rust
+/// counterpart of the `rng_seed` script function.
+/// ```
+/// use rhai::{packages::Package, Engine, FLOAT};
+/// use rhai_sci::SciPackage;
+/// let mut engine = Engine::new();
+/// engine.register_global_module(SciPackage::new().as_shared_module());
+/// rhai_sci::rng_seed(42);
+/// let first = engine.eval::<FLOAT>("rand()").unwrap();
+/// rhai_sci::rng_seed(42);
+/// assert_eq!(engine.eval::<FLOAT>("rand()").unwrap(), first);
+/// ```
+#[cfg(feature = "rand")]
+pub fn rng_seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = randlib::SeedableRng::seed_from_u64(seed));
+}
+
+/// Calls `f` with the random number generator of the package, for use by functions that draw
+/// random numbers. `f` must not evaluate script functions, which may draw random numbers
+/// themselves; draw a seed for a separate generator instead.
+#[cfg(feature = "rand")]
+pub fn with_rng<T>(f: impl FnOnce(&mut randlib::rngs::StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Reads the size of a random array: an integer `n` for an `n x n` matrix, `[n]` for a list
+/// or `[nx, ny]` for a matrix.
+#[cfg(feature = "rand")]
+fn random_shape(size: rhai::Dynamic) -> Result<Vec<usize>, Box<rhai::EvalAltResult>> {
+    let size = if size.is_int() {
+        let n = size.as_int().unwrap();
+        vec![n, n]
+    } else if size.is_array() {
+        size.into_array()
+            .unwrap()
+            .into_iter()
+            .map(|n| {
+                n.as_int()
+                    .map_err(|_| crate::arithmetic_error("Sizes must be integers"))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        return Err(crate::arithmetic_error(
+            "The size must be an integer or an array",
+        ));
+    };
+    if size.is_empty() || size.len() > 2 {
+        return Err(crate::arithmetic_error(
+            "The size must have one or two dimensions",
+        ));
+    }
+    if size.iter().any(|n| *n < 0) {
+        return Err(crate::arithmetic_error("Sizes must be non-negative"));
+    }
+    Ok(size.into_iter().map(|n| n as usize).collect())
+}
+
+/// Builds a list or matrix of the given shape from values drawn by `draw`.
+#[cfg(feature = "rand")]
+fn random_array(
+    shape: &[usize],
+    mut draw: impl FnMut(&mut randlib::rngs::StdRng) -> rhai::Dynamic,
+) -> rhai::Array {
+    with_rng(|rng| match shape {
+        [n] => (0..*n).map(|_| draw(rng)).collect(),
+        _ => (0..shape[0])
+            .map(|_| rhai::Dynamic::from_array((0..shape[1]).map(|_| draw(rng)).collect()))
+            .collect(),
+    })
+}
+
+/// Reads the range of `randi`: an integer `imax` for `1..=imax` or an array `[imin, imax]`.
+#[cfg(feature = "rand")]
+fn integer_range(
+    range: rhai::Dynamic,
+) -> Result<std::ops::RangeInclusive<rhai::INT>, Box<rhai::EvalAltResult>> {
+    let (low, high) = if range.is_int() {
+        (1, range.as_int().unwrap())
+    } else {
+        match range.into_array().as_deref() {
+            Ok([low, high]) if low.is_int() && high.is_int() => {
+                (low.as_int().unwrap(), high.as_int().unwrap())
+            }
+            _ => {
+                return Err(crate::arithmetic_error(
+                    "The range must be an integer or an array [imin, imax] of integers",
+                ))
+            }
+        }
+    };
+    if low > high {
+        return Err(crate::arithmetic_error(format!(
+            "The range [{low}, {high}] contains no integers"
+        )));
+    }
+    Ok(low..=high)
+}
+
+/// The distribution of `randn`.
+#[cfg(feature = "rand")]
+const STANDARD_NORMAL: crate::Distribution = crate::Distribution::Normal {
+    mu: 0.0,
+    sigma: 1.0,
+};
+
+/// A multivariate normal distribution, for `mvnrnd`.
+#[cfg(feature = "rand")]
+struct MultivariateNormal {
+    /// Mean.
+    mu: Vec<rhai::FLOAT>,
+    /// Lower Cholesky factor `L` of the covariance, `Sigma = L L^T`.
+    lower: Vec<Vec<rhai::FLOAT>>,
+}
+
+#[cfg(feature = "rand")]
+impl MultivariateNormal {
+    /// Reads the mean `mu` and covariance `sigma` and factors the covariance. Positive
+    /// semi-definite covariances are accepted, with zero columns in `L` for directions without
+    /// variance.
+    fn new(mu: rhai::Array, sigma: rhai::Array) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let mu = crate::dynamic_to_vec_float(rhai::Dynamic::from_array(mu))?;
+        let d = mu.len();
+        let sigma = sigma
+            .into_iter()
+            .map(crate::dynamic_to_vec_float)
+            .collect::<Result<Vec<_>, _>>()?;
+        if d == 0 || sigma.len() != d || sigma.iter().any(|row| row.len() != d) {
+            return Err(crate::arithmetic_error(format!(
+                "Sigma must be a {d} x {d} matrix, matching the length of mu"
+            )));
+        }
+        let scale = sigma
+            .iter()
+            .flatten()
+            .fold(0.0, |max: rhai::FLOAT, s| max.max(s.abs()));
+        let tol = 1e-10 * scale.max(rhai::FLOAT::MIN_POSITIVE);
+        let asymmetric = sigma.iter().enumerate().any(|(i, row)| {
+            row[..i]
+                .iter()
+                .zip(&sigma)
+                .any(|(s, other)| (s - other[i]).abs() > tol)
+        });
+        if asymmetric {
+            return Err(crate::arithmetic_error("Sigma must be symmetric"));
+        }
+        let mut lower = vec![vec![0.0; d]; d];
+        for j in 0..d {
+            let pivot = sigma[j][j] - lower[j][..j].iter().map(|l| l * l).sum::<rhai::FLOAT>();
+            if pivot < -tol {
+                return Err(crate::arithmetic_error(
+                    "Sigma must be positive semi-definite",
+                ));
+            }
+            if pivot <= tol {
+                continue;
+            }
+            let pivot = pivot.sqrt();
+            lower[j][j] = pivot;
+            for i in j + 1..d {
+                let dot: rhai::FLOAT = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+                lower[i][j] = (sigma[i][j] - dot) / pivot;
+            }
+        }
+        Ok(Self { mu, lower })
+    }
+
+    /// Draws a point `mu + L z`, where `z` holds standard normal numbers.
+    fn draw(&self, rng: &mut randlib::rngs::StdRng) -> rhai::Array {
+        let z: Vec<rhai::FLOAT> = self
+            .mu
+            .iter()
+            .map(|_| STANDARD_NORMAL.sample(rng))
+            .collect();
+        self.lower
+            .iter()
+            .zip(&self.mu)
+            .map(|(row, m)| {
+                rhai::Dynamic::from_float(
+                    m + row.iter().zip(&z).map(|(l, z)| l * z).sum::<rhai::FLOAT>(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[export_module]
+pub mod random_functions {
+    #[cfg(feature = "rand")]
+    use super::{integer_range, random_array, random_shape, MultivariateNormal, STANDARD_NORMAL};
+    #[cfg(feature = "rand")]
+    use crate::{arithmetic_error, with_rng};
+    #[cfg(feature = "rand")]
+    use randlib::{seq::SliceRandom, Rng};
+    #[cfg(feature = "rand")]
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
+
+    /// Seeds the random number generator used by every random function on the current thread,
+    /// so that the numbers drawn afterwards are reproducible.
+    /// ```typescript
+    /// rng_seed(42);
+    /// let a = [rand(), randn(), randi(6)];
+    /// rng_seed(42);
+    /// let b = [rand(), randn(), randi(6)];
+    /// assert_eq(a, b);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "rng_seed")]
+    pub fn rng_seed(seed: INT) {
+        crate::rng_seed(seed as u64);
+    }
+
+    /// Returns a random number drawn from the standard normal distribution.
+    /// ```typescript
+    /// let x = randn();
+    /// assert(x > -10.0 && x < 10.0);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randn")]
+    pub fn randn() -> FLOAT {
+        with_rng(|rng| STANDARD_NORMAL.sample(rng))
+    }
+
+    /// Returns an array of standard normal random numbers. The size is an integer `n` for an
+    /// `n x n` matrix, `[n]` for a list of `n` numbers or `[nx, ny]` for a matrix with `nx`
+    /// rows and `ny` columns.
+    /// ```typescript
+    /// rng_seed(1);
+    /// let x = randn([10000]);
+    /// assert_approx_eq(mean(x), 0.0, 0.05);
+    /// assert_approx_eq(std(x), 1.0, 0.05);
+    /// ```
+    /// ```typescript
+    /// assert_eq(size(randn(3)), [3, 3]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randn", return_raw)]
+    pub fn randn_array(size: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        let shape = random_shape(size)?;
+        Ok(random_array(&shape, |rng| {
+            Dynamic::from_float(STANDARD_NORMAL.sample(rng))
+        }))
+    }
+
+    /// Returns a matrix of standard normal random numbers with `nx` rows and `ny` columns.
+    /// ```typescript
+    /// assert_eq(size(randn(2, 4)), [2, 4]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randn", return_raw)]
+    pub fn randn_matrix(nx: INT, ny: INT) -> Result<Array, Box<EvalAltResult>> {
+        randn_array(Dynamic::from_array(vec![
+            Dynamic::from_int(nx),
+            Dynamic::from_int(ny),
+        ]))
+    }
+
+    /// Returns a uniformly distributed random integer. The range is an integer `imax` for the
+    /// integers from 1 to `imax`, as in MATLAB, or an array `[imin, imax]`.
+    /// ```typescript
+    /// let die = randi(6);
+    /// assert(die >= 1 && die <= 6);
+    /// ```
+    /// ```typescript
+    /// let x = randi([-2, 2]);
+    /// assert(x >= -2 && x <= 2);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randi", return_raw)]
+    pub fn randi(range: Dynamic) -> Result<INT, Box<EvalAltResult>> {
+        let range = integer_range(range)?;
+        Ok(with_rng(|rng| rng.random_range(range)))
+    }
+
+    /// Returns an array of uniformly distributed random integers in `range`, as for a single
+    /// integer. The size is given as for `randn`.
+    /// ```typescript
+    /// let x = randi([0, 1], [1000]);
+    /// assert_eq(x.len(), 1000);
+    /// assert(x.contains(0) && x.contains(1) && !x.contains(2));
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randi", return_raw)]
+    pub fn randi_array(range: Dynamic, size: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        let range = integer_range(range)?;
+        let shape = random_shape(size)?;
+        Ok(random_array(&shape, |rng| {
+            Dynamic::from_int(rng.random_range(range.clone()))
+        }))
+    }
+
+    /// Returns a matrix of uniformly distributed random integers in `range` with `nx` rows and
+    /// `ny` columns.
+    /// ```typescript
+    /// assert_eq(size(randi(10, 2, 3)), [2, 3]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randi", return_raw)]
+    pub fn randi_matrix(range: Dynamic, nx: INT, ny: INT) -> Result<Array, Box<EvalAltResult>> {
+        randi_array(
+            range,
+            Dynamic::from_array(vec![Dynamic::from_int(nx), Dynamic::from_int(ny)]),
+        )
+    }
+
+    /// Returns a random permutation of the indices `0` to `n - 1`.
+    /// ```typescript
+    /// let p = randperm(5);
+    /// p.sort();
+    /// assert_eq(p, [0, 1, 2, 3, 4]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randperm", return_raw)]
+    pub fn randperm(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        randperm_k(n, n)
+    }
+
+    /// Returns `k` distinct indices drawn at random from `0` to `n - 1`, in random order.
+    /// ```typescript
+    /// let p = randperm(10, 3);
+    /// assert_eq(p.len(), 3);
+    /// assert_eq(unique(p).len(), 3);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "randperm", return_raw)]
+    pub fn randperm_k(n: INT, k: INT) -> Result<Array, Box<EvalAltResult>> {
+        if n < 0 || k < 0 || k > n {
+            return Err(arithmetic_error(format!(
+                "Cannot draw {k} distinct indices from {n}"
+            )));
+        }
+        let indices = with_rng(|rng| randlib::seq::index::sample(rng, n as usize, k as usize));
+        Ok(indices
+            .into_iter()
+            .map(|i| Dynamic::from_int(i as INT))
+            .collect())
+    }
+
+    /// Returns a copy of an array with its elements in random order.
+    /// ```typescript
+    /// let x = shuffle([1, 2, 3, 4, 5]);
+    /// x.sort();
+    /// assert_eq(x, [1, 2, 3, 4, 5]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "shuffle", pure)]
+    pub fn shuffle(arr: &mut Array) -> Array {
+        let mut shuffled = arr.clone();
+        with_rng(|rng| shuffled.shuffle(rng));
+        shuffled
+    }
+
+    /// Returns `k` elements drawn at random from an array, without replacement.
+    /// ```typescript
+    /// let x = sample(["a", "b", "c", "d"], 2);
+    /// assert_eq(x.len(), 2);
+    /// assert(x[0] != x[1]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "sample", return_raw, pure)]
+    pub fn sample_without_replacement(
+        arr: &mut Array,
+        k: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        sample(arr, k, false)
+    }
+
+    /// Returns `k` elements drawn at random from an array, with replacement if `replace` is
+    /// true and without replacement otherwise.
+    /// ```typescript
+    /// let x = sample([1, 2, 3], 100, true);
+    /// assert_eq(x.len(), 100);
+    /// assert_eq(unique(x), [1, 2, 3]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "sample", return_raw, pure)]
+    pub fn sample(arr: &mut Array, k: INT, replace: bool) -> Result<Array, Box<EvalAltResult>> {
+        let n = arr.len();
+        if k < 0 {
+            return Err(arithmetic_error(
+                "The number of samples must be non-negative",
+            ));
+        }
+        let k = k as usize;
+        if replace {
+            if n == 0 && k > 0 {
+                return Err(arithmetic_error("Cannot sample from an empty array"));
+            }
+            Ok(with_rng(|rng| {
+                (0..k)
+                    .map(|_| arr[rng.random_range(0..n)].clone())
+                    .collect()
+            }))
+        } else if k > n {
+            Err(arithmetic_error(format!(
+                "Cannot sample {k} elements without replacement from an array of {n}"
+            )))
+        } else {
+            let indices = with_rng(|rng| randlib::seq::index::sample(rng, n, k));
+            Ok(indices.into_iter().map(|i| arr[i].clone()).collect())
+        }
+    }
+
+    /// Returns a random point drawn from the multivariate normal distribution with mean `mu`
+    /// and covariance matrix `Sigma`, which must be symmetric and positive semi-definite. The
+    /// point is `mu + L z`, where `L` is the Cholesky factor of `Sigma` and `z` holds standard
+    /// normal numbers.
+    /// ```typescript
+    /// let x = mvnrnd([1.0, -1.0], [[1.0, 0.0], [0.0, 0.0]]);
+    /// assert_eq(x.len(), 2);
+    /// assert_eq(x[1], -1.0);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "mvnrnd", return_raw)]
+    pub fn mvnrnd_single(mu: Array, sigma: Array) -> Result<Array, Box<EvalAltResult>> {
+        let distribution = MultivariateNormal::new(mu, sigma)?;
+        Ok(with_rng(|rng| distribution.draw(rng)))
+    }
+
+    /// Returns `n` random points drawn from the multivariate normal distribution with mean `mu`
+    /// and covariance matrix `Sigma`, as a matrix with one point per row.
+    /// ```typescript
+    /// rng_seed(3);
+    /// let x = mvnrnd([1.0, 2.0], [[4.0, 1.8], [1.8, 1.0]], 20000);
+    /// assert_eq(size(x), [20000, 2]);
+    /// let columns = transpose(x);
+    /// assert_approx_eq(mean(columns[0]), 1.0, 0.05);
+    /// assert_approx_eq(variance(columns[0]), 4.0, 0.2);
+    /// // The variance of x - 1.8 y is 4 - 2 * 1.8 * 1.8 + 1.8^2 = 0.76
+    /// let residual = x.map(|point| point[0] - 1.8 * point[1]);
+    /// assert_approx_eq(variance(residual), 0.76, 0.05);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "mvnrnd", return_raw)]
+    pub fn mvnrnd(mu: Array, sigma: Array, n: INT) -> Result<Array, Box<EvalAltResult>> {
+        let distribution = MultivariateNormal::new(mu, sigma)?;
+        if n < 0 {
+            return Err(arithmetic_error(
+                "The number of points must be non-negative",
+            ));
+        }
+        Ok(with_rng(|rng| {
+            (0..n)
+                .map(|_| Dynamic::from_array(distribution.draw(rng)))
+                .collect()
+        }))
+    }
+}
@@ -0,0 +1,835 @@
+use rhai::plugin::*;
+
+/// Scalar root-finding algorithms shared by `fzero`, `bisect` and `newton`.
+mod root_finding {
+    use crate::{
+        arithmetic_error, float_option, if_int_convert_to_float_and_do, int_option,
+        OperationCounter,
+    };
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// A real function of one variable, implemented by a Rhai function pointer.
+    pub(super) struct Scalar<'a> {
+        /// Context used to call back into the script.
+        pub(super) ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `f(x)`.
+        pub(super) f: &'a FnPtr,
+        /// Counter of the calls to `f`, shared with the caller.
+        pub(super) counter: &'a OperationCounter,
+    }
+
+    impl Scalar<'_> {
+        /// Evaluates `f(x)`. Errors raised by the script, including exceeding the engine's
+        /// operation limit, are propagated.
+        pub(super) fn eval(&self, x: FLOAT) -> Result<FLOAT, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            let value = self.f.call_raw(self.ctx, None, [Dynamic::from_float(x)])?;
+            if_int_convert_to_float_and_do(value, Ok)
+                .map_err(|_| arithmetic_error("The function must return an INT or FLOAT"))
+        }
+    }
+
+    /// Stopping criteria of the root finders.
+    pub(super) struct Settings {
+        /// Absolute tolerance on the root.
+        pub(super) tol: FLOAT,
+        /// Maximum number of iterations.
+        pub(super) max_iterations: INT,
+    }
+
+    impl Settings {
+        /// Reads the `tol` and `max_iterations` options.
+        pub(super) fn from_options(options: &Map) -> Result<Self, Box<EvalAltResult>> {
+            let tol = float_option(options, "tol", 1e-12)?;
+            let max_iterations = int_option(options, "max_iterations", 100)?;
+            if tol < 0.0 || max_iterations < 1 {
+                return Err(arithmetic_error(
+                    "The option 'tol' must be non-negative and 'max_iterations' positive",
+                ));
+            }
+            Ok(Self {
+                tol,
+                max_iterations,
+            })
+        }
+    }
+
+    /// Outcome of a root finder.
+    pub(super) struct Root {
+        /// Approximate root.
+        pub(super) x: FLOAT,
+        /// Function value at the root.
+        pub(super) fx: FLOAT,
+        /// Number of iterations performed.
+        pub(super) iterations: INT,
+        /// Whether the stopping tolerance was met.
+        pub(super) converged: bool,
+    }
+
+    impl Root {
+        /// Returns the result as an object map with entries `root`, `fval`, `iterations` and
+        /// `converged`.
+        pub(super) fn into_map(self) -> Map {
+            let mut result = Map::new();
+            result.insert("root".into(), Dynamic::from_float(self.x));
+            result.insert("fval".into(), Dynamic::from_float(self.fx));
+            result.insert("iterations".into(), Dynamic::from_int(self.iterations));
+            result.insert("converged".into(), Dynamic::from_bool(self.converged));
+            result
+        }
+    }
+
+    /// Searches outwards from `x0` for an interval over which `f` changes sign, growing the
+    /// search radius geometrically. Returns the interval and its function values.
+    pub(super) fn expand_bracket(
+        f: &Scalar,
+        x0: FLOAT,
+    ) -> Result<(FLOAT, FLOAT, FLOAT, FLOAT), Box<EvalAltResult>> {
+        let f0 = f.eval(x0)?;
+        if f0 == 0.0 {
+            return Ok((x0, x0, f0, f0));
+        }
+        let mut dx = if x0 == 0.0 {
+            1.0 / 50.0
+        } else {
+            x0.abs() / 50.0
+        };
+        while dx.is_finite() && dx < 1e300 {
+            let (a, b) = (x0 - dx, x0 + dx);
+            let (fa, fb) = (f.eval(a)?, f.eval(b)?);
+            if fa.is_finite() && (fa == 0.0 || fa.signum() != f0.signum()) {
+                return Ok((a, x0, fa, f0));
+            }
+            if fb.is_finite() && (fb == 0.0 || fb.signum() != f0.signum()) {
+                return Ok((x0, b, f0, fb));
+            }
+            dx *= FLOAT::sqrt(2.0);
+        }
+        Err(arithmetic_error(format!(
+            "Unable to find an interval around {x0} over which the function changes sign"
+        )))
+    }
+
+    /// Checks that the function changes sign over `[a, b]`.
+    pub(super) fn check_bracket(fa: FLOAT, fb: FLOAT) -> Result<(), Box<EvalAltResult>> {
+        if fa.is_nan() || fb.is_nan() || (fa.signum() == fb.signum() && fa != 0.0 && fb != 0.0) {
+            Err(arithmetic_error(
+                "The function values at the ends of the interval must differ in sign",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Brent's method on a bracketing interval, combining bisection, the secant method and
+    /// inverse quadratic interpolation.
+    pub(super) fn brent(
+        f: &Scalar,
+        (mut a, mut b): (FLOAT, FLOAT),
+        (mut fa, mut fb): (FLOAT, FLOAT),
+        settings: &Settings,
+    ) -> Result<Root, Box<EvalAltResult>> {
+        check_bracket(fa, fb)?;
+        let (mut c, mut fc) = (b, fb);
+        let (mut d, mut e) = (b - a, b - a);
+        for iteration in 0..=settings.max_iterations {
+            if fb.signum() == fc.signum() && fb != 0.0 {
+                (c, fc) = (a, fa);
+                d = b - a;
+                e = d;
+            }
+            if fc.abs() < fb.abs() {
+                (a, fa) = (b, fb);
+                (b, fb) = (c, fc);
+                (c, fc) = (a, fa);
+            }
+            let tol = 2.0 * FLOAT::EPSILON * b.abs() + 0.5 * settings.tol;
+            let m = 0.5 * (c - b);
+            if m.abs() <= tol || fb == 0.0 {
+                return Ok(Root {
+                    x: b,
+                    fx: fb,
+                    iterations: iteration,
+                    converged: true,
+                });
+            }
+            if iteration == settings.max_iterations {
+                break;
+            }
+            if e.abs() >= tol && fa.abs() > fb.abs() {
+                let s = fb / fa;
+                let (mut p, mut q) = if a == c {
+                    (2.0 * m * s, 1.0 - s)
+                } else {
+                    let q = fa / fc;
+                    let r = fb / fc;
+                    (
+                        s * (2.0 * m * q * (q - r) - (b - a) * (r - 1.0)),
+                        (q - 1.0) * (r - 1.0) * (s - 1.0),
+                    )
+                };
+                if p > 0.0 {
+                    q = -q;
+                } else {
+                    p = -p;
+                }
+                if 2.0 * p < (3.0 * m * q - (tol * q).abs()).min((e * q).abs()) {
+                    e = d;
+                    d = p / q;
+                } else {
+                    d = m;
+                    e = m;
+                }
+            } else {
+                d = m;
+                e = m;
+            }
+            (a, fa) = (b, fb);
+            b += if d.abs() > tol { d } else { tol.copysign(m) };
+            fb = f.eval(b)?;
+        }
+        Ok(Root {
+            x: b,
+            fx: fb,
+            iterations: settings.max_iterations,
+            converged: false,
+        })
+    }
+
+    /// Bisection on a bracketing interval.
+    pub(super) fn bisection(
+        f: &Scalar,
+        (mut a, mut b): (FLOAT, FLOAT),
+        settings: &Settings,
+    ) -> Result<Root, Box<EvalAltResult>> {
+        let (mut fa, fb) = (f.eval(a)?, f.eval(b)?);
+        check_bracket(fa, fb)?;
+        if fa == 0.0 || fb == 0.0 {
+            let (x, fx) = if fa == 0.0 { (a, fa) } else { (b, fb) };
+            return Ok(Root {
+                x,
+                fx,
+                iterations: 0,
+                converged: true,
+            });
+        }
+        for iteration in 1..=settings.max_iterations {
+            let mid = 0.5 * (a + b);
+            let fm = f.eval(mid)?;
+            let width = 0.5 * (b - a).abs();
+            if fm == 0.0 || width <= settings.tol.max(2.0 * FLOAT::EPSILON * mid.abs()) {
+                return Ok(Root {
+                    x: mid,
+                    fx: fm,
+                    iterations: iteration,
+                    converged: true,
+                });
+            }
+            if fm.signum() == fa.signum() {
+                (a, fa) = (mid, fm);
+            } else {
+                b = mid;
+            }
+        }
+        let x = 0.5 * (a + b);
+        Ok(Root {
+            x,
+            fx: f.eval(x)?,
+            iterations: settings.max_iterations,
+            converged: false,
+        })
+    }
+
+    /// Newton's method from `x0`, using `df` for the derivative or central differences if it is
+    /// not given. Stops without converging if the derivative vanishes.
+    pub(super) fn newton_raphson(
+        f: &Scalar,
+        df: Option<&Scalar>,
+        x0: FLOAT,
+        settings: &Settings,
+    ) -> Result<Root, Box<EvalAltResult>> {
+        let mut x = x0;
+        let mut fx = f.eval(x)?;
+        for iteration in 0..settings.max_iterations {
+            if fx == 0.0 {
+                return Ok(Root {
+                    x,
+                    fx,
+                    iterations: iteration,
+                    converged: true,
+                });
+            }
+            let slope = match df {
+                Some(df) => df.eval(x)?,
+                None => {
+                    let h = FLOAT::EPSILON.cbrt() * x.abs().max(1.0);
+                    (f.eval(x + h)? - f.eval(x - h)?) / (2.0 * h)
+                }
+            };
+            if slope == 0.0 || !slope.is_finite() {
+                break;
+            }
+            let step = fx / slope;
+            x -= step;
+            fx = f.eval(x)?;
+            if !x.is_finite() || !fx.is_finite() {
+                break;
+            }
+            if step.abs() <= settings.tol.max(4.0 * FLOAT::EPSILON * x.abs()) {
+                return Ok(Root {
+                    x,
+                    fx,
+                    iterations: iteration + 1,
+                    converged: true,
+                });
+            }
+        }
+        Ok(Root {
+            x,
+            fx,
+            iterations: settings.max_iterations,
+            converged: false,
+        })
+    }
+}
+
+/// Trust-region dogleg solver for systems of nonlinear equations.
+#[cfg(feature = "nalgebra")]
+mod nonlinear_systems {
+    use crate::matrix::RhaiMatrix;
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, float_option, int_option, OperationCounter,
+    };
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// A system of equations `F(x) = 0`, with an optional Jacobian, implemented by Rhai function
+    /// pointers.
+    pub(super) struct System<'a> {
+        /// Context used to call back into the script.
+        pub(super) ctx: &'a NativeCallContext<'a>,
+        /// Function pointer for `F(x)`.
+        pub(super) f: &'a FnPtr,
+        /// Function pointer for the Jacobian `J(x)`, if given.
+        pub(super) jacobian: Option<FnPtr>,
+        /// Whether the unknown is a scalar rather than an array.
+        pub(super) scalar: bool,
+        /// Counter of the calls to `F` and its Jacobian.
+        pub(super) counter: OperationCounter,
+    }
+
+    impl System<'_> {
+        /// Converts the unknowns into the form expected by the script.
+        fn argument(&self, x: &DVector<FLOAT>) -> Dynamic {
+            if self.scalar {
+                Dynamic::from_float(x[0])
+            } else {
+                Dynamic::from_array(x.iter().map(|v| Dynamic::from_float(*v)).collect())
+            }
+        }
+
+        /// Evaluates `F(x)`, checking that there is one equation per unknown.
+        fn eval(&self, x: &DVector<FLOAT>) -> Result<DVector<FLOAT>, Box<EvalAltResult>> {
+            self.counter.tick()?;
+            let value = self.f.call_raw(self.ctx, None, [self.argument(x)])?;
+            let fx = dynamic_to_vec_float(value).map_err(|_| {
+                arithmetic_error("The function must return a number or a numeric array")
+            })?;
+            if fx.len() != x.len() {
+                return Err(arithmetic_error(format!(
+                    "The function returned {} values but there are {} unknowns",
+                    fx.len(),
+                    x.len()
+                )));
+            }
+            Ok(DVector::from_vec(fx))
+        }
+
+        /// Evaluates the Jacobian at `x`, where `fx = F(x)`, from the user's function or by
+        /// forward differences.
+        fn jacobian(
+            &self,
+            x: &DVector<FLOAT>,
+            fx: &DVector<FLOAT>,
+        ) -> Result<DMatrix<FLOAT>, Box<EvalAltResult>> {
+            let n = x.len();
+            match &self.jacobian {
+                Some(jacobian) => {
+                    self.counter.tick()?;
+                    let value = jacobian.call_raw(self.ctx, None, [self.argument(x)])?;
+                    let jac = if value.is_int() || value.is_float() {
+                        DMatrix::from_element(1, 1, dynamic_to_vec_float(value)?[0])
+                    } else {
+                        let rows = value.into_array().map_err(|_| {
+                            arithmetic_error("The Jacobian function must return a matrix")
+                        })?;
+                        RhaiMatrix::from_array(rows).to_dmatrix()?
+                    };
+                    if jac.shape() != (n, n) {
+                        return Err(arithmetic_error(format!(
+                            "The Jacobian function must return a {n} x {n} matrix"
+                        )));
+                    }
+                    Ok(jac)
+                }
+                None => {
+                    let mut jac = DMatrix::zeros(n, n);
+                    let mut xp = x.clone();
+                    for j in 0..n {
+                        let delta = FLOAT::EPSILON.sqrt() * x[j].abs().max(1.0);
+                        xp[j] = x[j] + delta;
+                        let fp = self.eval(&xp)?;
+                        xp[j] = x[j];
+                        jac.set_column(j, &((fp - fx) / delta));
+                    }
+                    Ok(jac)
+                }
+            }
+        }
+    }
+
+    /// Maximum number of trial steps per iteration, each of which shrinks the trust region
+    /// fourfold.
+    const MAX_TRIALS: usize = 60;
+
+    /// Computes Powell's dogleg step within a trust region of radius `radius`, combining the
+    /// Gauss-Newton step with the Cauchy (steepest descent) step.
+    fn dogleg(jac: &DMatrix<FLOAT>, fx: &DVector<FLOAT>, radius: FLOAT) -> DVector<FLOAT> {
+        let gradient = jac.transpose() * fx;
+        let gauss_newton = jac
+            .clone()
+            .lu()
+            .solve(&-fx)
+            .filter(|step| step.iter().all(|v| v.is_finite()))
+            .or_else(|| {
+                jac.clone()
+                    .svd(true, true)
+                    .solve(&-fx, FLOAT::EPSILON.sqrt())
+                    .ok()
+            });
+        if let Some(step) = &gauss_newton {
+            if step.norm() <= radius {
+                return step.clone();
+            }
+        }
+        let jg = jac * &gradient;
+        let g_norm = gradient.norm();
+        if g_norm == 0.0 || jg.norm() == 0.0 {
+            return gauss_newton.unwrap_or_else(|| DVector::zeros(fx.len()));
+        }
+        let cauchy = -&gradient * (g_norm.powi(2) / jg.norm_squared());
+        let cauchy_norm = cauchy.norm();
+        match gauss_newton {
+            Some(step) if cauchy_norm < radius => {
+                // Find tau in [0, 1] with |cauchy + tau (step - cauchy)| = radius
+                let d = step - &cauchy;
+                let a = d.norm_squared();
+                let b = 2.0 * cauchy.dot(&d);
+                let c = cauchy_norm.powi(2) - radius.powi(2);
+                let tau = (-b + (b * b - 4.0 * a * c).max(0.0).sqrt()) / (2.0 * a);
+                cauchy + d * tau
+            }
+            _ => -&gradient * (radius / g_norm),
+        }
+    }
+
+    /// Solves `F(x) = 0` with the trust-region dogleg method, returning the result map of
+    /// `fsolve`.
+    pub(super) fn trust_region_dogleg(
+        system: &System,
+        x0: Vec<FLOAT>,
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let tol = float_option(options, "tol", 1e-10)?;
+        let step_tol = float_option(options, "step_tol", 1e-12)?;
+        let max_iterations = int_option(options, "max_iterations", 400)?;
+        if tol < 0.0 || step_tol < 0.0 || max_iterations < 0 {
+            return Err(arithmetic_error(
+                "The options 'tol', 'step_tol' and 'max_iterations' must be non-negative",
+            ));
+        }
+
+        let finite = |v: &DVector<FLOAT>| v.iter().all(|v| v.is_finite());
+        let mut x = DVector::from_vec(x0);
+        let mut fx = system.eval(&x)?;
+        if !finite(&x) || !finite(&fx) {
+            return Err(arithmetic_error(
+                "The function is not finite at the starting point",
+            ));
+        }
+        let mut radius = x.norm().max(1.0);
+        let mut iterations: INT = 0;
+        let status = loop {
+            if fx.norm() <= tol {
+                break "converged";
+            }
+            if iterations >= max_iterations {
+                break "max_iterations";
+            }
+            iterations += 1;
+            let jac = system.jacobian(&x, &fx)?;
+            if jac.iter().any(|v| !v.is_finite()) {
+                return Err(arithmetic_error(format!(
+                    "The Jacobian is not finite at {:?}",
+                    x.as_slice()
+                )));
+            }
+            let cost = 0.5 * fx.norm_squared();
+            let mut trials = 0;
+            let step = loop {
+                let step = dogleg(&jac, &fx, radius);
+                let step_norm = step.norm();
+                if !step_norm.is_finite()
+                    || !radius.is_finite()
+                    || step_norm <= step_tol * (step_tol + x.norm())
+                    || trials == MAX_TRIALS
+                {
+                    break None;
+                }
+                trials += 1;
+                let x_new = &x + &step;
+                let f_new = system.eval(&x_new)?;
+                if !finite(&f_new) {
+                    // The trial point lies outside the domain of `F`
+                    radius = 0.25 * step_norm;
+                    continue;
+                }
+                let predicted = cost - 0.5 * (&fx + &jac * &step).norm_squared();
+                let actual = cost - 0.5 * f_new.norm_squared();
+                let ratio = if predicted > 0.0 {
+                    actual / predicted
+                } else {
+                    -1.0
+                };
+                if !ratio.is_finite() || ratio < 0.25 {
+                    radius = 0.25 * step_norm;
+                } else if ratio > 0.75 && step_norm >= 0.99 * radius {
+                    radius *= 2.0;
+                }
+                if ratio.is_finite() && ratio > 1e-4 {
+                    break Some((x_new, f_new));
+                }
+            };
+            match step {
+                Some((x_new, f_new)) => {
+                    x = x_new;
+                    fx = f_new;
+                }
+                None => break "step_tolerance",
+            }
+        };
+
+        let floats = |v: &DVector<FLOAT>| {
+            if system.scalar {
+                Dynamic::from_float(v[0])
+            } else {
+                Dynamic::from_array(v.iter().map(|v| Dynamic::from_float(*v)).collect())
+            }
+        };
+        let mut result = Map::new();
+        result.insert("x".into(), floats(&x));
+        result.insert("fval".into(), floats(&fx));
+        result.insert("residual_norm".into(), Dynamic::from_float(fx.norm()));
+        result.insert("iterations".into(), Dynamic::from_int(iterations));
+        result.insert("status".into(), status.into());
+        result.insert(
+            "converged".into(),
+            Dynamic::from_bool(status == "converged"),
+        );
+        Ok(result)
+    }
+}
+
+#[export_module]
+pub mod roots_functions {
+    #[cfg(feature = "nalgebra")]
+    use super::nonlinear_systems::{trust_region_dogleg, System};
+    use super::root_finding::{bisection, brent, expand_bracket, newton_raphson, Scalar, Settings};
+    use crate::{
+        arithmetic_error, dynamic_to_vec_float, if_int_convert_to_float_and_do, OperationCounter,
+    };
+    use rhai::{Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext};
+
+    /// Finds a root of the function `f` using Brent's method, which combines bisection with
+    /// secant and inverse quadratic interpolation steps. `x0` is either a starting point, from
+    /// which an interval containing a sign change is searched for, or an array `[a, b]` with
+    /// `f(a)` and `f(b)` of opposite sign. Returns an object map with the `root`, the function
+    /// value `fval` there, the number of `iterations`, and whether the iteration `converged`.
+    /// Each call of `f` counts as one operation towards the engine's operation limit.
+    /// ```typescript
+    /// let result = fzero(|x| x * x - 2.0, [0.0, 2.0]);
+    /// assert_approx_eq(result.root, sqrt(2.0), 1e-12);
+    /// assert(result.converged);
+    /// ```
+    /// ```typescript
+    /// let result = fzero(|x| cos(x) - x, 1);
+    /// assert_approx_eq(result.root, 0.7390851332151607, 1e-12);
+    /// ```
+    #[rhai_fn(name = "fzero", return_raw)]
+    pub fn fzero(ctx: NativeCallContext, f: FnPtr, x0: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        fzero_with_options(ctx, f, x0, Map::new())
+    }
+
+    /// Finds a root of the function `f` using Brent's method, starting from a point or an
+    /// interval `[a, b]`. The following options are supported:
+    /// - `tol`: absolute tolerance on the root (default `1e-12`).
+    /// - `max_iterations`: maximum number of iterations (default `100`).
+    /// ```typescript
+    /// let result = fzero(|x| x ** 3 - 8, 10.0, #{tol: 1e-6});
+    /// assert_approx_eq(result.root, 2.0, 1e-6);
+    /// ```
+    /// ```typescript
+    /// let result = fzero(|x| exp(x) - 5.0, [0, 10], #{max_iterations: 2});
+    /// assert(!result.converged);
+    /// assert_eq(result.iterations, 2);
+    /// ```
+    #[rhai_fn(name = "fzero", return_raw)]
+    pub fn fzero_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let counter = OperationCounter::new(&ctx);
+        let f = Scalar {
+            ctx: &ctx,
+            f: &f,
+            counter: &counter,
+        };
+        let start = dynamic_to_vec_float(x0)?;
+        let (a, b, fa, fb) = match start[..] {
+            [x0] => expand_bracket(&f, x0)?,
+            [a, b] => (a, b, f.eval(a)?, f.eval(b)?),
+            _ => {
+                return Err(arithmetic_error(
+                    "The starting point must be a number or an interval [a, b]",
+                ))
+            }
+        };
+        Ok(brent(&f, (a, b), (fa, fb), &settings)?.into_map())
+    }
+
+    /// Finds a root of the function `f` in the interval `[a, b]` by bisection. `f(a)` and `f(b)`
+    /// must have opposite signs. Returns an object map with the `root`, the function value
+    /// `fval` there, the number of `iterations`, and whether the iteration `converged`.
+    /// ```typescript
+    /// let result = bisect(|x| x * x - 2.0, 0, 2);
+    /// assert_approx_eq(result.root, sqrt(2.0), 1e-12);
+    /// ```
+    #[rhai_fn(name = "bisect", return_raw)]
+    pub fn bisect(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        bisect_with_options(ctx, f, a, b, Map::new())
+    }
+
+    /// Finds a root of the function `f` in the interval `[a, b]` by bisection, with the `tol` and
+    /// `max_iterations` options of `fzero`.
+    /// ```typescript
+    /// let result = bisect(|x| sin(x), 3.0, 4.0, #{tol: 1e-4});
+    /// assert_approx_eq(result.root, pi, 1e-4);
+    /// assert(result.iterations < 20);
+    /// ```
+    #[rhai_fn(name = "bisect", return_raw)]
+    pub fn bisect_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        a: Dynamic,
+        b: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let a = if_int_convert_to_float_and_do(a, Ok)?;
+        let b = if_int_convert_to_float_and_do(b, Ok)?;
+        let counter = OperationCounter::new(&ctx);
+        let f = Scalar {
+            ctx: &ctx,
+            f: &f,
+            counter: &counter,
+        };
+        Ok(bisection(&f, (a, b), &settings)?.into_map())
+    }
+
+    /// Finds a root of the function `f` with Newton's method starting from `x0`, approximating
+    /// the derivative by central differences. Returns an object map with the `root`, the
+    /// function value `fval` there, the number of `iterations`, and whether the iteration
+    /// `converged`.
+    /// ```typescript
+    /// let result = newton(|x| x * x - 2.0, 1.0);
+    /// assert_approx_eq(result.root, sqrt(2.0), 1e-12);
+    /// ```
+    #[rhai_fn(name = "newton", return_raw)]
+    pub fn newton(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        newton_with_options(ctx, f, x0, Map::new())
+    }
+
+    /// Finds a root of the function `f` with Newton's method starting from `x0`, using the
+    /// derivative `df`.
+    /// ```typescript
+    /// let result = newton(|x| x * x - 2.0, 1.0, |x| 2.0 * x);
+    /// assert_approx_eq(result.root, sqrt(2.0), 1e-12);
+    /// assert(result.iterations <= 6);
+    /// ```
+    #[rhai_fn(name = "newton", return_raw)]
+    pub fn newton_with_derivative(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        df: FnPtr,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        newton_with_derivative_and_options(ctx, f, x0, df, Map::new())
+    }
+
+    /// Finds a root of the function `f` with Newton's method starting from `x0`, with the `tol`
+    /// and `max_iterations` options of `fzero`. The iteration stops without converging if the
+    /// derivative vanishes.
+    /// ```typescript
+    /// let result = newton(|x| x * x + 1.0, 0.0, #{max_iterations: 10});
+    /// assert(!result.converged);
+    /// ```
+    #[rhai_fn(name = "newton", return_raw)]
+    pub fn newton_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let x0 = if_int_convert_to_float_and_do(x0, Ok)?;
+        let counter = OperationCounter::new(&ctx);
+        let f = Scalar {
+            ctx: &ctx,
+            f: &f,
+            counter: &counter,
+        };
+        Ok(newton_raphson(&f, None, x0, &settings)?.into_map())
+    }
+
+    /// Finds a root of the function `f` with Newton's method starting from `x0`, using the
+    /// derivative `df` and the `tol` and `max_iterations` options of `fzero`.
+    /// ```typescript
+    /// let result = newton(|x| exp(x) - 3.0, 0, |x| exp(x), #{tol: 1e-14});
+    /// assert_approx_eq(result.root, ln(3.0), 1e-14);
+    /// ```
+    #[rhai_fn(name = "newton", return_raw)]
+    pub fn newton_with_derivative_and_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        df: FnPtr,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let x0 = if_int_convert_to_float_and_do(x0, Ok)?;
+        let counter = OperationCounter::new(&ctx);
+        let df = Scalar {
+            ctx: &ctx,
+            f: &df,
+            counter: &counter,
+        };
+        let f = Scalar {
+            ctx: &ctx,
+            f: &f,
+            counter: &counter,
+        };
+        Ok(newton_raphson(&f, Some(&df), x0, &settings)?.into_map())
+    }
+
+    /// Solves the system of nonlinear equations `F(x) = 0` with the trust-region dogleg method,
+    /// starting from `x0`. `F` takes an array of unknowns (or a number, if `x0` is a number) and
+    /// returns an array with one residual per unknown. The Jacobian is approximated by forward
+    /// differences.
+    ///
+    /// Returns an object map with the solution `x`, the residuals `fval` there, the Euclidean
+    /// `residual_norm`, the number of `iterations`, and the exit `status`: `"converged"` if the
+    /// residual norm is below the tolerance, `"step_tolerance"` if the steps became too small
+    /// before that (which typically indicates a local minimum of the residual norm), or
+    /// `"max_iterations"`. The `converged` entry is `true` for the first status only. Trial points
+    /// where `F` is not finite are rejected like any other unsuccessful step, but it is an error
+    /// for `F` to be not finite at `x0`, or for the Jacobian to be not finite.
+    /// ```typescript
+    /// // Intersection of the unit circle with the line y = x
+    /// let sol = fsolve(|v| [v[0] ** 2 + v[1] ** 2 - 1.0, v[0] - v[1]], [1.0, 0.0]);
+    /// assert(sol.converged);
+    /// assert_approx_eq(sol.x, [sqrt(0.5), sqrt(0.5)], 1e-10);
+    /// ```
+    /// ```typescript
+    /// // The first Newton step would end at x = -4, where ln(x) is not defined
+    /// let sol = fsolve(|x| ln(x) + 5.0, 1.0);
+    /// assert_approx_eq(sol.x, exp(-5.0), 1e-12);
+    /// ```
+    /// ```typescript
+    /// let sol = fsolve(|x| x * x + 1.0, 2.0);
+    /// assert_eq(sol.status, "step_tolerance");
+    /// assert_approx_eq(sol.x, 0.0, 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "fsolve", return_raw)]
+    pub fn fsolve(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        fsolve_with_options(ctx, f, x0, Map::new())
+    }
+
+    /// Solves the system of nonlinear equations `F(x) = 0` with the trust-region dogleg method.
+    /// The following options are supported:
+    /// - `tol`: tolerance on the residual norm (default `1e-10`).
+    /// - `step_tol`: relative tolerance on the step size (default `1e-12`).
+    /// - `max_iterations`: maximum number of iterations (default `400`).
+    /// - `jacobian`: function `J(x)` returning the matrix of partial derivatives `dF_i/dx_j` as
+    ///   an array of rows.
+    ///
+    /// Each call of `F` or `J` counts as one operation towards the engine's operation limit.
+    /// ```typescript
+    /// fn equations(v) {
+    ///     let x = v[0]; let y = v[1];
+    ///     [exp(-exp(-(x + y))) - y * (1.0 + x * x), x * cos(y) + y * sin(x) - 0.5]
+    /// }
+    /// let sol = fsolve(Fn("equations"), [0, 0], #{tol: 1e-12});
+    /// assert_approx_eq(sol.x, [0.3532, 0.6061], 1e-4);
+    /// assert(sol.residual_norm <= 1e-12);
+    /// ```
+    /// ```typescript
+    /// let sol = fsolve(|v| [v[0] + 2.0 * v[1] - 2.0, v[0] ** 2 + 4.0 * v[1] ** 2 - 4.0], [1, 2],
+    ///                  #{jacobian: |v| [[1.0, 2.0], [2.0 * v[0], 8.0 * v[1]]]});
+    /// assert_approx_eq(sol.x, [0.0, 1.0], 1e-8);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "fsolve", return_raw)]
+    pub fn fsolve_with_options(
+        ctx: NativeCallContext,
+        f: FnPtr,
+        x0: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let scalar = x0.is_int() || x0.is_float();
+        let x0 = dynamic_to_vec_float(x0)?;
+        if x0.is_empty() {
+            return Err(arithmetic_error("The starting point must not be empty"));
+        }
+        let jacobian = match options.get("jacobian") {
+            Some(jacobian) => Some(jacobian.clone().try_cast::<FnPtr>().ok_or_else(|| {
+                arithmetic_error("The option 'jacobian' must be a function pointer")
+            })?),
+            None => None,
+        };
+        let system = System {
+            ctx: &ctx,
+            f: &f,
+            jacobian,
+            scalar,
+            counter: OperationCounter::new(&ctx),
+        };
+        trust_region_dogleg(&system, x0, &options)
+    }
+}
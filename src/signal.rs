@@ -0,0 +1,1219 @@
+use rhai::plugin::*;
+
+/// Window generators, transforms and segmentation shared by the spectral estimators.
+mod spectral {
+    use crate::{
+        arithmetic_error, if_int_convert_to_float_and_do, if_list_convert_to_vec_float_and_do,
+        int_option,
+    };
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
+    use std::f64::consts::PI;
+
+    /// Converts a window length to `usize`, rejecting non-positive lengths.
+    pub(super) fn window_length(n: INT) -> Result<usize, Box<EvalAltResult>> {
+        if n < 1 {
+            Err(arithmetic_error("The window length must be at least 1"))
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// Evaluates a symmetric window of length `n` defined by `f` on the unit interval. The first
+    /// half is evaluated and mirrored so that the window is exactly symmetric.
+    pub(super) fn symmetric_window<F>(n: usize, f: F) -> Vec<FLOAT>
+    where
+        F: Fn(FLOAT) -> FLOAT,
+    {
+        if n == 1 {
+            return vec![1.0];
+        }
+        let mut window = (0..n.div_ceil(2))
+            .map(|k| f(k as FLOAT / (n - 1) as FLOAT))
+            .collect::<Vec<FLOAT>>();
+        let mirrored = window
+            .iter()
+            .rev()
+            .skip(n % 2)
+            .copied()
+            .collect::<Vec<FLOAT>>();
+        window.extend(mirrored);
+        window
+    }
+
+    /// Modified Bessel function of the first kind of order zero.
+    pub(super) fn bessel_i0(x: FLOAT) -> FLOAT {
+        let half = x / 2.0;
+        let mut term = 1.0;
+        let mut sum = 1.0;
+        let mut k = 1.0;
+        while term > 1e-17 * sum {
+            term *= (half / k).powi(2);
+            sum += term;
+            k += 1.0;
+        }
+        sum
+    }
+
+    /// Hann window of length `n`.
+    pub(super) fn hann_window(n: usize) -> Vec<FLOAT> {
+        symmetric_window(n, |x| 0.5 - 0.5 * (2.0 * PI * x).cos())
+    }
+
+    /// Hamming window of length `n`.
+    pub(super) fn hamming_window(n: usize) -> Vec<FLOAT> {
+        symmetric_window(n, |x| 0.54 - 0.46 * (2.0 * PI * x).cos())
+    }
+
+    /// Blackman window of length `n`.
+    pub(super) fn blackman_window(n: usize) -> Vec<FLOAT> {
+        symmetric_window(n, |x| {
+            (0.42 - 0.5 * (2.0 * PI * x).cos() + 0.08 * (4.0 * PI * x).cos()).max(0.0)
+        })
+    }
+
+    /// Kaiser window of length `n` with shape parameter `beta`.
+    pub(super) fn kaiser_window(n: usize, beta: FLOAT) -> Vec<FLOAT> {
+        let denominator = bessel_i0(beta);
+        symmetric_window(n, |x| {
+            let r = 2.0 * x - 1.0;
+            bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / denominator
+        })
+    }
+
+    /// Tukey (tapered cosine) window of length `n` with taper ratio `r`.
+    pub(super) fn tukey_window(n: usize, r: FLOAT) -> Vec<FLOAT> {
+        if r <= 0.0 {
+            return vec![1.0; n];
+        }
+        let r = r.min(1.0);
+        symmetric_window(n, |x| {
+            if x < r / 2.0 {
+                0.5 * (1.0 + (2.0 * PI / r * (x - r / 2.0)).cos())
+            } else if x >= 1.0 - r / 2.0 {
+                0.5 * (1.0 + (2.0 * PI / r * (x - 1.0 + r / 2.0)).cos())
+            } else {
+                1.0
+            }
+        })
+    }
+
+    /// Generates a window from its name.
+    pub(super) fn window_by_name(name: &str, n: usize) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        match name {
+            "hann" | "hanning" => Ok(hann_window(n)),
+            "hamming" => Ok(hamming_window(n)),
+            "blackman" => Ok(blackman_window(n)),
+            "kaiser" => Ok(kaiser_window(n, 0.5)),
+            "tukey" => Ok(tukey_window(n, 0.5)),
+            "rectwin" | "boxcar" | "rectangular" => Ok(vec![1.0; n]),
+            _ => Err(arithmetic_error(format!("Unknown window '{name}'"))),
+        }
+    }
+
+    /// Converts a window into a Rhai array.
+    pub(super) fn to_array(values: Vec<FLOAT>) -> Array {
+        values.into_iter().map(Dynamic::from_float).collect()
+    }
+
+    /// Reads a window from an options map. The window may be given by name (in which case it is
+    /// generated with `default_length` samples) or as an explicit array of weights.
+    pub(super) fn window_option(
+        options: &Map,
+        default_name: &str,
+        default_length: usize,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        match options.get("window") {
+            None => window_by_name(default_name, default_length),
+            Some(value) if value.is_string() => window_by_name(
+                value.clone().into_string().unwrap().as_str(),
+                default_length,
+            ),
+            Some(value) if value.is_array() => {
+                let window = if_list_convert_to_vec_float_and_do(
+                    &mut value.clone().into_array().unwrap(),
+                    Ok,
+                )?;
+                if window.is_empty() {
+                    Err(arithmetic_error("The window must not be empty"))
+                } else {
+                    Ok(window)
+                }
+            }
+            Some(_) => Err(arithmetic_error(
+                "The option 'window' must be a window name or an array of weights",
+            )),
+        }
+    }
+
+    /// Returns the smallest power of two that is at least `n`.
+    pub(super) fn next_pow2(n: usize) -> usize {
+        n.max(1).next_power_of_two()
+    }
+
+    /// Computes the discrete Fourier transform of a complex sequence in place. Power-of-two
+    /// lengths use an iterative radix-2 FFT; other lengths fall back to a direct DFT.
+    pub(super) fn fft_in_place(re: &mut [FLOAT], im: &mut [FLOAT]) {
+        let n = re.len();
+        if n < 2 {
+            return;
+        }
+        if !n.is_power_of_two() {
+            let mut out_re = vec![0.0; n];
+            let mut out_im = vec![0.0; n];
+            for k in 0..n {
+                for t in 0..n {
+                    let angle = -2.0 * PI * ((k * t) % n) as FLOAT / n as FLOAT;
+                    let (s, c) = angle.sin_cos();
+                    out_re[k] += re[t] * c - im[t] * s;
+                    out_im[k] += re[t] * s + im[t] * c;
+                }
+            }
+            re.copy_from_slice(&out_re);
+            im.copy_from_slice(&out_im);
+            return;
+        }
+
+        // Bit-reversal permutation
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        // Butterflies
+        let mut len = 2;
+        while len <= n {
+            let angle = -2.0 * PI / len as FLOAT;
+            let (w_im, w_re) = angle.sin_cos();
+            for start in (0..n).step_by(len) {
+                let (mut cur_re, mut cur_im) = (1.0, 0.0);
+                for k in 0..len / 2 {
+                    let a = start + k;
+                    let b = a + len / 2;
+                    let t_re = re[b] * cur_re - im[b] * cur_im;
+                    let t_im = re[b] * cur_im + im[b] * cur_re;
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                    let next_re = cur_re * w_re - cur_im * w_im;
+                    cur_im = cur_re * w_im + cur_im * w_re;
+                    cur_re = next_re;
+                }
+            }
+            len <<= 1;
+        }
+    }
+
+    /// One-sided power spectral density of a single windowed segment.
+    pub(super) fn segment_psd(
+        segment: &[FLOAT],
+        window: &[FLOAT],
+        nfft: usize,
+        fs: FLOAT,
+    ) -> Vec<FLOAT> {
+        let mut re = vec![0.0; nfft];
+        let mut im = vec![0.0; nfft];
+        for (i, (x, w)) in segment.iter().zip(window).enumerate() {
+            re[i] = x * w;
+        }
+        fft_in_place(&mut re, &mut im);
+
+        let scale = fs * window.iter().map(|w| w * w).sum::<FLOAT>();
+        let bins = nfft / 2 + 1;
+        let last_doubled = if nfft.is_multiple_of(2) {
+            bins - 1
+        } else {
+            bins
+        };
+        (0..bins)
+            .map(|k| {
+                let p = (re[k] * re[k] + im[k] * im[k]) / scale;
+                if k > 0 && k < last_doubled {
+                    2.0 * p
+                } else {
+                    p
+                }
+            })
+            .collect()
+    }
+
+    /// Frequencies of the one-sided spectrum for a transform of length `nfft`.
+    pub(super) fn frequencies(nfft: usize, fs: FLOAT) -> Vec<FLOAT> {
+        (0..=nfft / 2)
+            .map(|k| k as FLOAT * fs / nfft as FLOAT)
+            .collect()
+    }
+
+    /// Window, overlap and transform length used to split a signal into segments.
+    pub(super) struct Segmentation {
+        /// Window applied to each segment; its length is the segment length.
+        pub(super) window: Vec<FLOAT>,
+        /// Number of samples shared by consecutive segments.
+        pub(super) overlap: usize,
+        /// Length of the transform applied to each segment.
+        pub(super) nfft: usize,
+    }
+
+    impl Segmentation {
+        /// Reads the segmentation options for a signal of length `n`. By default the signal is
+        /// split into eight Hamming-windowed segments with 50% overlap.
+        pub(super) fn from_options(n: usize, options: &Map) -> Result<Self, Box<EvalAltResult>> {
+            let default_length = ((n as FLOAT / 4.5).floor() as usize).max(1);
+            let length = int_option(options, "segment_length", default_length as INT)?;
+            if length < 1 {
+                return Err(arithmetic_error("The segment length must be at least 1"));
+            }
+            let window = window_option(options, "hamming", length as usize)?;
+            if options.contains_key("segment_length") && window.len() != length as usize {
+                return Err(arithmetic_error(
+                    "The window length must match the segment length",
+                ));
+            }
+            if window.len() > n {
+                return Err(arithmetic_error(
+                    "The segment length must not exceed the signal length",
+                ));
+            }
+            let overlap = int_option(options, "overlap", (window.len() / 2) as INT)?;
+            if overlap < 0 || overlap as usize >= window.len() {
+                return Err(arithmetic_error(
+                    "The overlap must be non-negative and smaller than the segment length",
+                ));
+            }
+            let nfft = int_option(options, "nfft", next_pow2(window.len()).max(256) as INT)?;
+            if nfft < window.len() as INT {
+                return Err(arithmetic_error(
+                    "The number of FFT points must be at least the segment length",
+                ));
+            }
+            Ok(Self {
+                window,
+                overlap: overlap as usize,
+                nfft: nfft as usize,
+            })
+        }
+
+        /// Start indices of every complete segment of a signal of length `n`.
+        pub(super) fn starts(&self, n: usize) -> Vec<usize> {
+            let step = self.window.len() - self.overlap;
+            (0..=(n - self.window.len())).step_by(step).collect()
+        }
+    }
+
+    /// Reads a sampling frequency, which must be positive.
+    pub(super) fn sampling_frequency(fs: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        if_int_convert_to_float_and_do(fs, |fs| {
+            if fs > 0.0 {
+                Ok(fs)
+            } else {
+                Err(arithmetic_error("The sampling frequency must be positive"))
+            }
+        })
+    }
+}
+
+/// Peak detection, prominence and width measurement used by `findpeaks`.
+mod peaks {
+    use crate::float_option;
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT};
+
+    /// Indices of the local maxima of `y`. Flat peaks are reported at their first sample and the
+    /// end points are never peaks.
+    pub(super) fn local_maxima(y: &[FLOAT]) -> Vec<usize> {
+        let mut peaks = vec![];
+        let mut i = 1;
+        while i + 1 < y.len() {
+            if y[i] > y[i - 1] {
+                let mut j = i;
+                while j + 1 < y.len() && y[j + 1] == y[i] {
+                    j += 1;
+                }
+                if j + 1 < y.len() && y[j + 1] < y[i] {
+                    peaks.push(i);
+                }
+                i = j + 1;
+            } else {
+                i += 1;
+            }
+        }
+        peaks
+    }
+
+    /// Prominence of the peak at `p` together with the indices of its left and right bases.
+    pub(super) fn prominence(y: &[FLOAT], p: usize) -> (FLOAT, usize, usize) {
+        let (mut left_min, mut left_base) = (y[p], p);
+        for i in (0..p).rev() {
+            if y[i] > y[p] {
+                break;
+            }
+            if y[i] < left_min {
+                left_min = y[i];
+                left_base = i;
+            }
+        }
+        let (mut right_min, mut right_base) = (y[p], p);
+        for (i, &value) in y.iter().enumerate().skip(p + 1) {
+            if value > y[p] {
+                break;
+            }
+            if value < right_min {
+                right_min = value;
+                right_base = i;
+            }
+        }
+        (y[p] - left_min.max(right_min), left_base, right_base)
+    }
+
+    /// Width of the peak at `p` measured at half its prominence, interpolating linearly between
+    /// samples of `x`.
+    pub(super) fn half_prominence_width(
+        x: &[FLOAT],
+        y: &[FLOAT],
+        p: usize,
+        prominence: FLOAT,
+        left_base: usize,
+        right_base: usize,
+    ) -> FLOAT {
+        let reference = y[p] - prominence / 2.0;
+        let crossing = |a: usize, b: usize| {
+            if y[a] == y[b] {
+                x[a]
+            } else {
+                x[a] + (reference - y[a]) * (x[b] - x[a]) / (y[b] - y[a])
+            }
+        };
+
+        let mut i = p;
+        while i > left_base && y[i] > reference {
+            i -= 1;
+        }
+        let left = if i < p { crossing(i, i + 1) } else { x[p] };
+
+        let mut j = p;
+        while j < right_base && y[j] > reference {
+            j += 1;
+        }
+        let right = if j > p { crossing(j, j - 1) } else { x[p] };
+
+        right - left
+    }
+
+    /// Removes peaks closer than `min_distance` to a taller peak, keeping the tallest first.
+    pub(super) fn separate(
+        x: &[FLOAT],
+        y: &[FLOAT],
+        peaks: &[usize],
+        min_distance: FLOAT,
+    ) -> Vec<usize> {
+        let mut by_height = (0..peaks.len()).collect::<Vec<usize>>();
+        by_height.sort_by(|&a, &b| y[peaks[b]].partial_cmp(&y[peaks[a]]).unwrap());
+        let mut keep = vec![true; peaks.len()];
+        for &a in &by_height {
+            if keep[a] {
+                for (b, kept) in keep.iter_mut().enumerate() {
+                    if b != a && (x[peaks[b]] - x[peaks[a]]).abs() < min_distance {
+                        *kept = false;
+                    }
+                }
+            }
+        }
+        peaks
+            .iter()
+            .zip(keep)
+            .filter_map(|(&p, kept)| kept.then_some(p))
+            .collect()
+    }
+
+    /// Finds, filters and measures the peaks of `y` sampled at `x`.
+    pub(super) fn peak_search(
+        x: &[FLOAT],
+        y: &[FLOAT],
+        options: &Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let min_height = float_option(options, "min_height", FLOAT::NEG_INFINITY)?;
+        let min_prominence = float_option(options, "min_prominence", 0.0)?;
+        let min_distance = float_option(options, "min_distance", 0.0)?;
+
+        let candidates = local_maxima(y)
+            .into_iter()
+            .filter(|&p| y[p] >= min_height)
+            .filter(|&p| prominence(y, p).0 >= min_prominence)
+            .collect::<Vec<usize>>();
+        let found = if min_distance > 0.0 {
+            separate(x, y, &candidates, min_distance)
+        } else {
+            candidates
+        };
+
+        let mut values = Array::new();
+        let mut locations = Array::new();
+        let mut prominences = Array::new();
+        let mut widths = Array::new();
+        for p in found {
+            let (prom, left_base, right_base) = prominence(y, p);
+            values.push(Dynamic::from_float(y[p]));
+            locations.push(Dynamic::from_float(x[p]));
+            prominences.push(Dynamic::from_float(prom));
+            widths.push(Dynamic::from_float(half_prominence_width(
+                x, y, p, prom, left_base, right_base,
+            )));
+        }
+
+        let mut result = Map::new();
+        result.insert("values".into(), Dynamic::from_array(values));
+        result.insert("locations".into(), Dynamic::from_array(locations));
+        result.insert("prominences".into(), Dynamic::from_array(prominences));
+        result.insert("widths".into(), Dynamic::from_array(widths));
+        Ok(result)
+    }
+}
+
+/// Filter design and interpolation used by the resampling functions.
+mod resampling {
+    use super::spectral::{hamming_window, kaiser_window};
+    use crate::arithmetic_error;
+    use rhai::{EvalAltResult, FLOAT};
+    use std::f64::consts::PI;
+
+    /// Normalised sinc function, `sin(pi x) / (pi x)`.
+    fn sinc(x: FLOAT) -> FLOAT {
+        if x == 0.0 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        }
+    }
+
+    /// Windowed-sinc lowpass filter with `2 * half + 1` taps and a cutoff at `cutoff` times the
+    /// Nyquist frequency.
+    pub(super) fn lowpass(half: usize, cutoff: FLOAT, window: &[FLOAT]) -> Vec<FLOAT> {
+        (0..=2 * half)
+            .map(|k| {
+                let offset = k as FLOAT - half as FLOAT;
+                cutoff * sinc(cutoff * offset) * window[k]
+            })
+            .collect()
+    }
+
+    /// Greatest common divisor of two positive integers.
+    pub(super) fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    /// Changes the sample rate of `x` by the rational factor `p / q`. The signal is upsampled by
+    /// `p`, filtered with a Kaiser-windowed lowpass filter and downsampled by `q`; samples outside
+    /// the signal are taken to be zero.
+    pub(super) fn resample(x: &[FLOAT], p: usize, q: usize) -> Vec<FLOAT> {
+        let divisor = gcd(p, q);
+        let (p, q) = (p / divisor, q / divisor);
+        if p == 1 && q == 1 {
+            return x.to_vec();
+        }
+        let factor = p.max(q);
+        let half = 10 * factor;
+        let h = lowpass(
+            half,
+            1.0 / factor as FLOAT,
+            &kaiser_window(2 * half + 1, 5.0),
+        );
+
+        let n_out = (x.len() * p).div_ceil(q);
+        (0..n_out)
+            .map(|m| {
+                // Position of the output sample on the upsampled grid
+                let j = (m * q) as isize;
+                let first = ((j - half as isize).max(0) as usize).div_ceil(p);
+                let last = ((j + half as isize) as usize / p).min(x.len() - 1);
+                (first..=last)
+                    .map(|i| x[i] * h[(j - (i * p) as isize + half as isize) as usize])
+                    .sum::<FLOAT>()
+                    * p as FLOAT
+            })
+            .collect()
+    }
+
+    /// Lowpass filters `x` for a rate reduction by `r` and keeps every `r`-th sample. The filter
+    /// is a 30th-order Hamming-windowed FIR filter applied without phase shift; the signal is
+    /// extended at both ends by odd reflection to avoid edge transients.
+    pub(super) fn decimate(x: &[FLOAT], r: usize) -> Vec<FLOAT> {
+        let half = 15;
+        let mut h = lowpass(half, 1.0 / r as FLOAT, &hamming_window(2 * half + 1));
+        let gain = h.iter().sum::<FLOAT>();
+        h.iter_mut().for_each(|c| *c /= gain);
+
+        let n = x.len() as isize;
+        let extended = |i: isize| {
+            if i < 0 {
+                2.0 * x[0] - x[(-i).min(n - 1) as usize]
+            } else if i >= n {
+                2.0 * x[n as usize - 1] - x[(2 * (n - 1) - i).max(0) as usize]
+            } else {
+                x[i as usize]
+            }
+        };
+        (0..x.len())
+            .step_by(r)
+            .map(|m| {
+                h.iter()
+                    .enumerate()
+                    .map(|(k, c)| c * extended(m as isize + half as isize - k as isize))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Interpolates the samples `(t, y)` at `tq`. Queries outside the data are clamped to the
+    /// first and last values, as in `interp1`.
+    pub(super) fn interpolate(
+        t: &[FLOAT],
+        y: &[FLOAT],
+        tq: FLOAT,
+        method: &str,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        if tq <= t[0] {
+            return Ok(y[0]);
+        } else if tq >= t[t.len() - 1] {
+            return Ok(y[y.len() - 1]);
+        }
+        // Index of the first sample at or after the query
+        let b = t.partition_point(|&v| v < tq);
+        if t[b] == tq {
+            return Ok(y[b]);
+        }
+        let a = b - 1;
+        match method {
+            "linear" => Ok(y[a] + (tq - t[a]) * (y[b] - y[a]) / (t[b] - t[a])),
+            "nearest" => Ok(if tq - t[a] < t[b] - tq { y[a] } else { y[b] }),
+            "previous" => Ok(y[a]),
+            "next" => Ok(y[b]),
+            _ => Err(arithmetic_error(format!(
+                "Unknown interpolation method '{method}'"
+            ))),
+        }
+    }
+}
+
+#[export_module]
+pub mod signal_functions {
+    use super::peaks::peak_search;
+    use super::resampling::{
+        decimate as decimate_signal, interpolate, resample as resample_signal,
+    };
+    use super::spectral::{
+        blackman_window, frequencies, hamming_window, hann_window, kaiser_window, next_pow2,
+        sampling_frequency, segment_psd, to_array, tukey_window, window_length, window_option,
+        Segmentation,
+    };
+    use crate::{
+        arithmetic_error, float_option, if_int_convert_to_float_and_do,
+        if_list_convert_to_vec_float_and_do, if_list_do_int_or_do_float, int_option,
+    };
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
+
+    /// Returns a symmetric Hann window of length `n`.
+    /// ```typescript
+    /// let w = hann(5);
+    /// assert_approx_eq(w, [0.0, 0.5, 1.0, 0.5, 0.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "hann", return_raw)]
+    pub fn hann(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        window_length(n).map(|n| to_array(hann_window(n)))
+    }
+
+    /// Returns a symmetric Hamming window of length `n`.
+    /// ```typescript
+    /// let w = hamming(3);
+    /// assert_approx_eq(w, [0.08, 1.0, 0.08], 1e-12);
+    /// ```
+    #[rhai_fn(name = "hamming", return_raw)]
+    pub fn hamming(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        window_length(n).map(|n| to_array(hamming_window(n)))
+    }
+
+    /// Returns a symmetric Blackman window of length `n`.
+    /// ```typescript
+    /// let w = blackman(5);
+    /// assert_approx_eq(w, [0.0, 0.34, 1.0, 0.34, 0.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "blackman", return_raw)]
+    pub fn blackman(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        window_length(n).map(|n| to_array(blackman_window(n)))
+    }
+
+    /// Returns a Kaiser window of length `n` with the default shape parameter `beta = 0.5`.
+    /// ```typescript
+    /// let w = kaiser(3);
+    /// assert_approx_eq(w, [0.9403061933191572, 1.0, 0.9403061933191572], 1e-12);
+    /// ```
+    #[rhai_fn(name = "kaiser", return_raw)]
+    pub fn kaiser(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        kaiser_with_beta(n, Dynamic::from_float(0.5))
+    }
+
+    /// Returns a Kaiser window of length `n` with shape parameter `beta`. Larger values of `beta`
+    /// give a narrower window with lower side lobes.
+    /// ```typescript
+    /// let w = kaiser(5, 0);
+    /// assert_eq(w, [1.0, 1.0, 1.0, 1.0, 1.0]);
+    /// ```
+    /// ```typescript
+    /// let w = kaiser(4, 5.0);
+    /// assert(w[0] < 0.04 && w[0] == w[3] && w[1] == w[2]);
+    /// ```
+    #[rhai_fn(name = "kaiser", return_raw)]
+    pub fn kaiser_with_beta(n: INT, beta: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        let n = window_length(n)?;
+        if_int_convert_to_float_and_do(beta, |beta| Ok(to_array(kaiser_window(n, beta))))
+    }
+
+    /// Returns a Tukey (tapered cosine) window of length `n` with the default taper ratio of 0.5.
+    /// ```typescript
+    /// let w = tukey(5);
+    /// assert_approx_eq(w, [0.0, 1.0, 1.0, 1.0, 0.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "tukey", return_raw)]
+    pub fn tukey(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        tukey_with_ratio(n, Dynamic::from_float(0.5))
+    }
+
+    /// Returns a Tukey (tapered cosine) window of length `n` where `r` is the fraction of the
+    /// window inside the cosine tapers. A ratio of 0 gives a rectangular window and a ratio of 1
+    /// gives a Hann window.
+    /// ```typescript
+    /// let w = tukey(5, 1.0);
+    /// assert_approx_eq(w, hann(5), 1e-12);
+    /// ```
+    /// ```typescript
+    /// let w = tukey(4, 0);
+    /// assert_eq(w, [1.0, 1.0, 1.0, 1.0]);
+    /// ```
+    #[rhai_fn(name = "tukey", return_raw)]
+    pub fn tukey_with_ratio(n: INT, r: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        let n = window_length(n)?;
+        if_int_convert_to_float_and_do(r, |r| Ok(to_array(tukey_window(n, r))))
+    }
+
+    /// Estimates the one-sided power spectral density of `x` sampled at `fs` using a
+    /// rectangular window. Returns an object map with the frequencies `f` and the density `pxx`.
+    /// The number of FFT points is the larger of 256 and the next power of two above the signal
+    /// length.
+    /// ```typescript
+    /// let t = linspace(0.0, 255.0 / 256.0, 256);
+    /// let x = t.map(|v| sin(2.0 * pi * 32.0 * v));
+    /// let p = periodogram(x, 256);
+    /// assert_eq(p.f[argmax(p.pxx)], 32.0);
+    /// ```
+    #[rhai_fn(name = "periodogram", return_raw)]
+    pub fn periodogram(x: Array, fs: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        periodogram_with_options(x, fs, Map::new())
+    }
+
+    /// Estimates the one-sided power spectral density of `x` sampled at `fs`. The following
+    /// options are supported:
+    /// - `window`: window name (`"hann"`, `"hamming"`, `"blackman"`, `"kaiser"`, `"tukey"`,
+    ///   `"rectwin"`) or array of weights with the same length as `x` (default `"rectwin"`).
+    /// - `nfft`: number of FFT points (default the larger of 256 and the next power of two).
+    ///
+    /// Returns an object map with the frequencies `f` and the density `pxx`.
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0];
+    /// let p = periodogram(x, 1.0, #{nfft: 4});
+    /// assert_eq(p.f, [0.0, 0.25, 0.5]);
+    /// assert_approx_eq(p.pxx, [25.0, 4.0, 1.0], 1e-12);
+    /// ```
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0, 3.0, 2.0, 1.0, 0.0];
+    /// let p = periodogram(x, 10.0, #{window: "hann"});
+    /// assert_eq(len(p.f), 129);
+    /// ```
+    #[rhai_fn(name = "periodogram", return_raw)]
+    pub fn periodogram_with_options(
+        x: Array,
+        fs: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let fs = sampling_frequency(fs)?;
+        if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+            if x.is_empty() {
+                return Err(arithmetic_error("The signal must not be empty"));
+            }
+            let window = window_option(&options, "rectwin", x.len())?;
+            if window.len() != x.len() {
+                return Err(arithmetic_error(
+                    "The window length must match the signal length",
+                ));
+            }
+            let nfft = int_option(&options, "nfft", next_pow2(x.len()).max(256) as INT)?;
+            if nfft < x.len() as INT {
+                return Err(arithmetic_error(
+                    "The number of FFT points must be at least the signal length",
+                ));
+            }
+            let nfft = nfft as usize;
+
+            let mut result = Map::new();
+            result.insert(
+                "f".into(),
+                Dynamic::from_array(to_array(frequencies(nfft, fs))),
+            );
+            result.insert(
+                "pxx".into(),
+                Dynamic::from_array(to_array(segment_psd(&x, &window, nfft, fs))),
+            );
+            Ok(result)
+        })
+    }
+
+    /// Estimates the one-sided power spectral density of `x` sampled at `fs` using Welch's
+    /// method: the signal is split into eight Hamming-windowed segments with 50% overlap, and the
+    /// periodograms of the segments are averaged. Returns an object map with the frequencies `f`
+    /// and the density `pxx`.
+    /// ```typescript
+    /// let t = linspace(0.0, 1023.0 / 1024.0, 1024);
+    /// let x = t.map(|v| sin(2.0 * pi * 100.0 * v));
+    /// let p = pwelch(x, 1024);
+    /// assert_eq(p.f[argmax(p.pxx)], 100.0);
+    /// ```
+    #[rhai_fn(name = "pwelch", return_raw)]
+    pub fn pwelch(x: Array, fs: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        pwelch_with_options(x, fs, Map::new())
+    }
+
+    /// Estimates the one-sided power spectral density of `x` sampled at `fs` using Welch's
+    /// method. The following options are supported:
+    /// - `window`: window name or array of weights (default `"hamming"`).
+    /// - `segment_length`: number of samples per segment (default chosen to give eight segments).
+    /// - `overlap`: number of samples shared by consecutive segments (default half a segment).
+    /// - `nfft`: number of FFT points (default the larger of 256 and the next power of two above
+    ///   the segment length).
+    ///
+    /// Returns an object map with the frequencies `f` and the density `pxx`.
+    /// ```typescript
+    /// let x = [1.0, -1.0, 1.0, -1.0, 1.0, -1.0, 1.0, -1.0];
+    /// let p = pwelch(x, 8.0, #{window: "rectwin", segment_length: 4, overlap: 2, nfft: 4});
+    /// assert_eq(p.f, [0.0, 2.0, 4.0]);
+    /// assert_approx_eq(p.pxx, [0.0, 0.0, 0.5], 1e-12);
+    /// ```
+    #[rhai_fn(name = "pwelch", return_raw)]
+    pub fn pwelch_with_options(
+        x: Array,
+        fs: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let fs = sampling_frequency(fs)?;
+        if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+            if x.is_empty() {
+                return Err(arithmetic_error("The signal must not be empty"));
+            }
+            let segmentation = Segmentation::from_options(x.len(), &options)?;
+            let starts = segmentation.starts(x.len());
+            let length = segmentation.window.len();
+
+            let mut pxx = vec![0.0; segmentation.nfft / 2 + 1];
+            for &start in &starts {
+                let psd = segment_psd(
+                    &x[start..start + length],
+                    &segmentation.window,
+                    segmentation.nfft,
+                    fs,
+                );
+                for (total, p) in pxx.iter_mut().zip(psd) {
+                    *total += p / starts.len() as FLOAT;
+                }
+            }
+
+            let mut result = Map::new();
+            result.insert(
+                "f".into(),
+                Dynamic::from_array(to_array(frequencies(segmentation.nfft, fs))),
+            );
+            result.insert("pxx".into(), Dynamic::from_array(to_array(pxx)));
+            Ok(result)
+        })
+    }
+
+    /// Computes the spectrogram of `x` sampled at `fs` using the default segmentation of
+    /// [`pwelch`]. Returns an object map with the segment centre times `t`, the frequencies `f`
+    /// and the power spectral density `p` of every segment. `p` has one row per frequency and one
+    /// column per time, which is the layout of the grids returned by `meshgrid(t, f)`.
+    /// ```typescript
+    /// let x = linspace(0.0, 1.0, 900).map(|v| sin(2.0 * pi * 50.0 * v));
+    /// let s = spectrogram(x, 900);
+    /// let g = meshgrid(s.t, s.f);
+    /// assert_eq(size(g.x), size(s.p));
+    /// assert_eq(len(s.t), 8);
+    /// ```
+    #[rhai_fn(name = "spectrogram", return_raw)]
+    pub fn spectrogram(x: Array, fs: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        spectrogram_with_options(x, fs, Map::new())
+    }
+
+    /// Computes the spectrogram of `x` sampled at `fs`. Accepts the same `window`,
+    /// `segment_length`, `overlap` and `nfft` options as [`pwelch`]. Returns an object map with
+    /// the segment centre times `t`, the frequencies `f` and the power spectral density `p` of
+    /// every segment, with one row per frequency and one column per time.
+    /// ```typescript
+    /// let x = [0.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0];
+    /// let s = spectrogram(x, 4.0, #{window: "rectwin", segment_length: 4, overlap: 0, nfft: 4});
+    /// assert_eq(s.t, [0.5, 1.5]);
+    /// assert_eq(s.f, [0.0, 1.0, 2.0]);
+    /// assert_approx_eq(s.p[1], [0.5, 0.5], 1e-12);
+    /// ```
+    #[rhai_fn(name = "spectrogram", return_raw)]
+    pub fn spectrogram_with_options(
+        x: Array,
+        fs: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let fs = sampling_frequency(fs)?;
+        if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+            if x.is_empty() {
+                return Err(arithmetic_error("The signal must not be empty"));
+            }
+            let segmentation = Segmentation::from_options(x.len(), &options)?;
+            let starts = segmentation.starts(x.len());
+            let length = segmentation.window.len();
+
+            let columns = starts
+                .iter()
+                .map(|&start| {
+                    segment_psd(
+                        &x[start..start + length],
+                        &segmentation.window,
+                        segmentation.nfft,
+                        fs,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let p = (0..=segmentation.nfft / 2)
+                .map(|k| {
+                    Dynamic::from_array(columns.iter().map(|c| Dynamic::from_float(c[k])).collect())
+                })
+                .collect::<Array>();
+            let t = starts
+                .iter()
+                .map(|&start| Dynamic::from_float((start as FLOAT + length as FLOAT / 2.0) / fs))
+                .collect::<Array>();
+
+            let mut result = Map::new();
+            result.insert("t".into(), Dynamic::from_array(t));
+            result.insert(
+                "f".into(),
+                Dynamic::from_array(to_array(frequencies(segmentation.nfft, fs))),
+            );
+            result.insert("p".into(), Dynamic::from_array(p));
+            Ok(result)
+        })
+    }
+
+    /// Finds the local maxima of `y`. Returns an object map with the peak `values`, their
+    /// `locations` (indices into `y`), their `prominences` and their `widths` (in samples,
+    /// measured at half prominence). Flat peaks are reported at their first sample.
+    /// ```typescript
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y);
+    /// assert_eq(p.values, [2.0, 3.0, 1.0]);
+    /// assert_eq(p.locations, [1, 3, 5]);
+    /// assert_eq(p.prominences, [1.0, 3.0, 1.0]);
+    /// assert_eq(p.widths, [0.75, 1.25, 1.0]);
+    /// ```
+    #[rhai_fn(name = "findpeaks", return_raw)]
+    pub fn findpeaks(y: Array) -> Result<Map, Box<EvalAltResult>> {
+        findpeaks_with_options(y, Map::new())
+    }
+
+    /// Finds the local maxima of `y`, keeping only those that satisfy the following options:
+    /// - `min_height`: smallest allowed peak value.
+    /// - `min_prominence`: smallest allowed peak prominence.
+    /// - `min_distance`: smallest allowed separation (in samples) between peaks. Taller peaks are
+    ///   kept in preference to shorter ones.
+    ///
+    /// Returns an object map with the peak `values`, `locations`, `prominences` and `widths`.
+    /// ```typescript
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y, #{min_height: 1.5});
+    /// assert_eq(p.locations, [1, 3]);
+    /// ```
+    /// ```typescript
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y, #{min_prominence: 2});
+    /// assert_eq(p.locations, [3]);
+    /// ```
+    /// ```typescript
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y, #{min_distance: 3});
+    /// assert_eq(p.values, [3.0]);
+    /// ```
+    #[rhai_fn(name = "findpeaks", return_raw)]
+    pub fn findpeaks_with_options(y: Array, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |y| {
+            let x = (0..y.len()).map(|i| i as FLOAT).collect::<Vec<FLOAT>>();
+            let mut result = peak_search(&x, &y, &options)?;
+            let locations = result["locations"]
+                .clone()
+                .into_array()
+                .unwrap()
+                .into_iter()
+                .map(|l| Dynamic::from_int(l.as_float().unwrap() as INT))
+                .collect::<Array>();
+            result.insert("locations".into(), Dynamic::from_array(locations));
+            Ok(result)
+        })
+    }
+
+    /// Finds the local maxima of `y` sampled at the points `x`. The peak `locations` and `widths`
+    /// are reported in the units of `x`.
+    /// ```typescript
+    /// let x = [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0];
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y, x);
+    /// assert_eq(p.locations, [0.5, 1.5, 2.5]);
+    /// assert_eq(p.widths, [0.375, 0.625, 0.5]);
+    /// ```
+    #[rhai_fn(name = "findpeaks", return_raw)]
+    pub fn findpeaks_with_x(y: Array, x: Array) -> Result<Map, Box<EvalAltResult>> {
+        findpeaks_with_x_and_options(y, x, Map::new())
+    }
+
+    /// Finds the local maxima of `y` sampled at the points `x`, filtered with the `min_height`,
+    /// `min_prominence` and `min_distance` options. `min_distance` is given in the units of `x`.
+    /// ```typescript
+    /// let x = [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0];
+    /// let y = [0, 2, 1, 3, 0, 1, 0];
+    /// let p = findpeaks(y, x, #{min_distance: 1.5});
+    /// assert_eq(p.locations, [1.5]);
+    /// ```
+    #[rhai_fn(name = "findpeaks", return_raw)]
+    pub fn findpeaks_with_x_and_options(
+        y: Array,
+        x: Array,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if x.len() != y.len() {
+            return Err(arithmetic_error("The arrays must have the same length"));
+        }
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |y| {
+            if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| peak_search(&x, &y, &options))
+        })
+    }
+
+    /// Converts a rate-change factor to `usize`, rejecting non-positive factors.
+    fn rate_factor(n: INT) -> Result<usize, Box<EvalAltResult>> {
+        if n < 1 {
+            Err(arithmetic_error(
+                "The rate-change factor must be at least 1",
+            ))
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// Increases the sample rate of `x` by the integer factor `n` by inserting `n - 1` zeros
+    /// after every sample.
+    /// ```typescript
+    /// let y = upsample([1, 2, 3], 2);
+    /// assert_eq(y, [1, 0, 2, 0, 3, 0]);
+    /// ```
+    /// ```typescript
+    /// let y = upsample([1.5, 2.5], 3);
+    /// assert_eq(y, [1.5, 0.0, 0.0, 2.5, 0.0, 0.0]);
+    /// ```
+    #[rhai_fn(name = "upsample", return_raw)]
+    pub fn upsample(x: Array, n: INT) -> Result<Array, Box<EvalAltResult>> {
+        let n = rate_factor(n)?;
+        let interleave = |x: &mut Array, zero: Dynamic| {
+            let mut y = Array::with_capacity(x.len() * n);
+            for value in x.iter() {
+                y.push(value.clone());
+                y.extend(std::iter::repeat_n(zero.clone(), n - 1));
+            }
+            Ok(y)
+        };
+        if_list_do_int_or_do_float(
+            &mut x.clone(),
+            |x| interleave(x, Dynamic::ZERO),
+            |x| interleave(x, Dynamic::FLOAT_ZERO),
+        )
+    }
+
+    /// Decreases the sample rate of `x` by the integer factor `n` by keeping every `n`-th sample,
+    /// starting with the first. No anti-aliasing filter is applied; see [`decimate`].
+    /// ```typescript
+    /// let y = downsample([1, 2, 3, 4, 5], 2);
+    /// assert_eq(y, [1, 3, 5]);
+    /// ```
+    #[rhai_fn(name = "downsample", return_raw)]
+    pub fn downsample(x: Array, n: INT) -> Result<Array, Box<EvalAltResult>> {
+        let n = rate_factor(n)?;
+        let pick = |x: &mut Array| Ok(x.iter().step_by(n).cloned().collect::<Array>());
+        if_list_do_int_or_do_float(&mut x.clone(), pick, pick)
+    }
+
+    /// Decreases the sample rate of `x` by the integer factor `r` after applying a zero-phase
+    /// lowpass filter (a 30th-order FIR filter with its cutoff at `1 / r` of the Nyquist
+    /// frequency) to prevent aliasing.
+    /// ```typescript
+    /// let y = decimate(ones([40]), 4);
+    /// assert_eq(len(y), 10);
+    /// assert_approx_eq(y, ones([10]), 1e-12);
+    /// ```
+    /// ```typescript
+    /// let x = linspace(0.0, 1.0, 200).map(|t| sin(2.0 * pi * 2.0 * t) + 0.5 * sin(2.0 * pi * 90.0 * t));
+    /// let y = decimate(x, 5);
+    /// assert(abs(y[10] - sin(2.0 * pi * 2.0 * 50.0 / 199.0)) < 0.05);
+    /// ```
+    #[rhai_fn(name = "decimate", return_raw)]
+    pub fn decimate(x: Array, r: INT) -> Result<Array, Box<EvalAltResult>> {
+        let r = rate_factor(r)?;
+        if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+            if x.is_empty() {
+                return Err(arithmetic_error("The signal must not be empty"));
+            }
+            Ok(to_array(decimate_signal(&x, r)))
+        })
+    }
+
+    /// Changes the sample rate of `x` by the rational factor `p / q` using a polyphase
+    /// anti-aliasing filter (a Kaiser-windowed FIR lowpass filter). The output has
+    /// `ceil(len(x) * p / q)` samples. Samples beyond the ends of `x` are assumed to be zero, so
+    /// the first and last few outputs are attenuated.
+    /// ```typescript
+    /// let x = linspace(0.0, 1.0, 10);
+    /// let y = resample(x, 3, 2);
+    /// assert_eq(len(y), 15);
+    /// ```
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0];
+    /// let y = resample(x, 2, 2);
+    /// assert_eq(y, x);
+    /// ```
+    #[rhai_fn(name = "resample", return_raw)]
+    pub fn resample(x: Array, p: INT, q: INT) -> Result<Array, Box<EvalAltResult>> {
+        let (p, q) = (rate_factor(p)?, rate_factor(q)?);
+        if_list_convert_to_vec_float_and_do(&mut x.clone(), |x| {
+            if x.is_empty() {
+                return Err(arithmetic_error("The signal must not be empty"));
+            }
+            Ok(to_array(resample_signal(&x, p, q)))
+        })
+    }
+
+    /// Resamples the irregularly sampled data `(t, y)` onto a uniform grid with spacing `dt`
+    /// that starts at the first time and ends at (or just before) the last time, using linear
+    /// interpolation. Returns an object map with the uniform times `t` and the values `y`.
+    /// ```typescript
+    /// let t = [0.0, 0.3, 1.1, 2.0];
+    /// let y = [0.0, 3.0, 11.0, 20.0];
+    /// let r = retime(t, y, 0.5);
+    /// assert_eq(r.t, [0.0, 0.5, 1.0, 1.5, 2.0]);
+    /// assert_approx_eq(r.y, [0.0, 5.0, 10.0, 15.0, 20.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "retime", return_raw)]
+    pub fn retime(t: Array, y: Array, dt: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        retime_with_options(t, y, dt, Map::new())
+    }
+
+    /// Resamples the irregularly sampled data `(t, y)` onto a uniform grid with spacing `dt`.
+    /// The following options are supported:
+    /// - `method`: `"linear"` (default), `"nearest"`, `"previous"` or `"next"`.
+    /// - `start`: first time of the grid (default the first element of `t`).
+    /// - `end`: last time of the grid (default the last element of `t`).
+    ///
+    /// The time step and the start and end times must be finite. As with `interp1`, values outside
+    /// the range of `t` are clamped to the first and last values of `y`. Returns an object map with the uniform times `t` and the values `y`.
+    /// ```typescript
+    /// let t = [0.0, 0.3, 1.1, 2.0];
+    /// let y = [0.0, 3.0, 11.0, 20.0];
+    /// assert_eq(retime(t, y, 0.5, #{method: "previous"}).y, [0.0, 3.0, 3.0, 11.0, 20.0]);
+    /// assert_eq(retime(t, y, 0.5, #{method: "nearest"}).y, [0.0, 3.0, 11.0, 11.0, 20.0]);
+    /// assert_eq(retime(t, y, 0.5, #{method: "next"}).y, [0.0, 11.0, 11.0, 20.0, 20.0]);
+    /// ```
+    /// ```typescript
+    /// let r = retime([1.0, 2.0], [10, 20], 1, #{start: 0, end: 3});
+    /// assert_eq(r.t, [0.0, 1.0, 2.0, 3.0]);
+    /// assert_eq(r.y, [10.0, 10.0, 20.0, 20.0]);
+    /// ```
+    #[rhai_fn(name = "retime", return_raw)]
+    pub fn retime_with_options(
+        t: Array,
+        y: Array,
+        dt: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if t.len() != y.len() {
+            return Err(arithmetic_error("The arrays must have the same length"));
+        }
+        if t.is_empty() {
+            return Err(arithmetic_error("The arrays must not be empty"));
+        }
+        let dt = if_int_convert_to_float_and_do(dt, |dt| {
+            if dt > 0.0 && dt.is_finite() {
+                Ok(dt)
+            } else {
+                Err(arithmetic_error(
+                    "The time step must be positive and finite",
+                ))
+            }
+        })?;
+        let method = match options.get("method") {
+            Some(m) => m
+                .clone()
+                .into_string()
+                .map_err(|_| arithmetic_error("The option 'method' must be a string"))?,
+            None => "linear".to_string(),
+        };
+
+        if_list_convert_to_vec_float_and_do(&mut y.clone(), |y| {
+            if_list_convert_to_vec_float_and_do(&mut t.clone(), |t| {
+                if t.windows(2).any(|w| w[1] < w[0]) {
+                    return Err(arithmetic_error(
+                        "The times must be sorted in ascending order",
+                    ));
+                }
+                let start = float_option(&options, "start", t[0])?;
+                let end = float_option(&options, "end", t[t.len() - 1])?;
+                if !start.is_finite() || !end.is_finite() {
+                    return Err(arithmetic_error("The start and end times must be finite"));
+                }
+                if end < start {
+                    return Err(arithmetic_error(
+                        "The end time must not precede the start time",
+                    ));
+                }
+
+                let steps = (end - start) / dt + 1e-9;
+                if !steps.is_finite() {
+                    return Err(arithmetic_error(
+                        "The time step is too small for the time span",
+                    ));
+                }
+                let n = steps.floor() as usize + 1;
+                let grid = (0..n)
+                    .map(|k| start + k as FLOAT * dt)
+                    .collect::<Vec<FLOAT>>();
+                let values = grid
+                    .iter()
+                    .map(|&tq| interpolate(&t, &y, tq, &method))
+                    .collect::<Result<Vec<FLOAT>, _>>()?;
+
+                let mut result = Map::new();
+                result.insert("t".into(), Dynamic::from_array(to_array(grid)));
+                result.insert("y".into(), Dynamic::from_array(to_array(values)));
+                Ok(result)
+            })
+        })
+    }
+}
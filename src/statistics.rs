@@ -1,16 +1,217 @@
 use rhai::plugin::*;
 
+/// Shared machinery of the hypothesis tests.
+mod hypothesis_tests {
+    use crate::{arithmetic_error, Distribution};
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
+
+    /// The alternative hypothesis.
+    #[derive(Clone, Copy, PartialEq)]
+    pub(super) enum Tail {
+        /// The parameter differs from its null value.
+        Both,
+        /// The parameter is greater than its null value.
+        Right,
+        /// The parameter is less than its null value.
+        Left,
+    }
+
+    /// Significance level and alternative hypothesis of a test.
+    pub(super) struct Settings {
+        /// Significance level.
+        pub(super) alpha: FLOAT,
+        /// Alternative hypothesis.
+        pub(super) tail: Tail,
+    }
+
+    impl Settings {
+        /// Reads the `alpha` and `tail` options.
+        pub(super) fn from_options(options: &Map) -> Result<Self, Box<EvalAltResult>> {
+            let alpha = crate::float_option(options, "alpha", 0.05)?;
+            if alpha.is_nan() || alpha <= 0.0 || alpha >= 1.0 {
+                return Err(arithmetic_error(
+                    "The option 'alpha' must be between 0 and 1",
+                ));
+            }
+            let tail = match options.get("tail") {
+                Some(tail) => tail
+                    .clone()
+                    .into_string()
+                    .map_err(|_| arithmetic_error("The option 'tail' must be a string"))?,
+                None => "both".to_string(),
+            };
+            let tail = match tail.as_str() {
+                "both" => Tail::Both,
+                "right" => Tail::Right,
+                "left" => Tail::Left,
+                other => {
+                    return Err(arithmetic_error(format!(
+                        "Unknown tail '{other}', expected 'both', 'right' or 'left'"
+                    )))
+                }
+            };
+            Ok(Self { alpha, tail })
+        }
+    }
+
+    /// Reads a sample, which must hold at least `min_len` numbers.
+    pub(super) fn sample(
+        x: Array,
+        name: &str,
+        min_len: usize,
+    ) -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+        let x = crate::dynamic_to_vec_float(Dynamic::from_array(x))?;
+        if x.len() < min_len {
+            return Err(arithmetic_error(format!(
+                "'{name}' must hold at least {min_len} values"
+            )));
+        }
+        Ok(x)
+    }
+
+    /// Returns the mean and the (unbiased) variance of a sample.
+    pub(super) fn mean_and_variance(x: &[FLOAT]) -> (FLOAT, FLOAT) {
+        let n = x.len() as FLOAT;
+        let mean = x.iter().sum::<FLOAT>() / n;
+        let variance = x.iter().map(|v| (v - mean).powi(2)).sum::<FLOAT>() / (n - 1.0);
+        (mean, variance)
+    }
+
+    /// Returns the degrees of freedom of a test as a number or, for F statistics, a pair.
+    pub(super) fn degrees_of_freedom(df: &[FLOAT]) -> Dynamic {
+        let to_dynamic = |df: FLOAT| {
+            if df.fract() == 0.0 {
+                Dynamic::from_int(df as INT)
+            } else {
+                Dynamic::from_float(df)
+            }
+        };
+        match df {
+            [df] => to_dynamic(*df),
+            _ => Dynamic::from_array(df.iter().map(|df| to_dynamic(*df)).collect()),
+        }
+    }
+
+    /// Returns the result map of a test, with the `statistic`, its degrees of freedom `df`
+    /// (omitted if empty), the `pvalue`, the confidence interval `ci` (if any) and whether the
+    /// null hypothesis is rejected at level `alpha`.
+    pub(super) fn outcome(
+        statistic: FLOAT,
+        df: &[FLOAT],
+        pvalue: FLOAT,
+        ci: Option<(FLOAT, FLOAT)>,
+        alpha: FLOAT,
+    ) -> Map {
+        let mut result = Map::new();
+        result.insert("statistic".into(), Dynamic::from_float(statistic));
+        if !df.is_empty() {
+            result.insert("df".into(), degrees_of_freedom(df));
+        }
+        result.insert("pvalue".into(), Dynamic::from_float(pvalue));
+        if let Some((low, high)) = ci {
+            result.insert(
+                "ci".into(),
+                Dynamic::from_array(vec![Dynamic::from_float(low), Dynamic::from_float(high)]),
+            );
+        }
+        result.insert("reject".into(), Dynamic::from_bool(pvalue <= alpha));
+        result
+    }
+
+    /// Tests whether a location parameter equals `null`, from its `estimate` and standard error
+    /// `se`, with a statistic following the symmetric `distribution` (standard normal or t)
+    /// under the null hypothesis. The confidence interval is for the parameter.
+    pub(super) fn location_test(
+        estimate: FLOAT,
+        se: FLOAT,
+        null: FLOAT,
+        distribution: Distribution,
+        df: &[FLOAT],
+        settings: &Settings,
+    ) -> Map {
+        let statistic = (estimate - null) / se;
+        let Settings { alpha, tail } = *settings;
+        let (pvalue, ci) = match tail {
+            Tail::Both => {
+                let q = distribution.icdf(1.0 - alpha / 2.0);
+                (
+                    2.0 * distribution.cdf(-statistic.abs()),
+                    (estimate - q * se, estimate + q * se),
+                )
+            }
+            Tail::Right => (
+                distribution.cdf(-statistic),
+                (
+                    estimate - distribution.icdf(1.0 - alpha) * se,
+                    FLOAT::INFINITY,
+                ),
+            ),
+            Tail::Left => (
+                distribution.cdf(statistic),
+                (
+                    FLOAT::NEG_INFINITY,
+                    estimate + distribution.icdf(1.0 - alpha) * se,
+                ),
+            ),
+        };
+        outcome(statistic, df, pvalue, Some(ci), alpha)
+    }
+
+    /// Tests a statistic whose large values are evidence against the null hypothesis, such as
+    /// the chi-square and ANOVA statistics.
+    pub(super) fn upper_tail_test(
+        statistic: FLOAT,
+        distribution: Distribution,
+        df: &[FLOAT],
+        alpha: FLOAT,
+    ) -> Map {
+        outcome(
+            statistic,
+            df,
+            1.0 - distribution.cdf(statistic),
+            None,
+            alpha,
+        )
+    }
+
+    /// Returns the result map of an ANOVA term with sum of squares `ss` and degrees of freedom
+    /// `df`, tested against the error mean square `error_ms` with `error_df` degrees of freedom.
+    pub(super) fn anova_term(
+        ss: FLOAT,
+        df: FLOAT,
+        error_ms: FLOAT,
+        error_df: FLOAT,
+        alpha: FLOAT,
+    ) -> Map {
+        let ms = ss / df;
+        let mut result = upper_tail_test(
+            ms / error_ms,
+            Distribution::F {
+                nu1: df,
+                nu2: error_df,
+            },
+            &[df, error_df],
+            alpha,
+        );
+        result.insert("ss".into(), Dynamic::from_float(ss));
+        result.insert("ms".into(), Dynamic::from_float(ms));
+        result
+    }
+}
+
 #[export_module]
 pub mod stats {
+    use super::hypothesis_tests::{
+        self, anova_term, location_test, mean_and_variance, upper_tail_test, Settings, Tail,
+    };
     #[cfg(feature = "nalgebra")]
     use crate::matrix::RhaiMatrix;
+    use crate::Distribution;
     use crate::{
-        array_to_vec_float, array_to_vec_int, if_list_convert_to_vec_float_and_do, if_list_do,
-        if_list_do_int_or_do_float,
+        arithmetic_error, array_to_vec_float, array_to_vec_int,
+        if_list_convert_to_vec_float_and_do, if_list_do, if_list_do_int_or_do_float,
     };
-    #[cfg(feature = "nalgebra")]
-    use rhai::Map;
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT, INT};
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
 
     #[cfg(feature = "nalgebra")]
     use std::collections::BTreeMap;
@@ -197,7 +398,7 @@ pub mod stats {
     }
 
     /// Sum an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// it is an array with elements other than INT, FLOAT or dual numbers.
     /// ```typescript
     /// let data = [1, 2, 3];
     /// let m = sum(data);
@@ -208,8 +409,20 @@ pub mod stats {
     /// let m = sum(data);
     /// assert_eq(m, 6.0);
     /// ```
+    /// ```typescript
+    /// let g = grad(|x| sum(x.map(|v| v * v)), [1.0, 2.0]);
+    /// assert_eq(g, [2.0, 4.0]);
+    /// ```
     #[rhai_fn(name = "sum", return_raw, pure)]
     pub fn sum(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if arr.iter().any(|x| x.is::<crate::Dual>()) {
+            return arr
+                .iter()
+                .try_fold(crate::Dual::constant(0.0), |total, x| {
+                    crate::dual_functions::add_number(total, x.clone())
+                })
+                .map(Dynamic::from);
+        }
         if_list_do_int_or_do_float(
             arr,
             |arr| {
@@ -428,18 +641,12 @@ pub mod stats {
     #[rhai_fn(name = "prctile", return_raw, pure)]
     pub fn prctile(arr: &mut Array, p: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
         if arr.is_empty() {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "Array must not be empty".to_string(),
-                Position::NONE,
-            )
-            .into());
+            return Err(arithmetic_error("Array must not be empty"));
         }
         if !p.is_float() && !p.is_int() {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "Percentile value must either be INT or FLOAT".to_string(),
-                Position::NONE,
-            )
-            .into());
+            return Err(arithmetic_error(
+                "Percentile value must either be INT or FLOAT",
+            ));
         }
 
         if_list_convert_to_vec_float_and_do(arr, move |mut float_array| {
@@ -575,7 +782,7 @@ pub mod stats {
             .data(&regress_data)
             .data_columns("y", vars)
             .fit()
-            .map_err(|e| EvalAltResult::ErrorArithmetic(e.to_string(), Position::NONE))?;
+            .map_err(|e| arithmetic_error(e.to_string()))?;
 
         let parameters = Dynamic::from_array(
             model
@@ -608,4 +815,669 @@ pub mod stats {
         result.insert(se, standard_errors);
         Ok(result)
     }
+
+    /// Performs a one-sample t-test of the null hypothesis that the data in `x` come from a
+    /// normal distribution with mean `mu`. Returns an object map with the t `statistic`, the
+    /// degrees of freedom `df`, the `pvalue`, the confidence interval `ci` for the mean, the
+    /// sample standard deviation `sd` and whether the null hypothesis is rejected (`reject`)
+    /// at the 5% significance level.
+    /// ```typescript
+    /// let result = ttest([5.1, 4.9, 5.6, 5.8, 6.0, 5.3], 5.0);
+    /// assert_eq(result.df, 5);
+    /// assert_approx_eq(result.statistic, 2.6053232999393154, 1e-12);
+    /// assert_approx_eq(result.pvalue, 2.0 * cdf("t", -result.statistic, 5), 1e-15);
+    /// assert(result.reject);
+    /// ```
+    #[rhai_fn(name = "ttest", return_raw)]
+    pub fn ttest(x: Array, mu: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        ttest_with_options(x, mu, Map::new())
+    }
+
+    /// Performs a one-sample t-test, as above, with options:
+    ///
+    /// - `alpha`: significance level of the decision and of the confidence interval (default
+    ///   `0.05`).
+    /// - `tail`: alternative hypothesis, `"both"` (default) for a mean different from `mu`,
+    ///   `"right"` for a greater mean or `"left"` for a smaller mean. One-sided tests have
+    ///   one-sided confidence intervals.
+    /// ```typescript
+    /// let x = [5.1, 4.9, 5.6, 5.8, 6.0, 5.3];
+    /// let both = ttest(x, 5.0, #{alpha: 0.04});
+    /// let right = ttest(x, 5.0, #{tail: "right", alpha: 0.04});
+    /// assert_approx_eq(right.pvalue, both.pvalue / 2.0, 1e-15);
+    /// assert(!both.reject && right.reject);
+    /// assert_eq(right.ci[1], inf);
+    /// ```
+    #[rhai_fn(name = "ttest", return_raw)]
+    pub fn ttest_with_options(
+        x: Array,
+        mu: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let mu = crate::if_int_convert_to_float_and_do(mu, Ok)?;
+        let x = hypothesis_tests::sample(x, "x", 2)?;
+        let n = x.len() as FLOAT;
+        let (mean, variance) = mean_and_variance(&x);
+        let mut result = location_test(
+            mean,
+            (variance / n).sqrt(),
+            mu,
+            Distribution::StudentT { nu: n - 1.0 },
+            &[n - 1.0],
+            &settings,
+        );
+        result.insert("sd".into(), Dynamic::from_float(variance.sqrt()));
+        Ok(result)
+    }
+
+    /// Performs a two-sample t-test of the null hypothesis that the data in `x` and `y` come
+    /// from normal distributions with equal means and equal but unknown variances, using the
+    /// pooled variance. Returns an object map as for `ttest`, where `ci` is the confidence
+    /// interval for the difference of the means and `sd` the pooled standard deviation.
+    /// ```typescript
+    /// let x = [20.1, 22.3, 19.8, 21.5, 23.0, 20.7];
+    /// let y = [18.2, 19.5, 17.9, 20.1, 18.8];
+    /// let result = ttest2(x, y);
+    /// assert_eq(result.df, 9);
+    /// assert(result.reject);
+    /// assert(result.ci[0] > 0.0);
+    /// ```
+    #[rhai_fn(name = "ttest2", return_raw)]
+    pub fn ttest2(x: Array, y: Array) -> Result<Map, Box<EvalAltResult>> {
+        ttest2_with_options(x, y, Map::new())
+    }
+
+    /// Performs a two-sample t-test, as above, with the options of `ttest` and:
+    ///
+    /// - `vartype`: `"equal"` (default) for the pooled test, or `"unequal"` for Welch's test,
+    ///   which does not assume equal variances and uses the Welch-Satterthwaite degrees of
+    ///   freedom. Then `sd` holds the standard deviations of both samples.
+    /// - `paired`: if `true`, performs a paired t-test of the differences `x - y`, which must
+    ///   have the same length, against a zero mean. Then `sd` is the standard deviation of the
+    ///   differences.
+    /// ```typescript
+    /// let before = [72, 75, 71, 80, 77, 74];
+    /// let after = [70, 74, 68, 77, 77, 71];
+    /// let paired = ttest2(before, after, #{paired: true});
+    /// assert_eq(paired.df, 5);
+    /// assert(paired.reject);
+    /// assert(!ttest2(before, after).reject);
+    /// ```
+    /// ```typescript
+    /// let x = [20.1, 22.3, 19.8, 21.5, 23.0, 20.7];
+    /// let y = [18.2, 19.5, 17.9, 20.1, 18.8];
+    /// let welch = ttest2(x, y, #{vartype: "unequal"});
+    /// assert(welch.df > 8.0 && welch.df < 9.0);
+    /// assert_eq(welch.sd.len(), 2);
+    /// ```
+    #[rhai_fn(name = "ttest2", return_raw)]
+    pub fn ttest2_with_options(
+        x: Array,
+        y: Array,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let paired = match options.get("paired") {
+            Some(paired) => paired
+                .as_bool()
+                .map_err(|_| arithmetic_error("The option 'paired' must be a boolean"))?,
+            None => false,
+        };
+        let x = hypothesis_tests::sample(x, "x", 2)?;
+        let y = hypothesis_tests::sample(y, "y", 2)?;
+        if paired {
+            if x.len() != y.len() {
+                return Err(arithmetic_error(
+                    "The samples of a paired test must have the same length",
+                ));
+            }
+            let differences: Array = x
+                .iter()
+                .zip(&y)
+                .map(|(x, y)| Dynamic::from_float(x - y))
+                .collect();
+            let mut options = options;
+            options.remove("paired");
+            return ttest_with_options(differences, Dynamic::FLOAT_ZERO, options);
+        }
+        let (nx, ny) = (x.len() as FLOAT, y.len() as FLOAT);
+        let (mx, vx) = mean_and_variance(&x);
+        let (my, vy) = mean_and_variance(&y);
+        let vartype = match options.get("vartype") {
+            Some(vartype) => vartype
+                .clone()
+                .into_string()
+                .map_err(|_| arithmetic_error("The option 'vartype' must be a string"))?,
+            None => "equal".to_string(),
+        };
+        let (se, df, sd) = match vartype.as_str() {
+            "equal" => {
+                let df = nx + ny - 2.0;
+                let pooled = ((nx - 1.0) * vx + (ny - 1.0) * vy) / df;
+                (
+                    (pooled * (1.0 / nx + 1.0 / ny)).sqrt(),
+                    df,
+                    Dynamic::from_float(pooled.sqrt()),
+                )
+            }
+            "unequal" => {
+                let (ax, ay) = (vx / nx, vy / ny);
+                let df = (ax + ay).powi(2) / (ax * ax / (nx - 1.0) + ay * ay / (ny - 1.0));
+                (
+                    (ax + ay).sqrt(),
+                    df,
+                    Dynamic::from_array(vec![
+                        Dynamic::from_float(vx.sqrt()),
+                        Dynamic::from_float(vy.sqrt()),
+                    ]),
+                )
+            }
+            other => {
+                return Err(arithmetic_error(format!(
+                    "Unknown vartype '{other}', expected 'equal' or 'unequal'"
+                )))
+            }
+        };
+        let mut result = location_test(
+            mx - my,
+            se,
+            0.0,
+            Distribution::StudentT { nu: df },
+            &[df],
+            &settings,
+        );
+        result.insert("sd".into(), sd);
+        Ok(result)
+    }
+
+    /// Performs a z-test of the null hypothesis that the data in `x` come from a normal
+    /// distribution with mean `mu` and known standard deviation `sigma`. Returns an object map
+    /// with the z `statistic`, the `pvalue`, the confidence interval `ci` for the mean and
+    /// whether the null hypothesis is rejected (`reject`) at the 5% significance level. The
+    /// statistic is standard normal, so there are no degrees of freedom.
+    /// ```typescript
+    /// let result = ztest([2.9, 3.4, 3.1, 2.7, 3.6, 3.3, 3.0, 3.5], 3.0, 0.25);
+    /// assert_approx_eq(result.statistic, 2.121320343559643, 1e-12);
+    /// assert_approx_eq(result.ci, [3.0143, 3.3607], 1e-4);
+    /// assert(result.reject);
+    /// ```
+    #[rhai_fn(name = "ztest", return_raw)]
+    pub fn ztest(x: Array, mu: Dynamic, sigma: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        ztest_with_options(x, mu, sigma, Map::new())
+    }
+
+    /// Performs a z-test, as above, with the `alpha` and `tail` options of `ttest`.
+    /// ```typescript
+    /// let result = ztest([2.9, 3.4, 3.1, 2.7, 3.6, 3.3, 3.0, 3.5], 3.0, 0.25, #{tail: "left"});
+    /// assert(!result.reject);
+    /// assert_eq(result.ci[0], -inf);
+    /// ```
+    #[rhai_fn(name = "ztest", return_raw)]
+    pub fn ztest_with_options(
+        x: Array,
+        mu: Dynamic,
+        sigma: Dynamic,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let mu = crate::if_int_convert_to_float_and_do(mu, Ok)?;
+        let sigma = crate::if_int_convert_to_float_and_do(sigma, Ok)?;
+        if sigma.is_nan() || sigma <= 0.0 {
+            return Err(arithmetic_error("The standard deviation must be positive"));
+        }
+        let x = hypothesis_tests::sample(x, "x", 1)?;
+        let n = x.len() as FLOAT;
+        let mean = x.iter().sum::<FLOAT>() / n;
+        Ok(location_test(
+            mean,
+            sigma / n.sqrt(),
+            mu,
+            Distribution::Normal {
+                mu: 0.0,
+                sigma: 1.0,
+            },
+            &[],
+            &settings,
+        ))
+    }
+
+    /// Performs a chi-square goodness-of-fit test of the null hypothesis that the counts in
+    /// `observed` follow the `expected` frequencies, given as counts or probabilities and scaled
+    /// to the total of the observed counts. Returns an object map with the chi-square
+    /// `statistic`, the degrees of freedom `df`, the `pvalue`, whether the null hypothesis is
+    /// rejected (`reject`) at the 5% significance level and the `expected` counts.
+    /// ```typescript
+    /// // Is the die fair?
+    /// let result = chi2gof([22, 17, 20, 26, 22, 13], [1, 1, 1, 1, 1, 1]);
+    /// assert_eq(result.df, 5);
+    /// assert_approx_eq(result.statistic, 5.1, 1e-12);
+    /// assert(!result.reject);
+    /// ```
+    #[rhai_fn(name = "chi2gof", return_raw)]
+    pub fn chi2gof(observed: Array, expected: Array) -> Result<Map, Box<EvalAltResult>> {
+        chi2gof_with_options(observed, expected, Map::new())
+    }
+
+    /// Performs a chi-square goodness-of-fit test, as above, with options:
+    ///
+    /// - `alpha`: significance level (default `0.05`).
+    /// - `nparams`: number of parameters of the expected distribution estimated from the data,
+    ///   which are subtracted from the degrees of freedom (default `0`).
+    /// ```typescript
+    /// let result = chi2gof([30, 45, 25], [0.25, 0.5, 0.25], #{nparams: 1});
+    /// assert_eq(result.df, 1);
+    /// assert_eq(result.expected, [25.0, 50.0, 25.0]);
+    /// ```
+    #[rhai_fn(name = "chi2gof", return_raw)]
+    pub fn chi2gof_with_options(
+        observed: Array,
+        expected: Array,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let nparams = crate::int_option(&options, "nparams", 0)?;
+        let observed = hypothesis_tests::sample(observed, "observed", 2)?;
+        let expected = hypothesis_tests::sample(expected, "expected", 2)?;
+        if observed.len() != expected.len() {
+            return Err(arithmetic_error(
+                "The observed and expected frequencies must have the same length",
+            ));
+        }
+        if observed.iter().any(|o| *o < 0.0) || expected.iter().any(|e| e.is_nan() || *e <= 0.0) {
+            return Err(arithmetic_error(
+                "The observed counts must be non-negative and the expected frequencies positive",
+            ));
+        }
+        let df = observed.len() as INT - 1 - nparams;
+        if nparams < 0 || df < 1 {
+            return Err(arithmetic_error(format!(
+                "'nparams' must be between 0 and {}",
+                observed.len() - 2
+            )));
+        }
+        let scale = observed.iter().sum::<FLOAT>() / expected.iter().sum::<FLOAT>();
+        let expected: Vec<FLOAT> = expected.iter().map(|e| e * scale).collect();
+        let statistic = observed
+            .iter()
+            .zip(&expected)
+            .map(|(o, e)| (o - e).powi(2) / e)
+            .sum();
+        let df = df as FLOAT;
+        let mut result = upper_tail_test(
+            statistic,
+            Distribution::ChiSquared { nu: df },
+            &[df],
+            settings.alpha,
+        );
+        result.insert(
+            "expected".into(),
+            Dynamic::from_array(expected.into_iter().map(Dynamic::from_float).collect()),
+        );
+        Ok(result)
+    }
+
+    /// Performs a chi-square test of independence of the rows and columns of a contingency
+    /// table of counts. Returns an object map with the chi-square `statistic`, the degrees of
+    /// freedom `df`, the `pvalue`, whether the null hypothesis of independence is rejected
+    /// (`reject`) at the 5% significance level and the `expected` counts under independence.
+    /// ```typescript
+    /// let result = chi2test([[10, 20], [20, 10]]);
+    /// assert_eq(result.df, 1);
+    /// assert_approx_eq(result.statistic, 20.0 / 3.0, 1e-12);
+    /// assert_approx_eq(result.pvalue, 0.009823274507519235, 1e-12);
+    /// assert_eq(result.expected, [[15.0, 15.0], [15.0, 15.0]]);
+    /// ```
+    #[rhai_fn(name = "chi2test", return_raw)]
+    pub fn chi2test(table: Array) -> Result<Map, Box<EvalAltResult>> {
+        chi2test_with_options(table, Map::new())
+    }
+
+    /// Performs a chi-square test of independence, as above, with the significance level given
+    /// by the option `alpha` (default `0.05`).
+    /// ```typescript
+    /// let result = chi2test([[10, 20], [20, 10]], #{alpha: 0.001});
+    /// assert(!result.reject);
+    /// ```
+    #[rhai_fn(name = "chi2test", return_raw)]
+    pub fn chi2test_with_options(table: Array, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let table = table
+            .into_iter()
+            .map(crate::dynamic_to_vec_float)
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns = table.first().map_or(0, Vec::len);
+        if table.len() < 2 || columns < 2 || table.iter().any(|row| row.len() != columns) {
+            return Err(arithmetic_error(
+                "The contingency table must be a matrix with at least 2 rows and 2 columns",
+            ));
+        }
+        if table.iter().flatten().any(|count| *count < 0.0) {
+            return Err(arithmetic_error("The counts must be non-negative"));
+        }
+        let row_totals: Vec<FLOAT> = table.iter().map(|row| row.iter().sum()).collect();
+        let column_totals: Vec<FLOAT> = (0..columns)
+            .map(|j| table.iter().map(|row| row[j]).sum())
+            .collect();
+        let total: FLOAT = row_totals.iter().sum();
+        if row_totals.contains(&0.0) || column_totals.contains(&0.0) {
+            return Err(arithmetic_error(
+                "Every row and column of the table must have a positive total",
+            ));
+        }
+        let expected: Vec<Vec<FLOAT>> = row_totals
+            .iter()
+            .map(|r| column_totals.iter().map(|c| r * c / total).collect())
+            .collect();
+        let statistic = table
+            .iter()
+            .flatten()
+            .zip(expected.iter().flatten())
+            .map(|(o, e)| (o - e).powi(2) / e)
+            .sum();
+        let df = ((table.len() - 1) * (columns - 1)) as FLOAT;
+        let mut result = upper_tail_test(
+            statistic,
+            Distribution::ChiSquared { nu: df },
+            &[df],
+            settings.alpha,
+        );
+        result.insert(
+            "expected".into(),
+            Dynamic::from_array(
+                expected
+                    .into_iter()
+                    .map(|row| {
+                        Dynamic::from_array(row.into_iter().map(Dynamic::from_float).collect())
+                    })
+                    .collect(),
+            ),
+        );
+        Ok(result)
+    }
+
+    /// Performs a two-sample F-test of the null hypothesis that the data in `x` and `y` come
+    /// from normal distributions with the same variance. Returns an object map with the F
+    /// `statistic` (the ratio of the sample variances), the degrees of freedom `df` of its
+    /// numerator and denominator, the `pvalue`, the confidence interval `ci` for the ratio of
+    /// the variances and whether the null hypothesis is rejected (`reject`) at the 5%
+    /// significance level.
+    /// ```typescript
+    /// let x = [4.2, 5.1, 3.9, 6.3, 5.5, 4.8, 5.9];
+    /// let y = [5.0, 5.2, 4.9, 5.1, 5.3, 4.8];
+    /// let result = vartest2(x, y);
+    /// assert_eq(result.df, [6, 5]);
+    /// assert(result.reject);
+    /// assert(result.ci[0] > 1.0);
+    /// ```
+    #[rhai_fn(name = "vartest2", return_raw)]
+    pub fn vartest2(x: Array, y: Array) -> Result<Map, Box<EvalAltResult>> {
+        vartest2_with_options(x, y, Map::new())
+    }
+
+    /// Performs a two-sample F-test, as above, with the `alpha` and `tail` options of `ttest`,
+    /// where `"right"` is the alternative that the variance of `x` is greater.
+    /// ```typescript
+    /// let x = [4.2, 5.1, 3.9, 6.3, 5.5, 4.8, 5.9];
+    /// let y = [5.0, 5.2, 4.9, 5.1, 5.3, 4.8];
+    /// let both = vartest2(x, y);
+    /// let right = vartest2(x, y, #{tail: "right"});
+    /// assert_approx_eq(right.pvalue, both.pvalue / 2.0, 1e-15);
+    /// assert_eq(right.ci[1], inf);
+    /// ```
+    #[rhai_fn(name = "vartest2", return_raw)]
+    pub fn vartest2_with_options(
+        x: Array,
+        y: Array,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let Settings { alpha, tail } = Settings::from_options(&options)?;
+        let x = hypothesis_tests::sample(x, "x", 2)?;
+        let y = hypothesis_tests::sample(y, "y", 2)?;
+        let (dfx, dfy) = (x.len() as FLOAT - 1.0, y.len() as FLOAT - 1.0);
+        let statistic = mean_and_variance(&x).1 / mean_and_variance(&y).1;
+        let f = Distribution::F { nu1: dfx, nu2: dfy };
+        let lower = f.cdf(statistic);
+        let (pvalue, ci) = match tail {
+            Tail::Both => (
+                2.0 * lower.min(1.0 - lower),
+                (
+                    statistic / f.icdf(1.0 - alpha / 2.0),
+                    statistic / f.icdf(alpha / 2.0),
+                ),
+            ),
+            Tail::Right => (
+                1.0 - lower,
+                (statistic / f.icdf(1.0 - alpha), FLOAT::INFINITY),
+            ),
+            Tail::Left => (lower, (0.0, statistic / f.icdf(alpha))),
+        };
+        Ok(hypothesis_tests::outcome(
+            statistic,
+            &[dfx, dfy],
+            pvalue,
+            Some(ci),
+            alpha,
+        ))
+    }
+
+    /// Performs a one-way analysis of variance of the null hypothesis that all groups have the
+    /// same mean. Each element of `groups` is the sample of one group, and the groups may have
+    /// different sizes. Returns an object map with the F `statistic`, the degrees of freedom
+    /// `df` between and within the groups, the `pvalue`, whether the null hypothesis is
+    /// rejected (`reject`) at the 5% significance level, and the sums of squares `ss` and mean
+    /// squares `ms` between and within the groups.
+    /// ```typescript
+    /// let result = anova([[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+    /// assert_eq(result.df, [2, 6]);
+    /// assert_approx_eq(result.statistic, 27.0, 1e-12);
+    /// assert_approx_eq(result.pvalue, 0.001, 1e-12);
+    /// assert_approx_eq(result.ss, [54.0, 6.0], 1e-12);
+    /// ```
+    #[rhai_fn(name = "anova", return_raw)]
+    pub fn anova(groups: Array) -> Result<Map, Box<EvalAltResult>> {
+        anova_with_options(groups, Map::new())
+    }
+
+    /// Performs a one-way analysis of variance, as above, with the significance level given by
+    /// the option `alpha` (default `0.05`).
+    /// ```typescript
+    /// let result = anova([[5.1, 4.8, 5.3], [5.0, 5.4], [5.6, 5.9, 5.5, 5.8]], #{alpha: 0.01});
+    /// assert_eq(result.df, [2, 6]);
+    /// assert(result.pvalue < 0.05 && !result.reject);
+    /// ```
+    #[rhai_fn(name = "anova", return_raw)]
+    pub fn anova_with_options(groups: Array, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let groups = groups
+            .into_iter()
+            .map(crate::dynamic_to_vec_float)
+            .collect::<Result<Vec<_>, _>>()?;
+        let total = groups.iter().map(Vec::len).sum::<usize>();
+        if groups.len() < 2 || groups.iter().any(Vec::is_empty) || total <= groups.len() {
+            return Err(arithmetic_error(
+                "There must be at least 2 non-empty groups and more values than groups",
+            ));
+        }
+        let grand_mean = groups.iter().flatten().sum::<FLOAT>() / total as FLOAT;
+        let (mut between, mut within) = (0.0, 0.0);
+        for group in &groups {
+            let mean = group.iter().sum::<FLOAT>() / group.len() as FLOAT;
+            between += group.len() as FLOAT * (mean - grand_mean).powi(2);
+            within += group.iter().map(|v| (v - mean).powi(2)).sum::<FLOAT>();
+        }
+        let df_between = (groups.len() - 1) as FLOAT;
+        let df_within = (total - groups.len()) as FLOAT;
+        let mut result = anova_term(
+            between,
+            df_between,
+            within / df_within,
+            df_within,
+            settings.alpha,
+        );
+        result.insert(
+            "ss".into(),
+            Dynamic::from_array(vec![
+                Dynamic::from_float(between),
+                Dynamic::from_float(within),
+            ]),
+        );
+        result.insert(
+            "ms".into(),
+            Dynamic::from_array(vec![
+                Dynamic::from_float(between / df_between),
+                Dynamic::from_float(within / df_within),
+            ]),
+        );
+        Ok(result)
+    }
+
+    /// Performs a balanced two-way analysis of variance. The columns of the matrix `table` are
+    /// the levels of the first factor, and its rows hold the levels of the second factor, each
+    /// taking `reps` consecutive rows of replicates, as in MATLAB's `anova2`. Returns an object
+    /// map with the results for the `columns` and `rows` factors and, if `reps` is greater than
+    /// one, their `interaction`, each holding the F `statistic`, the degrees of freedom `df` of
+    /// the term and of the error, the `pvalue`, the decision `reject` at the 5% significance
+    /// level, and the sum of squares `ss` and mean square `ms` of the term. The `error` entry
+    /// holds the `ss`, `df` and `ms` of the residuals.
+    /// ```typescript
+    /// // Two columns, two row levels with two replicates each
+    /// let table = [[1.0, 2.0],
+    ///              [1.2, 2.2],
+    ///              [3.0, 4.1],
+    ///              [3.1, 3.9]];
+    /// let result = anova(table, 2);
+    /// assert_eq(result.columns.df, [1, 4]);
+    /// assert(result.columns.reject && result.rows.reject);
+    /// assert(!result.interaction.reject);
+    /// assert_eq(result.error.df, 4);
+    /// ```
+    #[rhai_fn(name = "anova", return_raw)]
+    pub fn anova2(table: Array, reps: INT) -> Result<Map, Box<EvalAltResult>> {
+        anova2_with_options(table, reps, Map::new())
+    }
+
+    /// Performs a balanced two-way analysis of variance, as above, with the significance level
+    /// given by the option `alpha` (default `0.05`). With a single replicate, there is no
+    /// interaction term and the residuals are the interaction.
+    /// ```typescript
+    /// let table = [[1.0, 2.0, 3.1], [2.1, 2.9, 4.0], [2.9, 4.2, 5.0]];
+    /// let result = anova(table, 1, #{alpha: 0.01});
+    /// assert_eq(result.rows.df, [2, 4]);
+    /// assert(!("interaction" in result));
+    /// ```
+    #[rhai_fn(name = "anova", return_raw)]
+    pub fn anova2_with_options(
+        table: Array,
+        reps: INT,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let settings = Settings::from_options(&options)?;
+        let table = table
+            .into_iter()
+            .map(crate::dynamic_to_vec_float)
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns = table.first().map_or(0, Vec::len);
+        if table.iter().any(|row| row.len() != columns) {
+            return Err(arithmetic_error("The table must be a matrix"));
+        }
+        if reps < 1 || table.len() % reps as usize != 0 {
+            return Err(arithmetic_error(format!(
+                "The {} rows of the table cannot be split into groups of {reps} replicates",
+                table.len()
+            )));
+        }
+        let reps = reps as usize;
+        let rows = table.len() / reps;
+        if rows < 2 || columns < 2 {
+            return Err(arithmetic_error("Both factors must have at least 2 levels"));
+        }
+        let cell_mean = |i: usize, j: usize| {
+            table[i * reps..(i + 1) * reps]
+                .iter()
+                .map(|row| row[j])
+                .sum::<FLOAT>()
+                / reps as FLOAT
+        };
+        let cells: Vec<Vec<FLOAT>> = (0..rows)
+            .map(|i| (0..columns).map(|j| cell_mean(i, j)).collect())
+            .collect();
+        let grand_mean = cells.iter().flatten().sum::<FLOAT>() / (rows * columns) as FLOAT;
+        let row_means: Vec<FLOAT> = cells
+            .iter()
+            .map(|row| row.iter().sum::<FLOAT>() / columns as FLOAT)
+            .collect();
+        let column_means: Vec<FLOAT> = (0..columns)
+            .map(|j| cells.iter().map(|row| row[j]).sum::<FLOAT>() / rows as FLOAT)
+            .collect();
+        let squares = |means: &[FLOAT]| {
+            means
+                .iter()
+                .map(|m| (m - grand_mean).powi(2))
+                .sum::<FLOAT>()
+        };
+        let ss_columns = (rows * reps) as FLOAT * squares(&column_means);
+        let ss_rows = (columns * reps) as FLOAT * squares(&row_means);
+        let ss_interaction = reps as FLOAT
+            * cells
+                .iter()
+                .zip(&row_means)
+                .flat_map(|(row, r)| {
+                    row.iter()
+                        .zip(&column_means)
+                        .map(move |(cell, c)| (cell - r - c + grand_mean).powi(2))
+                })
+                .sum::<FLOAT>();
+        let ss_replicates: FLOAT = table
+            .iter()
+            .enumerate()
+            .flat_map(|(k, row)| {
+                row.iter()
+                    .zip(&cells[k / reps])
+                    .map(|(v, cell)| (v - cell).powi(2))
+            })
+            .sum();
+        let df_columns = (columns - 1) as FLOAT;
+        let df_rows = (rows - 1) as FLOAT;
+        let df_interaction = df_columns * df_rows;
+        let (ss_error, df_error) = if reps > 1 {
+            (ss_replicates, (rows * columns * (reps - 1)) as FLOAT)
+        } else {
+            (ss_interaction, df_interaction)
+        };
+        let ms_error = ss_error / df_error;
+        let alpha = settings.alpha;
+        let mut result = Map::new();
+        result.insert(
+            "columns".into(),
+            Dynamic::from_map(anova_term(
+                ss_columns, df_columns, ms_error, df_error, alpha,
+            )),
+        );
+        result.insert(
+            "rows".into(),
+            Dynamic::from_map(anova_term(ss_rows, df_rows, ms_error, df_error, alpha)),
+        );
+        if reps > 1 {
+            result.insert(
+                "interaction".into(),
+                Dynamic::from_map(anova_term(
+                    ss_interaction,
+                    df_interaction,
+                    ms_error,
+                    df_error,
+                    alpha,
+                )),
+            );
+        }
+        let mut error = Map::new();
+        error.insert("ss".into(), Dynamic::from_float(ss_error));
+        error.insert(
+            "df".into(),
+            hypothesis_tests::degrees_of_freedom(&[df_error]),
+        );
+        error.insert("ms".into(), Dynamic::from_float(ms_error));
+        result.insert("error".into(), Dynamic::from_map(error));
+        Ok(result)
+    }
 }
@@ -0,0 +1,71 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, Map, FLOAT};
+
+#[test]
+fn simply_supported_beam_deflection() {
+    // EI w'''' = q with w = w'' = 0 at both ends; the midspan deflection is 5 q L^4 / (384 EI)
+    let sol: Map = engine()
+        .eval(
+            r#"
+            let q = 2.0;
+            let ei = 3.0;
+            bvp(|x, w| [w[1], w[2], w[3], q / ei],
+                |wa, wb| [wa[0], wa[2], wb[0], wb[2]],
+                linspace(0.0, 4.0, 9), [0.0, 0.0, 0.0, 0.0], #{tol: 1e-6})
+            "#,
+        )
+        .unwrap();
+    assert!(sol["converged"].as_bool().unwrap());
+    let x: Vec<FLOAT> = sol["x"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    let y: Array = sol["y"].clone().into_array().unwrap();
+    let mid = x.iter().position(|x| *x == 2.0).unwrap();
+    let deflection = y[mid].clone().into_array().unwrap()[0].as_float().unwrap();
+    let expected = 5.0 * 2.0 * 4.0_f64.powi(4) / (384.0 * 3.0);
+    assert!((deflection - expected).abs() < 1e-6, "{deflection}");
+}
+
+#[test]
+fn nonlinear_bratu_problem_refines_mesh() {
+    // y'' + exp(y) = 0, y(0) = y(1) = 0 has a lower solution with y(0.5) ~ 0.1405
+    let sol: Map = engine()
+        .eval(
+            r#"
+            bvp(|x, y| [y[1], -exp(y[0])], |ya, yb| [ya[0], yb[0]],
+                [0.0, 1.0], [0.0, 0.0], #{tol: 1e-6})
+            "#,
+        )
+        .unwrap();
+    assert!(sol["converged"].as_bool().unwrap());
+    let x = sol["x"].clone().into_array().unwrap();
+    assert!(x.len() > 2);
+    let max = sol["y"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|row| row.into_array().unwrap()[0].as_float().unwrap())
+        .fold(0.0, FLOAT::max);
+    assert!((max - 0.140539).abs() < 1e-4, "{max}");
+}
+
+#[test]
+fn scalar_problem_with_function_guess() {
+    // A first-order scalar problem needs a single condition, here y(0) = 2
+    let end: FLOAT = engine()
+        .eval(
+            r#"
+            let sol = bvp(|x, y| y, |ya, yb| ya - 2.0, [0.0, 0.25, 0.5, 0.75, 1.0], |x| 1.0);
+            sol.y[-1]
+            "#,
+        )
+        .unwrap();
+    assert!((end - 2.0 * std::f64::consts::E).abs() < 1e-3);
+}
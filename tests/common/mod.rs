@@ -0,0 +1,9 @@
+use rhai::{packages::Package, Engine};
+use rhai_sci::SciPackage;
+
+/// Returns an engine with the package registered.
+pub fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.register_global_module(SciPackage::new().as_shared_module());
+    engine
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, Map, FLOAT};
+
+fn grid(value: &rhai::Dynamic) -> Vec<Vec<FLOAT>> {
+    value
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|row| {
+            row.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.as_float().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn gradient_and_del2_match_analytic_fields_on_meshgrid_outputs() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            let x = linspace(0, 1, 41);
+            let y = linspace(-1, 0.5, 31);
+            let g = meshgrid(x, y);
+            // A harmonic field, so its Laplacian vanishes
+            let F = g.x.map(|row, i| row.map(|x| exp(x) * cos(y[i])));
+            let dF = gradient(F, g.x, g.y);
+            #{x: x, y: y, fx: dF.x, fy: dF.y, L: del2(F, x, y)}
+            "#,
+        )
+        .unwrap();
+    let x: Vec<FLOAT> = result["x"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    let y: Vec<FLOAT> = result["y"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    let (fx, fy, laplacian) = (grid(&result["fx"]), grid(&result["fy"]), grid(&result["L"]));
+    for i in 1..y.len() - 1 {
+        for j in 1..x.len() - 1 {
+            let (dx, dy) = (x[j].exp() * y[i].cos(), -x[j].exp() * y[i].sin());
+            assert!((fx[i][j] - dx).abs() < 1e-3, "{} vs {dx}", fx[i][j]);
+            assert!((fy[i][j] - dy).abs() < 1e-3, "{} vs {dy}", fy[i][j]);
+        }
+    }
+    let worst = laplacian
+        .iter()
+        .flatten()
+        .fold(0.0 as FLOAT, |m, l| m.max(l.abs()));
+    assert!(worst < 5e-3, "{worst}");
+}
+
+#[test]
+fn diff_keeps_integers_and_empties_past_the_length() {
+    let result: Array = engine().eval("diff([[1, 2, 4], [3, 5, 8]], 3, 2)").unwrap();
+    assert_eq!(format!("{result:?}"), "[[], []]");
+    let result: Array = engine().eval("diff([[1, 2], [3, 5.5]], 1, 1)").unwrap();
+    assert_eq!(format!("{result:?}"), "[[2, 3.5]]");
+    let error = engine().eval::<Array>("diff([1, 2, 3], 1, 1)").unwrap_err();
+    assert!(error.to_string().contains("dimension 2"));
+}
+
+#[test]
+fn spacing_must_match_the_data() {
+    let error = engine()
+        .eval::<Map>("gradient([[1, 2], [3, 4]], [0, 1, 2], 1)")
+        .unwrap_err();
+    assert!(error.to_string().contains("'hx' has 3 coordinates"));
+    let error = engine()
+        .eval::<Array>("del2([1, 2, 3], [0, 1, 1])")
+        .unwrap_err();
+    assert!(error.to_string().contains("distinct"));
+}
+
+#[test]
+fn derivative_is_accurate_for_every_order() {
+    // Derivatives of exp(2x) at x = 0.3 are 2^n exp(0.6)
+    for order in 1..=4 {
+        let value: FLOAT = engine()
+            .eval(&format!("derivative(|x| exp(2.0 * x), 0.3, {order})"))
+            .unwrap();
+        let exact = 2.0_f64.powi(order) * 0.6_f64.exp();
+        let tolerance = [1e-10, 1e-8, 1e-6, 1e-4][order as usize - 1];
+        assert!(
+            (value - exact).abs() < tolerance * exact,
+            "order {order}: {value} vs {exact}"
+        );
+    }
+    let error = engine()
+        .eval::<FLOAT>("derivative(|x| x, 1.0, 5)")
+        .unwrap_err();
+    assert!(error.to_string().contains("between 1 and 4"));
+}
+
+#[test]
+fn jacobian_and_hessian_agree_for_gradients() {
+    // The Jacobian of the analytic gradient is the Hessian
+    let result: Map = engine()
+        .eval(
+            r#"
+            let f = |x| x[0] ** 3 * x[1] + sin(x[1] * x[2]) + x[2] ** 2;
+            let df = |x| [
+                3.0 * x[0] ** 2 * x[1],
+                x[0] ** 3 + x[2] * cos(x[1] * x[2]),
+                x[1] * cos(x[1] * x[2]) + 2.0 * x[2],
+            ];
+            let x = [0.7, -0.4, 1.3];
+            #{jacobian: jacobian(df, x), hessian: hessian(f, x)}
+            "#,
+        )
+        .unwrap();
+    let (jacobian, hessian) = (grid(&result["jacobian"]), grid(&result["hessian"]));
+    for i in 0..3 {
+        for j in 0..3 {
+            assert!((jacobian[i][j] - hessian[i][j]).abs() < 1e-6);
+            assert_eq!(hessian[i][j], hessian[j][i]);
+        }
+    }
+}
+
+#[test]
+fn differentiation_reports_bad_functions() {
+    let error = engine()
+        .eval::<FLOAT>("derivative(|x| ln(x), 0.05)")
+        .unwrap_err();
+    assert!(error.to_string().contains("not finite"));
+    let error = engine()
+        .eval::<Array>("hessian(|x| x, [1.0, 2.0])")
+        .unwrap_err();
+    assert!(error.to_string().contains("must return a number"));
+    let error = engine()
+        .eval::<Array>("jacobian(|x| if x[0] > 1.0 { [x[0]] } else { [x[0], x[1]] }, [1.0, 2.0])")
+        .unwrap_err();
+    assert!(error.to_string().contains("same number of values"));
+}
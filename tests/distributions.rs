@@ -0,0 +1,195 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, FLOAT};
+use rhai_sci::Distribution;
+
+const CONTINUOUS: [(&str, &[FLOAT]); 10] = [
+    ("normal", &[1.5, 2.0]),
+    ("lognormal", &[0.2, 0.7]),
+    ("uniform", &[-1.0, 3.0]),
+    ("exponential", &[2.5]),
+    ("gamma", &[0.6, 2.0]),
+    ("beta", &[2.5, 0.8]),
+    ("t", &[3.5]),
+    ("chi2", &[7.0]),
+    ("f", &[4.0, 9.0]),
+    ("weibull", &[1.5, 2.5]),
+];
+
+const DISCRETE: [(&str, &[FLOAT]); 3] = [
+    ("binomial", &[25.0, 0.3]),
+    ("poisson", &[6.5]),
+    ("geometric", &[0.2]),
+];
+
+#[test]
+fn icdf_inverts_cdf() {
+    for (name, params) in CONTINUOUS {
+        let d = Distribution::new(name, params).unwrap();
+        for p in [1e-6, 0.01, 0.2, 0.5, 0.8, 0.99, 1.0 - 1e-6] {
+            let x = d.icdf(p);
+            assert!(
+                (d.cdf(x) - p).abs() < 1e-12,
+                "{name}: cdf(icdf({p})) = {}",
+                d.cdf(x)
+            );
+        }
+    }
+    for (name, params) in DISCRETE {
+        let d = Distribution::new(name, params).unwrap();
+        for p in [1e-6, 0.01, 0.2, 0.5, 0.8, 0.99, 1.0 - 1e-6] {
+            let k = d.icdf(p);
+            assert!(d.cdf(k) >= p * (1.0 - 1e-12), "{name}: {k} at {p}");
+            assert!(k == 0.0 || d.cdf(k - 1.0) < p, "{name}: {k} at {p}");
+        }
+    }
+}
+
+#[test]
+fn pdf_integrates_to_cdf() {
+    for (name, params) in CONTINUOUS {
+        let d = Distribution::new(name, params).unwrap();
+        let (a, b) = (d.icdf(0.05), d.icdf(0.9));
+        let integral: FLOAT = engine()
+            .eval(&format!(
+                "integral(|x| pdf(\"{name}\", x, {params:?}), {a:?}, {b:?})"
+            ))
+            .unwrap();
+        assert!((integral - 0.85).abs() < 1e-8, "{name}: {integral}");
+    }
+    for (name, params) in DISCRETE {
+        let d = Distribution::new(name, params).unwrap();
+        let mut total = 0.0;
+        for k in 0..=20 {
+            total += d.pdf(k as FLOAT);
+            assert!((total - d.cdf(k as FLOAT)).abs() < 1e-12, "{name} at {k}");
+        }
+        assert_eq!(d.pdf(2.5), 0.0);
+        assert_eq!(d.cdf(2.5), d.cdf(2.0));
+    }
+}
+
+#[test]
+fn moments_match_the_densities() {
+    for (name, params) in CONTINUOUS {
+        let d = Distribution::new(name, params).unwrap();
+        let moments: Array = engine()
+            .eval(&format!(
+                r#"
+                let f = |x| pdf("{name}", x, {params:?});
+                let m = integral(|x| x * f.call(x), {a:?}, {b:?}, #{{rel_tol: 1e-12}});
+                [m, integral(|x| (x - m) ** 2 * f.call(x), {a:?}, {b:?}, #{{rel_tol: 1e-12}})]
+                "#,
+                a = d.icdf(0.0),
+                b = d.icdf(1.0),
+            ))
+            .unwrap();
+        let mean = moments[0].as_float().unwrap();
+        let variance = moments[1].as_float().unwrap();
+        assert!(
+            (mean - d.mean()).abs() < 1e-6 * d.mean().abs().max(1.0),
+            "{name}: {mean}"
+        );
+        assert!(
+            (variance - d.variance()).abs() < 1e-6 * d.variance(),
+            "{name}: {variance}"
+        );
+    }
+    for (name, params) in DISCRETE {
+        let d = Distribution::new(name, params).unwrap();
+        let mean: FLOAT = (0..500).map(|k| k as FLOAT * d.pdf(k as FLOAT)).sum();
+        let variance: FLOAT = (0..500)
+            .map(|k| (k as FLOAT - mean).powi(2) * d.pdf(k as FLOAT))
+            .sum();
+        assert!((mean - d.mean()).abs() < 1e-10, "{name}: {mean}");
+        assert!((variance - d.variance()).abs() < 1e-9, "{name}: {variance}");
+    }
+}
+
+#[test]
+fn large_shapes_keep_their_accuracy() {
+    let e = engine();
+    for (script, expected) in [
+        (r#"cdf("gamma", 1e8, [1e8, 1.0])"#, 0.500_013_298_076_014),
+        (r#"cdf("poisson", 1e8, [1e8])"#, 0.500_026_596_151_993),
+        (r#"cdf("gamma", 1e12, [1e12, 1.0])"#, 0.5 + 1.329_807_6e-7),
+        (r#"cdf("poisson", 1e12, [1e12])"#, 0.5 + 2.659_615_2e-7),
+    ] {
+        let value: FLOAT = e.eval(script).unwrap();
+        assert!((value - expected).abs() < 1e-12, "{script}: {value}");
+    }
+    // The series and the asymptotic expansion agree where they meet
+    for x in [0.99e5, 1e5, 1.01e5] {
+        let below = Distribution::new("gamma", &[1e5, 1.0]).unwrap().cdf(x);
+        let above = Distribution::new("gamma", &[1e5 * (1.0 + 1e-15), 1.0])
+            .unwrap()
+            .cdf(x);
+        assert!((below - above).abs() < 1e-9, "{x}: {below} and {above}");
+    }
+}
+
+#[test]
+fn discrete_quantiles_far_from_the_origin() {
+    for (name, params) in [
+        ("geometric", &[1e-15][..]),
+        ("poisson", &[1e9]),
+        ("poisson", &[1e12]),
+        ("binomial", &[1e6, 0.4]),
+    ] {
+        let d = Distribution::new(name, params).unwrap();
+        for p in [1e-6, 0.5, 1.0 - 1e-6] {
+            let k = d.icdf(p);
+            assert!(d.cdf(k) >= p * (1.0 - 1e-12), "{name}: {k} at {p}");
+            assert!(k == 0.0 || d.cdf(k - 1.0) < p, "{name}: {k} at {p}");
+        }
+    }
+    let median: FLOAT = engine().eval("icdf(\"geometric\", 0.5, [1e-15])").unwrap();
+    let expected = (0.5_f64.ln() / (-1e-15_f64).ln_1p() - 1.0).ceil();
+    assert!((median - expected).abs() <= 1e-12 * expected, "{median}");
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn samples_follow_the_distribution() {
+    use randlib::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(7);
+    let n = 20000;
+    for (name, params) in CONTINUOUS.iter().chain(DISCRETE.iter()) {
+        let d = Distribution::new(name, params).unwrap();
+        let samples: Vec<FLOAT> = (0..n).map(|_| d.sample(&mut rng)).collect();
+        // The fraction below each quartile is binomial, with a standard deviation near 0.003
+        for p in [0.25, 0.5, 0.75] {
+            let q = d.icdf(p);
+            let below = samples.iter().filter(|x| **x <= q).count() as FLOAT / n as FLOAT;
+            let expected = d.cdf(q);
+            assert!(
+                (below - expected).abs() < 0.015,
+                "{name}: {below} vs {expected}"
+            );
+        }
+    }
+    // Large means are drawn by bisection rather than by stepping through every integer
+    let draws: Array = engine()
+        .eval("rng_seed(3); random(\"poisson\", [1e9], 100)")
+        .unwrap();
+    for draw in draws {
+        let draw = draw.as_float().unwrap();
+        assert!((draw - 1e9).abs() < 6.0 * 1e9_f64.sqrt(), "{draw}");
+    }
+}
+
+#[test]
+fn invalid_distributions_are_reported() {
+    for (script, message) in [
+        ("pdf(\"cauchy\", 0, [0, 1])", "Unknown distribution"),
+        ("cdf(\"normal\", 0, 1)", "takes 2 parameters"),
+        ("cdf(\"normal\", 0, [0, -1])", "sigma must be positive"),
+        ("pdf(\"binomial\", 1, [2.5, 0.5])", "non-negative integer"),
+        ("icdf(\"t\", 1.5, 3)", "between 0 and 1"),
+        ("pdf(\"normal\", \"x\", [0, 1])", "array of numbers"),
+    ] {
+        let error = engine().eval::<rhai::Dynamic>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
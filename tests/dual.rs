@@ -0,0 +1,126 @@
+mod common;
+
+use common::engine;
+use rhai::FLOAT;
+
+#[test]
+fn dual_overloads_match_numerical_derivatives() {
+    let inside = [
+        "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "atanh",
+        "sqrt", "exp", "ln", "log", "abs", "deg2rad", "rad2deg", "sind", "cosd", "tand", "asind",
+        "acosd", "atand", "sinhd", "coshd", "tanhd", "asinhd", "atanhd", "csc", "sec", "cot",
+        "cscd", "secd", "cotd", "acot", "acotd", "csch", "sech", "coth", "cschd", "sechd", "cothd",
+        "acsch", "asech", "acschd", "asechd",
+    ];
+    let outside = [
+        "acosh", "acoshd", "acsc", "asec", "acscd", "asecd", "acoth", "acothd",
+    ];
+    let engine = engine();
+    for (names, x) in [(&inside[..], 0.4), (&outside[..], 1.7)] {
+        for name in names {
+            let exact: FLOAT = engine.eval(&format!("grad(|x| {name}(x), {x})")).unwrap();
+            let numerical: FLOAT = engine
+                .eval(&format!("derivative(|x| {name}(x), {x})"))
+                .unwrap();
+            assert!(
+                (exact - numerical).abs() < 1e-8 * numerical.abs().max(1.0),
+                "{name}: {exact} vs {numerical}"
+            );
+        }
+    }
+}
+
+#[test]
+fn two_argument_inverse_tangents_match_numerical_derivatives() {
+    let engine = engine();
+    for name in ["atan", "atand"] {
+        for (y, x) in [(0.5, 1.0), (0.5, -1.0), (-2.0, -0.3), (-1.0, 0.2)] {
+            let numerical: rhai::Array = engine
+                .eval(&format!(
+                    "[derivative(|t| {name}(t, {x:?}), {y:?}), derivative(|t| {name}({y:?}, t), {x:?})]"
+                ))
+                .unwrap();
+            // Both arguments dual, and one dual argument with a number
+            for script in [
+                format!("grad(|v| {name}(v[0], v[1]), [{y:?}, {x:?}])"),
+                format!("[grad(|t| {name}(t, {x:?}), {y:?}), grad(|t| {name}({y:?}, t), {x:?})]"),
+            ] {
+                let exact: rhai::Array = engine.eval(&script).unwrap();
+                for (e, n) in exact.iter().zip(&numerical) {
+                    let (e, n) = (e.as_float().unwrap(), n.as_float().unwrap());
+                    assert!(
+                        (e - n).abs() < 1e-8 * n.abs().max(1.0),
+                        "{script}: {e} vs {n}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn grad_of_a_script_model_matches_finite_differences() {
+    // A one-neuron logistic model with a branch and integer constants
+    let script = r#"
+        fn loss(w) {
+            let total = 0;
+            for sample in [[0.0, 1.0, 0.0], [1.0, 0.5, 1.0], [2.0, -1.0, 1.0]] {
+                let z = w[0] * sample[0] + w[1] * sample[1] + w[2];
+                let p = 1 / (1 + exp(-z));
+                let error = if p > sample[2] { p - sample[2] } else { sample[2] - p };
+                total += error ** 2 + 0.1 * hypot(w[0], w[1]);
+            }
+            total
+        }
+        let w = [0.3, -0.8, 0.1];
+        [grad(loss, w), [0, 1, 2].map(|i| derivative(|t| {
+            let v = w;
+            v[i] = t;
+            loss(v)
+        }, w[i]))]
+    "#;
+    let result: rhai::Array = engine().eval(script).unwrap();
+    let exact = result[0].clone().into_array().unwrap();
+    let numerical = result[1].clone().into_array().unwrap();
+    for (e, n) in exact.iter().zip(&numerical) {
+        let (e, n) = (e.as_float().unwrap(), n.as_float().unwrap());
+        assert!((e - n).abs() < 1e-8, "{e} vs {n}");
+    }
+}
+
+#[test]
+fn piecewise_functions_match_numerical_derivatives() {
+    let engine = engine();
+    for (body, x) in [
+        ("max(x * x, 1.0)", 2.0),
+        ("max(1, 3.0 * x)", 0.5),
+        ("max(x, x * x)", 0.5),
+        ("min(x * x, 1)", 0.5),
+        ("min(2.0, exp(x))", 1.0),
+        ("sum([x, x * x, 1, 2.0])", 1.5),
+        ("floor(x) * x + round(x) + ceiling(x)", 2.3),
+        ("x % 2.0 + 7.0 % x", 2.7),
+        ("(x * x) % x", 1.3),
+    ] {
+        let exact: FLOAT = engine.eval(&format!("grad(|x| {body}, {x:?})")).unwrap();
+        let numerical: FLOAT = engine
+            .eval(&format!("derivative(|x| {body}, {x:?})"))
+            .unwrap();
+        assert!(
+            (exact - numerical).abs() < 1e-6 * numerical.abs().max(1.0),
+            "{body}: {exact} vs {numerical}"
+        );
+    }
+}
+
+#[test]
+fn grad_rejects_non_numeric_results() {
+    let error = engine()
+        .eval::<rhai::Array>("grad(|x| [x[0], x[1]], [1.0, 2.0])")
+        .unwrap_err();
+    assert!(error.to_string().contains("must return a number"));
+    let error = engine()
+        .eval::<rhai::Dynamic>(r#"dual(1.0, [1.0]) + "a""#)
+        .unwrap_err();
+    assert!(error.to_string().contains("only be combined with numbers"));
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, Dynamic, FLOAT, INT};
+
+/// Converts a matrix to rows of floats.
+fn rows(matrix: Array) -> Vec<Vec<FLOAT>> {
+    matrix
+        .into_iter()
+        .map(|row| {
+            row.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|x| x.as_float().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn sobol_points_form_nets() {
+    let m = 8;
+    let points = rows(engine().eval(&format!("sobol({}, 21)", 1 << m)).unwrap());
+    // Every coordinate takes each value k / 2^m exactly once
+    for j in 0..21 {
+        let mut column: Vec<FLOAT> = points.iter().map(|p| p[j] * (1 << m) as FLOAT).collect();
+        column.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (k, x) in column.iter().enumerate() {
+            assert_eq!(*x, k as FLOAT, "dimension {j}");
+        }
+    }
+    // The first two dimensions form a (0, m, 2)-net: every box of area 2^-m with dyadic sides
+    // holds exactly one point
+    for a in 0..=m {
+        let b = m - a;
+        let mut counts = vec![0; 1 << m];
+        for p in &points {
+            let i = (p[0] * (1 << a) as FLOAT) as usize;
+            let j = (p[1] * (1 << b) as FLOAT) as usize;
+            counts[(i << b) + j] += 1;
+        }
+        assert!(counts.iter().all(|c| *c == 1), "boxes of {a} and {b} bits");
+    }
+    let error = engine().eval::<Array>("sobol(4, 22)").unwrap_err();
+    assert!(error.to_string().contains("21 dimensions"));
+}
+
+#[test]
+fn halton_points_are_radical_inverses() {
+    let points = rows(engine().eval("halton(30, 3)").unwrap());
+    for (i, p) in points.iter().enumerate() {
+        for (x, base) in p.iter().zip([2, 3, 5]) {
+            assert_eq!(*x, rhai_sci::radical_inverse(i as u64, base));
+        }
+    }
+}
+
+#[test]
+fn factorial_designs_are_balanced_and_orthogonal() {
+    let full: Array = engine().eval("fullfact([3, 2, 4])").unwrap();
+    let mut runs: Vec<Vec<INT>> = full
+        .into_iter()
+        .map(|run| {
+            run.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|x| x.as_int().unwrap())
+                .collect()
+        })
+        .collect();
+    runs.sort();
+    runs.dedup();
+    assert_eq!(runs.len(), 24);
+
+    for script in [
+        "fracfact(\"a b c ab ac bc abc\")",
+        "fracfact(\"a b c d abc -bcd\")",
+        "bbdesign(5, 0)",
+        "ccdesign(3, #{center: 0, alpha: 1.5})",
+    ] {
+        let design = rows(engine().eval(script).unwrap());
+        let columns = design[0].len();
+        for i in 0..columns {
+            assert_eq!(design.iter().map(|r| r[i]).sum::<FLOAT>(), 0.0, "{script}");
+            for j in 0..i {
+                let dot: FLOAT = design.iter().map(|r| r[i] * r[j]).sum();
+                assert!(dot.abs() < 1e-12, "{script}: columns {i} and {j}");
+            }
+        }
+    }
+}
+
+#[test]
+fn central_composite_types_scale_the_design() {
+    let inscribed = rows(
+        engine()
+            .eval("ccdesign(2, #{type: \"inscribed\"})")
+            .unwrap(),
+    );
+    let alpha = 2.0_f64.sqrt();
+    assert_eq!(inscribed.len(), 9);
+    assert!(inscribed[..4]
+        .iter()
+        .flatten()
+        .all(|x| (x.abs() - 1.0 / alpha).abs() < 1e-15));
+    assert!(inscribed[4..8]
+        .iter()
+        .all(|r| r.iter().map(|x| x.abs()).sum::<FLOAT>() == 1.0));
+    // The rotatable design puts the factorial and axial runs on one sphere
+    let circumscribed = rows(engine().eval("ccdesign(4, #{center: 0})").unwrap());
+    for run in circumscribed {
+        let radius: FLOAT = run.iter().map(|x| x * x).sum();
+        assert!((radius - 4.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn invalid_designs_are_reported() {
+    for (script, message) in [
+        ("fracfact(\"a b ac\")", "not a basic factor"),
+        ("fracfact(\"a b aab\")", "repeats"),
+        ("fracfact(\"ab\")", "basic factor"),
+        ("fullfact([2, 0])", "positive integers"),
+        ("bbdesign(2)", "at least 3"),
+        ("ccdesign(2, #{type: \"cube\"})", "Unknown type"),
+        ("halton(3, 0)", "dimension positive"),
+    ] {
+        let error = engine().eval::<Dynamic>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn latin_hypercube_samples_fill_every_stratum() {
+    let n = 50;
+    let script = format!("rng_seed(11); lhsdesign({n}, 4)");
+    let points = rows(engine().eval(&script).unwrap());
+    for j in 0..4 {
+        let mut strata: Vec<usize> = points
+            .iter()
+            .map(|p| (p[j] * n as FLOAT) as usize)
+            .collect();
+        strata.sort();
+        assert_eq!(strata, (0..n).collect::<Vec<_>>());
+    }
+    assert_eq!(points, rows(engine().eval(&script).unwrap()));
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn mvnrnd_follows_degenerate_covariances() {
+    // The second coordinate is twice the first, so the covariance is singular
+    let points = rows(
+        engine()
+            .eval("rng_seed(5); mvnrnd([1, 2], [[1, 2], [2, 4]], 200)")
+            .unwrap(),
+    );
+    for p in points {
+        assert!((p[1] - 2.0 - 2.0 * (p[0] - 1.0)).abs() < 1e-12);
+    }
+    for (script, message) in [
+        ("mvnrnd([0, 0], [[1, 2], [0, 1]])", "symmetric"),
+        ("mvnrnd([0, 0], [[1, 2], [2, 1]])", "positive semi-definite"),
+        ("mvnrnd([0, 0, 0], [[1, 0], [0, 1]])", "3 x 3"),
+    ] {
+        let error = engine().eval::<Dynamic>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::engine;
+use rhai::{Dynamic, Map, FLOAT};
+
+fn test(script: &str) -> Map {
+    engine().eval(script).unwrap()
+}
+
+fn float(result: &Map, key: &str) -> FLOAT {
+    result[key].as_float().unwrap()
+}
+
+fn ci(result: &Map) -> (FLOAT, FLOAT) {
+    let ci = result["ci"].clone().into_array().unwrap();
+    (ci[0].as_float().unwrap(), ci[1].as_float().unwrap())
+}
+
+const X: &str = "[20.1, 22.3, 19.8, 21.5, 23.0, 20.7]";
+const Y: &str = "[18.2, 19.5, 17.9, 20.1, 18.8, 21.9]";
+
+#[test]
+fn paired_tests_are_tests_of_the_differences() {
+    let paired = test(&format!("ttest2({X}, {Y}, #{{paired: true}})"));
+    let differences = test(&format!(
+        "let d = []; for i in 0..6 {{ d.push({X}[i] - {Y}[i]); }} ttest(d, 0)"
+    ));
+    for key in ["statistic", "pvalue", "sd"] {
+        assert_eq!(float(&paired, key), float(&differences, key), "{key}");
+    }
+    assert_eq!(ci(&paired), ci(&differences));
+}
+
+#[test]
+fn welch_and_pooled_tests_agree_for_equal_sizes() {
+    // With equal sizes the statistics coincide, but Welch's test loses degrees of freedom as
+    // the variances differ
+    let y = "[19.2, 19.6, 18.9, 19.5, 19.9, 19.1]";
+    let pooled = test(&format!("ttest2({X}, {y})"));
+    let welch = test(&format!("ttest2({X}, {y}, #{{vartype: \"unequal\"}})"));
+    assert!((float(&pooled, "statistic") - float(&welch, "statistic")).abs() < 1e-12);
+    assert_eq!(pooled["df"].as_int().unwrap(), 10);
+    let df = float(&welch, "df");
+    assert!(df > 5.0 && df < 10.0, "{df}");
+    assert!(float(&welch, "pvalue") > float(&pooled, "pvalue"));
+}
+
+#[test]
+fn confidence_intervals_match_the_decisions() {
+    for tail in ["both", "right", "left"] {
+        for mu in [19.0, 20.0, 21.0, 21.5, 22.0, 23.0] {
+            for alpha in [0.01, 0.05, 0.2] {
+                let options = format!("#{{tail: \"{tail}\", alpha: {alpha}}}");
+                let t = test(&format!("ttest({X}, {mu}, {options})"));
+                let z = test(&format!("ztest({X}, {mu}, 1.2, {options})"));
+                for result in [t, z] {
+                    let (lo, hi) = ci(&result);
+                    let excluded = mu < lo || mu > hi;
+                    assert_eq!(
+                        excluded,
+                        result["reject"].as_bool().unwrap(),
+                        "{tail} {mu} {alpha}"
+                    );
+                }
+            }
+        }
+        let f = test(&format!("vartest2({X}, {Y}, #{{tail: \"{tail}\"}})"));
+        let (lo, hi) = ci(&f);
+        assert_eq!(
+            1.0 < lo || 1.0 > hi,
+            f["reject"].as_bool().unwrap(),
+            "{tail}"
+        );
+    }
+}
+
+#[test]
+fn two_way_sums_of_squares_add_up() {
+    let table = "[[4.1, 5.0, 6.2], [3.9, 5.3, 6.0], [4.4, 6.1, 7.5], [4.8, 5.9, 7.1]]";
+    let values = [4.1, 5.0, 6.2, 3.9, 5.3, 6.0, 4.4, 6.1, 7.5, 4.8, 5.9, 7.1];
+    let mean = values.iter().sum::<FLOAT>() / 12.0;
+    let total: FLOAT = values.iter().map(|v| (v - mean).powi(2)).sum();
+    for reps in [1, 2] {
+        let result = test(&format!("anova({table}, {reps})"));
+        let ss: FLOAT = ["columns", "rows", "interaction", "error"]
+            .iter()
+            .filter_map(|term| result.get(*term))
+            .map(|term| float(&term.clone().cast::<Map>(), "ss"))
+            .sum();
+        assert!((ss - total).abs() < 1e-12, "{reps}: {ss} vs {total}");
+    }
+    // With one replicate per cell, the error term is the interaction of the replicated design
+    let single = test(&format!("anova({table}, 1)"));
+    let columns = single["columns"].clone().cast::<Map>();
+    assert_eq!(format!("{:?}", columns["df"]), "[2, 6]");
+}
+
+#[test]
+fn one_way_anova_of_two_groups_is_a_pooled_t_test() {
+    let anova = test(&format!("anova([{X}, {Y}])"));
+    let t = test(&format!("ttest2({X}, {Y})"));
+    let statistic = float(&t, "statistic");
+    assert!((float(&anova, "statistic") - statistic * statistic).abs() < 1e-10);
+    assert!((float(&anova, "pvalue") - float(&t, "pvalue")).abs() < 1e-12);
+}
+
+#[test]
+fn invalid_tests_are_reported() {
+    for (script, message) in [
+        ("ttest([1], 0)", "at least 2 values"),
+        ("ttest([1, 2, 3], 0, #{alpha: 1.5})", "alpha"),
+        ("ttest([1, 2, 3], 0, #{tail: \"up\"})", "tail"),
+        ("ttest2([1, 2], [1, 2, 3], #{paired: true})", "same length"),
+        (
+            "ttest2([1, 2], [1, 2], #{vartype: \"other\"})",
+            "Unknown vartype",
+        ),
+        ("ztest([1, 2], 0, 0)", "must be positive"),
+        ("chi2gof([1, 2, 3], [1, 1])", "same length"),
+        ("chi2gof([1, 2, 3], [1, 1, 1], #{nparams: 2})", "nparams"),
+        ("chi2test([[1, 2], [0, 0]])", "positive total"),
+        ("chi2test([[1, 2]])", "at least 2 rows"),
+        ("anova([[1, 2]])", "at least 2 non-empty groups"),
+        (
+            "anova([[1, 2], [3, 4], [5, 6]], 2)",
+            "groups of 2 replicates",
+        ),
+    ] {
+        let error = engine().eval::<Dynamic>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
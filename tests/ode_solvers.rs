@@ -0,0 +1,183 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, FLOAT};
+
+#[test]
+fn projectile_with_drag_matches_between_solvers() {
+    // Position and velocity of a projectile with quadratic drag
+    let result: Array = engine()
+        .eval(
+            r#"
+            fn projectile(t, s) {
+                let speed = sqrt(s[2] ** 2 + s[3] ** 2);
+                [s[2], s[3], -0.1 * speed * s[2], -9.81 - 0.1 * speed * s[3]]
+            }
+            let s0 = [0.0, 0.0, 20.0, 20.0];
+            let tight = #{rtol: 1e-10, atol: 1e-10};
+            let a = ode45(Fn("projectile"), [0.0, 2.0], s0, tight);
+            let b = ode23(Fn("projectile"), [0.0, 2.0], s0, tight);
+            let c = rk4(Fn("projectile"), [0.0, 2.0], s0, #{step: 0.001});
+            [a.y[-1], b.y[-1], c.y[-1]]
+            "#,
+        )
+        .unwrap();
+    let states: Vec<Vec<FLOAT>> = result
+        .into_iter()
+        .map(|s| {
+            s.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.as_float().unwrap())
+                .collect()
+        })
+        .collect();
+    for other in &states[1..] {
+        for (u, v) in states[0].iter().zip(other) {
+            assert!((u - v).abs() < 1e-6, "{u} vs {v}");
+        }
+    }
+}
+
+#[test]
+fn backward_integration_recovers_initial_state() {
+    let y0: FLOAT = engine()
+        .eval(
+            r#"
+            let f = |t, y| y * cos(t);
+            let forward = ode45(f, [0.0, 3.0], 2.0, #{rtol: 1e-9, atol: 1e-12});
+            let backward = ode45(f, [3.0, 0.0], forward.y[-1], #{rtol: 1e-9, atol: 1e-12});
+            backward.y[-1]
+            "#,
+        )
+        .unwrap();
+    assert!((y0 - 2.0).abs() < 1e-7);
+}
+
+#[test]
+fn mismatched_derivative_length_is_an_error() {
+    let error = engine()
+        .eval::<rhai::Map>("ode45(|t, y| [y[0]], [0.0, 1.0], [1.0, 2.0])")
+        .unwrap_err();
+    assert!(error.to_string().contains("returned 1 values"));
+}
+
+#[test]
+fn non_finite_derivatives_shrink_the_step() {
+    // Trial steps past y = 0 make sqrt(y) NaN; they are rejected like steps that are too long
+    let y: FLOAT = engine()
+        .eval("ode45(|t, y| -sqrt(y), [0.0, 1.99], 1.0).y[-1]")
+        .unwrap();
+    assert!((y - 0.005_f64.powi(2)).abs() < 1e-4, "{y}");
+    for script in [
+        "ode45(|t, y| sqrt(y), [0.0, 1.0], -1.0)",
+        "ode23(|t, y| 0.0 / 0.0, [0.0, 1.0], 1.0)",
+    ] {
+        let error = engine().eval::<rhai::Map>(script).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Unable to meet the integration tolerances"),
+            "{script}: {error}"
+        );
+    }
+}
+
+#[test]
+fn operation_limit_stops_integration() {
+    let mut engine = engine();
+    engine.set_max_operations(100);
+    let error = engine
+        .eval::<rhai::Map>("ode45(|t, y| -y, [0.0, 10.0], 1.0, #{rtol: 1e-10})")
+        .unwrap_err();
+    assert!(matches!(
+        *error,
+        rhai::EvalAltResult::ErrorTooManyOperations(..)
+    ));
+}
+
+#[test]
+fn stiff_solvers_agree_on_van_der_pol() {
+    // The van der Pol oscillator with mu = 1000 is too stiff for explicit methods
+    let result: Array = engine()
+        .eval(
+            r#"
+            let vdp = |t, y| [y[1], 1000.0 * (1.0 - y[0] ** 2) * y[1] - y[0]];
+            let options = #{rtol: 1e-6, atol: 1e-8};
+            let a = ode15s(vdp, [0.0, 500.0], [2.0, 0.0], options);
+            let b = ode23s(vdp, [0.0, 500.0], [2.0, 0.0], options);
+            [a.y[-1][0], b.y[-1][0], len(a.t), len(b.t)]
+            "#,
+        )
+        .unwrap();
+    let (a, b) = (result[0].as_float().unwrap(), result[1].as_float().unwrap());
+    assert!((a - b).abs() < 1e-3, "{a} vs {b}");
+    assert!(a > 1.0 && a < 2.0);
+    assert!(result[2].as_int().unwrap() < 2000);
+}
+
+#[test]
+fn robertson_kinetics_reaches_long_times() {
+    let result: Array = engine()
+        .eval(
+            r#"
+            fn robertson(t, y) {
+                [-0.04 * y[0] + 1e4 * y[1] * y[2],
+                 0.04 * y[0] - 1e4 * y[1] * y[2] - 3e7 * y[1] ** 2,
+                 3e7 * y[1] ** 2]
+            }
+            fn jac(t, y) {
+                [[-0.04, 1e4 * y[2], 1e4 * y[1]],
+                 [0.04, -1e4 * y[2] - 6e7 * y[1], -1e4 * y[1]],
+                 [0.0, 6e7 * y[1], 0.0]]
+            }
+            let options = #{rtol: 1e-6, atol: 1e-10, jacobian: Fn("jac")};
+            let sol = ode15s(Fn("robertson"), [0.0, 4e5], [1.0, 0.0, 0.0], options);
+            [sol.y[-1][0], len(sol.t)]
+            "#,
+        )
+        .unwrap();
+    // Reference value from the literature for t = 4e5
+    assert!((result[0].as_float().unwrap() - 0.0049).abs() < 1e-4);
+    assert!(result[1].as_int().unwrap() < 1000);
+}
+
+#[test]
+fn non_terminal_events_are_all_recorded() {
+    // sin(t) crosses zero upwards at 2 pi and 4 pi
+    let result: Array = engine()
+        .eval(
+            r#"
+            let sol = ode45(|t, y| [y[1], -y[0]], [0.1, 13.0], [sin(0.1), cos(0.1)],
+                            #{events: |t, y| y[0], direction: 1, rtol: 1e-9, atol: 1e-12});
+            [sol.te, sol.t[-1]]
+            "#,
+        )
+        .unwrap();
+    let te = result[0].clone().into_array().unwrap();
+    assert_eq!(te.len(), 2);
+    for (k, t) in te.iter().enumerate() {
+        let expected = 2.0 * std::f64::consts::PI * (k + 1) as FLOAT;
+        assert!((t.as_float().unwrap() - expected).abs() < 1e-7);
+    }
+    assert_eq!(result[1].as_float().unwrap(), 13.0);
+}
+
+#[test]
+fn operation_limit_stops_stiff_callbacks() {
+    // The Jacobian and event functions count towards the limit as well
+    for script in [
+        "ode15s(|t, y| -1e3 * y, [0.0, 1.0], 1.0, #{jacobian: |t, y| [[-1e3]]})",
+        "ode23s(|t, y| -1e3 * y, [0.0, 1.0], 1.0, #{events: |t, y| y - 0.5})",
+    ] {
+        let mut engine = engine();
+        engine.set_max_operations(100);
+        let error = engine.eval::<rhai::Map>(script).unwrap_err();
+        assert!(
+            matches!(*error, rhai::EvalAltResult::ErrorTooManyOperations(..)),
+            "{script}: {error}"
+        );
+        engine.set_max_operations(0);
+        assert!(engine.eval::<rhai::Map>(script).is_ok(), "{script}");
+    }
+}
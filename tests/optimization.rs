@@ -0,0 +1,637 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, Map, FLOAT};
+
+#[test]
+fn nelder_mead_minimizes_shifted_quadratic_in_four_dimensions() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            let f = |x| (x[0] - 1.0) ** 2 + 2.0 * (x[1] + 1.0) ** 2 + 3.0 * (x[2] - 0.5) ** 2 + x[3] ** 2;
+            fminsearch(f, [0, 0, 0, 1], #{tol_x: 1e-8, tol_f: 1e-14, max_evaluations: 5000, max_iterations: 5000})
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    let x: Vec<FLOAT> = result["x"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    for (u, v) in x.iter().zip([1.0, -1.0, 0.5, 0.0]) {
+        assert!((u - v).abs() < 1e-6, "{u} vs {v}");
+    }
+}
+
+#[test]
+fn evaluation_limit_stops_the_search() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            fminsearch(|x| 100.0 * (x[1] - x[0] ** 2) ** 2 + (1.0 - x[0]) ** 2, [-1.2, 1.0],
+                       #{max_evaluations: 30})
+            "#,
+        )
+        .unwrap();
+    assert_eq!(
+        result["status"].clone().into_string().unwrap(),
+        "max_evaluations"
+    );
+    assert!(!result["converged"].as_bool().unwrap());
+    // The last iteration may use a few evaluations beyond the limit
+    assert!(result["evaluations"].as_int().unwrap() < 30 + 3);
+}
+
+#[test]
+fn callback_sees_decreasing_values() {
+    let values: Array = engine()
+        .eval(
+            r#"
+            let log = [];
+            fminbnd(|x| cos(x), 2.0, 5.0, #{callback: |state| { log.push(state.fval); }});
+            log
+            "#,
+        )
+        .unwrap();
+    let values: Vec<FLOAT> = values.into_iter().map(|v| v.as_float().unwrap()).collect();
+    assert!(values.len() > 3);
+    assert!(values.windows(2).all(|w| w[1] <= w[0]));
+    assert!((values.last().unwrap() + 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn reversed_interval_is_an_error() {
+    let error = engine()
+        .eval::<Map>("fminbnd(|x| x * x, 1.0, -1.0)")
+        .unwrap_err();
+    assert!(error.to_string().contains("lower bound"));
+}
+
+#[test]
+fn nelder_mead_does_not_converge_on_nan_values() {
+    let error = engine()
+        .eval::<Map>("fminsearch(|x| sqrt(x[0]) + x[1] * x[1], [-1.0, 1.0])")
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("not finite at the starting point"));
+
+    // Finite only at the starting point, so the spread of the simplex is NaN
+    let result: Map = engine()
+        .eval(
+            "fminsearch(|x| if x == [1.0, 1.0] { 0.0 } else { 0.0 / 0.0 }, [1.0, 1.0], \
+             #{max_iterations: 20})",
+        )
+        .unwrap();
+    assert!(!result["converged"].as_bool().unwrap());
+    assert_eq!(
+        result["status"].clone().into_string().unwrap(),
+        "max_iterations"
+    );
+}
+
+#[test]
+fn lbfgs_solves_extended_rosenbrock_with_analytic_gradient() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            fn rosenbrock(x) {
+                let total = 0.0;
+                for i in 0..len(x) - 1 {
+                    total += 100.0 * (x[i + 1] - x[i] ** 2) ** 2 + (1.0 - x[i]) ** 2;
+                }
+                total
+            }
+            fn rosenbrock_gradient(x) {
+                let g = [];
+                g.pad(len(x), 0.0);
+                for i in 0..len(x) - 1 {
+                    let t = x[i + 1] - x[i] ** 2;
+                    g[i] += -400.0 * x[i] * t - 2.0 * (1.0 - x[i]);
+                    g[i + 1] += 200.0 * t;
+                }
+                g
+            }
+            let x0 = [];
+            x0.pad(50, -1.2);
+            fminunc(Fn("rosenbrock"), x0, Fn("rosenbrock_gradient"), #{method: "lbfgs", max_iterations: 2000})
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    for x in result["x"].clone().into_array().unwrap() {
+        assert!((x.as_float().unwrap() - 1.0).abs() < 1e-5);
+    }
+    let history = result["history"].clone().cast::<Map>();
+    let fvals: Vec<FLOAT> = history["fval"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    assert!(fvals.windows(2).all(|w| w[1] <= w[0]));
+}
+
+#[test]
+fn finite_differences_agree_with_analytic_gradient() {
+    let result: Array = engine()
+        .eval(
+            r#"
+            let f = |x| exp(x[0] - 1.0) - x[0] + (x[1] - x[0]) ** 2 + x[2] ** 4 + x[2] ** 2;
+            let g = |x| [exp(x[0] - 1.0) - 1.0 - 2.0 * (x[1] - x[0]), 2.0 * (x[1] - x[0]), 4.0 * x[2] ** 3 + 2.0 * x[2]];
+            let a = fminunc(f, [0, 0, 1], g);
+            let b = fminunc(f, [0, 0, 1]);
+            [a.x, b.x, a.evaluations < b.evaluations]
+            "#,
+        )
+        .unwrap();
+    let a = result[0].clone().into_array().unwrap();
+    let b = result[1].clone().into_array().unwrap();
+    for ((u, v), expected) in a.iter().zip(&b).zip([1.0, 1.0, 0.0]) {
+        assert!((u.as_float().unwrap() - expected).abs() < 1e-6);
+        assert!((v.as_float().unwrap() - expected).abs() < 1e-6);
+    }
+    assert!(result[2].as_bool().unwrap());
+}
+
+#[test]
+fn gradient_of_wrong_length_is_an_error() {
+    let error = engine()
+        .eval::<Map>("fminunc(|x| x[0] ** 2 + x[1] ** 2, [1.0, 1.0], |x| [2.0 * x[0]])")
+        .unwrap_err();
+    assert!(error.to_string().contains("returned 1 values"));
+}
+
+#[test]
+fn fmincon_solves_hock_schittkowski_71() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            let f = |x| x[0] * x[3] * (x[0] + x[1] + x[2]) + x[2];
+            let constraints = #{
+                lb: 1, ub: 5,
+                c: |x| 25.0 - x[0] * x[1] * x[2] * x[3],
+                ceq: |x| x[0] ** 2 + x[1] ** 2 + x[2] ** 2 + x[3] ** 2 - 40.0
+            };
+            fmincon(f, [1, 5, 5, 1], constraints)
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    assert!((result["fval"].as_float().unwrap() - 17.0140173).abs() < 1e-4);
+    let x: Vec<FLOAT> = result["x"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    for (u, v) in x.iter().zip([1.0, 4.742_999_6, 3.821_150_0, 1.379_408_3]) {
+        assert!((u - v).abs() < 1e-3, "{u} vs {v}");
+    }
+    // The lower bound of the first variable and both nonlinear constraints are active
+    let lambda = result["lambda"].clone().cast::<Map>();
+    let lower = lambda["lower"].clone().into_array().unwrap();
+    assert!(lower[0].as_float().unwrap() > 0.1);
+    assert!(lower[1].as_float().unwrap().abs() < 1e-6);
+    assert!(
+        lambda["ineqnonlin"].clone().into_array().unwrap()[0]
+            .as_float()
+            .unwrap()
+            > 0.1
+    );
+}
+
+#[test]
+fn fmincon_designs_a_beam_with_stress_and_mass_constraints() {
+    // Minimize the deflection b^-1 h^-3 of a cantilever of width b and height h, subject to a
+    // bending stress limit 6 / (b h^2) <= 1 and a mass (cross-section) limit b h <= 4
+    let result: Map = engine()
+        .eval(
+            r#"
+            let constraints = #{
+                lb: [0.5, 0.5], ub: [3.0, 5.0],
+                A: [[0, 1]], b: [4.5],
+                c: |x| [6.0 / (x[0] * x[1] ** 2) - 1.0, x[0] * x[1] - 4.0]
+            };
+            fmincon(|x| 1.0 / (x[0] * x[1] ** 3), [1.0, 1.0], constraints)
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    assert!(result["max_violation"].as_float().unwrap() < 1e-6);
+    let x: Vec<FLOAT> = result["x"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    // The height reaches its linear limit and the mass constraint fixes the width
+    assert!((x[1] - 4.5).abs() < 1e-5);
+    assert!((x[0] - 4.0 / 4.5).abs() < 1e-5);
+}
+
+#[test]
+fn fmincon_only_evaluates_functions_inside_the_bounds() {
+    // The logarithms are not defined below the lower bounds, where the unconstrained minimum of
+    // the objective lies
+    let result: Map = engine()
+        .eval(
+            r#"
+            fn check(x) {
+                if x[0] < 0.0 || x[1] < 1.0 {
+                    throw `evaluated outside the bounds at ${x}`;
+                }
+            }
+            let f = |x| { check(x); (x[0] + 1.0) ** 2 + x[1] - ln(x[1]) };
+            let c = |x| { check(x); x[0] - ln(x[1]) - 1.0 };
+            fmincon(f, [-1.0, 3.0], #{lb: [0, 1], c: c})
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    let x = result["x"].clone().into_array().unwrap();
+    assert_eq!(x[0].as_float().unwrap(), 0.0);
+    assert_eq!(x[1].as_float().unwrap(), 1.0);
+    let lambda = result["lambda"].clone().cast::<Map>();
+    let lower = lambda["lower"].clone().into_array().unwrap();
+    assert!((lower[0].as_float().unwrap() - 2.0).abs() < 1e-4);
+    assert!(lower[1].as_float().unwrap().abs() < 1e-4);
+}
+
+#[test]
+fn fmincon_reports_infeasible_constraints() {
+    let result: Map = engine()
+        .eval("fmincon(|x| x * x, 1.0, #{c: |x| x * x + 1.0})")
+        .unwrap();
+    assert_eq!(
+        result["status"].clone().into_string().unwrap(),
+        "infeasible"
+    );
+    assert!(!result["converged"].as_bool().unwrap());
+}
+
+#[test]
+fn linprog_terminates_on_beales_cycling_example() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            let c = [-0.75, 20.0, -0.5, 6.0];
+            let A = [[0.25, -8.0, -1.0, 9.0], [0.5, -12.0, -0.5, 3.0], [0.0, 0.0, 1.0, 0.0]];
+            linprog(c, A, [0, 0, 1], [], [], [0, 0, 0, 0], [])
+            "#,
+        )
+        .unwrap();
+    assert!((result["fval"].as_float().unwrap() + 1.25).abs() < 1e-12);
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn quadprog_with_zero_hessian_matches_linprog() {
+    let result: Array = engine()
+        .eval(
+            r#"
+            let c = [-1, -2, 1, -3];
+            let A = [[1, 1, 1, 1], [2, -1, 0, 3], [0, 1, -2, 1]];
+            let b = [10, 12, 4];
+            let lb = [0, 0, 0, 0];
+            let ub = [5, 5, 5, 5];
+            let lp = linprog(c, A, b, [[1, 0, 1, 0]], [3], lb, ub);
+            let qp = quadprog(zeros(4, 4), c, A, b, [[1, 0, 1, 0]], [3], lb, ub);
+            [lp.fval, qp.fval]
+            "#,
+        )
+        .unwrap();
+    let (lp, qp) = (result[0].as_float().unwrap(), result[1].as_float().unwrap());
+    assert!((lp - qp).abs() < 1e-9, "{lp} vs {qp}");
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn quadprog_reports_unbounded_and_infeasible_problems() {
+    let unbounded = engine()
+        .eval::<Map>("quadprog([[1, 0], [0, 0]], [0, -1], [[1, 0]], [5])")
+        .unwrap_err();
+    assert!(unbounded.to_string().contains("unbounded"));
+    let infeasible = engine()
+        .eval::<Map>("quadprog(eye(2), [0, 0], [[1, 1]], [-1], [], [], [0, 0], [])")
+        .unwrap_err();
+    assert!(infeasible.to_string().contains("infeasible"));
+    let indefinite = engine()
+        .eval::<Map>("quadprog([[1, 0], [0, -1]], [0, 0])")
+        .unwrap_err();
+    assert!(indefinite.to_string().contains("positive semidefinite"));
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn linear_and_quadratic_programs_reject_non_finite_coefficients() {
+    for (script, message) in [
+        (
+            "linprog([0.0 / 0.0, 1.0], [[1.0, 1.0]], [1.0], [], [], [0.0, 0.0], [])",
+            "'c' must contain only finite numbers",
+        ),
+        ("linprog([1, 1], [[1, inf]], [1])", "'A' must contain"),
+        ("linprog([1, 1], [[1, 1]], [-inf])", "'b' must contain"),
+        (
+            "linprog([1, 1], [], [], [[1, 1]], [0.0 / 0.0])",
+            "'beq' must contain",
+        ),
+        ("quadprog(eye(2), [inf, 1.0])", "'f' must contain"),
+        (
+            "quadprog([[1, 0], [0, 0.0 / 0.0]], [1, 1])",
+            "'H' must contain",
+        ),
+        (
+            "quadprog(eye(2), [1, 1], [], [], [], [], [0.0 / 0.0, 0], [])",
+            "must not be NaN",
+        ),
+    ] {
+        let error = engine().eval::<Map>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn lsqcurvefit_standard_errors_match_regress_for_a_linear_model() {
+    let result: Array = engine()
+        .eval(
+            r#"
+            let x = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+            let y = [0.9, 3.2, 4.8, 7.1, 9.2, 10.8];
+            let fit = lsqcurvefit(|p, x| p[0] + p[1] * x, [0, 0], x, y);
+            let design = x.map(|x| [1.0, x]);
+            let linear = regress(design, y.map(|y| [y]));
+            [fit.parameters, linear.parameters, fit.standard_errors, linear.standard_errors]
+            "#,
+        )
+        .unwrap();
+    let values: Vec<Vec<FLOAT>> = result
+        .into_iter()
+        .map(|v| {
+            v.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.as_float().unwrap())
+                .collect()
+        })
+        .collect();
+    // `regress` also fits its own intercept, so only the slope is comparable
+    assert!((values[0][1] - values[1][1]).abs() < 1e-6);
+    assert!((values[2][1] - values[3][1]).abs() < 1e-6);
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn lsqnonlin_accepts_an_analytic_jacobian() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            // Intersection of a circle and a parabola
+            let f = |p| [p[0] ** 2 + p[1] ** 2 - 4.0, p[1] - p[0] ** 2];
+            let J = |p| [[2.0 * p[0], 2.0 * p[1]], [-2.0 * p[0], 1.0]];
+            lsqnonlin(f, [1.0, 1.0], #{jacobian: J, lb: [0.0, 0.0]})
+            "#,
+        )
+        .unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    assert!(result["resnorm"].as_float().unwrap() < 1e-20);
+    let p = result["parameters"].clone().into_array().unwrap();
+    let y = p[1].as_float().unwrap();
+    // y + y^2 = 4
+    assert!((y - (17.0_f64.sqrt() - 1.0) / 2.0).abs() < 1e-10);
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn least_squares_stop_on_non_finite_residuals() {
+    for (script, message) in [
+        (
+            "lsqnonlin(|p| [sqrt(p[0]) - 1.0, p[1]], [-1.0, 1.0])",
+            "not finite at the starting point",
+        ),
+        (
+            "lsqcurvefit(|p, x| p * x, 1.0, [1.0, 2.0], [1.0, 0.0 / 0.0])",
+            "not finite at the starting point",
+        ),
+        (
+            "lsqnonlin(|p| [p[0] - 1.0], [0.5], #{jacobian: |p| [[0.0 / 0.0]]})",
+            "Jacobian of the residuals is not finite",
+        ),
+    ] {
+        let error = engine().eval::<Map>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+
+    // The residuals are finite only at the starting point, so every step is rejected
+    let result: Map = engine()
+        .eval("lsqnonlin(|p| if p == [2.0] { [1.0] } else { [0.0 / 0.0] }, [2.0], #{jacobian: |p| [[1.0]]})")
+        .unwrap();
+    assert_eq!(
+        result["status"].clone().into_string().unwrap(),
+        "step_tolerance"
+    );
+    assert!(result["evaluations"].as_int().unwrap() < 50);
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn lsqcurvefit_checks_data_lengths() {
+    let error = engine()
+        .eval::<Map>("lsqcurvefit(|p, x| p * x, 1.0, [1, 2, 3], [1, 2])")
+        .unwrap_err();
+    assert!(error.to_string().contains("xdata"));
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn global_minimizers_are_reproducible_with_a_seed() {
+    for method in [
+        "ga",
+        "particleswarm",
+        "simulannealbnd",
+        "differential_evolution",
+    ] {
+        let script = format!(
+            r#"
+            let f = |x| (x[0] - 0.5) ** 2 + abs(x[1]) + 0.1 * sin(10.0 * x[2]);
+            let options = #{{seed: 2024, population_size: 12, generations: 15}};
+            let first = {method}(f, [-1, -1, -1], [1, 1, 1], options);
+            let second = {method}(f, [-1, -1, -1], [1, 1, 1], options);
+            options.seed = 2025;
+            let third = {method}(f, [-1, -1, -1], [1, 1, 1], options);
+            [first, second, third]
+            "#
+        );
+        let results: Array = engine().eval(&script).unwrap();
+        let text: Vec<String> = results.iter().map(|r| r.to_string()).collect();
+        assert_eq!(text[0], text[1], "{method} is not reproducible");
+        assert_ne!(text[0], text[2], "{method} ignores the seed");
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn global_minimizers_stay_in_bounds_and_record_history() {
+    for method in [
+        "ga",
+        "particleswarm",
+        "simulannealbnd",
+        "differential_evolution",
+    ] {
+        let script = format!(
+            r#"
+            let points = [];
+            // The unconstrained minimum at (3, -3) lies outside the box
+            let f = |x| {{ points.push(x); (x[0] - 3.0) ** 2 + (x[1] + 3.0) ** 2 }};
+            let result = {method}(f, [-1, -2], [1, 2], #{{seed: 5, generations: 40}});
+            let inside = points.all(|x| x[0] >= -1.0 && x[0] <= 1.0 && x[1] >= -2.0 && x[1] <= 2.0);
+            [inside, result]
+            "#
+        );
+        let output: Array = engine().eval(&script).unwrap();
+        assert!(output[0].as_bool().unwrap(), "{method} left the box");
+        let result = output[1].clone().cast::<Map>();
+        let x: Vec<FLOAT> = result["x"]
+            .clone()
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.as_float().unwrap())
+            .collect();
+        assert!(
+            (x[0] - 1.0).abs() < 0.05 && (x[1] + 2.0).abs() < 0.05,
+            "{method}: {x:?}"
+        );
+        let history = result["history"].clone().cast::<Map>();
+        let best: Vec<FLOAT> = history["fval"]
+            .clone()
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.as_float().unwrap())
+            .collect();
+        assert_eq!(
+            best.len() as i64,
+            result["iterations"].as_int().unwrap() + 1
+        );
+        assert!(best.windows(2).all(|w| w[1] <= w[0]), "{method}");
+        assert_eq!(best.last().unwrap(), &result["fval"].as_float().unwrap());
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn global_minimizers_reject_unbounded_boxes() {
+    let error = engine()
+        .eval::<Map>("particleswarm(|x| x * x, -inf, 1)")
+        .unwrap_err();
+    assert!(error.to_string().contains("finite"));
+    let error = engine()
+        .eval::<Map>("differential_evolution(|x| x[0], [0], [1], #{population_size: 3})")
+        .unwrap_err();
+    assert!(error.to_string().contains("population_size"));
+}
+
+#[test]
+fn hypervolume_of_three_objectives_matches_a_grid_count() {
+    let points: Vec<[FLOAT; 3]> = (0..12)
+        .map(|k| {
+            let t = k as FLOAT;
+            [
+                (0.37 * t).sin().abs(),
+                (1.3 * t + 0.5).cos().abs(),
+                ((0.71 * t).sin() * (0.53 * t).cos()).abs(),
+            ]
+        })
+        .collect();
+    let matrix = format!(
+        "[{}]",
+        points
+            .iter()
+            .map(|p| format!("[{:?}, {:?}, {:?}]", p[0], p[1], p[2]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let volume: FLOAT = engine()
+        .eval(&format!("hypervolume({matrix}, [1.0, 1.0, 1.0])"))
+        .unwrap();
+    let cells = 100;
+    let h = 1.0 / cells as FLOAT;
+    let mut count = 0;
+    for i in 0..cells {
+        for j in 0..cells {
+            for k in 0..cells {
+                let c = [
+                    (i as FLOAT + 0.5) * h,
+                    (j as FLOAT + 0.5) * h,
+                    (k as FLOAT + 0.5) * h,
+                ];
+                if points.iter().any(|p| (0..3).all(|d| p[d] <= c[d])) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    let estimate = count as FLOAT * h * h * h;
+    assert!((volume - estimate).abs() < 0.02, "{volume} vs {estimate}");
+
+    let front: Array = engine().eval(&format!("paretofront({matrix})")).unwrap();
+    let front: Vec<usize> = front.iter().map(|i| i.as_int().unwrap() as usize).collect();
+    for (i, p) in points.iter().enumerate() {
+        let dominated = points
+            .iter()
+            .any(|q| (0..3).all(|d| q[d] <= p[d]) && (0..3).any(|d| q[d] < p[d]));
+        assert_eq!(front.contains(&i), !dominated, "row {i}");
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn gamultiobj_approaches_the_zdt1_front() {
+    let result: Map = engine()
+        .eval(
+            r#"
+            let zdt1 = |x| {
+                let g = 1.0 + 9.0 * (x[1] + x[2] + x[3]) / 3.0;
+                [x[0], g * (1.0 - sqrt(x[0] / g))]
+            };
+            gamultiobj(zdt1, [0, 0, 0, 0], [1, 1, 1, 1], #{seed: 17, population_size: 40, generations: 80})
+            "#,
+        )
+        .unwrap();
+    let fval = result["fval"].clone().into_array().unwrap();
+    assert!(fval.len() >= 20);
+    let mut previous = FLOAT::NEG_INFINITY;
+    for row in fval {
+        let row = row.into_array().unwrap();
+        let (f1, f2) = (row[0].as_float().unwrap(), row[1].as_float().unwrap());
+        assert!(f1 >= previous);
+        previous = f1;
+        // The true front is f2 = 1 - sqrt(f1)
+        assert!(f2 - (1.0 - f1.sqrt()) < 0.1, "({f1}, {f2})");
+    }
+    assert_eq!(result["iterations"].as_int().unwrap(), 80);
+    assert_eq!(result["evaluations"].as_int().unwrap(), 40 * 81);
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn gamultiobj_checks_the_number_of_objectives() {
+    let error = engine()
+        .eval::<Map>(
+            "gamultiobj(|x| if x[0] < 0.5 { [x[0]] } else { [x[0], x[1]] }, [0, 0], [1, 1])",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("same"));
+    let error = engine()
+        .eval::<FLOAT>("hypervolume([[1, 2, 3, 4]], [5, 5, 5, 5])")
+        .unwrap_err();
+    assert!(error.to_string().contains("two or three"));
+}
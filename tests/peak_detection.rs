@@ -0,0 +1,54 @@
+use rhai::{Array, Dynamic, Map, FLOAT, INT};
+use rhai_sci::signal_functions::{findpeaks, findpeaks_with_options};
+
+fn floats(values: &[FLOAT]) -> Array {
+    values.iter().map(|v| Dynamic::from_float(*v)).collect()
+}
+
+fn ints_of(map: &Map, key: &str) -> Vec<INT> {
+    map[key]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_int().unwrap())
+        .collect()
+}
+
+#[test]
+fn flat_peaks_are_reported_at_their_first_sample() {
+    let y = floats(&[0.0, 1.0, 3.0, 3.0, 3.0, 1.0, 0.0, 2.0, 2.0]);
+    let result = findpeaks(y).unwrap();
+    // The trailing plateau never descends, so it is not a peak
+    assert_eq!(ints_of(&result, "locations"), vec![2]);
+}
+
+#[test]
+fn resonance_peaks_survive_prominence_filter() {
+    // Two resonances on top of a small ripple
+    let y: Array = (0..200)
+        .map(|i| {
+            let x = i as FLOAT;
+            let resonance =
+                |centre: FLOAT, height: FLOAT| height / (1.0 + ((x - centre) / 3.0).powi(2));
+            Dynamic::from_float(
+                resonance(60.0, 5.0) + resonance(140.0, 2.0) + 0.05 * (x * 1.7).sin(),
+            )
+        })
+        .collect();
+
+    let mut options = Map::new();
+    options.insert("min_prominence".into(), Dynamic::from_float(1.0));
+    let result = findpeaks_with_options(y, options).unwrap();
+
+    assert_eq!(ints_of(&result, "locations"), vec![60, 140]);
+    let widths: Vec<FLOAT> = result["widths"]
+        .clone()
+        .into_array()
+        .unwrap()
+        .into_iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    // A Lorentzian with half width 3 has a full width at half maximum of 6
+    assert!(widths.iter().all(|w| (w - 6.0).abs() < 1.0));
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::engine;
+use rhai::{EvalAltResult, Map, FLOAT};
+
+#[test]
+fn quadgk_error_estimate_bounds_the_true_error() {
+    let cases = [
+        // A sharp peak at x = 0.3
+        (
+            "|x| 1.0 / ((x - 0.3) ** 2 + 0.01)",
+            "0",
+            "1",
+            10.0 * (7.0_f64.atan() + 3.0_f64.atan()),
+        ),
+        ("|x| exp(x)", "-inf", "1.5", 1.5_f64.exp()),
+        (
+            "|x| 1.0 / (1.0 + x * x)",
+            "-inf",
+            "inf",
+            std::f64::consts::PI,
+        ),
+        ("|x| ln(x)", "0", "1", -1.0),
+        ("|x| cos(x)", "2", "0", -(2.0_f64.sin())),
+    ];
+    for (f, a, b, exact) in cases {
+        let result: Map = engine()
+            .eval(&format!("quadgk({f}, {a}, {b}, #{{rel_tol: 1e-8}})"))
+            .unwrap();
+        let value = result["value"].as_float().unwrap();
+        let error = result["error"].as_float().unwrap();
+        assert!(result["converged"].as_bool().unwrap(), "{f}");
+        assert!(
+            (value - exact).abs() <= error.max(1e-14),
+            "{f}: {value} vs {exact}"
+        );
+        assert!(error <= 1e-8 * exact.abs(), "{f}: error {error}");
+    }
+}
+
+#[test]
+fn integral_rejects_non_finite_integrands() {
+    let error = engine()
+        .eval::<FLOAT>("integral(|x| if x > 0.5 { inf } else { 1.0 }, 0, 1)")
+        .unwrap_err();
+    assert!(error.to_string().contains("not finite"));
+}
+
+#[test]
+fn integral_reports_unmet_tolerance() {
+    let error = engine()
+        .eval::<FLOAT>("integral(|x| sin(1.0 / x), 0.0001, 1, #{max_intervals: 30})")
+        .unwrap_err();
+    assert!(error.to_string().contains("tolerance"));
+}
+
+#[test]
+fn operation_limit_stops_quadrature() {
+    let mut engine = engine();
+    engine.set_max_operations(100);
+    let error = engine
+        .eval::<FLOAT>("integral(|x| sin(1.0 / x), 0.0001, 1)")
+        .unwrap_err();
+    assert!(matches!(*error, EvalAltResult::ErrorTooManyOperations(_)));
+}
+
+#[test]
+fn integral2_accepts_limits_depending_on_x() {
+    // The region between y = x^2 and y = x for 0 <= x <= 1
+    let value: FLOAT = engine()
+        .eval("integral2(|x, y| x * y, 0, 1, |x| x * x, |x| x)")
+        .unwrap();
+    assert!((value - 1.0 / 24.0).abs() < 1e-12);
+    let error = engine()
+        .eval::<FLOAT>("integral2(|x, y| 1.0, 0, |y| y, 0, 1)")
+        .unwrap_err();
+    assert!(error.to_string().contains("limits of x"));
+}
+
+#[test]
+fn trapz2_is_exact_for_bilinear_data_on_nonuniform_grids() {
+    let value: FLOAT = engine()
+        .eval(
+            r#"
+            let x = [0.0, 0.1, 0.5, 1.2, 2.0];
+            let y = [-1.0, 0.3, 0.4, 1.0];
+            let g = meshgrid(x, y);
+            let z = g.x.map(|row, i| row.map(|x| (x + 1.0) * (2.0 - y[i])));
+            trapz2(x, g.y, z)
+            "#,
+        )
+        .unwrap();
+    // (integral of x + 1 over [0, 2]) * (integral of 2 - y over [-1, 1])
+    assert!((value - 4.0 * 4.0).abs() < 1e-12, "{value}");
+    let error = engine()
+        .eval::<FLOAT>("trapz2([1, 2, 3], [1, 2], [[1, 2], [3, 4]])")
+        .unwrap_err();
+    assert!(error.to_string().contains("columns"));
+}
+
+#[test]
+fn simpson_and_cumsimpson_are_exact_for_quadratics_on_nonuniform_grids() {
+    for x in [
+        "[0.0, 0.3, 1.1, 1.2, 2.0]",
+        "[-1.0, -0.2, 0.5, 0.6, 1.4, 2.0]",
+    ] {
+        let result: Map = engine()
+            .eval(&format!(
+                r#"
+                let x = {x};
+                let y = x.map(|x| 3.0 * x ** 2 - 2.0 * x + 1.0);
+                #{{simpson: simpson(x, y), cumsimpson: cumsimpson(x, y), x: x}}
+                "#
+            ))
+            .unwrap();
+        let primitive = |x: FLOAT| x.powi(3) - x * x + x;
+        let x: Vec<FLOAT> = result["x"]
+            .clone()
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.as_float().unwrap())
+            .collect();
+        let cumulative = result["cumsimpson"].clone().into_array().unwrap();
+        for (xi, ci) in x.iter().zip(cumulative) {
+            let exact = primitive(*xi) - primitive(x[0]);
+            let ci = ci.as_float().unwrap();
+            assert!((ci - exact).abs() < 1e-12, "{x:?}: {ci} vs {exact}");
+        }
+        let exact = primitive(x[x.len() - 1]) - primitive(x[0]);
+        let value = result["simpson"].as_float().unwrap();
+        assert!((value - exact).abs() < 1e-12, "{x:?}: {value} vs {exact}");
+    }
+    let error = engine()
+        .eval::<FLOAT>("simpson([0, 1, 1, 2], [1, 2, 3, 4])")
+        .unwrap_err();
+    assert!(error.to_string().contains("distinct"));
+}
+
+#[test]
+fn romberg_requires_power_of_two_intervals() {
+    let error = engine().eval::<FLOAT>("romberg([1, 2, 3, 4])").unwrap_err();
+    assert!(error.to_string().contains("2^k + 1"));
+}
+
+#[test]
+fn sparsegrid_converges_with_the_level() {
+    let results: rhai::Array = engine()
+        .eval(
+            r#"
+            // Product of cosines in six dimensions
+            let f = |x| x.reduce(|p, v| p * cos(v), 1.0);
+            let bounds = [[0, 1], [0, 1], [0, 1], [0, 1], [0, 1], [0, 1]];
+            [sparsegrid(f, bounds, 2), sparsegrid(f, bounds, 4)]
+            "#,
+        )
+        .unwrap();
+    let exact = 1.0_f64.sin().powi(6);
+    let maps: Vec<Map> = results.into_iter().map(|r| r.cast::<Map>()).collect();
+    let value = |m: &Map| m["value"].as_float().unwrap();
+    let error = |m: &Map| m["error"].as_float().unwrap();
+    for m in &maps {
+        assert!((value(m) - exact).abs() < error(m));
+    }
+    assert!((value(&maps[1]) - exact).abs() < 1e-6);
+    // A tensor product grid with 9 points per variable would need 531441 points
+    assert_eq!(maps[1]["evaluations"].as_int().unwrap(), 1457);
+}
+
+#[test]
+fn sparsegrid_rejects_invalid_levels_and_bounds() {
+    for (script, message) in [
+        ("sparsegrid(|x| x[0], [[0, 1]], 0)", "between 1 and 12"),
+        ("sparsegrid(|x| x[0], [[0, 1]], 13)", "between 1 and 12"),
+        ("sparsegrid(|x| x[0], [[0, 1, 2]], 2)", "one row [a, b]"),
+    ] {
+        let error = engine().eval::<Map>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn mcintegrate_error_shrinks_with_more_points() {
+    let results: rhai::Array = engine()
+        .eval(
+            r#"
+            // Product of cosines in six dimensions
+            let f = |x| x.reduce(|p, v| p * cos(v), 1.0);
+            let bounds = [[0, 1], [0, 1], [0, 1], [0, 1], [0, 1], [0, 1]];
+            [mcintegrate(f, bounds, 500, 3), mcintegrate(f, bounds, 20000, 3),
+             mcintegrate(f, bounds, 500, 3)]
+            "#,
+        )
+        .unwrap();
+    let exact = 1.0_f64.sin().powi(6);
+    let maps: Vec<Map> = results.into_iter().map(|r| r.cast::<Map>()).collect();
+    let value = |m: &Map| m["value"].as_float().unwrap();
+    let error = |m: &Map| m["error"].as_float().unwrap();
+    assert!(error(&maps[1]) < 0.2 * error(&maps[0]));
+    for m in &maps[..2] {
+        assert!((value(m) - exact).abs() < 4.0 * error(m));
+    }
+    assert_eq!(value(&maps[0]), value(&maps[2]));
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "rand")]
+
+use rhai::{packages::Package, Array, Dynamic, Engine, INT};
+use rhai_sci::SciPackage;
+
+fn engine(seed: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine.register_global_module(SciPackage::reseeding_thread_rng(seed).as_shared_module());
+    engine
+}
+
+/// A script drawing from every random function of the package.
+const DRAWS: &str = r#"
+    [rand(), rand(2, 3), rand([4]), randn(), randn([3]), randi(6), randi([-5, 5], 2, 2),
+     randperm(8), randperm(20, 4), shuffle(["a", "b", "c", "d"]), sample([1, 2, 3], 5, true),
+     sample([1, 2, 3, 4, 5], 3), random("gamma", [2, 1.5], 3),
+     mcintegrate(|x| x[0] * x[1], [[0, 1], [0, 1]], 64).value,
+     ga(|x| x[0] ** 2, [-1], [1], #{population_size: 8, generations: 5}).x]
+"#;
+
+#[test]
+fn seeded_scripts_are_reproducible() {
+    let first: Array = engine(42).eval(DRAWS).unwrap();
+    let second: Array = engine(42).eval(DRAWS).unwrap();
+    assert_eq!(format!("{first:?}"), format!("{second:?}"));
+
+    // Reseeding from the script restarts the same sequence
+    let reseeded: Array = engine(7).eval(&format!("rng_seed(42); {DRAWS}")).unwrap();
+    assert_eq!(format!("{first:?}"), format!("{reseeded:?}"));
+
+    let other: Array = engine(43).eval(DRAWS).unwrap();
+    assert_ne!(format!("{first:?}"), format!("{other:?}"));
+}
+
+#[test]
+fn seeds_apply_to_the_current_thread() {
+    let first: Array = engine(42).eval(DRAWS).unwrap();
+    let engine = engine(42);
+    // Seeding another thread neither restarts nor disturbs the sequence of this one
+    let other = std::thread::spawn(|| {
+        rhai_sci::rng_seed(42);
+        let mut engine = Engine::new();
+        engine.register_global_module(SciPackage::new().as_shared_module());
+        format!("{:?}", engine.eval::<Array>(DRAWS).unwrap())
+    })
+    .join()
+    .unwrap();
+    let second: Array = engine.eval(DRAWS).unwrap();
+    assert_eq!(format!("{first:?}"), other);
+    assert_eq!(format!("{first:?}"), format!("{second:?}"));
+}
+
+#[test]
+fn randi_covers_its_range_uniformly() {
+    let draws: Array = engine(1).eval("randi([3, 7], [5000])").unwrap();
+    let mut counts = [0; 5];
+    for d in draws {
+        counts[(d.as_int().unwrap() - 3) as usize] += 1;
+    }
+    // Each count is binomial with mean 1000 and standard deviation near 28
+    for count in counts {
+        assert!((count - 1000 as INT).abs() < 150, "{counts:?}");
+    }
+}
+
+#[test]
+fn sampling_without_replacement_draws_distinct_elements() {
+    let result: Array = engine(3)
+        .eval("let a = []; for i in 0..50 { a.push(i); } let x = sample(a, 50, false); x.sort(); x")
+        .unwrap();
+    let expected: Array = (0..50).map(|i| Dynamic::from_int(i as INT)).collect();
+    assert_eq!(format!("{result:?}"), format!("{expected:?}"));
+}
+
+#[test]
+fn invalid_requests_are_reported() {
+    for (script, message) in [
+        ("sample([1, 2], 3, false)", "without replacement"),
+        ("sample([], 1, true)", "empty array"),
+        ("randperm(3, 4)", "distinct indices"),
+        ("randi([4, 2])", "contains no integers"),
+        ("randn([1, 2, 3])", "one or two dimensions"),
+        ("randi(5, -1)", "non-negative"),
+    ] {
+        let error = engine(0).eval::<Dynamic>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
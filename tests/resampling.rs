@@ -0,0 +1,70 @@
+mod common;
+
+use common::engine;
+use rhai::{Array, Map, FLOAT};
+
+fn to_floats(values: Array) -> Vec<FLOAT> {
+    values.into_iter().map(|v| v.as_float().unwrap()).collect()
+}
+
+#[test]
+fn resample_preserves_in_band_sinusoid() {
+    // A 5 Hz tone sampled at 100 Hz, resampled to 150 Hz
+    let y = to_floats(
+        engine()
+            .eval(
+                r#"
+                let x = [];
+                for i in 0..300 { x.push(sin(2.0 * pi * 5.0 * i / 100.0)); }
+                resample(x, 3, 2)
+                "#,
+            )
+            .unwrap(),
+    );
+    assert_eq!(y.len(), 450);
+    // Ignore the filter transients at both ends
+    for (m, value) in y.iter().enumerate().take(400).skip(50) {
+        let expected = (2.0 * std::f64::consts::PI * 5.0 * m as FLOAT / 150.0).sin();
+        assert!(
+            (value - expected).abs() < 1e-3,
+            "sample {m}: {value} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn retime_aligns_logs_recorded_at_different_rates() {
+    // Two logs of the same ramp recorded with different, irregular timestamps
+    let aligned: Array = engine()
+        .eval(
+            r#"
+            let t1 = [0.0, 0.4, 0.9, 1.3, 2.2, 3.0];
+            let t2 = [0.1, 1.0, 1.7, 2.5, 3.1];
+            let a = retime(t1, t1.map(|t| 2.0 * t), 0.25, #{start: 0.5, end: 2.5});
+            let b = retime(t2, t2.map(|t| 2.0 * t), 0.25, #{start: 0.5, end: 2.5});
+            [a.t == b.t, a.y, b.y]
+            "#,
+        )
+        .unwrap();
+    assert!(aligned[0].as_bool().unwrap());
+    let a = to_floats(aligned[1].clone().into_array().unwrap());
+    let b = to_floats(aligned[2].clone().into_array().unwrap());
+    assert_eq!(a.len(), 9);
+    for (u, v) in a.iter().zip(&b) {
+        assert!((u - v).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn retime_rejects_non_finite_times() {
+    for script in [
+        "retime([0.0, 1.0], [0.0, 1.0], 0.5, #{end: 1.0 / 0.0})",
+        "retime([0.0, 1.0], [0.0, 1.0], 0.5, #{start: -1.0 / 0.0})",
+        "retime([0.0, 1.0], [0.0, 1.0], 1.0 / 0.0)",
+        "retime([0.0, 1.0], [0.0, 1.0], 0.0 / 0.0)",
+        "retime([0.0, 1.0 / 0.0], [0.0, 1.0], 0.5)",
+        "retime([0.0, 1e300], [0.0, 1.0], 1e-300)",
+    ] {
+        assert!(engine().eval::<Map>(script).is_err(), "{script}");
+    }
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::engine;
+use rhai::{EvalAltResult, Map};
+
+#[test]
+fn brent_needs_fewer_iterations_than_bisection() {
+    let iterations: rhai::Array = engine()
+        .eval(
+            r#"
+            let f = |x| x ** 3 - 2.0 * x - 5.0;
+            let a = fzero(f, [2, 3]);
+            let b = bisect(f, 2, 3);
+            assert_approx_eq(a.root, b.root, 1e-11);
+            [a.iterations, b.iterations]
+            "#,
+        )
+        .unwrap();
+    let brent = iterations[0].as_int().unwrap();
+    let bisection = iterations[1].as_int().unwrap();
+    assert!(brent < 12 && bisection > 30, "{brent} vs {bisection}");
+}
+
+#[test]
+fn bracket_expansion_finds_distant_root() {
+    let result: Map = engine().eval("fzero(|x| x - 1000.0, 1)").unwrap();
+    assert!(result["converged"].as_bool().unwrap());
+    assert!((result["root"].as_float().unwrap() - 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn bracket_without_sign_change_is_an_error() {
+    let error = engine()
+        .eval::<Map>("fzero(|x| x * x + 1.0, [-1, 1])")
+        .unwrap_err();
+    assert!(error.to_string().contains("differ in sign"));
+}
+
+#[test]
+fn operation_limit_stops_root_finding() {
+    // Each call of the function counts towards the limit, even though every single call is cheap
+    let mut engine = engine();
+    engine.set_max_operations(40);
+    let error = engine
+        .eval::<Map>("bisect(|x| x - 0.3, 0, 1, #{tol: 1e-15})")
+        .unwrap_err();
+    assert!(matches!(*error, EvalAltResult::ErrorTooManyOperations(..)));
+
+    engine.set_max_operations(0);
+    assert!(engine
+        .eval::<Map>("bisect(|x| x - 0.3, 0, 1, #{tol: 1e-15})")
+        .is_ok());
+}
+
+#[test]
+fn fsolve_handles_fifty_coupled_unknowns() {
+    // Broyden's tridiagonal system
+    let result: Map = engine()
+        .eval(
+            r#"
+            fn broyden(x) {
+                let n = len(x);
+                let f = [];
+                for i in 0..n {
+                    let value = (3.0 - 2.0 * x[i]) * x[i] + 1.0;
+                    if i > 0 { value -= x[i - 1]; }
+                    if i < n - 1 { value -= 2.0 * x[i + 1]; }
+                    f.push(value);
+                }
+                f
+            }
+            let x0 = [];
+            for i in 0..50 { x0.push(-1.0); }
+            fsolve(Fn("broyden"), x0)
+            "#,
+        )
+        .unwrap();
+    assert_eq!(result["status"].clone().into_string().unwrap(), "converged");
+    assert!(result["residual_norm"].as_float().unwrap() <= 1e-10);
+    assert!(result["iterations"].as_int().unwrap() < 20);
+}
+
+#[test]
+fn fsolve_with_analytic_jacobian_matches_finite_differences() {
+    let solutions: rhai::Array = engine()
+        .eval(
+            r#"
+            let f = |v| [v[0] * v[1] - 6.0, v[0] + v[1] ** 2 - 11.0];
+            let a = fsolve(f, [1.0, 1.0]);
+            let b = fsolve(f, [1.0, 1.0], #{jacobian: |v| [[v[1], v[0]], [1.0, 2.0 * v[1]]]});
+            [a.x, b.x]
+            "#,
+        )
+        .unwrap();
+    let a = solutions[0].clone().into_array().unwrap();
+    let b = solutions[1].clone().into_array().unwrap();
+    for (u, v) in a.iter().zip(&b) {
+        assert!((u.as_float().unwrap() - v.as_float().unwrap()).abs() < 1e-8);
+    }
+}
+
+#[test]
+fn fsolve_reports_non_finite_values() {
+    for (script, message) in [
+        (
+            "fsolve(|x| [sqrt(x[0]) - 1.0, x[1]], [-1.0, 1.0])",
+            "not finite at the starting point",
+        ),
+        (
+            "fsolve(|x| [x[0] - 1.0, x[1]], [0.5, 1.0], #{jacobian: |x| [[0.0 / 0.0, 0.0], [0.0, 1.0]]})",
+            "The Jacobian is not finite",
+        ),
+    ] {
+        let error = engine().eval::<Map>(script).unwrap_err();
+        assert!(error.to_string().contains(message), "{script}: {error}");
+    }
+}
+
+#[test]
+fn fsolve_rejects_steps_out_of_the_domain() {
+    // Full Newton steps leave the domain of the logarithm and the square root
+    let result: rhai::Array = engine()
+        .eval(
+            r#"
+            let a = fsolve(|x| ln(x) + 5.0, 1.0);
+            let b = fsolve(|x| [ln(x[0]) + 5.0, x[1]], [1.0, 1.0]);
+            let c = fsolve(|x| sqrt(x) - 0.1, 0.5);
+            [a.x, b.x[0], b.x[1], c.x, a.status, b.status, c.status]
+            "#,
+        )
+        .unwrap();
+    let expected = [(-5.0_f64).exp(), (-5.0_f64).exp(), 0.0, 0.01];
+    for (value, expected) in result.iter().zip(expected) {
+        assert!(
+            (value.as_float().unwrap() - expected).abs() < 1e-10,
+            "{value}"
+        );
+    }
+    for status in &result[4..] {
+        assert_eq!(status.clone().into_string().unwrap(), "converged");
+    }
+}
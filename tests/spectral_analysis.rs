@@ -0,0 +1,70 @@
+mod common;
+
+use common::engine;
+use rhai::FLOAT;
+
+#[test]
+fn periodogram_satisfies_parseval() {
+    // The integral of the one-sided PSD equals the mean square of the signal
+    let (power, mean_square): (FLOAT, FLOAT) = engine()
+        .eval::<rhai::Array>(
+            r#"
+            let x = [0.3, -1.2, 2.5, 0.7, -0.4, 1.1, -2.2, 0.9, 0.0, 1.6];
+            let p = periodogram(x, 50.0, #{window: "hann", nfft: 16});
+            let df = p.f[1] - p.f[0];
+            let w = hann(10);
+            let power = sum(p.pxx) * df;
+            let weighted = 0.0;
+            for i in 0..10 { weighted += (x[i] * w[i]) ** 2; }
+            [power, weighted / sum(w.map(|v| v * v))]
+            "#,
+        )
+        .map(|r| (r[0].as_float().unwrap(), r[1].as_float().unwrap()))
+        .unwrap();
+    assert!((power - mean_square).abs() < 1e-10);
+}
+
+#[test]
+fn pwelch_recovers_white_noise_level() {
+    // A deterministic pseudo-random sequence with unit variance has a flat density of 2/fs
+    let level: FLOAT = engine()
+        .eval(
+            r#"
+            let state = 12345;
+            let x = [];
+            for i in 0..4096 {
+                state = (state * 1103515245 + 12345) % 2147483648;
+                x.push((state / 2147483648.0 - 0.5) * sqrt(12.0));
+            }
+            let p = pwelch(x, 100.0, #{segment_length: 256, window: "hann"});
+            mean(p.pxx)
+            "#,
+        )
+        .unwrap();
+    assert!((level - 0.02).abs() < 0.002);
+}
+
+#[test]
+fn spectrogram_tracks_frequency_step() {
+    // The dominant frequency in the first half differs from the second half
+    let peaks: rhai::Array = engine()
+        .eval(
+            r#"
+            let fs = 1000.0;
+            let x = [];
+            for i in 0..2000 {
+                let f = if i < 1000 { 100.0 } else { 300.0 };
+                x.push(sin(2.0 * pi * f * i / fs));
+            }
+            let s = spectrogram(x, fs, #{segment_length: 200, overlap: 0, window: "hann"});
+            let first = s.p.map(|row| row[0]);
+            let last = s.p.map(|row| row[9]);
+            [s.f[argmax(first)], s.f[argmax(last)]]
+            "#,
+        )
+        .unwrap();
+    let first = peaks[0].as_float().unwrap();
+    let last = peaks[1].as_float().unwrap();
+    assert!((first - 100.0).abs() < 4.0);
+    assert!((last - 300.0).abs() < 4.0);
+}